@@ -0,0 +1,218 @@
+use std::fmt::Write;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+use crate::Error;
+use crate::json::Json;
+use crate::log::{debug, warning};
+use crate::network::{NetworkOptions, check_input, is_url};
+use crate::tool::Tool;
+
+#[derive(Debug, Clone)]
+pub struct AnalyzeOptions {
+    /// Decode every frame to tell I, P and B frames apart. Without it only
+    /// the packets are read, which is much faster but only shows keyframes.
+    pub frame_types: bool,
+}
+
+impl Default for AnalyzeOptions {
+    fn default() -> Self {
+        Self { frame_types: true }
+    }
+}
+
+/// How many frames of each picture type the video has.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameTypes {
+    pub i: u64,
+    pub p: u64,
+    pub b: u64,
+    /// Switching and unknown picture types.
+    pub other: u64,
+}
+
+impl FrameTypes {
+    pub fn total(&self) -> u64 {
+        self.i + self.p + self.b + self.other
+    }
+
+    pub fn to_json(&self) -> Json {
+        Json::object([
+            ("i", Json::from(self.i)),
+            ("p", self.p.into()),
+            ("b", self.b.into()),
+            ("other", self.other.into()),
+        ])
+    }
+}
+
+/// The bitrate and GOP structure of the first video stream.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamAnalysis {
+    /// The bits of video in each second, counted from the first one.
+    pub bits_per_second: Vec<u64>,
+    /// When each keyframe is shown, counted from the first frame.
+    pub keyframes: Vec<Duration>,
+    /// `None` when only packets were read.
+    pub frame_types: Option<FrameTypes>,
+}
+
+impl StreamAnalysis {
+    /// Parses ffprobe's JSON from `-show_frames` or `-show_packets`.
+    pub fn parse(output: &str) -> Result<Self, Error> {
+        let json = Json::parse(output)?;
+        let (items, frames) = match (json.get("frames"), json.get("packets")) {
+            (Some(frames), _) => (frames, true),
+            (None, Some(packets)) => (packets, false),
+            (None, None) => return Err(Error::ParseError),
+        };
+        let items = items.as_array().ok_or(Error::ParseError)?;
+
+        let time = |item: &Json| {
+            let keys: &[&str] = if frames {
+                &["best_effort_timestamp_time", "pts_time", "pkt_dts_time"]
+            } else {
+                &["pts_time", "dts_time"]
+            };
+            keys.iter()
+                .find_map(|key| item.get(key).and_then(Json::as_f64))
+                .filter(|t| t.is_finite())
+        };
+        let start = items.iter().filter_map(time).fold(f64::INFINITY, f64::min);
+
+        let mut analysis = StreamAnalysis {
+            frame_types: frames.then(FrameTypes::default),
+            ..Default::default()
+        };
+        for item in items {
+            let Some(t) = time(item).map(|t| (t - start).max(0.0)) else {
+                continue;
+            };
+            let size = item
+                .get(if frames { "pkt_size" } else { "size" })
+                .and_then(Json::as_u64)
+                .unwrap_or_default();
+            let second = t as usize;
+            if analysis.bits_per_second.len() <= second {
+                analysis.bits_per_second.resize(second + 1, 0);
+            }
+            analysis.bits_per_second[second] += size * 8;
+
+            let key = if frames {
+                item.get("key_frame").and_then(Json::as_u64) == Some(1)
+            } else {
+                item.get("flags")
+                    .and_then(Json::as_str)
+                    .is_some_and(|flags| flags.starts_with('K'))
+            };
+            if key {
+                analysis.keyframes.push(Duration::from_secs_f64(t));
+            }
+
+            if let Some(types) = &mut analysis.frame_types {
+                match item.get("pict_type").and_then(Json::as_str) {
+                    Some("I") => types.i += 1,
+                    Some("P") => types.p += 1,
+                    Some("B") => types.b += 1,
+                    _ => types.other += 1,
+                }
+            }
+        }
+        // Packets come in decoding order.
+        analysis.keyframes.sort();
+        Ok(analysis)
+    }
+
+    /// The time between each keyframe and the next.
+    pub fn keyframe_intervals(&self) -> Vec<Duration> {
+        self.keyframes.windows(2).map(|w| w[1] - w[0]).collect()
+    }
+
+    /// The mean bitrate in bits per second, over the whole seconds only:
+    /// the last one is usually cut short.
+    pub fn average_bitrate(&self) -> Option<u64> {
+        let full = match self.bits_per_second.len() {
+            0 => return None,
+            1 => &self.bits_per_second[..],
+            n => &self.bits_per_second[..n - 1],
+        };
+        Some(full.iter().sum::<u64>() / full.len() as u64)
+    }
+
+    pub fn peak_bitrate(&self) -> Option<u64> {
+        self.bits_per_second.iter().copied().max()
+    }
+
+    pub fn to_json(&self) -> Json {
+        Json::object([
+            ("bits_per_second", Json::from(self.bits_per_second.clone())),
+            ("average_bitrate", self.average_bitrate().into()),
+            ("peak_bitrate", self.peak_bitrate().into()),
+            ("keyframes", self.keyframes.clone().into()),
+            ("keyframe_intervals", self.keyframe_intervals().into()),
+            (
+                "frame_types",
+                self.frame_types.as_ref().map(FrameTypes::to_json).into(),
+            ),
+        ])
+    }
+
+    /// One line per second with its kbit/s and number of keyframes, after a
+    /// `#` header so gnuplot skips it, e.g. for
+    /// `plot "bitrate.csv" using 1:2 with lines`.
+    pub fn to_csv(&self) -> String {
+        let mut keyframes = vec![0u64; self.bits_per_second.len()];
+        for key in &self.keyframes {
+            if let Some(count) = keyframes.get_mut(key.as_secs() as usize) {
+                *count += 1;
+            }
+        }
+        let mut csv = String::from("# second kbps keyframes\n");
+        for (second, (bits, keys)) in self.bits_per_second.iter().zip(keyframes).enumerate() {
+            let _ = writeln!(csv, "{second} {:.1} {keys}", *bits as f64 / 1000.0);
+        }
+        csv
+    }
+}
+
+/// Reads the packets, or with [`AnalyzeOptions::frame_types`] decodes the
+/// frames, of the first video stream of `path`.
+pub fn analyze(path: &Path, options: &AnalyzeOptions) -> Result<StreamAnalysis, Error> {
+    check_input(path)?;
+
+    let entries = if options.frame_types {
+        "frame=best_effort_timestamp_time,pkt_size,key_frame,pict_type"
+    } else {
+        "packet=pts_time,dts_time,size,flags"
+    };
+    debug!("analyzing {}", path.display());
+    let output = Command::new(Tool::current().ffprobe())
+        .args(["-v", "error", "-select_streams", "v:0"])
+        .arg(if options.frame_types {
+            "-show_frames"
+        } else {
+            "-show_packets"
+        })
+        .args(["-show_entries", entries, "-of", "json"])
+        .args(if is_url(path) {
+            NetworkOptions::current().input_args()
+        } else {
+            Vec::new()
+        })
+        .arg(path)
+        .output()
+        .map_err(Error::CommandError)?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        warning!("ffprobe failed on {}: {}", path.display(), stderr.trim());
+        return Err(Error::ProbeError(stderr.trim().to_string()));
+    }
+
+    let analysis = StreamAnalysis::parse(&String::from_utf8_lossy(&output.stdout))?;
+    if analysis.bits_per_second.is_empty() {
+        return Err(Error::StreamNotFound);
+    }
+    Ok(analysis)
+}
@@ -0,0 +1,125 @@
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, Input, ProcessResult};
+use crate::time::format_timestamp;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AnimationFormat {
+    #[default]
+    Gif,
+    WebP,
+}
+
+impl AnimationFormat {
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "gif" => Some(AnimationFormat::Gif),
+            "webp" => Some(AnimationFormat::WebP),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for AnimationFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gif" => Ok(AnimationFormat::Gif),
+            "webp" => Ok(AnimationFormat::WebP),
+            _ => Err(Error::invalid(format!("unknown animation format `{s}`"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AnimationOptions {
+    /// Defaults to the format implied by the output extension.
+    pub format: Option<AnimationFormat>,
+    pub start: Option<Duration>,
+    pub end: Option<Duration>,
+    pub fps: u32,
+    /// Output width in pixels, the height keeps the aspect ratio.
+    pub width: Option<u32>,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        Self {
+            format: None,
+            start: None,
+            end: None,
+            fps: 10,
+            width: Some(480),
+        }
+    }
+}
+
+pub fn animation_command(
+    input: &Path,
+    output: &Path,
+    options: &AnimationOptions,
+) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+    if options.fps == 0 {
+        return Err(Error::invalid("the frame rate must be positive"));
+    }
+
+    let format = options
+        .format
+        .or_else(|| AnimationFormat::from_extension(output))
+        .unwrap_or_default();
+
+    let start = options.start.unwrap_or_default();
+    if options.end.is_some_and(|end| end <= start) {
+        return Err(Error::InvalidTimeRange);
+    }
+
+    let mut source = Input::new(input);
+    if !start.is_zero() {
+        source = source.args(["-ss", &format_timestamp(start)]);
+    }
+    let mut command = FfmpegCommand::new().add_input(source);
+    if let Some(end) = options.end {
+        command = command
+            .args(["-t", &format_timestamp(end - start)])
+            .duration(end - start);
+    }
+
+    let mut filters = format!("fps={}", options.fps);
+    if let Some(width) = options.width {
+        filters.push_str(&format!(",scale={width}:-1:flags=lanczos"));
+    }
+
+    command = match format {
+        // A palette generated from the clip itself gives far better colours
+        // than the default 256-colour web palette.
+        AnimationFormat::Gif => command
+            .filter_complex(format!(
+                "[0:v]{filters},split[a][b];[a]palettegen[p];[b][p]paletteuse[gif]"
+            ))
+            .map("[gif]")
+            .args(["-loop", "0"]),
+        AnimationFormat::WebP => command
+            .video_filter(filters)
+            .video_codec("libwebp")
+            .args(["-loop", "0", "-q:v", "75"])
+            .arg("-an"),
+    };
+
+    Ok(command.output(output))
+}
+
+pub fn animation(
+    input: &Path,
+    output: &Path,
+    options: &AnimationOptions,
+) -> Result<ProcessResult, Error> {
+    animation_command(input, output, options)?.run()?.check()
+}
@@ -0,0 +1,342 @@
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, ProcessResult};
+use crate::probe::probe;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioFormat {
+    Mp3,
+    Aac,
+    Flac,
+    Wav,
+    Opus,
+}
+
+impl AudioFormat {
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "mp3" => Some(AudioFormat::Mp3),
+            "aac" | "m4a" => Some(AudioFormat::Aac),
+            "flac" => Some(AudioFormat::Flac),
+            "wav" => Some(AudioFormat::Wav),
+            "opus" => Some(AudioFormat::Opus),
+            _ => None,
+        }
+    }
+
+    pub fn encoder(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "libmp3lame",
+            AudioFormat::Aac => "aac",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Wav => "pcm_s16le",
+            AudioFormat::Opus => "libopus",
+        }
+    }
+
+    /// The extension outputs in this format are given.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Aac => "m4a",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Wav => "wav",
+            AudioFormat::Opus => "opus",
+        }
+    }
+
+    /// Whether a stream in `codec` can be copied into this format as-is.
+    pub fn can_copy(&self, codec: &str) -> bool {
+        match self {
+            AudioFormat::Mp3 => codec == "mp3",
+            AudioFormat::Aac => codec == "aac",
+            AudioFormat::Flac => codec == "flac",
+            AudioFormat::Wav => codec.starts_with("pcm_"),
+            AudioFormat::Opus => codec == "opus",
+        }
+    }
+}
+
+impl FromStr for AudioFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mp3" => Ok(AudioFormat::Mp3),
+            "aac" => Ok(AudioFormat::Aac),
+            "flac" => Ok(AudioFormat::Flac),
+            "wav" => Ok(AudioFormat::Wav),
+            "opus" => Ok(AudioFormat::Opus),
+            _ => Err(Error::invalid(format!("unknown audio format `{s}`"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExtractAudioOptions {
+    /// Defaults to the format implied by the output extension.
+    pub format: Option<AudioFormat>,
+    /// Only used when the audio has to be re-encoded.
+    pub bitrate: Option<String>,
+}
+
+pub fn extract_audio_command(
+    input: &Path,
+    output: &Path,
+    options: &ExtractAudioOptions,
+) -> Result<FfmpegCommand, Error> {
+    let format = options
+        .format
+        .or_else(|| AudioFormat::from_extension(output))
+        .ok_or_else(|| Error::invalid("cannot tell the audio format from the output name"))?;
+
+    let info = probe(input)?;
+    let stream = info.audio().ok_or(Error::StreamNotFound)?;
+
+    let mut command = FfmpegCommand::new().input(input).map("0:a:0").arg("-vn");
+    if let Some(duration) = info.duration {
+        command = command.duration(duration);
+    }
+
+    if format.can_copy(&stream.codec) {
+        command = command.audio_codec("copy");
+    } else {
+        command = command.audio_codec(format.encoder());
+        if let Some(bitrate) = &options.bitrate {
+            command = command.args(["-b:a", bitrate]);
+        }
+    }
+
+    Ok(command.output(output))
+}
+
+pub fn extract_audio(
+    input: &Path,
+    output: &Path,
+    options: &ExtractAudioOptions,
+) -> Result<ProcessResult, Error> {
+    extract_audio_command(input, output, options)?
+        .run()?
+        .check()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ConvertAudioOptions {
+    /// Defaults to the format implied by the output extension.
+    pub format: Option<AudioFormat>,
+    /// A constant bitrate such as `192k`.
+    pub bitrate: Option<String>,
+    /// A variable quality instead of a bitrate. What it means depends on the
+    /// format: the LAME level from 0 (best) to 9 for mp3, 0.1 to 2 for aac
+    /// and the compression level from 0 to 12 for flac.
+    pub quality: Option<f64>,
+    /// In Hz, e.g. 44100.
+    pub sample_rate: Option<u32>,
+    /// 1 for mono, 2 for stereo.
+    pub channels: Option<u32>,
+}
+
+impl ConvertAudioOptions {
+    fn target_format(&self, output: &Path) -> Result<AudioFormat, Error> {
+        if self.sample_rate == Some(0) || self.channels == Some(0) {
+            return Err(Error::invalid(
+                "the sample rate and channel count must be positive",
+            ));
+        }
+        self.format
+            .or_else(|| AudioFormat::from_extension(output))
+            .ok_or_else(|| Error::invalid("cannot tell the audio format from the output name"))
+    }
+
+    fn encode(
+        &self,
+        mut command: FfmpegCommand,
+        format: AudioFormat,
+    ) -> Result<FfmpegCommand, Error> {
+        command = command.audio_codec(format.encoder());
+        if let Some(bitrate) = &self.bitrate {
+            command = command.args(["-b:a", bitrate]);
+        }
+        if let Some(quality) = self.quality {
+            command = command.args(quality_args(format, quality)?);
+        }
+        if let Some(rate) = self.sample_rate {
+            command = command.args(["-ar", &rate.to_string()]);
+        }
+        if let Some(channels) = self.channels {
+            command = command.args(["-ac", &channels.to_string()]);
+        }
+        Ok(command)
+    }
+}
+
+/// Converts the first audio track of `input`, copying it when it is in the
+/// target format already and nothing else changes.
+pub fn convert_audio_command(
+    input: &Path,
+    output: &Path,
+    options: &ConvertAudioOptions,
+) -> Result<FfmpegCommand, Error> {
+    let format = options.target_format(output)?;
+
+    let info = probe(input)?;
+    let stream = info.audio().ok_or(Error::StreamNotFound)?;
+
+    let mut command = FfmpegCommand::new().input(input).map("0:a:0").arg("-vn");
+    if let Some(duration) = info.duration {
+        command = command.duration(duration);
+    }
+
+    let unchanged = options.bitrate.is_none()
+        && options.quality.is_none()
+        && options.sample_rate.is_none()
+        && options.channels.is_none();
+    if unchanged && format.can_copy(&stream.codec) {
+        return Ok(command.audio_codec("copy").output(output));
+    }
+
+    Ok(options.encode(command, format)?.output(output))
+}
+
+pub fn convert_audio(
+    input: &Path,
+    output: &Path,
+    options: &ConvertAudioOptions,
+) -> Result<ProcessResult, Error> {
+    convert_audio_command(input, output, options)?
+        .run()?
+        .check()
+}
+
+fn quality_args(format: AudioFormat, quality: f64) -> Result<[String; 2], Error> {
+    let (name, arg, range) = match format {
+        AudioFormat::Mp3 => ("mp3", "-q:a", 0.0..=9.0),
+        AudioFormat::Aac => ("aac", "-q:a", 0.1..=2.0),
+        AudioFormat::Flac if quality.fract() == 0.0 => ("flac", "-compression_level", 0.0..=12.0),
+        AudioFormat::Flac => {
+            return Err(Error::invalid(
+                "the flac compression level is a whole number",
+            ));
+        }
+        AudioFormat::Opus => {
+            return Err(Error::invalid("opus takes a bitrate, not a quality"));
+        }
+        AudioFormat::Wav => return Err(Error::invalid("wav has no quality setting")),
+    };
+    if !range.contains(&quality) {
+        return Err(Error::invalid(format!(
+            "the {name} quality must be between {} and {}",
+            range.start(),
+            range.end()
+        )));
+    }
+    Ok([arg.to_string(), quality.to_string()])
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ConcatAudioOptions {
+    /// Blend each track into the next over this long instead of cutting
+    /// from one to the other.
+    pub crossfade: Option<Duration>,
+    /// The output format and encoder settings. The sample rate and channels
+    /// default to the first track's.
+    pub convert: ConvertAudioOptions,
+}
+
+/// Joins the first audio track of each file, in order, into one.
+pub fn concat_audio_command<P: AsRef<Path>>(
+    files: &[P],
+    output: &Path,
+    options: &ConcatAudioOptions,
+) -> Result<FfmpegCommand, Error> {
+    let format = options.convert.target_format(output)?;
+    if files.is_empty() {
+        return Err(Error::invalid("there are no files to join"));
+    }
+    let inputs = files
+        .iter()
+        .map(|file| probe(file.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+    if inputs.iter().any(|info| info.audio().is_none()) {
+        return Err(Error::StreamNotFound);
+    }
+    let crossfade = options
+        .crossfade
+        .filter(|d| !d.is_zero() && inputs.len() > 1);
+    if let Some(crossfade) = crossfade
+        && let Some(short) = inputs
+            .iter()
+            .find(|info| info.duration.is_none_or(|d| d <= crossfade))
+    {
+        return Err(Error::invalid(format!(
+            "{} is not longer than the crossfade",
+            short.path.display()
+        )));
+    }
+
+    // Both filters need every track in the same format.
+    let first = inputs[0].audio();
+    let rate = options
+        .convert
+        .sample_rate
+        .or_else(|| first.and_then(|s| s.sample_rate))
+        .unwrap_or(48000);
+    let layout = match options
+        .convert
+        .channels
+        .or_else(|| first.and_then(|s| s.channels))
+        .unwrap_or(2)
+    {
+        1 => "mono".to_string(),
+        2 => "stereo".to_string(),
+        n => format!("{n}c"),
+    };
+
+    let mut command = FfmpegCommand::new();
+    let mut graph = Vec::new();
+    for (i, info) in inputs.iter().enumerate() {
+        command = command.input(&info.path);
+        graph.push(format!(
+            "[{i}:a:0]aresample={rate},aformat=sample_rates={rate}:channel_layouts={layout}[a{i}]"
+        ));
+    }
+    let n = inputs.len();
+    match crossfade {
+        Some(crossfade) => {
+            let d = crossfade.as_secs_f64();
+            let mut previous = "a0".to_string();
+            for i in 1..n {
+                let joined = if i == n - 1 {
+                    "a".to_string()
+                } else {
+                    format!("x{i}")
+                };
+                graph.push(format!("[{previous}][a{i}]acrossfade=d={d}[{joined}]"));
+                previous = joined;
+            }
+        }
+        None => {
+            let segments: String = (0..n).map(|i| format!("[a{i}]")).collect();
+            graph.push(format!("{segments}concat=n={n}:v=0:a=1[a]"));
+        }
+    }
+    command = command.filter_complex(graph.join(";")).map("[a]");
+
+    if let Some(total) = inputs.iter().map(|i| i.duration).sum::<Option<Duration>>() {
+        let overlap = crossfade.unwrap_or_default() * (n as u32 - 1);
+        command = command.duration(total.saturating_sub(overlap));
+    }
+    Ok(options.convert.encode(command, format)?.output(output))
+}
+
+pub fn concat_audio<P: AsRef<Path>>(
+    files: &[P],
+    output: &Path,
+    options: &ConcatAudioOptions,
+) -> Result<ProcessResult, Error> {
+    concat_audio_command(files, output, options)?.run()?.check()
+}
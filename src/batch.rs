@@ -0,0 +1,251 @@
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::Error;
+use crate::audio::{ConvertAudioOptions, convert_audio_command};
+use crate::ffmpeg::{FfmpegCommand, OverwritePolicy, ProcessResult};
+use crate::frames::{FrameOptions, extract_frames_command};
+use crate::pool::JobPool;
+use crate::scan::{name_matches, walk};
+use crate::template::OutputTemplate;
+use crate::transcode::{TranscodeOptions, transcode_command};
+use crate::trim::{TrimOptions, trim_command};
+
+#[derive(Debug, Clone)]
+pub enum BatchOperation {
+    Transcode(TranscodeOptions),
+    Trim(TrimOptions),
+    /// Frames of each input go to a directory named by the output template.
+    Thumbnail(FrameOptions),
+    ConvertAudio(ConvertAudioOptions),
+}
+
+impl BatchOperation {
+    pub fn command(&self, input: &Path, output: &Path) -> Result<FfmpegCommand, Error> {
+        match self {
+            BatchOperation::Transcode(options) => transcode_command(input, output, options),
+            BatchOperation::Trim(options) => trim_command(input, output, options),
+            BatchOperation::Thumbnail(options) => {
+                // `{index}` numbers the frames here.
+                let options = FrameOptions {
+                    pattern: OutputTemplate::new(options.pattern.as_str()).render(input, None)?,
+                    ..options.clone()
+                };
+                extract_frames_command(input, output, &options)
+            }
+            BatchOperation::ConvertAudio(options) => convert_audio_command(input, output, options),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// Glob matched against file names, e.g. `*.MP4`.
+    pub pattern: String,
+    pub recursive: bool,
+    pub output_dir: PathBuf,
+    /// Output name relative to `output_dir`, see [`OutputTemplate`].
+    /// `{index}` counts the inputs from 1.
+    pub template: String,
+    /// How many ffmpeg processes run at once.
+    pub jobs: usize,
+    pub overwrite: OverwritePolicy,
+    /// Leave out inputs whose output is already there. A run that was
+    /// interrupted may have left a partial output behind, which
+    /// [`checkpoint`](Self::checkpoint) doesn't mistake for a finished one.
+    pub skip_existing: bool,
+    /// A file listing the inputs that finished, one absolute path per line,
+    /// so a run from another working directory still finds them. Inputs in it
+    /// are left out, and each one that succeeds is added.
+    pub checkpoint: Option<PathBuf>,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            pattern: "*".to_string(),
+            recursive: false,
+            output_dir: PathBuf::from("."),
+            template: "{name}".to_string(),
+            jobs: 1,
+            overwrite: OverwritePolicy::default(),
+            skip_existing: false,
+            checkpoint: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct BatchItem {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub result: Result<ProcessResult, Error>,
+}
+
+impl BatchItem {
+    pub fn success(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    pub items: Vec<BatchItem>,
+    /// Inputs left out because they were done already.
+    pub skipped: Vec<PathBuf>,
+}
+
+impl BatchReport {
+    pub fn succeeded(&self) -> impl Iterator<Item = &BatchItem> {
+        self.items.iter().filter(|item| item.success())
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &BatchItem> {
+        self.items.iter().filter(|item| !item.success())
+    }
+}
+
+/// The `(input, output)` pairs a batch run would process.
+pub fn batch_plan(dir: &Path, options: &BatchOptions) -> Result<Vec<(PathBuf, PathBuf)>, Error> {
+    Ok(pending(dir, options)?.0)
+}
+
+type Plan = Vec<(PathBuf, PathBuf)>;
+
+/// The plan without the inputs that are done, and those inputs.
+fn pending(dir: &Path, options: &BatchOptions) -> Result<(Plan, Vec<PathBuf>), Error> {
+    let finished = match &options.checkpoint {
+        Some(checkpoint) => read_checkpoint(checkpoint)?,
+        None => HashSet::new(),
+    };
+    let (done, todo): (Vec<_>, Vec<_>) =
+        all_items(dir, options)?
+            .into_iter()
+            .partition(|(input, output)| {
+                std::path::absolute(input).is_ok_and(|input| finished.contains(&input))
+                    || (options.skip_existing && output.exists())
+            });
+    Ok((todo, done.into_iter().map(|(input, _)| input).collect()))
+}
+
+fn read_checkpoint(path: &Path) -> Result<HashSet<PathBuf>, Error> {
+    match std::fs::read(path) {
+        Ok(bytes) => Ok(bytes
+            .split(|&byte| byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(path_from_bytes)
+            .collect()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(HashSet::new()),
+        Err(e) => Err(Error::AccessDenied(e)),
+    }
+}
+
+fn all_items(dir: &Path, options: &BatchOptions) -> Result<Plan, Error> {
+    let absolute = |path: &Path| std::path::absolute(path).map_err(Error::AccessDenied);
+    let output_dir = absolute(&options.output_dir)?;
+    // Never pick up our own outputs when writing below the input folder.
+    let separate_output = output_dir != absolute(dir)?;
+
+    let template = OutputTemplate::new(options.template.as_str());
+    let mut plan = Vec::new();
+    for input in walk(dir, options.recursive)? {
+        if separate_output && absolute(&input)?.starts_with(&output_dir) {
+            continue;
+        }
+        if !name_matches(&input, &options.pattern) {
+            continue;
+        }
+
+        let relative = input
+            .parent()
+            .and_then(|parent| parent.strip_prefix(dir).ok())
+            .unwrap_or(Path::new(""));
+        let output = options
+            .output_dir
+            .join(relative)
+            .join(template.render_path(&input, Some(plan.len() as u64 + 1))?);
+        plan.push((input, output));
+    }
+    Ok(plan)
+}
+
+pub fn batch(
+    dir: &Path,
+    operation: &BatchOperation,
+    options: &BatchOptions,
+) -> Result<BatchReport, Error> {
+    batch_with(dir, operation, options, |_| {})
+}
+
+/// Like [`batch`], calling `on_item` as each file finishes.
+pub fn batch_with<F>(
+    dir: &Path,
+    operation: &BatchOperation,
+    options: &BatchOptions,
+    on_item: F,
+) -> Result<BatchReport, Error>
+where
+    F: Fn(&BatchItem) + Sync,
+{
+    let (plan, skipped) = pending(dir, options)?;
+    let checkpoint = match &options.checkpoint {
+        Some(path) => Some(Mutex::new(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(Error::CreateOutputError)?,
+        )),
+        None => None,
+    };
+
+    let items = JobPool::new(options.jobs).run(plan, |(input, output)| {
+        let mut result = output
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .map_err(Error::CreateOutputError)
+            .and_then(|_| operation.command(&input, &output))
+            .and_then(|command| command.overwrite_policy(options.overwrite).run())
+            .and_then(ProcessResult::check);
+        if result.is_ok()
+            && let Some(checkpoint) = &checkpoint
+            && let Err(e) = record_finished(checkpoint, &input)
+        {
+            result = Err(e);
+        }
+        let item = BatchItem {
+            input,
+            output,
+            result,
+        };
+        on_item(&item);
+        item
+    });
+
+    Ok(BatchReport { items, skipped })
+}
+
+fn record_finished(checkpoint: &Mutex<File>, input: &Path) -> Result<(), Error> {
+    let input = std::path::absolute(input).map_err(Error::AccessDenied)?;
+    let mut file = checkpoint.lock().unwrap_or_else(|e| e.into_inner());
+    let mut line = input.into_os_string().into_encoded_bytes();
+    line.push(b'\n');
+    file.write_all(&line).map_err(Error::WriteFileError)
+}
+
+/// Reads a path [`record_finished`] wrote, as it was on unix, where paths
+/// are bytes, and as UTF-8 elsewhere.
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+    }
+    #[cfg(not(unix))]
+    {
+        PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+    }
+}
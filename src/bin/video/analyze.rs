@@ -0,0 +1,77 @@
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::{AnalyzeOptions, Error, analyze};
+
+use crate::{args, report};
+
+pub fn command() -> Command {
+    Command::new("analyze")
+        .about("Report the bitrate per second, keyframe intervals and frame types of a video.")
+        .args([
+            args::input(),
+            Arg::new("fast")
+                .long("fast")
+                .action(ArgAction::SetTrue)
+                .help("Read the packets only, without decoding, which leaves out the frame types"),
+            Arg::new("csv")
+                .long("csv")
+                .help(
+                    "Also write the bitrate per second to this file, for gnuplot or a spreadsheet",
+                )
+                .value_parser(value_parser!(PathBuf)),
+        ])
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let options = AnalyzeOptions {
+        frame_types: !args.get_flag("fast"),
+    };
+
+    let analysis = analyze(input, &options)?;
+    if let Some(csv) = args.get_one::<PathBuf>("csv") {
+        std::fs::write(csv, analysis.to_csv()).map_err(Error::WriteFileError)?;
+    }
+    if report::json() {
+        report::field("analysis", analysis.to_json());
+        return Ok(());
+    }
+
+    let kbps = |bits: Option<u64>| bits.unwrap_or_default() as f64 / 1000.0;
+    println!(
+        "Bitrate: {:.1} kb/s average, {:.1} kb/s peak over {}s",
+        kbps(analysis.average_bitrate()),
+        kbps(analysis.peak_bitrate()),
+        analysis.bits_per_second.len()
+    );
+
+    let intervals = analysis.keyframe_intervals();
+    match (intervals.iter().min(), intervals.iter().max()) {
+        (Some(min), Some(max)) => println!(
+            "Keyframes: {}, every {:.2}s on average ({:.2}s to {:.2}s)",
+            analysis.keyframes.len(),
+            (intervals.iter().sum::<Duration>() / intervals.len() as u32).as_secs_f64(),
+            min.as_secs_f64(),
+            max.as_secs_f64()
+        ),
+        _ => println!("Keyframes: {}", analysis.keyframes.len()),
+    }
+
+    if let Some(types) = analysis.frame_types {
+        let share = |n: u64| n as f64 * 100.0 / types.total().max(1) as f64;
+        println!(
+            "Frames: {} I ({:.1}%), {} P ({:.1}%), {} B ({:.1}%)",
+            types.i,
+            share(types.i),
+            types.p,
+            share(types.p),
+            types.b,
+            share(types.b)
+        );
+        if types.other > 0 {
+            println!("        {} of other types", types.other);
+        }
+    }
+    Ok(())
+}
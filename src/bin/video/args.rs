@@ -0,0 +1,105 @@
+use clap::{Arg, value_parser};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use video::{Error, OutputTemplate, Rendition};
+
+pub fn input() -> Arg {
+    Arg::new("input")
+        .short('i')
+        .long("input")
+        .required(true)
+        .help("The input file path")
+        .value_parser(value_parser!(PathBuf))
+}
+
+pub fn output() -> Arg {
+    Arg::new("output")
+        .short('o')
+        .long("output")
+        .required(true)
+        .help("The output file path")
+        .value_parser(value_parser!(PathBuf))
+}
+
+pub fn timestamp(value: &str) -> Result<Duration, String> {
+    video::time::parse_timestamp(value).map_err(|_| format!("invalid timestamp `{value}`"))
+}
+
+/// A length such as `2s`, `500ms` or `1.5`, in seconds without a unit.
+pub fn length(value: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid length `{value}`, expected e.g. 2s or 500ms");
+    let (number, scale) = if let Some(ms) = value.strip_suffix("ms") {
+        (ms, 0.001)
+    } else {
+        (value.strip_suffix('s').unwrap_or(value), 1.0)
+    };
+    number
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite() && *n >= 0.0)
+        .and_then(|n| Duration::try_from_secs_f64(n * scale).ok())
+        .ok_or_else(invalid)
+}
+
+/// A signed offset such as `250ms`, `-1.5s` or `-40`, in milliseconds.
+pub fn offset_ms(value: &str) -> Result<i64, String> {
+    let invalid = || format!("invalid offset `{value}`, expected e.g. 250ms or -1.5s");
+    let (number, scale) = if let Some(ms) = value.strip_suffix("ms") {
+        (ms, 1.0)
+    } else if let Some(s) = value.strip_suffix('s') {
+        (s, 1000.0)
+    } else {
+        (value, 1.0)
+    };
+    number
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite())
+        .map(|n| (n * scale).round() as i64)
+        .ok_or_else(invalid)
+}
+
+pub fn size(value: &str) -> Result<(u32, u32), String> {
+    value
+        .split_once(['x', 'X'])
+        .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+        .ok_or_else(|| format!("invalid size `{value}`, expected WIDTHxHEIGHT"))
+}
+
+/// A `HEIGHT:BITRATE` rendition such as `720:2800k`.
+pub fn rendition(value: &str) -> Result<Rendition, String> {
+    value
+        .split_once(':')
+        .and_then(|(height, bitrate)| {
+            let height = height.trim_end_matches('p').parse().ok()?;
+            (!bitrate.is_empty()).then(|| Rendition::new(height, bitrate))
+        })
+        .ok_or_else(|| format!("invalid rendition `{value}`, expected HEIGHT:BITRATE"))
+}
+
+pub fn position() -> Arg {
+    Arg::new("position")
+        .long("position")
+        .default_value("bottom-right")
+        .help("Where to place the overlay")
+        .value_parser([
+            "top-left",
+            "top-right",
+            "bottom-left",
+            "bottom-right",
+            "center",
+        ])
+}
+
+/// Fills in the [`OutputTemplate`] placeholders of an output given on the
+/// command line. `{index}` becomes the counter ffmpeg numbers files with.
+pub fn render_output(output: &Path, input: &Path) -> Result<PathBuf, Error> {
+    match output.to_str() {
+        Some(template) if template.contains('{') => {
+            OutputTemplate::new(template).render_path(input, None)
+        }
+        _ => Ok(output.to_path_buf()),
+    }
+}
@@ -0,0 +1,189 @@
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::{
+    AudioFormat, BatchOperation, BatchOptions, ConcatAudioOptions, ConvertAudioOptions, Error,
+    audio_stats, audio_stats_command, concat_audio_command, convert_audio_command,
+};
+
+use crate::{args, batch, report, settings};
+
+pub fn command() -> Command {
+    Command::new("audio")
+        .about("Work on audio files.")
+        .subcommand_required(true)
+        .subcommands([convert_command(), concat_command(), stats_command()])
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    match args.subcommand() {
+        Some(("convert", sub)) => convert(sub),
+        Some(("concat", sub)) => concat(sub),
+        Some(("stats", sub)) => stats(sub),
+        _ => Err(Error::InvalidArgument("unknown audio command".to_string())),
+    }
+}
+
+/// The output format and encoder settings of convert and concat.
+fn encoder_args() -> Vec<Arg> {
+    vec![
+        Arg::new("format")
+            .long("format")
+            .help("The audio format, defaults to the output extension")
+            .value_parser(["mp3", "aac", "opus", "flac", "wav"]),
+        Arg::new("bitrate")
+            .long("bitrate")
+            .help("The audio bitrate, e.g. 192k")
+            .value_parser(value_parser!(String)),
+        Arg::new("quality")
+            .long("quality")
+            .conflicts_with("bitrate")
+            .help("A variable quality: 0 (best) to 9 for mp3, 0.1 to 2 for aac, the compression level 0 to 12 for flac")
+            .value_parser(value_parser!(f64)),
+        Arg::new("sample-rate")
+            .long("sample-rate")
+            .help("The sample rate in Hz, e.g. 44100")
+            .value_parser(value_parser!(u32).range(1..)),
+        Arg::new("channels")
+            .long("channels")
+            .help("The number of channels, 1 for mono or 2 for stereo")
+            .value_parser(value_parser!(u32).range(1..)),
+    ]
+}
+
+fn convert_options(args: &ArgMatches) -> Result<ConvertAudioOptions, Error> {
+    Ok(ConvertAudioOptions {
+        format: args
+            .get_one::<String>("format")
+            .map(|f| f.parse::<AudioFormat>())
+            .transpose()?,
+        bitrate: args.get_one::<String>("bitrate").cloned(),
+        quality: args.get_one::<f64>("quality").copied(),
+        sample_rate: args.get_one::<u32>("sample-rate").copied(),
+        channels: args.get_one::<u32>("channels").copied(),
+    })
+}
+
+fn convert_command() -> Command {
+    Command::new("convert")
+        .about("Convert between mp3, aac, opus, flac and wav, one file or a whole folder.")
+        .args([
+            args::input().required(false),
+            Arg::new("folder")
+                .short('f')
+                .long("folder")
+                .help("Convert every matching file in this folder")
+                .value_parser(value_parser!(PathBuf)),
+            args::output().help("The output file, or the folder the outputs go to with --folder"),
+            Arg::new("pattern")
+                .long("pattern")
+                .requires("folder")
+                .default_value("*")
+                .help("The glob the file names in the folder must match, e.g. \"*.wav\"")
+                .value_parser(value_parser!(String)),
+            Arg::new("recursive")
+                .short('r')
+                .long("recursive")
+                .requires("folder")
+                .action(ArgAction::SetTrue)
+                .help("Descend into subfolders"),
+            Arg::new("jobs")
+                .short('j')
+                .long("jobs")
+                .requires("folder")
+                .default_value("1")
+                .help("How many files are converted at once")
+                .value_parser(value_parser!(usize)),
+        ])
+        .args(encoder_args())
+        .group(
+            ArgGroup::new("source")
+                .args(["input", "folder"])
+                .required(true),
+        )
+}
+
+fn convert(args: &ArgMatches) -> Result<(), Error> {
+    let output = args.get_one::<PathBuf>("output").unwrap();
+    let options = convert_options(args)?;
+
+    let Some(folder) = args.get_one::<PathBuf>("folder") else {
+        let input = args.get_one::<PathBuf>("input").unwrap();
+        return report::execute(&convert_audio_command(input, output, &options)?);
+    };
+
+    let format = options.format.ok_or_else(|| {
+        Error::InvalidArgument("--format is needed to convert a folder".to_string())
+    })?;
+    let batch_options = BatchOptions {
+        pattern: args.get_one::<String>("pattern").unwrap().clone(),
+        recursive: args.get_flag("recursive"),
+        output_dir: output.clone(),
+        template: format!("{{stem}}.{}", format.extension()),
+        jobs: *args.get_one::<usize>("jobs").unwrap(),
+        overwrite: settings::get().overwrite,
+        ..Default::default()
+    };
+    batch::execute(
+        folder,
+        &BatchOperation::ConvertAudio(options),
+        &batch_options,
+    )
+}
+
+fn concat_command() -> Command {
+    Command::new("concat")
+        .about("Join audio files into one, optionally crossfading from each into the next.")
+        .args([
+            Arg::new("files")
+                .long("files")
+                .num_args(1..)
+                .required(true)
+                .help("The audio files, in the order they are joined in")
+                .value_parser(value_parser!(PathBuf)),
+            args::output(),
+            Arg::new("crossfade")
+                .long("crossfade")
+                .help("Blend each track into the next over this long, e.g. 2s")
+                .value_parser(args::length),
+        ])
+        .args(encoder_args())
+}
+
+fn concat(args: &ArgMatches) -> Result<(), Error> {
+    let files: Vec<&PathBuf> = args.get_many::<PathBuf>("files").unwrap().collect();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+    let options = ConcatAudioOptions {
+        crossfade: args.get_one::<Duration>("crossfade").copied(),
+        convert: convert_options(args)?,
+    };
+    report::execute(&concat_audio_command(&files, output, &options)?)
+}
+
+fn stats_command() -> Command {
+    Command::new("stats")
+        .about("Measure the loudness, true peak, RMS level and clipping of an audio track.")
+        .arg(args::input())
+}
+
+fn stats(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    if settings::get().dry_run {
+        return report::execute(&audio_stats_command(input)?);
+    }
+
+    let stats = audio_stats(input)?;
+    if report::json() {
+        report::field("stats", stats.to_json());
+        return Ok(());
+    }
+    println!("Integrated loudness: {:.1} LUFS", stats.integrated);
+    println!("Loudness range:      {:.1} LU", stats.loudness_range);
+    println!("True peak:           {:.1} dBTP", stats.true_peak);
+    println!("Sample peak:         {:.1} dBFS", stats.peak);
+    println!("RMS level:           {:.1} dBFS", stats.rms);
+    if let Some(clipped) = stats.clipped_samples {
+        println!("Clipped samples:     {clipped}");
+    }
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use std::path::{Path, PathBuf};
+use video::json::Json;
+use video::{BatchItem, BatchOperation, BatchOptions, Error, batch, batch_plan, batch_with};
+
+use crate::{config, report, settings, thumbnail, transcode, trim};
+
+pub fn command() -> Command {
+    Command::new("batch")
+        .about("Apply an operation to every matching file in a folder.")
+        .subcommand_required(true)
+        .args([
+            Arg::new("folder")
+                .short('f')
+                .long("folder")
+                .required(true)
+                .help("The folder contains the input files.")
+                .value_parser(value_parser!(PathBuf)),
+            config::default(
+                Arg::new("output")
+                    .short('o')
+                    .long("output")
+                    .required(config::get().output_dir.is_none())
+                    .help("The folder the outputs are written to")
+                    .value_parser(value_parser!(PathBuf)),
+                &config::get().output_dir,
+            ),
+            Arg::new("pattern")
+                .long("pattern")
+                .default_value("*")
+                .help("The glob the file names must match, e.g. \"*.MP4\"")
+                .value_parser(value_parser!(String)),
+            Arg::new("recursive")
+                .short('r')
+                .long("recursive")
+                .action(ArgAction::SetTrue)
+                .help("Descend into subfolders"),
+            Arg::new("template")
+                .long("template")
+                .help("The output name, with {stem}, {ext}, {name}, {index}, {date} and {resolution} of the input")
+                .value_parser(value_parser!(String)),
+            Arg::new("skip-existing")
+                .long("skip-existing")
+                .action(ArgAction::SetTrue)
+                .help("Leave out inputs whose output already exists"),
+            Arg::new("checkpoint")
+                .long("checkpoint")
+                .help("Record finished inputs in this file and leave them out when run again")
+                .value_parser(value_parser!(PathBuf)),
+            config::default(
+                Arg::new("jobs")
+                    .short('j')
+                    .long("jobs")
+                    .default_value("1")
+                    .help("How many files are processed at once")
+                    .value_parser(value_parser!(usize)),
+                &config::get().jobs,
+            ),
+        ])
+        .subcommands(operations())
+}
+
+/// The operations that can run over many files, shared with `watch`.
+pub fn operations() -> [Command; 3] {
+    [
+        Command::new("transcode")
+            .about("Re-encode every file.")
+            .args(transcode::option_args())
+            .args(transcode::stream_args()),
+        Command::new("trim")
+            .about("Cut the same section out of every file.")
+            .args(trim::option_args()),
+        Command::new("thumbnail")
+            .about("Grab frames of every file into a folder per input.")
+            .args(thumbnail::option_args())
+            .group(thumbnail::selection_group()),
+    ]
+}
+
+/// The chosen operation and the output template it defaults to.
+pub fn operation(args: &ArgMatches) -> Result<(BatchOperation, &'static str), Error> {
+    match args.subcommand() {
+        Some(("transcode", sub)) => Ok((
+            BatchOperation::Transcode(transcode::options(sub)?),
+            "{name}",
+        )),
+        Some(("trim", sub)) => Ok((BatchOperation::Trim(trim::options(sub)), "{name}")),
+        Some(("thumbnail", sub)) => Ok((
+            BatchOperation::Thumbnail(thumbnail::options(sub)?),
+            "{stem}",
+        )),
+        _ => Err(Error::InvalidArgument("unknown operation".to_string())),
+    }
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let folder = args.get_one::<PathBuf>("folder").unwrap();
+
+    let (operation, default_template) = operation(args)?;
+
+    let options = BatchOptions {
+        pattern: args.get_one::<String>("pattern").unwrap().clone(),
+        recursive: args.get_flag("recursive"),
+        output_dir: args.get_one::<PathBuf>("output").unwrap().clone(),
+        template: args
+            .get_one::<String>("template")
+            .cloned()
+            .unwrap_or_else(|| default_template.to_string()),
+        jobs: *args.get_one::<usize>("jobs").unwrap(),
+        overwrite: settings::get().overwrite,
+        skip_existing: args.get_flag("skip-existing"),
+        checkpoint: args.get_one::<PathBuf>("checkpoint").cloned(),
+    };
+
+    execute(folder, &operation, &options)
+}
+
+/// Runs `operation` over the folder, printing each file as it finishes.
+pub fn execute(
+    folder: &Path,
+    operation: &BatchOperation,
+    options: &BatchOptions,
+) -> Result<(), Error> {
+    if settings::get().dry_run {
+        for (input, output) in batch_plan(folder, options)? {
+            report::execute(&operation.command(&input, &output)?)?;
+        }
+        return Ok(());
+    }
+
+    if report::json() {
+        let result = batch(folder, operation, options)?;
+        let items: Vec<Json> = result.items.iter().map(item_json).collect();
+        report::field("items", items);
+        report::field("skipped", result.skipped);
+        return Ok(());
+    }
+
+    let report = batch_with(folder, operation, options, print_item)?;
+    let failed = report.failed().count();
+    if report.skipped.is_empty() {
+        println!("{} succeeded, {failed} failed", report.items.len() - failed);
+    } else {
+        println!(
+            "{} succeeded, {failed} failed, {} skipped",
+            report.items.len() - failed,
+            report.skipped.len()
+        );
+    }
+    Ok(())
+}
+
+pub fn item_json(item: &BatchItem) -> Json {
+    Json::object([
+        ("input", Json::from(item.input.as_path())),
+        ("output", item.output.as_path().into()),
+        ("success", item.success().into()),
+        (
+            "error",
+            item.result.as_ref().err().map(|e| e.to_string()).into(),
+        ),
+    ])
+}
+
+pub fn print_item(item: &BatchItem) {
+    match &item.result {
+        Ok(_) => println!(
+            "ok     {} -> {}",
+            item.input.display(),
+            item.output.display()
+        ),
+        Err(e) => println!("failed {}: {e}", item.input.display()),
+    }
+}
@@ -0,0 +1,103 @@
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::json::Json;
+use video::{CameraBackend, CaptureOptions, DeviceKind, Error, capture_command, list_devices};
+
+use crate::{args, report, settings, transcode};
+
+pub fn command() -> Command {
+    Command::new("capture")
+        .about("Record a camera until Ctrl-C is pressed.")
+        .args([
+            args::output()
+                .required(false)
+                .required_unless_present("list-devices"),
+            Arg::new("list-devices")
+                .long("list-devices")
+                .action(ArgAction::SetTrue)
+                .help("Print the cameras and microphones that can be recorded"),
+            Arg::new("device")
+                .long("device")
+                .help("The camera, e.g. /dev/video1, 1 or \"Integrated Camera\"")
+                .value_parser(value_parser!(String)),
+            Arg::new("size")
+                .long("size")
+                .help("The resolution to ask the camera for, as WxH")
+                .value_parser(args::size),
+            Arg::new("fps")
+                .long("fps")
+                .help("The frame rate to ask the camera for")
+                .value_parser(value_parser!(f64)),
+            Arg::new("mic")
+                .long("mic")
+                .help("Record this microphone too")
+                .value_parser(value_parser!(String)),
+            Arg::new("duration")
+                .long("duration")
+                .help("Stop after this long")
+                .value_parser(args::timestamp),
+        ])
+        .args(transcode::option_args())
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    if args.get_flag("list-devices") {
+        return print_devices();
+    }
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let options = CaptureOptions {
+        device: args.get_one::<String>("device").cloned(),
+        size: args.get_one::<(u32, u32)>("size").copied(),
+        fps: args.get_one::<f64>("fps").copied(),
+        microphone: args.get_one::<String>("mic").cloned(),
+        duration: args.get_one::<Duration>("duration").copied(),
+        transcode: transcode::options(args)?,
+        ..CaptureOptions::default()
+    };
+
+    let command = capture_command(output, &options)?;
+    if options.duration.is_none() && !settings::get().dry_run {
+        eprintln!("Recording to {}, press Ctrl-C to stop", output.display());
+    }
+    report::execute_until_interrupted(&command, Some(output))
+}
+
+fn print_devices() -> Result<(), Error> {
+    let devices = list_devices(CameraBackend::current())?;
+
+    if report::json() {
+        let devices = devices
+            .iter()
+            .map(|device| {
+                Json::object([
+                    ("kind", Json::from(kind(device.kind))),
+                    ("id", Json::from(device.id.as_str())),
+                    ("name", Json::from(device.name.as_str())),
+                ])
+            })
+            .collect::<Vec<_>>();
+        report::field("devices", devices);
+        return Ok(());
+    }
+
+    if devices.is_empty() {
+        println!("No devices found");
+    }
+    for device in &devices {
+        if device.id == device.name {
+            println!("{}: {}", kind(device.kind), device.id);
+        } else {
+            println!("{}: {} ({})", kind(device.kind), device.id, device.name);
+        }
+    }
+    Ok(())
+}
+
+fn kind(kind: DeviceKind) -> &'static str {
+    match kind {
+        DeviceKind::Video => "video",
+        DeviceKind::Audio => "audio",
+    }
+}
@@ -0,0 +1,86 @@
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::path::Path;
+use video::json::Json;
+use video::{EncoderKind, Error, HwAccel, Tool};
+
+use crate::report;
+
+pub fn command() -> Command {
+    Command::new("check")
+        .about("Verify that ffmpeg and ffprobe can be found and list the available encoders.")
+        .arg(
+            Arg::new("no-encoders")
+                .long("no-encoders")
+                .action(ArgAction::SetTrue)
+                .help("Only report the binaries and their versions"),
+        )
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let tool = Tool::current();
+    binary("ffmpeg", tool.ffmpeg())?;
+    binary("ffprobe", tool.ffprobe())?;
+
+    if args.get_flag("no-encoders") {
+        return Ok(());
+    }
+
+    let accels = HwAccel::detect(&tool)?;
+    let names: Vec<_> = accels.iter().map(|a| a.name()).collect();
+    let encoders = tool.encoders()?;
+    let kinds = [
+        (EncoderKind::Video, "video", "Video encoders"),
+        (EncoderKind::Audio, "audio", "Audio encoders"),
+        (EncoderKind::Subtitle, "subtitle", "Subtitle encoders"),
+    ];
+
+    if report::json() {
+        report::field("hardware_encoders", names);
+        let encoders: Vec<Json> = encoders
+            .iter()
+            .map(|encoder| {
+                let kind = kinds.iter().find(|(k, ..)| *k == encoder.kind).unwrap().1;
+                Json::object([
+                    ("kind", kind),
+                    ("name", &encoder.name),
+                    ("description", &encoder.description),
+                ])
+            })
+            .collect();
+        report::field("encoders", encoders);
+        return Ok(());
+    }
+
+    println!();
+    if names.is_empty() {
+        println!("Hardware encoders: none");
+    } else {
+        println!("Hardware encoders: {}", names.join(", "));
+    }
+    for (kind, _, title) in kinds {
+        println!();
+        println!("{title}:");
+        for encoder in encoders.iter().filter(|e| e.kind == kind) {
+            println!("  {:<24} {}", encoder.name, encoder.description);
+        }
+    }
+
+    Ok(())
+}
+
+fn binary(name: &str, binary: &Path) -> Result<(), Error> {
+    let Some(path) = Tool::resolve(binary) else {
+        return Err(Error::FileNotFound(binary.to_path_buf()));
+    };
+    let version = Tool::version(&path)?;
+    if report::json() {
+        report::field(
+            name,
+            Json::object([("path", Json::from(path)), ("version", version.into())]),
+        );
+    } else {
+        println!("{name:<8} {}", path.display());
+        println!("         {version}");
+    }
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use video::{Error, QualityOptions, quality_metrics, quality_metrics_command};
+
+use crate::{args, report, settings};
+
+pub fn command() -> Command {
+    Command::new("compare")
+        .about("Measure how closely an encode matches its source with PSNR, SSIM and VMAF.")
+        .args([
+            Arg::new("reference")
+                .short('r')
+                .long("reference")
+                .required(true)
+                .help("The original video")
+                .value_parser(value_parser!(PathBuf)),
+            args::input().help("The encoded video"),
+            Arg::new("no-psnr")
+                .long("no-psnr")
+                .action(ArgAction::SetTrue)
+                .help("Skip PSNR"),
+            Arg::new("no-ssim")
+                .long("no-ssim")
+                .action(ArgAction::SetTrue)
+                .help("Skip SSIM"),
+            Arg::new("no-vmaf")
+                .long("no-vmaf")
+                .action(ArgAction::SetTrue)
+                .help("Skip VMAF, which ffmpeg only has when built with libvmaf"),
+        ])
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let reference = args.get_one::<PathBuf>("reference").unwrap();
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let options = QualityOptions {
+        psnr: !args.get_flag("no-psnr"),
+        ssim: !args.get_flag("no-ssim"),
+        vmaf: !args.get_flag("no-vmaf"),
+    };
+
+    if settings::get().dry_run {
+        return report::execute(&quality_metrics_command(reference, input, &options)?);
+    }
+
+    let metrics = quality_metrics(reference, input, &options)?;
+    if report::json() {
+        report::field("metrics", metrics.to_json());
+        return Ok(());
+    }
+    if let Some(psnr) = metrics.psnr {
+        println!("PSNR: {psnr:.2} dB");
+    }
+    if let Some(ssim) = metrics.ssim {
+        println!("SSIM: {ssim:.4}");
+    }
+    match metrics.vmaf {
+        Some(vmaf) => println!("VMAF: {vmaf:.2}"),
+        None if options.vmaf => println!("VMAF: unavailable, ffmpeg lacks libvmaf"),
+        None => {}
+    }
+    Ok(())
+}
@@ -0,0 +1,206 @@
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use video::Error;
+
+pub fn command() -> Command {
+    Command::new("completions")
+        .about("Print a script that completes subcommands and flags in your shell.")
+        .long_about(
+            "Print a script that completes subcommands and flags in your shell, e.g.\n  \
+             bash: source <(video completions bash)\n  \
+             zsh: video completions zsh > ~/.zfunc/_video\n  \
+             fish: video completions fish > ~/.config/fish/completions/video.fish\n  \
+             PowerShell: video completions powershell | Out-String | Invoke-Expression",
+        )
+        .args([
+            Arg::new("shell")
+                .required_unless_present("complete")
+                .value_parser(["bash", "zsh", "fish", "powershell"]),
+            // What the scripts call back with the command line typed so far.
+            Arg::new("complete")
+                .long("complete")
+                .hide(true)
+                .action(ArgAction::SetTrue),
+            Arg::new("words")
+                .hide(true)
+                .num_args(0..)
+                .last(true)
+                .value_parser(value_parser!(String)),
+        ])
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    if args.get_flag("complete") {
+        let words: Vec<&str> = args
+            .get_many::<String>("words")
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        for candidate in candidates(&words) {
+            println!("{candidate}");
+        }
+        return Ok(());
+    }
+
+    let script = match args.get_one::<String>("shell").map(String::as_str) {
+        Some("bash") => BASH,
+        Some("zsh") => ZSH,
+        Some("fish") => FISH,
+        Some("powershell") => POWERSHELL,
+        _ => return Err(Error::InvalidArgument("unknown shell".to_string())),
+    };
+    print!("{script}");
+    Ok(())
+}
+
+/// What may come next on a command line: `words` starts with the program and
+/// ends with the word being completed, empty when there is none yet. Nothing
+/// is offered where a file goes, the shells complete those themselves.
+fn candidates(words: &[&str]) -> Vec<String> {
+    let Some((current, typed)) = words.split_last() else {
+        return Vec::new();
+    };
+    let mut command = crate::command_builder();
+    // Hands the global flags down to every subcommand.
+    command.build();
+
+    let mut command = &command;
+    let mut value_of: Option<&Arg> = None;
+    let mut positional = false;
+    for word in typed.iter().skip(1) {
+        if value_of.take().is_some() {
+            continue;
+        }
+        if positional {
+            continue;
+        }
+        if *word == "--" {
+            positional = true;
+        } else if word.starts_with('-') {
+            value_of = find_flag(command, word).filter(|arg| takes_separate_value(arg));
+        } else if let Some(sub) = command.find_subcommand(word) {
+            command = sub;
+        }
+    }
+
+    let matching = |candidates: Vec<String>| -> Vec<String> {
+        candidates
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(current))
+            .collect()
+    };
+    if let Some(arg) = value_of {
+        return matching(possible_values(arg));
+    }
+    if positional {
+        return Vec::new();
+    }
+    if current.starts_with('-') {
+        let mut flags = Vec::new();
+        for arg in command.get_arguments().filter(|arg| !arg.is_hide_set()) {
+            if let Some(long) = arg.get_long() {
+                flags.push(format!("--{long}"));
+            }
+            if let Some(short) = arg.get_short() {
+                flags.push(format!("-{short}"));
+            }
+        }
+        return matching(flags);
+    }
+
+    let mut next: Vec<String> = command
+        .get_subcommands()
+        .filter(|sub| !sub.is_hide_set())
+        .map(|sub| sub.get_name().to_string())
+        .collect();
+    for arg in command.get_positionals().filter(|arg| !arg.is_hide_set()) {
+        next.extend(possible_values(arg));
+    }
+    matching(next)
+}
+
+fn find_flag<'a>(command: &'a Command, word: &str) -> Option<&'a Arg> {
+    if let Some(long) = word.strip_prefix("--") {
+        // `--flag=value` carries its value along.
+        if long.contains('=') {
+            return None;
+        }
+        return command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(long));
+    }
+    // Only a lone `-x`, `-xVALUE` carries its value.
+    let mut chars = word.strip_prefix('-')?.chars();
+    let short = chars.next()?;
+    if chars.next().is_some() {
+        return None;
+    }
+    command
+        .get_arguments()
+        .find(|arg| arg.get_short() == Some(short))
+}
+
+/// Whether the word after the flag is its value.
+fn takes_separate_value(arg: &Arg) -> bool {
+    arg.get_action().takes_values() && !arg.is_require_equals_set()
+}
+
+fn possible_values(arg: &Arg) -> Vec<String> {
+    arg.get_possible_values()
+        .into_iter()
+        .filter(|value| !value.is_hide_set())
+        .map(|value| value.get_name().to_string())
+        .collect()
+}
+
+const BASH: &str = r#"_video() {
+    local IFS=$'\n'
+    COMPREPLY=($(video completions --complete -- "${COMP_WORDS[@]:0:COMP_CWORD}" "${COMP_WORDS[COMP_CWORD]}" 2>/dev/null))
+}
+complete -F _video -o bashdefault -o default video
+"#;
+
+const ZSH: &str = r#"#compdef video
+
+_video() {
+    local -a candidates
+    candidates=("${(@f)$(video completions --complete -- "${(@)words[1,CURRENT-1]}" "${words[CURRENT]}" 2>/dev/null)}")
+    if [[ -n "${candidates[1]}" ]]; then
+        compadd -a candidates
+    else
+        _files
+    fi
+}
+
+if [[ "${funcstack[1]}" == "_video" ]]; then
+    _video "$@"
+else
+    compdef _video video
+fi
+"#;
+
+const FISH: &str = r#"function __video_complete
+    set -l candidates (video completions --complete -- (commandline -opc) (commandline -ct) 2>/dev/null)
+    if test (count $candidates) -gt 0
+        printf '%s\n' $candidates
+    else
+        __fish_complete_path (commandline -ct)
+    end
+end
+
+complete -c video -f -a '(__video_complete)'
+"#;
+
+const POWERSHELL: &str = r#"Register-ArgumentCompleter -Native -CommandName video -ScriptBlock {
+    param($wordToComplete, $commandAst, $cursorPosition)
+    $words = @($commandAst.CommandElements |
+        Where-Object { $_.Extent.EndOffset -le $cursorPosition } |
+        ForEach-Object { $_.ToString() })
+    if ($wordToComplete -eq '') {
+        $words += ''
+    }
+    video completions --complete -- @words 2>$null | ForEach-Object {
+        [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+    }
+}
+"#;
@@ -0,0 +1,325 @@
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Command, value_parser};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use video::temp::TempFile;
+use video::{
+    ConcatMode, ConcatOptions, DuplicateCheck, Error, SortOrder, SpaceCheck, Transition,
+    TransitionKind, concat_files_command, concat_inputs, remux_command, transcode_command,
+};
+
+use crate::{args, report, settings, transcode};
+
+pub fn command() -> Command {
+    Command::new("concat")
+        .about("Concat multiple video files into one.")
+        .args([
+            Arg::new("folder")
+                .short('f')
+                .long("folder")
+                .help("The folder contains the video files.")
+                .value_parser(value_parser!(PathBuf)),
+            Arg::new("files")
+                .long("files")
+                .num_args(1..)
+                .help("The video files, in the order they are joined in")
+                .value_parser(value_parser!(PathBuf)),
+            Arg::new("from-list")
+                .long("from-list")
+                .help("A text file naming one video file per line, relative to the list")
+                .value_parser(value_parser!(PathBuf)),
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .required(true)
+                .help("The output file path")
+                .value_parser(value_parser!(PathBuf)),
+            Arg::new("ext")
+                .long("ext")
+                .required_unless_present_any(["pattern", "files", "from-list"])
+                .conflicts_with_all(["files", "from-list"])
+                .help("The video files' extension")
+                .value_parser(value_parser!(String)),
+            Arg::new("prefix")
+                .short('p')
+                .long("prefix")
+                .required_unless_present_any(["pattern", "files", "from-list"])
+                .conflicts_with_all(["files", "from-list"])
+                .help("The video files' prefix")
+                .value_parser(value_parser!(String)),
+            Arg::new("pattern")
+                .long("pattern")
+                .conflicts_with_all(["files", "from-list"])
+                .help("The glob the file names must match, e.g. \"cam1_*.MP4\"")
+                .value_parser(value_parser!(String)),
+            Arg::new("recursive")
+                .short('r')
+                .long("recursive")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["files", "from-list"])
+                .help("Descend into subfolders"),
+            Arg::new("sort")
+                .long("sort")
+                .default_value("lexical")
+                .help("The order the files in the folder are joined in")
+                .value_parser(["lexical", "natural", "mtime", "ctime"]),
+            Arg::new("review")
+                .long("review")
+                .action(ArgAction::SetTrue)
+                .help("Open the order the clips are joined in with $EDITOR, to fix it before joining"),
+            Arg::new("order-file")
+                .long("order-file")
+                .help("Write the order to this file and stop, to edit and pass to --from-list; with --review, the file to edit")
+                .value_parser(value_parser!(PathBuf)),
+            Arg::new("reencode")
+                .long("reencode")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("auto")
+                .help("Re-encode the inputs so clips with different formats can be joined"),
+            Arg::new("auto")
+                .long("auto")
+                .action(ArgAction::SetTrue)
+                .help("Re-encode only when the inputs don't match"),
+            Arg::new("chapters")
+                .long("chapters")
+                .action(ArgAction::SetTrue)
+                .help("Add a chapter at the start of every clip, named after its file"),
+            Arg::new("transition")
+                .long("transition")
+                .help("Blend each clip into the next instead of cutting, which re-encodes")
+                .value_parser([
+                    "fade",
+                    "fadeblack",
+                    "dissolve",
+                    "wipe",
+                    "wipeleft",
+                    "wiperight",
+                    "wipeup",
+                    "wipedown",
+                    "slideleft",
+                    "slideright",
+                    "circleopen",
+                ]),
+            Arg::new("transition-duration")
+                .long("transition-duration")
+                .default_value("1s")
+                .requires("transition")
+                .help("How long each transition takes")
+                .value_parser(args::length),
+            Arg::new("space-check")
+                .long("space-check")
+                .default_value("refuse")
+                .help("What to do when the output may not fit on its disk")
+                .value_parser(["refuse", "warn", "off"]),
+            Arg::new("duplicates")
+                .long("duplicates")
+                .default_value("off")
+                .conflicts_with_all(["files", "from-list"])
+                .help("Warn about or skip files in the folder with the same content as one before them")
+                .value_parser(["off", "warn", "skip"]),
+            Arg::new("size")
+                .long("size")
+                .help("The frame size when re-encoding, e.g. 1280x720")
+                .value_parser(args::size),
+            Arg::new("fps")
+                .long("fps")
+                .help("The frame rate when re-encoding")
+                .value_parser(value_parser!(f64)),
+        ])
+        .args(transcode::option_args())
+        .args(transcode::stream_args())
+        .group(
+            ArgGroup::new("inputs")
+                .args(["folder", "files", "from-list"])
+                .required(true),
+        )
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let mode = if args.get_flag("reencode") {
+        ConcatMode::Reencode
+    } else if args.get_flag("auto") {
+        ConcatMode::Auto
+    } else {
+        ConcatMode::Copy
+    };
+    let transition = match args.get_one::<String>("transition") {
+        Some(kind) => Some(Transition {
+            kind: kind.parse::<TransitionKind>()?,
+            duration: *args.get_one::<Duration>("transition-duration").unwrap(),
+        }),
+        None => None,
+    };
+    let options = ConcatOptions {
+        sort: args
+            .get_one::<String>("sort")
+            .unwrap()
+            .parse::<SortOrder>()?,
+        pattern: args.get_one::<String>("pattern").cloned(),
+        recursive: args.get_flag("recursive"),
+        mode,
+        size: args.get_one::<(u32, u32)>("size").copied(),
+        fps: args.get_one::<f64>("fps").copied(),
+        transcode: transcode::options(args)?,
+        chapters: args.get_flag("chapters"),
+        transition,
+        space_check: args
+            .get_one::<String>("space-check")
+            .unwrap()
+            .parse::<SpaceCheck>()?,
+        duplicates: args
+            .get_one::<String>("duplicates")
+            .unwrap()
+            .parse::<DuplicateCheck>()?,
+    };
+
+    let files = if let Some(files) = args.get_many::<PathBuf>("files") {
+        files.cloned().collect()
+    } else if let Some(list) = args.get_one::<PathBuf>("from-list") {
+        read_list(list)?
+    } else {
+        // A pattern alone selects the files, without an extension or prefix.
+        let ext = args.get_one::<String>("ext").map_or("", String::as_str);
+        let prefix = args.get_one::<String>("prefix").map_or("", String::as_str);
+        let folder = args.get_one::<PathBuf>("folder").unwrap();
+        concat_inputs(folder, prefix, ext, &options)?
+    };
+
+    let order_file = args.get_one::<PathBuf>("order-file");
+    let files = if args.get_flag("review") {
+        review(&files, order_file.map(PathBuf::as_path))?
+    } else if let Some(order_file) = order_file {
+        write_list(order_file, &files)?;
+        println!(
+            "Wrote the order to {0}, edit it and join the clips with --from-list {0}",
+            order_file.display()
+        );
+        return Ok(());
+    } else {
+        files
+    };
+    match concat_files_command(&files, output, &options) {
+        // With nothing to join to it, the one file is written as it is.
+        Err(Error::SingleInput(file)) => {
+            settings::warning!(
+                "{} is the only input, converting it to the output instead of joining",
+                file.display()
+            );
+            let command = if options.mode == ConcatMode::Reencode || options.transition.is_some() {
+                transcode_command(&file, output, &options.transcode)?
+            } else {
+                remux_command(&file, output)?
+            };
+            report::execute(&command)
+        }
+        command => report::execute(&command?),
+    }
+}
+
+/// Lets the user reorder `files` in their editor, in `list` or else a
+/// temporary file, and returns the order they saved.
+fn review(files: &[PathBuf], list: Option<&Path>) -> Result<Vec<PathBuf>, Error> {
+    let temp;
+    let list = match list {
+        Some(list) => list,
+        None => {
+            temp = TempFile::create("media_tools-concat-order", "txt")?.0;
+            temp.path()
+        }
+    };
+    write_list(list, files)?;
+    edit(list)?;
+    let files = read_list(list)?;
+    if files.is_empty() {
+        return Err(Error::InvalidArgument(format!(
+            "{} names no files to join",
+            list.display()
+        )));
+    }
+    Ok(files)
+}
+
+/// Writes `files` the way [`read_list`] reads them, with absolute paths so
+/// the list can be anywhere.
+fn write_list(list: &Path, files: &[PathBuf]) -> Result<(), Error> {
+    let mut text = b"# The clips are joined in this order. Move the lines to reorder them,\n\
+         # delete them or put a # in front to leave clips out.\n"
+        .to_vec();
+    for file in files {
+        let path = if video::is_url(file) {
+            file.clone()
+        } else {
+            std::path::absolute(file).map_err(Error::AccessDenied)?
+        };
+        text.extend(path.into_os_string().into_encoded_bytes());
+        text.push(b'\n');
+    }
+    std::fs::write(list, text).map_err(Error::WriteFileError)
+}
+
+/// Opens `file` with `$VISUAL` or `$EDITOR` and waits until it is closed.
+fn edit(file: &Path) -> Result<(), Error> {
+    let editor = ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+    // The shell splits off arguments such as `code --wait`.
+    let status = if cfg!(windows) {
+        std::process::Command::new("cmd")
+            .arg("/C")
+            .arg(format!("{editor} \"{}\"", file.display()))
+            .status()
+    } else {
+        std::process::Command::new("sh")
+            .args(["-c", &format!("{editor} \"$1\""), "sh"])
+            .arg(file)
+            .status()
+    }
+    .map_err(|e| Error::InvalidArgument(format!("cannot start {editor}: {e}")))?;
+    if !status.success() {
+        return Err(Error::InvalidArgument(format!(
+            "{editor} exited with {status}, the clips were not joined"
+        )));
+    }
+    Ok(())
+}
+
+/// Reads one path or URL per line, skipping blank lines and `#` comments.
+fn read_list(list: &Path) -> Result<Vec<PathBuf>, Error> {
+    let bytes = std::fs::read(list).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => Error::FileNotFound(list.to_path_buf()),
+        _ => Error::AccessDenied(e),
+    })?;
+    let dir = list.parent().unwrap_or(Path::new(""));
+    // Names are taken as they are, spaces included; only the `\r` of
+    // Windows line endings is dropped.
+    Ok(bytes
+        .split(|&byte| byte == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|line| !line.trim_ascii().is_empty() && !line.starts_with(b"#"))
+        .map(|line| {
+            let line = path_from_bytes(line);
+            if video::is_url(&line) {
+                line
+            } else {
+                dir.join(line)
+            }
+        })
+        .collect())
+}
+
+/// A path as [`write_list`] wrote it: its bytes on unix, where paths are
+/// bytes, and UTF-8 elsewhere.
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+    }
+    #[cfg(not(unix))]
+    {
+        PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+    }
+}
@@ -0,0 +1,330 @@
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use video::{Error, OverwritePolicy};
+
+use crate::report;
+
+/// Environment variable naming a config file to use instead of the default.
+const CONFIG_ENV: &str = "MEDIA_TOOLS_CONFIG";
+
+const TEMPLATE: &str = r#"# Defaults for the video command line tool. Flags given on the command line
+# take precedence over these.
+
+# The ffmpeg and ffprobe binaries to run.
+# ffmpeg = "/usr/local/bin/ffmpeg"
+# ffprobe = "/usr/local/bin/ffprobe"
+
+# How many files batch processes at once.
+# jobs = 4
+
+# The folder batch writes its outputs to.
+# output_dir = "converted"
+
+# What to do when an output file exists: "always", "never" or "prompt".
+# overwrite = "never"
+
+# The file `video queue` keeps its jobs in, by default queue.jsonl next to
+# this file.
+# queue = "/var/lib/media_tools/queue.jsonl"
+
+# Report how each subcommand went as JSON, to a webhook and/or a shell
+# command that gets it on stdin.
+# notify_url = "https://hooks.example.com/media"
+# notify_command = "mail -s 'video done' me@example.com"
+
+[transcode]
+# vcodec = "libx264"
+# acodec = "aac"
+# crf = 23
+# preset = "medium"
+
+# Encoding presets for --preset, alongside the built-in web-1080p, youtube,
+# instagram and archival. One table per preset, every key is optional.
+# [presets.phone]
+# vcodec = "libx264"
+# acodec = "aac"
+# crf = 26
+# preset = "fast"
+# vbitrate = "1500k"
+# abitrate = "96k"
+# pix_fmt = "yuv420p"
+# faststart = true
+"#;
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Defaults read from the config file. Values are kept as text and parsed by
+/// the flags they provide defaults for.
+#[derive(Debug, Default)]
+pub struct Config {
+    pub ffmpeg: Option<String>,
+    pub ffprobe: Option<String>,
+    pub jobs: Option<String>,
+    pub output_dir: Option<String>,
+    pub overwrite: Option<String>,
+    pub queue: Option<String>,
+    pub notify_url: Option<String>,
+    pub notify_command: Option<String>,
+    pub vcodec: Option<String>,
+    pub acodec: Option<String>,
+    pub crf: Option<String>,
+    pub preset: Option<String>,
+    /// The `[presets.NAME]` tables, in the order they appear.
+    pub presets: Vec<(String, PresetConfig)>,
+}
+
+/// A user-defined encoding preset, kept as text like the rest.
+#[derive(Debug, Default)]
+pub struct PresetConfig {
+    pub vcodec: Option<String>,
+    pub acodec: Option<String>,
+    pub crf: Option<String>,
+    pub preset: Option<String>,
+    pub vbitrate: Option<String>,
+    pub abitrate: Option<String>,
+    pub pix_fmt: Option<String>,
+    pub faststart: Option<String>,
+}
+
+/// The user-defined preset called `name`, if there is one.
+pub fn preset(name: &str) -> Option<&'static PresetConfig> {
+    get()
+        .presets
+        .iter()
+        .find(|(preset, _)| preset == name)
+        .map(|(_, preset)| preset)
+}
+
+/// `$MEDIA_TOOLS_CONFIG`, or `config.toml` in the `media_tools` folder of the
+/// user's config directory.
+pub fn path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(CONFIG_ENV).filter(|p| !p.is_empty()) {
+        return Some(path.into());
+    }
+    let dir = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    }?;
+    Some(dir.join("media_tools").join("config.toml"))
+}
+
+/// Reads the config file, an absent file gives the built-in defaults.
+pub fn load() -> Result<(), Error> {
+    let config = match path() {
+        Some(path) if path.is_file() => {
+            let text = std::fs::read_to_string(&path).map_err(Error::AccessDenied)?;
+            parse(&text).map_err(|message| {
+                Error::InvalidArgument(format!("{}: {message}", path.display()))
+            })?
+        }
+        _ => Config::default(),
+    };
+    let _ = CONFIG.set(config);
+    Ok(())
+}
+
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+/// The configured overwrite policy, checked when the file was read.
+pub fn overwrite() -> Option<OverwritePolicy> {
+    get()
+        .overwrite
+        .as_ref()
+        .and_then(|policy| policy.parse().ok())
+}
+
+/// Gives `arg` the configured default, if there is one.
+pub fn default(arg: Arg, value: &'static Option<String>) -> Arg {
+    match value {
+        Some(value) => arg.default_value(value.as_str()),
+        None => arg,
+    }
+}
+
+/// Parses the subset of TOML the config needs: tables and string, integer,
+/// float and boolean values.
+fn parse(text: &str) -> Result<Config, String> {
+    let mut config = Config::default();
+    let mut table = String::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        let at = |message: String| format!("line {}: {message}", number + 1);
+
+        if let Some(name) = line.strip_prefix('[') {
+            let name = name
+                .strip_suffix(']')
+                .ok_or_else(|| at("unclosed table header".to_string()))?;
+            table = name.trim().to_string();
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| at(format!("expected `key = value`, found `{line}`")))?;
+        let key = key.trim();
+        let value = parse_value(value.trim()).map_err(at)?;
+
+        if let Some(name) = table.strip_prefix("presets.") {
+            let index = match config.presets.iter().position(|(n, _)| n == name) {
+                Some(index) => index,
+                None => {
+                    config
+                        .presets
+                        .push((name.to_string(), PresetConfig::default()));
+                    config.presets.len() - 1
+                }
+            };
+            let preset = &mut config.presets[index].1;
+            let slot = match key {
+                "vcodec" => &mut preset.vcodec,
+                "acodec" => &mut preset.acodec,
+                "crf" => &mut preset.crf,
+                "preset" => &mut preset.preset,
+                "vbitrate" => &mut preset.vbitrate,
+                "abitrate" => &mut preset.abitrate,
+                "pix_fmt" => &mut preset.pix_fmt,
+                "faststart" => &mut preset.faststart,
+                key => return Err(at(format!("unknown key `{table}.{key}`"))),
+            };
+            *slot = Some(value);
+            continue;
+        }
+
+        let slot = match (table.as_str(), key) {
+            ("", "ffmpeg") => &mut config.ffmpeg,
+            ("", "ffprobe") => &mut config.ffprobe,
+            ("", "jobs") => &mut config.jobs,
+            ("", "output_dir") => &mut config.output_dir,
+            ("", "overwrite") => &mut config.overwrite,
+            ("", "queue") => &mut config.queue,
+            ("", "notify_url") => &mut config.notify_url,
+            ("", "notify_command") => &mut config.notify_command,
+            ("transcode", "vcodec") => &mut config.vcodec,
+            ("transcode", "acodec") => &mut config.acodec,
+            ("transcode", "crf") => &mut config.crf,
+            ("transcode", "preset") => &mut config.preset,
+            ("", key) => return Err(at(format!("unknown key `{key}`"))),
+            (table, key) => return Err(at(format!("unknown key `{table}.{key}`"))),
+        };
+        if key == "overwrite" {
+            value
+                .parse::<OverwritePolicy>()
+                .map_err(|e| at(e.to_string()))?;
+        }
+        *slot = Some(value);
+    }
+
+    Ok(config)
+}
+
+/// Drops a `#` comment that is not inside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), c) if c == q && !escaped => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '#') => return &line[..i],
+            _ => {}
+        }
+        escaped = false;
+    }
+    line
+}
+
+fn parse_value(value: &str) -> Result<String, String> {
+    if let Some(rest) = value.strip_prefix('\'') {
+        return rest
+            .strip_suffix('\'')
+            .map(str::to_string)
+            .ok_or_else(|| "unterminated string".to_string());
+    }
+    if let Some(rest) = value.strip_prefix('"') {
+        let body = rest
+            .strip_suffix('"')
+            .ok_or_else(|| "unterminated string".to_string())?;
+        let mut text = String::new();
+        let mut chars = body.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                text.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('"') => text.push('"'),
+                Some('\\') => text.push('\\'),
+                Some('n') => text.push('\n'),
+                Some('t') => text.push('\t'),
+                Some(c) => return Err(format!("unknown escape `\\{c}`")),
+                None => return Err("unterminated string".to_string()),
+            }
+        }
+        return Ok(text);
+    }
+
+    let number = value.replace('_', "");
+    if value == "true" || value == "false" || number.parse::<f64>().is_ok() {
+        Ok(number)
+    } else {
+        Err(format!("unsupported value `{value}`"))
+    }
+}
+
+pub fn command() -> Command {
+    Command::new("config")
+        .about("Manage the file default options are read from.")
+        .subcommand_required(true)
+        .subcommands([
+            Command::new("init")
+                .about("Write a config file with every option commented out.")
+                .arg(
+                    Arg::new("force")
+                        .long("force")
+                        .action(ArgAction::SetTrue)
+                        .help("Replace an existing config file"),
+                ),
+            Command::new("path").about("Print where the config file is read from."),
+        ])
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let path = path().ok_or_else(|| {
+        Error::InvalidArgument("cannot tell where the config directory is".to_string())
+    })?;
+
+    if let Some(("init", args)) = args.subcommand() {
+        if path.exists() && !args.get_flag("force") {
+            return Err(Error::InvalidArgument(format!(
+                "{} already exists, use --force to replace it",
+                path.display()
+            )));
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(Error::CreateOutputError)?;
+        }
+        std::fs::write(&path, TEMPLATE).map_err(Error::WriteFileError)?;
+    }
+
+    if report::json() {
+        report::field("path", path);
+    } else {
+        println!("{}", path.display());
+    }
+    Ok(())
+}
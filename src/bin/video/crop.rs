@@ -0,0 +1,87 @@
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::{CropArea, CropMode, CropOptions, Error, crop_command, crop_preview};
+
+use crate::{args, report, settings, transcode};
+
+pub fn command() -> Command {
+    Command::new("crop")
+        .about("Crop a video to a given area or to its detected black bars.")
+        .args([
+            args::input(),
+            args::output(),
+            Arg::new("w")
+                .long("w")
+                .requires("h")
+                .help("The width of the area")
+                .value_parser(value_parser!(u32).range(1..)),
+            Arg::new("h")
+                .long("h")
+                .requires("w")
+                .help("The height of the area")
+                .value_parser(value_parser!(u32).range(1..)),
+            Arg::new("x")
+                .long("x")
+                .requires("w")
+                .help("The left edge of the area, centred by default")
+                .value_parser(value_parser!(u32)),
+            Arg::new("y")
+                .long("y")
+                .requires("w")
+                .help("The top edge of the area, centred by default")
+                .value_parser(value_parser!(u32)),
+            Arg::new("auto")
+                .long("auto")
+                .action(ArgAction::SetTrue)
+                .help("Detect the black bars with cropdetect"),
+            Arg::new("start")
+                .long("start")
+                .requires("auto")
+                .help("Where detection starts, a tenth into the video by default")
+                .value_parser(args::timestamp),
+            Arg::new("window")
+                .long("window")
+                .requires("auto")
+                .default_value("10")
+                .help("How long detection samples the video")
+                .value_parser(args::timestamp),
+        ])
+        .group(ArgGroup::new("area").args(["w", "auto"]).required(true))
+        .args(transcode::option_args())
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let mode = if args.get_flag("auto") {
+        CropMode::Auto {
+            start: args.get_one::<Duration>("start").copied(),
+            window: *args.get_one::<Duration>("window").unwrap(),
+        }
+    } else {
+        CropMode::Manual(CropArea {
+            width: *args.get_one::<u32>("w").unwrap(),
+            height: *args.get_one::<u32>("h").unwrap(),
+            x: args.get_one::<u32>("x").copied(),
+            y: args.get_one::<u32>("y").copied(),
+        })
+    };
+    let options = CropOptions {
+        mode,
+        transcode: transcode::options(args)?,
+    };
+
+    // The crop needs what cropdetect finds, so nothing can be shown of it
+    // but where that goes.
+    if settings::get().dry_run {
+        for command in crop_preview(input, output, &options)? {
+            report::execute(&command)?;
+        }
+        return Ok(());
+    }
+
+    let command = crop_command(input, output, &options)?;
+    report::execute(&command)
+}
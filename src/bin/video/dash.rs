@@ -0,0 +1,59 @@
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::{DashOptions, Error, Rendition, TrimMode, package_dash_command};
+
+use crate::{args, report, transcode};
+
+pub fn command() -> Command {
+    Command::new("dash")
+        .about("Package a video as an MPEG-DASH manifest with segments.")
+        .args([
+            args::input(),
+            args::output().help("The output directory"),
+            Arg::new("segment-time")
+                .long("segment-time")
+                .default_value("4")
+                .help("The target length of each segment")
+                .value_parser(args::timestamp),
+            Arg::new("manifest")
+                .long("manifest")
+                .default_value("manifest.mpd")
+                .help("The manifest file name")
+                .value_parser(value_parser!(String)),
+            Arg::new("representation")
+                .long("representation")
+                .action(ArgAction::Append)
+                .help("Encode a representation, e.g. 720:2800k; repeat for more")
+                .value_parser(args::rendition),
+            Arg::new("reencode")
+                .long("reencode")
+                .action(ArgAction::SetTrue)
+                .help("Re-encode instead of copying the streams"),
+        ])
+        .args(transcode::option_args())
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let representations: Vec<Rendition> = args
+        .get_many::<Rendition>("representation")
+        .map(|r| r.cloned().collect())
+        .unwrap_or_default();
+    let mode = if args.get_flag("reencode") || !representations.is_empty() {
+        TrimMode::Reencode(transcode::options(args)?)
+    } else {
+        TrimMode::Copy
+    };
+    let options = DashOptions {
+        segment_duration: *args.get_one::<Duration>("segment-time").unwrap(),
+        manifest: args.get_one::<String>("manifest").unwrap().clone(),
+        representations,
+        mode,
+    };
+
+    let command = package_dash_command(input, output, &options)?;
+    report::execute(&command)
+}
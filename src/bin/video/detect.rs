@@ -0,0 +1,75 @@
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::{DetectOptions, Error, detect, detect_command};
+
+use crate::{args, report, settings};
+
+pub fn command() -> Command {
+    Command::new("detect")
+        .about("Find the stretches of black or frozen video, e.g. to check a captured broadcast.")
+        .args([
+            args::input(),
+            Arg::new("no-black")
+                .long("no-black")
+                .action(ArgAction::SetTrue)
+                .help("Don't look for black frames"),
+            Arg::new("no-freeze")
+                .long("no-freeze")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("no-black")
+                .help("Don't look for frozen frames"),
+            Arg::new("min-duration")
+                .long("min-duration")
+                .default_value("2")
+                .help("Shorter stretches are not reported")
+                .value_parser(args::timestamp),
+            Arg::new("black-threshold")
+                .long("black-threshold")
+                .default_value("0.1")
+                .help("How dark a pixel must be to count as black, from 0 to 1")
+                .value_parser(value_parser!(f64)),
+            Arg::new("freeze-noise")
+                .long("freeze-noise")
+                .default_value("-60")
+                .allow_negative_numbers(true)
+                .help("How many dB two frames may differ by and still count as frozen")
+                .value_parser(value_parser!(f64)),
+        ])
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let options = DetectOptions {
+        black: !args.get_flag("no-black"),
+        freeze: !args.get_flag("no-freeze"),
+        min_duration: *args.get_one::<Duration>("min-duration").unwrap(),
+        black_threshold: *args.get_one::<f64>("black-threshold").unwrap(),
+        freeze_noise: *args.get_one::<f64>("freeze-noise").unwrap(),
+    };
+
+    if settings::get().dry_run {
+        return report::execute(&detect_command(input, &options)?);
+    }
+
+    let detections = detect(input, &options)?;
+    if report::json() {
+        report::field("detections", detections.to_json());
+        return Ok(());
+    }
+    if detections.is_empty() {
+        println!("nothing found");
+    }
+    let found = [("black", &detections.black), ("frozen", &detections.freeze)];
+    for (kind, ranges) in found {
+        for range in ranges {
+            println!(
+                "{kind:<6} {:.3} - {:.3} ({:.3}s)",
+                range.start.as_secs_f64(),
+                range.end.as_secs_f64(),
+                range.duration().as_secs_f64()
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use clap::{Arg, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use video::{AudioFormat, Error, ExtractAudioOptions, extract_audio_command};
+
+use crate::{args, report};
+
+pub fn command() -> Command {
+    Command::new("extract-audio")
+        .about("Extract the audio track of a video file.")
+        .args([
+            args::input(),
+            args::output(),
+            Arg::new("format")
+                .long("format")
+                .help("The audio format, defaults to the output extension")
+                .value_parser(["mp3", "aac", "flac", "wav", "opus"]),
+            Arg::new("bitrate")
+                .long("bitrate")
+                .help("The audio bitrate when re-encoding, e.g. 192k")
+                .value_parser(value_parser!(String)),
+        ])
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let options = ExtractAudioOptions {
+        format: args
+            .get_one::<String>("format")
+            .map(|f| f.parse::<AudioFormat>())
+            .transpose()?,
+        bitrate: args.get_one::<String>("bitrate").cloned(),
+    };
+
+    let command = extract_audio_command(input, output, &options)?;
+    report::execute(&command)
+}
@@ -0,0 +1,41 @@
+use clap::{Arg, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use video::{Error, FpsMode, FpsOptions, convert_fps_command};
+
+use crate::{args, report, transcode};
+
+pub fn command() -> Command {
+    Command::new("fps")
+        .about("Convert a video to another frame rate, optionally interpolating the new frames.")
+        .args([
+            args::input(),
+            args::output(),
+            Arg::new("fps")
+                .long("fps")
+                .required(true)
+                .help("The output frame rate, e.g. 60 or 29.97")
+                .value_parser(value_parser!(f64)),
+            Arg::new("mode")
+                .long("mode")
+                .default_value("simple")
+                .help(
+                    "simple drops or repeats frames, interpolate synthesizes them from the motion",
+                )
+                .value_parser(["simple", "interpolate"]),
+        ])
+        .args(transcode::option_args())
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let options = FpsOptions {
+        fps: *args.get_one::<f64>("fps").unwrap(),
+        mode: args.get_one::<String>("mode").unwrap().parse::<FpsMode>()?,
+        transcode: transcode::options(args)?,
+    };
+
+    let command = convert_fps_command(input, output, &options)?;
+    report::execute(&command)
+}
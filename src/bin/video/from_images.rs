@@ -0,0 +1,81 @@
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use video::scan::{SortOrder, name_matches, sort_paths, walk};
+use video::{Error, ImageInput, ImageSequenceOptions, images_to_video_command};
+
+use crate::{args, report, transcode};
+
+pub fn command() -> Command {
+    Command::new("from-images")
+        .about("Make a video out of a sequence of images.")
+        .args([
+            Arg::new("folder")
+                .short('f')
+                .long("folder")
+                .required(true)
+                .help("The folder contains the images")
+                .value_parser(value_parser!(PathBuf)),
+            Arg::new("pattern")
+                .long("pattern")
+                .required_unless_present("gather")
+                .help("The numbered image names, e.g. \"frame_%04d.png\", or a glob with --gather")
+                .value_parser(value_parser!(String)),
+            Arg::new("gather")
+                .long("gather")
+                .action(ArgAction::SetTrue)
+                .help("Take the images matching a glob in sorted order, however they are named"),
+            Arg::new("sort")
+                .long("sort")
+                .default_value("natural")
+                .requires("gather")
+                .help("The order gathered images are shown in")
+                .value_parser(["lexical", "natural", "mtime", "ctime"]),
+            Arg::new("fps")
+                .long("fps")
+                .default_value("30")
+                .help("How many images are shown per second")
+                .value_parser(value_parser!(f64)),
+            Arg::new("start-number")
+                .long("start-number")
+                .conflicts_with("gather")
+                .help("The number of the first image, by default the lowest found")
+                .value_parser(value_parser!(u32)),
+            args::output(),
+        ])
+        .args(transcode::option_args())
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let folder = args.get_one::<PathBuf>("folder").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+    let pattern = args.get_one::<String>("pattern");
+
+    let images = if args.get_flag("gather") {
+        let glob = pattern.map_or("*", String::as_str);
+        let mut files: Vec<PathBuf> = walk(folder, false)?
+            .into_iter()
+            .filter(|path| name_matches(path, glob))
+            .collect();
+        sort_paths(
+            &mut files,
+            args.get_one::<String>("sort")
+                .unwrap()
+                .parse::<SortOrder>()?,
+        );
+        ImageInput::Files(files)
+    } else {
+        if !folder.is_dir() {
+            return Err(Error::FolderNotFound(folder.clone()));
+        }
+        ImageInput::Pattern(folder.join(pattern.unwrap()))
+    };
+
+    let options = ImageSequenceOptions {
+        fps: *args.get_one::<f64>("fps").unwrap(),
+        start_number: args.get_one::<u32>("start-number").copied(),
+        transcode: transcode::options(args)?,
+    };
+
+    let command = images_to_video_command(&images, output, &options)?;
+    report::execute(&command)
+}
@@ -0,0 +1,93 @@
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::{Error, GenerateOptions, TestPattern, TestTone, generate_command};
+
+use crate::{args, report, transcode};
+
+pub fn command() -> Command {
+    Command::new("generate")
+        .about("Make test or placeholder media: test patterns, color bars, a tone or silence.")
+        .args([
+            args::output(),
+            Arg::new("video")
+                .long("video")
+                .default_value("testsrc")
+                .help("testsrc, bars, or a color name for a solid color")
+                .value_parser(value_parser!(String)),
+            Arg::new("no-video")
+                .long("no-video")
+                .action(ArgAction::SetTrue)
+                .help("Make an audio-only file"),
+            Arg::new("tone")
+                .long("tone")
+                .default_value("1000")
+                .help("The frequency in Hz of the sine tone")
+                .value_parser(value_parser!(f64)),
+            Arg::new("silence")
+                .long("silence")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("tone")
+                .help("Make the audio silent instead of a tone"),
+            Arg::new("no-audio")
+                .long("no-audio")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["silence", "no-video"])
+                .help("Leave the audio track out"),
+            Arg::new("duration")
+                .short('t')
+                .long("duration")
+                .default_value("10")
+                .help("How long the output runs, e.g. 10 or 00:01:00")
+                .value_parser(args::timestamp),
+            Arg::new("size")
+                .long("size")
+                .default_value("1280x720")
+                .help("The frame size as WIDTHxHEIGHT")
+                .value_parser(args::size),
+            Arg::new("fps")
+                .long("fps")
+                .default_value("30")
+                .help("The frame rate")
+                .value_parser(value_parser!(f64)),
+            Arg::new("sample-rate")
+                .long("sample-rate")
+                .default_value("48000")
+                .help("The audio sample rate in Hz")
+                .value_parser(value_parser!(u32).range(1..)),
+        ])
+        .args(transcode::option_args())
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let video = if args.get_flag("no-video") {
+        None
+    } else {
+        Some(
+            args.get_one::<String>("video")
+                .unwrap()
+                .parse::<TestPattern>()?,
+        )
+    };
+    let audio = if args.get_flag("no-audio") {
+        None
+    } else if args.get_flag("silence") {
+        Some(TestTone::Silence)
+    } else {
+        Some(TestTone::Sine(*args.get_one::<f64>("tone").unwrap()))
+    };
+    let options = GenerateOptions {
+        video,
+        audio,
+        duration: *args.get_one::<Duration>("duration").unwrap(),
+        size: *args.get_one::<(u32, u32)>("size").unwrap(),
+        fps: *args.get_one::<f64>("fps").unwrap(),
+        sample_rate: *args.get_one::<u32>("sample-rate").unwrap(),
+        transcode: transcode::options(args)?,
+    };
+
+    let command = generate_command(output, &options)?;
+    report::execute(&command)
+}
@@ -0,0 +1,56 @@
+use clap::{Arg, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::{AnimationFormat, AnimationOptions, Error, animation_command};
+
+use crate::{args, report};
+
+pub fn command() -> Command {
+    Command::new("gif")
+        .about("Convert a clip to an animated GIF or WebP.")
+        .args([
+            args::input(),
+            args::output(),
+            Arg::new("start")
+                .long("start")
+                .help("The start timestamp")
+                .value_parser(args::timestamp),
+            Arg::new("end")
+                .long("end")
+                .help("The end timestamp")
+                .value_parser(args::timestamp),
+            Arg::new("fps")
+                .long("fps")
+                .default_value("10")
+                .help("The frame rate of the animation")
+                .value_parser(value_parser!(u32).range(1..)),
+            Arg::new("width")
+                .long("width")
+                .default_value("480")
+                .help("The width in pixels, 0 keeps the source size")
+                .value_parser(value_parser!(u32)),
+            Arg::new("format")
+                .long("format")
+                .help("The animation format, defaults to the output extension")
+                .value_parser(["gif", "webp"]),
+        ])
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let options = AnimationOptions {
+        format: args
+            .get_one::<String>("format")
+            .map(|f| f.parse::<AnimationFormat>())
+            .transpose()?,
+        start: args.get_one::<Duration>("start").copied(),
+        end: args.get_one::<Duration>("end").copied(),
+        fps: *args.get_one::<u32>("fps").unwrap(),
+        width: args.get_one::<u32>("width").copied().filter(|w| *w > 0),
+    };
+
+    let command = animation_command(input, output, &options)?;
+    report::execute(&command)
+}
@@ -0,0 +1,49 @@
+use clap::{Arg, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use video::{Error, GridOptions, grid_command};
+
+use crate::{args, report, transcode};
+
+pub fn command() -> Command {
+    Command::new("grid")
+        .about("Tile 2 to 9 videos side by side or in a grid, e.g. to compare encodes.")
+        .args([
+            Arg::new("inputs")
+                .short('i')
+                .long("inputs")
+                .num_args(2..=9)
+                .required(true)
+                .help("The videos, filled in row by row")
+                .value_parser(value_parser!(PathBuf)),
+            args::output(),
+            Arg::new("columns")
+                .long("columns")
+                .help("How many videos go in a row, by default as many as make the grid square")
+                .value_parser(value_parser!(u32).range(1..)),
+            Arg::new("cell-size")
+                .long("cell-size")
+                .help("The size each video is scaled to, by default the first video's")
+                .value_parser(args::size),
+            Arg::new("audio")
+                .long("audio")
+                .default_value("1")
+                .help("Keep the audio of this input, counted from 1, or 0 for none")
+                .value_parser(value_parser!(usize)),
+        ])
+        .args(transcode::option_args())
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let inputs: Vec<&PathBuf> = args.get_many::<PathBuf>("inputs").unwrap().collect();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let options = GridOptions {
+        columns: args.get_one::<u32>("columns").copied(),
+        cell_size: args.get_one::<(u32, u32)>("cell-size").copied(),
+        audio: args.get_one::<usize>("audio").unwrap().checked_sub(1),
+        transcode: transcode::options(args)?,
+    };
+
+    let command = grid_command(&inputs, output, &options)?;
+    report::execute(&command)
+}
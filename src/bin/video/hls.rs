@@ -0,0 +1,95 @@
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::{Error, HlsOptions, PlaylistType, SegmentFormat, TrimMode, package_hls_command};
+
+use crate::{args, report, transcode};
+
+pub fn command() -> Command {
+    Command::new("hls")
+        .about("Package a video as an HLS playlist with segments.")
+        .args([
+            args::input(),
+            args::output().help("The output directory"),
+            Arg::new("reencode")
+                .long("reencode")
+                .action(ArgAction::SetTrue)
+                .help("Re-encode instead of copying the streams"),
+        ])
+        .args(option_args())
+        .args(
+            transcode::option_args()
+                .into_iter()
+                .map(|arg| arg.requires("reencode")),
+        )
+}
+
+pub fn option_args() -> Vec<Arg> {
+    vec![
+        Arg::new("segment-time")
+            .long("segment-time")
+            .default_value("6")
+            .help("The target length of each segment")
+            .value_parser(args::timestamp),
+        Arg::new("segment-format")
+            .long("segment-format")
+            .default_value("ts")
+            .help("The segment container")
+            .value_parser(["ts", "fmp4"]),
+        Arg::new("playlist-type")
+            .long("playlist-type")
+            .default_value("vod")
+            .help("The playlist type")
+            .value_parser(["vod", "event", "live"]),
+        Arg::new("list-size")
+            .long("list-size")
+            .default_value("6")
+            .help("The number of segments a live playlist keeps")
+            .value_parser(value_parser!(u32).range(1..)),
+        Arg::new("playlist")
+            .long("playlist")
+            .default_value("index.m3u8")
+            .help("The playlist file name")
+            .value_parser(value_parser!(String)),
+        Arg::new("segment-name")
+            .long("segment-name")
+            .default_value("segment_%05d")
+            .help("The segment file name pattern, without extension")
+            .value_parser(value_parser!(String)),
+    ]
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let mut options = options(args)?;
+    if args.get_flag("reencode") {
+        options.mode = TrimMode::Reencode(transcode::options(args)?);
+    }
+    let command = package_hls_command(input, output, &options)?;
+    report::execute(&command)
+}
+
+/// The packaging options, copying the streams.
+pub fn options(args: &ArgMatches) -> Result<HlsOptions, Error> {
+    let mut playlist_type = args
+        .get_one::<String>("playlist-type")
+        .unwrap()
+        .parse::<PlaylistType>()?;
+    if let PlaylistType::Live { list_size } = &mut playlist_type {
+        *list_size = *args.get_one::<u32>("list-size").unwrap();
+    }
+
+    Ok(HlsOptions {
+        segment_duration: *args.get_one::<Duration>("segment-time").unwrap(),
+        segment_format: args
+            .get_one::<String>("segment-format")
+            .unwrap()
+            .parse::<SegmentFormat>()?,
+        playlist_type,
+        playlist: args.get_one::<String>("playlist").unwrap().clone(),
+        segment_pattern: args.get_one::<String>("segment-name").unwrap().clone(),
+        mode: TrimMode::Copy,
+    })
+}
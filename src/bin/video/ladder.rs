@@ -0,0 +1,58 @@
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::path::PathBuf;
+use video::{Error, LadderOptions, Rendition, ladder_command};
+
+use crate::{args, hls, report, transcode};
+
+pub fn command() -> Command {
+    Command::new("ladder")
+        .about("Encode one source into several resolutions in a single pass.")
+        .args([
+            args::input(),
+            args::output()
+                .help("The output file, suffixed with each height, or directory with --hls"),
+            Arg::new("rendition")
+                .long("rendition")
+                .action(ArgAction::Append)
+                .help("A rendition such as 720:2800k; repeat for more, 1080p/720p/480p by default")
+                .value_parser(args::rendition),
+            Arg::new("upscale")
+                .long("upscale")
+                .action(ArgAction::SetTrue)
+                .help("Keep renditions taller than the source"),
+            Arg::new("hls")
+                .long("hls")
+                .action(ArgAction::SetTrue)
+                .help("Package the renditions under a master HLS playlist"),
+        ])
+        .args(
+            hls::option_args()
+                .into_iter()
+                .map(|arg| arg.requires("hls")),
+        )
+        .args(transcode::option_args())
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let renditions = args
+        .get_many::<Rendition>("rendition")
+        .map(|r| r.cloned().collect())
+        .unwrap_or_else(Rendition::standard_ladder);
+    let hls = if args.get_flag("hls") {
+        Some(hls::options(args)?)
+    } else {
+        None
+    };
+    let options = LadderOptions {
+        renditions,
+        upscale: args.get_flag("upscale"),
+        transcode: transcode::options(args)?,
+        hls,
+    };
+
+    let command = ladder_command(input, output, &options)?;
+    report::execute(&command)
+}
@@ -0,0 +1,53 @@
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::{Error, LoopLength, LoopOptions, loop_video_command};
+
+use crate::{args, report, transcode};
+
+pub fn command() -> Command {
+    Command::new("loop")
+        .about("Repeat a short clip a number of times or until it runs for a given duration.")
+        .args([
+            args::input(),
+            args::output(),
+            Arg::new("times")
+                .long("times")
+                .help("How many times the clip plays in total")
+                .value_parser(value_parser!(u32).range(1..)),
+            Arg::new("duration")
+                .long("duration")
+                .help("Repeat the clip until the output is this long, e.g. 00:10:00")
+                .value_parser(args::timestamp),
+            Arg::new("reencode")
+                .long("reencode")
+                .action(ArgAction::SetTrue)
+                .help("Re-encode instead of copying the streams, for an exact --duration"),
+        ])
+        .args(transcode::option_args())
+        .group(
+            ArgGroup::new("length")
+                .args(["times", "duration"])
+                .required(true),
+        )
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let length = match args.get_one::<u32>("times") {
+        Some(times) => LoopLength::Times(*times),
+        None => LoopLength::Duration(*args.get_one::<Duration>("duration").unwrap()),
+    };
+    let options = LoopOptions {
+        length,
+        transcode: args
+            .get_flag("reencode")
+            .then(|| transcode::options(args))
+            .transpose()?,
+    };
+
+    let command = loop_video_command(input, output, &options)?;
+    report::execute(&command)
+}
@@ -0,0 +1,379 @@
+use clap::{Arg, ArgAction, Command, command, value_parser};
+use std::path::PathBuf;
+use std::time::Instant;
+
+mod analyze;
+mod args;
+mod audio;
+mod batch;
+mod capture;
+mod check;
+mod compare;
+mod completions;
+mod concat;
+mod config;
+mod crop;
+mod dash;
+mod detect;
+mod extract_audio;
+mod fps;
+mod from_images;
+mod generate;
+mod gif;
+mod grid;
+mod hls;
+mod ladder;
+mod looping;
+mod man;
+mod metadata;
+mod mux;
+mod normalize_audio;
+mod notify;
+mod offset;
+mod optimize_web;
+mod pip;
+mod probe;
+mod queue;
+mod record;
+mod remove_silence;
+mod remux;
+mod report;
+mod reverse;
+mod rotate;
+mod scale;
+#[cfg(feature = "serve")]
+mod serve;
+mod settings;
+mod spectrogram;
+mod speed;
+mod split;
+mod stabilize;
+mod stream;
+mod subtitles;
+mod text;
+mod thumbnail;
+mod to_images;
+mod transcode;
+mod trim;
+mod tui;
+mod watch;
+mod watermark;
+mod waveform;
+
+fn main() {
+    // Without the config, so its defaults don't end up in the pages.
+    if let Some(dir) = man::requested() {
+        if let Err(e) = man::generate(&dir) {
+            report::error(&e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    // The config provides defaults for the flags, so it's read first.
+    if let Err(e) = config::load() {
+        report::error(&e);
+        std::process::exit(1);
+    }
+    let command = command_builder();
+    let matches = command.get_matches();
+    settings::init(&matches);
+    let started = Instant::now();
+
+    let result = match matches.subcommand() {
+        Some(("concat", args)) => concat::run(args),
+        Some(("transcode", args)) => transcode::run(args),
+        Some(("trim", args)) => trim::run(args),
+        Some(("probe", args)) => probe::run(args),
+        Some(("extract-audio", args)) => extract_audio::run(args),
+        Some(("thumbnail", args)) => thumbnail::run(args),
+        Some(("gif", args)) => gif::run(args),
+        Some(("watermark", args)) => watermark::run(args),
+        Some(("subtitles", args)) => subtitles::run(args),
+        Some(("batch", args)) => batch::run(args),
+        Some(("check", args)) => check::run(args),
+        Some(("scale", args)) => scale::run(args),
+        Some(("rotate", args)) => rotate::run(args),
+        Some(("crop", args)) => crop::run(args),
+        Some(("speed", args)) => speed::run(args),
+        Some(("normalize-audio", args)) => normalize_audio::run(args),
+        Some(("split", args)) => split::run(args),
+        Some(("hls", args)) => hls::run(args),
+        Some(("dash", args)) => dash::run(args),
+        Some(("ladder", args)) => ladder::run(args),
+        Some(("config", args)) => config::run(args),
+        Some(("metadata", args)) => metadata::run(args),
+        Some(("mux", args)) => mux::run(args),
+        Some(("offset", args)) => offset::run(args),
+        Some(("from-images", args)) => from_images::run(args),
+        Some(("to-images", args)) => to_images::run(args),
+        Some(("record", args)) => record::run(args),
+        Some(("capture", args)) => capture::run(args),
+        Some(("stream", args)) => stream::run(args),
+        Some(("compare", args)) => compare::run(args),
+        Some(("analyze", args)) => analyze::run(args),
+        Some(("remove-silence", args)) => remove_silence::run(args),
+        Some(("detect", args)) => detect::run(args),
+        Some(("waveform", args)) => waveform::run(args),
+        Some(("spectrogram", args)) => spectrogram::run(args),
+        Some(("audio", args)) => audio::run(args),
+        Some(("pip", args)) => pip::run(args),
+        Some(("grid", args)) => grid::run(args),
+        Some(("text", args)) => text::run(args),
+        Some(("fps", args)) => fps::run(args),
+        Some(("stabilize", args)) => stabilize::run(args),
+        Some(("reverse", args)) => reverse::run(args),
+        Some(("loop", args)) => looping::run(args),
+        Some(("generate", args)) => generate::run(args),
+        Some(("optimize-web", args)) => optimize_web::run(args),
+        Some(("remux", args)) => remux::run(args),
+        Some(("queue", args)) => queue::run(args),
+        Some(("watch", args)) => watch::run(args),
+        Some(("completions", args)) => completions::run(args),
+        Some(("tui", args)) => tui::run(args),
+        #[cfg(feature = "serve")]
+        Some(("serve", args)) => serve::run(args),
+        _ => {
+            eprintln!("No subcommand was provided");
+            Ok(())
+        }
+    };
+
+    report::finish(
+        matches.subcommand_name().unwrap_or_default(),
+        &result,
+        started.elapsed(),
+    );
+    if result.is_err() {
+        std::process::exit(1);
+    }
+}
+
+fn command_builder() -> Command {
+    let command = command!()
+        .version("v0.1.0")
+        .propagate_version(true)
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .global(true)
+                .action(ArgAction::Count)
+                .help("Log what is run and stream ffmpeg's output; repeat for more detail"),
+        )
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .conflicts_with("verbose")
+                .help("Only print errors"),
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Print the ffmpeg commands instead of running them"),
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Print the results as JSON on stdout"),
+        )
+        .arg(
+            Arg::new("overwrite")
+                .short('y')
+                .long("overwrite")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Replace outputs that already exist"),
+        )
+        .arg(
+            Arg::new("no-overwrite")
+                .short('n')
+                .long("no-overwrite")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .conflicts_with("overwrite")
+                .help("Fail instead of replacing outputs; by default you are asked"),
+        )
+        .arg(
+            Arg::new("ffmpeg")
+                .long("ffmpeg")
+                .global(true)
+                .env(video::FFMPEG_ENV)
+                .help("The ffmpeg binary to run")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("ffprobe")
+                .long("ffprobe")
+                .global(true)
+                .env(video::FFPROBE_ENV)
+                .help("The ffprobe binary to run, by default the one next to ffmpeg")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("timeout")
+                .long("timeout")
+                .global(true)
+                .help("Stop ffmpeg and fail when a single run takes longer than this")
+                .value_parser(args::timestamp),
+        )
+        .arg(
+            Arg::new("validate")
+                .long("validate")
+                .global(true)
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("1")
+                .help("Re-probe and decode the outputs, allowing their duration to be this far off")
+                .value_parser(args::timestamp),
+        )
+        .arg(
+            Arg::new("network-timeout")
+                .long("network-timeout")
+                .global(true)
+                .help("Give up on a URL input when its server sends nothing for this long")
+                .value_parser(args::timestamp),
+        )
+        .arg(
+            Arg::new("allow-protocol")
+                .long("allow-protocol")
+                .global(true)
+                .action(ArgAction::Append)
+                .help("Also accept inputs with this URL scheme besides http and https, may be repeated")
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            Arg::new("retry")
+                .long("retry")
+                .global(true)
+                .default_value("0")
+                .help("Run ffmpeg again this many times when a network input or device fails for now")
+                .value_parser(value_parser!(u32)),
+        )
+        .arg(
+            Arg::new("retry-delay")
+                .long("retry-delay")
+                .global(true)
+                .default_value("1s")
+                .help("How long to wait before the first retry, doubling for each one after it")
+                .value_parser(args::length),
+        )
+        .arg(
+            Arg::new("nice")
+                .long("nice")
+                .global(true)
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("10")
+                .help("Run ffmpeg at a lower CPU priority, from 1 to 19 as with nice")
+                .value_parser(value_parser!(i32).range(1..=19)),
+        )
+        .arg(
+            Arg::new("idle-io")
+                .long("idle-io")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Only let ffmpeg use the disk when nothing else does (Linux)"),
+        )
+        .arg(
+            Arg::new("threads")
+                .long("threads")
+                .global(true)
+                .help("How many threads ffmpeg's encoders and filters may use")
+                .value_parser(value_parser!(u32).range(1..)),
+        )
+        .arg(
+            Arg::new("cpu-limit")
+                .long("cpu-limit")
+                .global(true)
+                .help("The CPU time ffmpeg may use in percent of one core, e.g. 200 for two")
+                .value_parser(value_parser!(u32).range(1..)),
+        )
+        .arg(
+            Arg::new("cpu-limiter")
+                .long("cpu-limiter")
+                .global(true)
+                .default_value("cgroup")
+                .help("Enforce --cpu-limit with a cgroup through systemd-run, or with cpulimit")
+                .value_parser(["cgroup", "cpulimit"]),
+        )
+        .arg(config::default(
+            Arg::new("notify-url")
+                .long("notify-url")
+                .global(true)
+                .help("POST how the subcommand went as JSON to this URL once it is done")
+                .value_parser(value_parser!(String)),
+            &config::get().notify_url,
+        ))
+        .arg(config::default(
+            Arg::new("notify-command")
+                .long("notify-command")
+                .global(true)
+                .help("Run this shell command once the subcommand is done, with the JSON report on stdin")
+                .value_parser(value_parser!(String)),
+            &config::get().notify_command,
+        ))
+        .subcommands([
+            concat::command(),
+            transcode::command(),
+            trim::command(),
+            probe::command(),
+            extract_audio::command(),
+            thumbnail::command(),
+            gif::command(),
+            watermark::command(),
+            subtitles::command(),
+            batch::command(),
+            check::command(),
+            scale::command(),
+            rotate::command(),
+            crop::command(),
+            speed::command(),
+            normalize_audio::command(),
+            split::command(),
+            hls::command(),
+            dash::command(),
+            ladder::command(),
+            config::command(),
+            metadata::command(),
+            mux::command(),
+            offset::command(),
+            from_images::command(),
+            to_images::command(),
+            record::command(),
+            capture::command(),
+            stream::command(),
+            compare::command(),
+            analyze::command(),
+            remove_silence::command(),
+            detect::command(),
+            waveform::command(),
+            spectrogram::command(),
+            audio::command(),
+            pip::command(),
+            grid::command(),
+            text::command(),
+            fps::command(),
+            stabilize::command(),
+            reverse::command(),
+            looping::command(),
+            generate::command(),
+            optimize_web::command(),
+            remux::command(),
+            queue::command(),
+            watch::command(),
+            completions::command(),
+            tui::command(),
+        ]);
+    #[cfg(feature = "serve")]
+    let command = command.subcommand(serve::command());
+    command
+}
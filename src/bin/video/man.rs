@@ -0,0 +1,225 @@
+//! Man pages for `video` and each of its subcommands, written from the same
+//! command definitions `--help` uses.
+
+use clap::{Arg, Command};
+use std::path::{Path, PathBuf};
+use video::Error;
+
+/// The flag packagers run, `video --generate-man DIR`. It's not a clap flag
+/// since the command line otherwise requires a subcommand.
+const FLAG: &str = "--generate-man";
+
+/// The folder to write the pages to, when the command line asks for them.
+pub fn requested() -> Option<PathBuf> {
+    let mut args = std::env::args_os().skip(1);
+    if args.next()? != FLAG {
+        return None;
+    }
+    Some(
+        args.next()
+            .map_or_else(|| PathBuf::from("."), PathBuf::from),
+    )
+}
+
+/// Writes `video.1` and a `video-<subcommand>.1` for every subcommand to
+/// `dir`, creating it if needed.
+pub fn generate(dir: &Path) -> Result<(), Error> {
+    std::fs::create_dir_all(dir).map_err(Error::CreateOutputError)?;
+    let mut command = crate::command_builder();
+    // Hands the global flags down to every subcommand, marked as global.
+    command.build();
+    let version = command
+        .get_version()
+        .unwrap_or_default()
+        .trim_start_matches('v')
+        .to_string();
+    write_pages(dir, &command, &[], &version)
+}
+
+fn write_pages(
+    dir: &Path,
+    command: &Command,
+    parents: &[&str],
+    version: &str,
+) -> Result<(), Error> {
+    let mut path: Vec<&str> = parents.to_vec();
+    path.push(command.get_name());
+
+    let page = render(command, &path, version);
+    std::fs::write(dir.join(format!("{}.1", path.join("-"))), page)
+        .map_err(Error::WriteFileError)?;
+    for sub in subcommands(command) {
+        write_pages(dir, sub, &path, version)?;
+    }
+    Ok(())
+}
+
+fn subcommands(command: &Command) -> impl Iterator<Item = &Command> {
+    command
+        .get_subcommands()
+        .filter(|sub| !sub.is_hide_set() && sub.get_name() != "help")
+}
+
+fn render(command: &Command, path: &[&str], version: &str) -> String {
+    let name = path.join("-");
+    let is_root = path.len() == 1;
+    let mut page = format!(
+        ".TH {} 1 \"\" \"{} {version}\" \"User Commands\"\n",
+        escape(&name.to_uppercase()),
+        escape(path[0])
+    );
+
+    let about = command
+        .get_about()
+        .map(|about| about.to_string())
+        .unwrap_or_default();
+    page.push_str(".SH NAME\n");
+    page.push_str(&format!(
+        "{} \\- {}\n",
+        escape(&name),
+        escape(about.trim_end_matches('.'))
+    ));
+
+    page.push_str(".SH SYNOPSIS\n");
+    let usage = command.clone().render_usage().to_string();
+    let usage = usage.trim().trim_start_matches("Usage:").trim();
+    // The usage names the command by itself, not with its parents.
+    let usage = usage
+        .strip_prefix(command.get_name())
+        .map_or(usage.to_string(), |rest| {
+            format!("{}{rest}", path.join(" "))
+        });
+    page.push_str(&format!(".B {}\n", escape(&usage)));
+
+    page.push_str(".SH DESCRIPTION\n");
+    let description = command
+        .get_long_about()
+        .map(|about| about.to_string())
+        .unwrap_or(about);
+    page.push_str(&paragraphs(&description));
+
+    let args: Vec<&Arg> = command
+        .get_arguments()
+        .filter(|arg| !arg.is_hide_set() && (is_root || !arg.is_global_set()))
+        .collect();
+    if !args.is_empty() {
+        page.push_str(".SH OPTIONS\n");
+        for arg in args {
+            page.push_str(&option(arg));
+        }
+    }
+    if !is_root {
+        page.push_str(&format!(
+            ".PP\nThe options every subcommand takes are listed in \\fB{}\\fR(1).\n",
+            escape(path[0])
+        ));
+    }
+
+    let subs: Vec<&Command> = subcommands(command).collect();
+    if !subs.is_empty() {
+        page.push_str(".SH SUBCOMMANDS\n");
+        for sub in subs {
+            page.push_str(&format!(
+                ".TP\n\\fB{}\\-{}\\fR(1)\n{}\n",
+                escape(&name),
+                escape(sub.get_name()),
+                escape(&sub.get_about().map(|a| a.to_string()).unwrap_or_default())
+            ));
+        }
+    }
+
+    let mut see_also: Vec<String> = Vec::new();
+    if path.len() > 1 {
+        see_also.push(path[..path.len() - 1].join("-"));
+    }
+    if path.len() > 2 {
+        see_also.push(path[0].to_string());
+    }
+    if !see_also.is_empty() {
+        page.push_str(".SH SEE ALSO\n");
+        let pages: Vec<String> = see_also
+            .iter()
+            .map(|page| format!("\\fB{}\\fR(1)", escape(page)))
+            .collect();
+        page.push_str(&pages.join(", "));
+        page.push('\n');
+    }
+    page
+}
+
+/// A `.TP` entry: the flag with its value, then what it does.
+fn option(arg: &Arg) -> String {
+    let mut names = Vec::new();
+    if let Some(short) = arg.get_short() {
+        names.push(format!("\\fB\\-{}\\fR", escape(&short.to_string())));
+    }
+    if let Some(long) = arg.get_long() {
+        names.push(format!("\\fB\\-\\-{}\\fR", escape(long)));
+    }
+    let mut head = names.join(", ");
+    if arg.get_action().takes_values() {
+        let value = arg
+            .get_value_names()
+            .and_then(|names| names.first())
+            .map_or_else(|| arg.get_id().to_string(), |name| name.to_string());
+        let value = format!("\\fI<{}>\\fR", escape(&value));
+        head = match (head.is_empty(), arg.is_require_equals_set()) {
+            (true, _) => value,
+            (false, true) => format!("{head}[={value}]"),
+            (false, false) => format!("{head} {value}"),
+        };
+    }
+
+    let mut help = arg.get_help().map(|h| h.to_string()).unwrap_or_default();
+    let possible: Vec<String> = arg
+        .get_possible_values()
+        .iter()
+        .filter(|value| !value.is_hide_set())
+        .map(|value| value.get_name().to_string())
+        .collect();
+    if !possible.is_empty() {
+        help.push_str(&format!(" [possible values: {}]", possible.join(", ")));
+    }
+    let defaults: Vec<String> = arg
+        .get_default_values()
+        .iter()
+        .map(|value| value.to_string_lossy().into_owned())
+        .collect();
+    if !defaults.is_empty() && arg.get_action().takes_values() {
+        help.push_str(&format!(" [default: {}]", defaults.join(", ")));
+    }
+    if let Some(env) = arg.get_env() {
+        help.push_str(&format!(" [env: {}]", env.to_string_lossy()));
+    }
+    format!(".TP\n{head}\n{}\n", escape(help.trim()))
+}
+
+/// Text with blank lines between paragraphs. Indented lines, such as
+/// examples, start a line of their own.
+fn paragraphs(text: &str) -> String {
+    let mut roff = String::new();
+    for (i, paragraph) in text.split("\n\n").enumerate() {
+        if i > 0 {
+            roff.push_str(".PP\n");
+        }
+        for line in paragraph.lines() {
+            if line.starts_with(char::is_whitespace) {
+                roff.push_str(".br\n");
+            }
+            roff.push_str(&escape(line.trim()));
+            roff.push('\n');
+        }
+    }
+    roff
+}
+
+/// Keeps roff from reading backslashes, dashes or a leading dot or quote as
+/// markup.
+fn escape(text: &str) -> String {
+    let text = text.replace('\\', "\\e").replace('-', "\\-");
+    if text.starts_with(['.', '\'']) {
+        format!("\\&{text}")
+    } else {
+        text
+    }
+}
@@ -0,0 +1,171 @@
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Command, value_parser};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use video::json::Json;
+use video::{Error, Metadata, write_metadata_command};
+
+use crate::{args, report, settings};
+
+pub fn command() -> Command {
+    Command::new("metadata")
+        .about("Read or edit the container tags of a media file.")
+        .subcommand_required(true)
+        .subcommands([
+            Command::new("get")
+                .about("Print the tags, or the value of one of them.")
+                .args([
+                    Arg::new("file")
+                        .required(true)
+                        .help("The media file to read")
+                        .value_parser(value_parser!(PathBuf)),
+                    Arg::new("key")
+                        .help("Only print this tag, e.g. title")
+                        .value_parser(value_parser!(String)),
+                ]),
+            Command::new("set")
+                .about("Write a copy of the file with changed tags, the streams are copied.")
+                .args([
+                    args::input(),
+                    args::output().required(false),
+                    Arg::new("in-place")
+                        .long("in-place")
+                        .action(ArgAction::SetTrue)
+                        .help("Replace the input instead of writing a new file"),
+                    Arg::new("title")
+                        .long("title")
+                        .value_parser(value_parser!(String)),
+                    Arg::new("artist")
+                        .long("artist")
+                        .value_parser(value_parser!(String)),
+                    Arg::new("comment")
+                        .long("comment")
+                        .value_parser(value_parser!(String)),
+                    Arg::new("creation-time")
+                        .long("creation-time")
+                        .help("A UTC time such as 2024-05-01T12:30:00Z, or now")
+                        .value_parser(creation_time),
+                    Arg::new("tag")
+                        .long("tag")
+                        .action(ArgAction::Append)
+                        .help("Set any tag as KEY=VALUE, may be repeated")
+                        .value_parser(tag),
+                    Arg::new("remove")
+                        .long("remove")
+                        .action(ArgAction::Append)
+                        .help("Clear a tag, may be repeated")
+                        .value_parser(value_parser!(String)),
+                ])
+                .group(
+                    ArgGroup::new("destination")
+                        .args(["output", "in-place"])
+                        .required(true),
+                ),
+        ])
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    match args.subcommand() {
+        Some(("get", args)) => get(args),
+        Some(("set", args)) => set(args),
+        _ => Err(Error::InvalidArgument(
+            "unknown metadata command".to_string(),
+        )),
+    }
+}
+
+fn get(args: &ArgMatches) -> Result<(), Error> {
+    let file = args.get_one::<PathBuf>("file").unwrap();
+    let metadata = Metadata::read(file)?;
+
+    if let Some(key) = args.get_one::<String>("key") {
+        let value = metadata.get(key);
+        if report::json() {
+            report::field(key, value);
+        } else if let Some(value) = value {
+            println!("{value}");
+        } else {
+            return Err(Error::InvalidArgument(format!(
+                "{} has no {key} tag",
+                file.display()
+            )));
+        }
+        return Ok(());
+    }
+
+    if report::json() {
+        report::field("tags", Json::object(metadata.iter()));
+    } else {
+        for (key, value) in metadata.iter() {
+            println!("{key}: {value}");
+        }
+    }
+    Ok(())
+}
+
+fn set(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+
+    let mut metadata = Metadata::read(input)?;
+    for key in ["title", "artist", "comment"] {
+        if let Some(value) = args.get_one::<String>(key) {
+            metadata.set(key, value);
+        }
+    }
+    if let Some(time) = args.get_one::<SystemTime>("creation-time") {
+        metadata.set_creation_time(*time);
+    }
+    for (key, value) in args
+        .get_many::<(String, String)>("tag")
+        .into_iter()
+        .flatten()
+    {
+        metadata.set(key, value);
+    }
+    for key in args.get_many::<String>("remove").into_iter().flatten() {
+        metadata.remove(key);
+    }
+
+    let Some(output) = args.get_one::<PathBuf>("output") else {
+        return in_place(input, &metadata);
+    };
+    report::execute(&write_metadata_command(input, output, &metadata)?)
+}
+
+/// ffmpeg can't write to the file it reads, so the copy is written next to
+/// the input and then renamed over it.
+fn in_place(input: &Path, metadata: &Metadata) -> Result<(), Error> {
+    let mut name = OsString::from(".");
+    name.push(input.file_stem().unwrap_or_default());
+    name.push(".metadata");
+    if let Some(ext) = input.extension() {
+        name.push(".");
+        name.push(ext);
+    }
+    let temp = input.with_file_name(name);
+
+    let command = write_metadata_command(input, &temp, metadata)?;
+    if let Err(e) = report::execute(&command) {
+        let _ = std::fs::remove_file(&temp);
+        return Err(e);
+    }
+    if !settings::get().dry_run {
+        std::fs::rename(&temp, input).map_err(Error::WriteFileError)?;
+    }
+    Ok(())
+}
+
+fn creation_time(value: &str) -> Result<SystemTime, String> {
+    if value == "now" {
+        return Ok(SystemTime::now());
+    }
+    video::time::parse_datetime(value).map_err(|_| format!("invalid date `{value}`"))
+}
+
+fn tag(value: &str) -> Result<(String, String), String> {
+    value
+        .split_once('=')
+        .filter(|(key, _)| !key.is_empty())
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .ok_or_else(|| format!("invalid tag `{value}`, expected KEY=VALUE"))
+}
@@ -0,0 +1,51 @@
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use video::{Error, MuxOptions, mux_command};
+
+use crate::{args, report, transcode};
+
+pub fn command() -> Command {
+    Command::new("mux")
+        .about("Put a separate audio file onto a video, replacing its audio.")
+        .args([
+            args::input().help("The video file"),
+            Arg::new("audio")
+                .short('a')
+                .long("audio")
+                .required(true)
+                .help("The audio file")
+                .value_parser(value_parser!(PathBuf)),
+            args::output(),
+            Arg::new("offset")
+                .long("offset")
+                .default_value("0")
+                .allow_hyphen_values(true)
+                .help("How long to delay the audio, e.g. 250ms; negative starts it earlier")
+                .value_parser(args::offset_ms),
+            Arg::new("shortest")
+                .long("shortest")
+                .action(ArgAction::SetTrue)
+                .help("Stop at the end of the shorter of the video and the audio"),
+            Arg::new("keep-audio")
+                .long("keep-audio")
+                .action(ArgAction::SetTrue)
+                .help("Keep the video's own audio as a second track"),
+        ])
+        .args(transcode::option_args())
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let audio = args.get_one::<PathBuf>("audio").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let options = MuxOptions {
+        audio_offset_ms: *args.get_one::<i64>("offset").unwrap(),
+        shortest: args.get_flag("shortest"),
+        keep_original_audio: args.get_flag("keep-audio"),
+        transcode: transcode::options(args)?,
+    };
+
+    let command = mux_command(input, audio, output, &options)?;
+    report::execute(&command)
+}
@@ -0,0 +1,56 @@
+use clap::{Arg, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use video::{Error, NormalizeAudioOptions, normalize_audio_command, normalize_audio_preview};
+
+use crate::{args, report, settings, transcode};
+
+pub fn command() -> Command {
+    Command::new("normalize-audio")
+        .about("Normalize the loudness of the audio with two-pass EBU R128 loudnorm.")
+        .args([
+            args::input(),
+            args::output(),
+            Arg::new("lufs")
+                .long("lufs")
+                .default_value("-23")
+                .allow_negative_numbers(true)
+                .help("The integrated loudness target in LUFS")
+                .value_parser(value_parser!(f64)),
+            Arg::new("tp")
+                .long("tp")
+                .default_value("-1")
+                .allow_negative_numbers(true)
+                .help("The maximum true peak in dBTP")
+                .value_parser(value_parser!(f64)),
+            Arg::new("lra")
+                .long("lra")
+                .default_value("7")
+                .help("The loudness range target in LU")
+                .value_parser(value_parser!(f64)),
+        ])
+        .args(transcode::option_args())
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let options = NormalizeAudioOptions {
+        integrated: *args.get_one::<f64>("lufs").unwrap(),
+        true_peak: *args.get_one::<f64>("tp").unwrap(),
+        range: *args.get_one::<f64>("lra").unwrap(),
+        transcode: transcode::options(args)?,
+    };
+
+    // The second pass needs what the first one measures, so nothing can be
+    // shown of it but where those values go.
+    if settings::get().dry_run {
+        for command in normalize_audio_preview(input, output, &options)? {
+            report::execute(&command)?;
+        }
+        return Ok(());
+    }
+
+    let command = normalize_audio_command(input, output, &options)?;
+    report::execute(&command)
+}
@@ -0,0 +1,142 @@
+//! Tells a webhook or a command how a subcommand went, so a long encode can
+//! be left alone until it pings back.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use video::json::Json;
+
+use crate::settings;
+
+/// How long the webhook may take to answer.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Whether a webhook or command is configured.
+pub fn enabled() -> bool {
+    let settings = settings::get();
+    settings.notify_url.is_some() || settings.notify_command.is_some()
+}
+
+/// Sends `summary`, the `--json` report of the subcommand, to the webhook and
+/// the command. A notification that fails only gets a warning, the
+/// subcommand's own result stands.
+pub fn send(summary: &Json) {
+    let settings = settings::get();
+    if settings.dry_run {
+        return;
+    }
+    let body = summary.to_string();
+    if let Some(url) = &settings.notify_url
+        && let Err(e) = post(url, &body)
+    {
+        settings::warning!("could not notify {url}: {e}");
+    }
+    if let Some(command) = &settings.notify_command
+        && let Err(e) = run(command, summary, &body)
+    {
+        settings::warning!("the notify command failed: {e}");
+    }
+}
+
+/// POSTs `body` as JSON. Plain http is sent directly, https through `curl`.
+fn post(url: &str, body: &str) -> Result<(), String> {
+    if url.starts_with("https://") {
+        return pipe(
+            Command::new("curl")
+                .args(["--silent", "--show-error", "--fail", "--max-time", "10"])
+                .args([
+                    "-H",
+                    "Content-Type: application/json",
+                    "--data-binary",
+                    "@-",
+                ])
+                .arg(url),
+            body,
+        );
+    }
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or("only http:// and https:// URLs are supported")?;
+    let (host, path) = match rest.find('/') {
+        Some(slash) => rest.split_at(slash),
+        None => (rest, "/"),
+    };
+    let address = if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{host}:80")
+    };
+    let address = address
+        .to_socket_addrs()
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or_else(|| format!("cannot resolve {host}"))?;
+
+    let mut stream = TcpStream::connect_timeout(&address, TIMEOUT).map_err(|e| e.to_string())?;
+    stream
+        .set_read_timeout(Some(TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    write!(
+        stream,
+        "POST {path} HTTP/1.1\r\nHost: {host}\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+    .map_err(|e| e.to_string())?;
+
+    let mut status = String::new();
+    BufReader::new(stream)
+        .read_line(&mut status)
+        .map_err(|e| e.to_string())?;
+    match status.split_whitespace().nth(1) {
+        Some(code) if code.starts_with('2') => Ok(()),
+        Some(_) => Err(format!("the server answered {}", status.trim())),
+        None => Err("the server sent no answer".to_string()),
+    }
+}
+
+/// Runs `command` in the shell with the summary on stdin, and its outcome in
+/// `MEDIA_TOOLS_SUBCOMMAND`, `MEDIA_TOOLS_SUCCESS` and `MEDIA_TOOLS_ELAPSED`
+/// (in seconds) for scripts that don't read JSON.
+fn run(command: &str, summary: &Json, body: &str) -> Result<(), String> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    let text = |key| match summary.get(key) {
+        Some(Json::String(value)) => value.clone(),
+        Some(value) => value.to_string(),
+        None => String::new(),
+    };
+    shell
+        .arg(command)
+        .env("MEDIA_TOOLS_SUBCOMMAND", text("command"))
+        .env("MEDIA_TOOLS_SUCCESS", text("success"))
+        .env("MEDIA_TOOLS_ELAPSED", text("elapsed"));
+    pipe(&mut shell, body)
+}
+
+/// Runs `command` with `input` on stdin, failing unless it exits cleanly.
+fn pipe(command: &mut Command, input: &str) -> Result<(), String> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A command that ignores its input may exit before reading it.
+        let _ = stdin.write_all(input.as_bytes());
+    }
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(status.to_string())
+    }
+}
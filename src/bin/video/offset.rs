@@ -0,0 +1,34 @@
+use clap::{Arg, ArgMatches, Command};
+use std::path::PathBuf;
+use video::{Error, OffsetOptions, offset_command};
+
+use crate::{args, report, transcode};
+
+pub fn command() -> Command {
+    Command::new("offset")
+        .about("Shift the audio against the video to fix out-of-sync recordings.")
+        .args([
+            args::input(),
+            args::output(),
+            Arg::new("audio-delay")
+                .long("audio-delay")
+                .required(true)
+                .allow_hyphen_values(true)
+                .help("How long to delay the audio, e.g. 250ms; negative makes it earlier")
+                .value_parser(args::offset_ms),
+        ])
+        .args(transcode::option_args())
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let options = OffsetOptions {
+        audio_delay_ms: *args.get_one::<i64>("audio-delay").unwrap(),
+        transcode: transcode::options(args)?,
+    };
+
+    let command = offset_command(input, output, &options)?;
+    report::execute(&command)
+}
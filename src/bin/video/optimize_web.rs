@@ -0,0 +1,30 @@
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::path::PathBuf;
+use video::{Error, WebOptimizeOptions, optimize_web_command};
+
+use crate::{args, report};
+
+pub fn command() -> Command {
+    Command::new("optimize-web")
+        .about("Move an MP4's index to the front, without re-encoding, so it plays before it is fully downloaded.")
+        .args([
+            args::input(),
+            args::output(),
+            Arg::new("fragmented")
+                .long("fragmented")
+                .action(ArgAction::SetTrue)
+                .help("Write a fragmented MP4 instead, e.g. for live or low-latency playback"),
+        ])
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let options = WebOptimizeOptions {
+        fragmented: args.get_flag("fragmented"),
+    };
+
+    let command = optimize_web_command(input, output, &options)?;
+    report::execute(&command)
+}
@@ -0,0 +1,71 @@
+use clap::{Arg, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::{Error, PipOptions, Position, pip_command};
+
+use crate::{args, report};
+
+pub fn command() -> Command {
+    Command::new("pip")
+        .about("Show a second video as a small picture-in-picture inset.")
+        .args([
+            args::input(),
+            args::output(),
+            Arg::new("inset")
+                .long("inset")
+                .required(true)
+                .help("The video shown in the inset")
+                .value_parser(value_parser!(PathBuf)),
+            args::position(),
+            Arg::new("margin")
+                .long("margin")
+                .default_value("20")
+                .help("The distance from the edges in pixels")
+                .value_parser(value_parser!(u32)),
+            Arg::new("scale")
+                .long("scale")
+                .default_value("0.25")
+                .help("The inset width as a fraction of the main video's")
+                .value_parser(value_parser!(f64)),
+            Arg::new("border")
+                .long("border")
+                .default_value("0")
+                .help("The width of a frame around the inset in pixels")
+                .value_parser(value_parser!(u32)),
+            Arg::new("border-color")
+                .long("border-color")
+                .default_value("white")
+                .help("The color of the frame")
+                .value_parser(value_parser!(String)),
+            Arg::new("start")
+                .long("start")
+                .help("Start the inset at this timestamp")
+                .value_parser(args::timestamp),
+            Arg::new("end")
+                .long("end")
+                .help("Hide the inset after this timestamp")
+                .value_parser(args::timestamp),
+        ])
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+    let inset = args.get_one::<PathBuf>("inset").unwrap();
+
+    let options = PipOptions {
+        position: args
+            .get_one::<String>("position")
+            .unwrap()
+            .parse::<Position>()?,
+        margin: *args.get_one::<u32>("margin").unwrap(),
+        scale: *args.get_one::<f64>("scale").unwrap(),
+        border: *args.get_one::<u32>("border").unwrap(),
+        border_color: args.get_one::<String>("border-color").unwrap().clone(),
+        start: args.get_one::<Duration>("start").copied(),
+        end: args.get_one::<Duration>("end").copied(),
+    };
+
+    let command = pip_command(input, inset, output, &options)?;
+    report::execute(&command)
+}
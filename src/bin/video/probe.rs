@@ -0,0 +1,96 @@
+use clap::{Arg, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::probe::StreamKind;
+use video::{Error, MediaInfo, probe};
+
+use crate::report;
+
+pub fn command() -> Command {
+    Command::new("probe")
+        .about("Show the container and stream details of a media file.")
+        .arg(
+            Arg::new("file")
+                .required(true)
+                .help("The media file to inspect")
+                .value_parser(value_parser!(PathBuf)),
+        )
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let file = args.get_one::<PathBuf>("file").unwrap();
+    let info = probe(file)?;
+    if report::json() {
+        report::field("probe", info.to_json());
+        return Ok(());
+    }
+    for line in describe(&info) {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+/// The details `probe` prints, one line each.
+pub fn describe(info: &MediaInfo) -> Vec<String> {
+    let mut lines = vec![
+        format!("File:      {}", info.path.display()),
+        format!("Container: {}", info.container),
+    ];
+    if let Some(duration) = info.duration {
+        lines.push(format!("Duration:  {}", format_duration(duration)));
+    }
+    if let Some(bit_rate) = info.bit_rate {
+        lines.push(format!("Bitrate:   {} kb/s", bit_rate / 1000));
+    }
+
+    for stream in &info.streams {
+        let mut line = format!(
+            "Stream #{}: {:?} {}",
+            stream.index, stream.kind, stream.codec
+        );
+        match stream.kind {
+            StreamKind::Video => {
+                if let Some((w, h)) = stream.resolution() {
+                    line.push_str(&format!(", {w}x{h}"));
+                }
+                if let Some(fps) = stream.frame_rate {
+                    line.push_str(&format!(", {:.3} fps", fps.as_f64()));
+                }
+                if stream.is_interlaced() {
+                    line.push_str(", interlaced");
+                }
+                if stream.is_hdr() {
+                    line.push_str(", HDR");
+                }
+            }
+            StreamKind::Audio => {
+                if let Some(rate) = stream.sample_rate {
+                    line.push_str(&format!(", {rate} Hz"));
+                }
+                if let Some(channels) = stream.channels {
+                    line.push_str(&format!(", {channels} ch"));
+                }
+            }
+            _ => {}
+        }
+        if let Some(bit_rate) = stream.bit_rate {
+            line.push_str(&format!(", {} kb/s", bit_rate / 1000));
+        }
+        if let Some(language) = &stream.language {
+            line.push_str(&format!(" ({language})"));
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        duration.subsec_millis()
+    )
+}
@@ -0,0 +1,265 @@
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use video::json::Json;
+use video::{Error, JobQueue, JobStatus, QueuedJob};
+
+use crate::{args, config, report, settings};
+
+pub fn command() -> Command {
+    Command::new("queue")
+        .about("Queue subcommands to run one after the other, kept across restarts.")
+        .subcommand_required(true)
+        .arg(config::default(
+            Arg::new("file")
+                .long("file")
+                .global(true)
+                .help(
+                    "The file the queue is kept in, by default queue.jsonl next to the config file",
+                )
+                .value_parser(value_parser!(PathBuf)),
+            &config::get().queue,
+        ))
+        .subcommands([
+            Command::new("add")
+                .about("Queue a subcommand, e.g. `video queue add -- transcode -i a.mkv -o a.mp4`.")
+                .args([
+                    Arg::new("priority")
+                        .long("priority")
+                        .default_value("0")
+                        .allow_negative_numbers(true)
+                        .help("Jobs with a higher priority run first")
+                        .value_parser(value_parser!(i32)),
+                    Arg::new("retries")
+                        .long("retries")
+                        .default_value("0")
+                        .help("How many times a failed job is tried again")
+                        .value_parser(value_parser!(u32)),
+                    Arg::new("job")
+                        .required(true)
+                        .num_args(1..)
+                        .last(true)
+                        .help("The subcommand and its arguments")
+                        .value_parser(value_parser!(OsString)),
+                ]),
+            Command::new("status")
+                .about("List the pending, running and failed jobs.")
+                .arg(
+                    Arg::new("all")
+                        .long("all")
+                        .action(ArgAction::SetTrue)
+                        .help("Also list the finished jobs"),
+                ),
+            Command::new("work")
+                .about("Run the queued jobs, waiting for new ones when it is empty.")
+                .args([
+                    Arg::new("once")
+                        .long("once")
+                        .action(ArgAction::SetTrue)
+                        .help("Stop once the queue is empty"),
+                    Arg::new("poll")
+                        .long("poll")
+                        .default_value("5s")
+                        .help("How often an empty queue is checked for new jobs")
+                        .value_parser(args::length),
+                ]),
+            Command::new("retry")
+                .about("Queue a failed job again.")
+                .arg(job_id()),
+            Command::new("remove")
+                .about("Take a job that is not running out of the queue.")
+                .arg(job_id()),
+            Command::new("clear").about("Take the finished jobs out of the queue."),
+        ])
+}
+
+fn job_id() -> Arg {
+    Arg::new("id")
+        .required(true)
+        .help("The job's number, as `queue status` shows it")
+        .value_parser(value_parser!(u64))
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let path = match args.get_one::<PathBuf>("file") {
+        Some(path) => path.clone(),
+        None => config::path()
+            .and_then(|config| Some(config.parent()?.join("queue.jsonl")))
+            .ok_or_else(|| {
+                Error::InvalidArgument("cannot tell where to keep the queue, use --file".into())
+            })?,
+    };
+    let queue = JobQueue::open(path);
+
+    match args.subcommand() {
+        Some(("add", sub)) => add(&queue, sub),
+        Some(("status", sub)) => status(&queue, sub.get_flag("all")),
+        Some(("work", sub)) => work(&queue, sub),
+        Some(("retry", sub)) => queue.retry(*sub.get_one::<u64>("id").unwrap()),
+        Some(("remove", sub)) => queue.remove(*sub.get_one::<u64>("id").unwrap()),
+        Some(("clear", _)) => {
+            let removed = queue.clear_done()?;
+            if report::json() {
+                report::field("removed", removed);
+            } else {
+                println!("removed {removed} finished jobs");
+            }
+            Ok(())
+        }
+        _ => unreachable!("a subcommand is required"),
+    }
+}
+
+fn add(queue: &JobQueue, args: &ArgMatches) -> Result<(), Error> {
+    let job = args
+        .get_many::<OsString>("job")
+        .unwrap()
+        .map(|arg| {
+            arg.to_str().map(str::to_string).ok_or_else(|| {
+                Error::InvalidArgument(format!(
+                    "`{}` isn't valid Unicode, which the queue can't keep",
+                    arg.display()
+                ))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    // Mistakes show up now rather than when the job runs.
+    let matches = crate::command_builder()
+        .try_get_matches_from(std::iter::once("video".to_string()).chain(job.clone()))
+        .map_err(|e| {
+            let message = e.render().to_string();
+            let message = message.trim().trim_start_matches("error: ");
+            Error::InvalidArgument(message.to_string())
+        })?;
+    if matches!(matches.subcommand_name(), Some("queue" | "config")) {
+        return Err(Error::InvalidArgument(
+            "queue and config cannot be queued".into(),
+        ));
+    }
+
+    let dir = std::env::current_dir().map_err(Error::AccessDenied)?;
+    let id = queue.push(
+        job,
+        dir,
+        *args.get_one::<i32>("priority").unwrap(),
+        *args.get_one::<u32>("retries").unwrap(),
+    )?;
+    if report::json() {
+        report::field("id", id);
+    } else {
+        println!("queued job {id}");
+    }
+    Ok(())
+}
+
+fn status(queue: &JobQueue, all: bool) -> Result<(), Error> {
+    let jobs: Vec<QueuedJob> = queue
+        .jobs()?
+        .into_iter()
+        .filter(|job| all || job.status != JobStatus::Done)
+        .collect();
+    if report::json() {
+        report::field(
+            "jobs",
+            Json::Array(jobs.iter().map(QueuedJob::to_json).collect()),
+        );
+        return Ok(());
+    }
+
+    if jobs.is_empty() {
+        println!("no jobs in {}", queue.path().display());
+    }
+    for job in &jobs {
+        let mut line = format!(
+            "{:>4}  {:<8} {}",
+            job.id,
+            job.status.name(),
+            job.args.join(" ")
+        );
+        if job.priority != 0 {
+            line.push_str(&format!("  (priority {})", job.priority));
+        }
+        if job.attempts > 0 && job.status != JobStatus::Done {
+            line.push_str(&format!(
+                "  (attempt {} of {})",
+                job.attempts,
+                job.retries + 1
+            ));
+        }
+        println!("{line}");
+        if let Some(error) = &job.error
+            && job.status != JobStatus::Done
+        {
+            println!("      {error}");
+        }
+    }
+    Ok(())
+}
+
+fn work(queue: &JobQueue, args: &ArgMatches) -> Result<(), Error> {
+    let settings = settings::get();
+    if settings.dry_run {
+        let mut jobs = queue.jobs()?;
+        jobs.retain(|job| job.status == JobStatus::Pending);
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.priority));
+        for job in jobs {
+            println!("{:>4}  video {}", job.id, job.args.join(" "));
+        }
+        return Ok(());
+    }
+
+    let _worker = queue.start_worker()?;
+    let poll = *args.get_one::<Duration>("poll").unwrap();
+    let exe = std::env::current_exe().map_err(Error::CommandError)?;
+    let mut ran = Vec::new();
+    loop {
+        let Some(job) = queue.claim()? else {
+            if args.get_flag("once") {
+                break;
+            }
+            std::thread::sleep(poll);
+            continue;
+        };
+
+        if !settings.quiet {
+            eprintln!("job {}: video {}", job.id, job.args.join(" "));
+        }
+        let result = std::process::Command::new(&exe)
+            .args(&job.args)
+            .current_dir(&job.dir)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| e.to_string())
+            .and_then(|output| {
+                if output.status.success() {
+                    return Ok(());
+                }
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                Err(stderr
+                    .lines()
+                    .rfind(|line| !line.trim().is_empty())
+                    .map(|line| line.trim_start_matches("error: "))
+                    .map_or_else(|| output.status.to_string(), str::to_string))
+            });
+        let status = queue.finish(job.id, result.clone())?;
+        if !settings.quiet {
+            match (&result, status) {
+                (Ok(()), _) => eprintln!("job {}: done", job.id),
+                (Err(e), JobStatus::Pending) => eprintln!("job {}: {e}, will retry", job.id),
+                (Err(e), _) => eprintln!("job {}: failed, {e}", job.id),
+            }
+        }
+        ran.push(Json::object([
+            ("id", Json::from(job.id)),
+            ("status", status.name().into()),
+            ("error", result.err().into()),
+        ]));
+    }
+
+    if report::json() {
+        report::field("jobs", Json::Array(ran));
+    }
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use clap::{Arg, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::{Error, RecordOptions, Region, record_command};
+
+use crate::{args, report, settings, transcode};
+
+pub fn command() -> Command {
+    Command::new("record")
+        .about("Record the screen until Ctrl-C is pressed.")
+        .args([
+            args::output(),
+            Arg::new("screen")
+                .long("screen")
+                .help("The display or screen device, e.g. :0.0 or \"Capture screen 1\"")
+                .value_parser(value_parser!(String)),
+            Arg::new("region")
+                .long("region")
+                .help("Only record this part of the screen, as WxH+X+Y")
+                .value_parser(region),
+            Arg::new("fps")
+                .long("fps")
+                .default_value("30")
+                .help("Frames per second")
+                .value_parser(value_parser!(f64)),
+            Arg::new("mic")
+                .long("mic")
+                .num_args(0..=1)
+                .default_missing_value(default_microphone())
+                .help("Record a microphone too, by default the system's default input")
+                .value_parser(value_parser!(String)),
+            Arg::new("duration")
+                .long("duration")
+                .help("Stop after this long")
+                .value_parser(args::timestamp),
+        ])
+        .args(transcode::option_args())
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let options = RecordOptions {
+        screen: args.get_one::<String>("screen").cloned(),
+        region: args.get_one::<Region>("region").copied(),
+        fps: *args.get_one::<f64>("fps").unwrap(),
+        microphone: args.get_one::<String>("mic").cloned(),
+        duration: args.get_one::<Duration>("duration").copied(),
+        transcode: transcode::options(args)?,
+        ..RecordOptions::default()
+    };
+
+    let command = record_command(output, &options)?;
+    if options.duration.is_none() && !settings::get().dry_run {
+        eprintln!("Recording to {}, press Ctrl-C to stop", output.display());
+    }
+    report::execute_until_interrupted(&command, Some(output))
+}
+
+fn default_microphone() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "0"
+    } else {
+        "default"
+    }
+}
+
+/// `WxH+X+Y`, or `WxH` for the top left corner.
+fn region(value: &str) -> Result<Region, String> {
+    let invalid = || format!("invalid region `{value}`, expected WIDTHxHEIGHT+X+Y");
+    let (size, offset) = value.split_once('+').unwrap_or((value, "0+0"));
+    let (width, height) = args::size(size).map_err(|_| invalid())?;
+    let (x, y) = offset
+        .split_once('+')
+        .and_then(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)))
+        .ok_or_else(invalid)?;
+    Ok(Region {
+        x,
+        y,
+        width,
+        height,
+    })
+}
@@ -0,0 +1,88 @@
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::json::Json;
+use video::time::TimeRange;
+use video::{
+    Error, SilenceOptions, detect_silence, detect_silence_command, remove_silence_command,
+    remove_silence_preview,
+};
+
+use crate::{args, report, settings, transcode};
+
+pub fn command() -> Command {
+    Command::new("remove-silence")
+        .about("Cut the pauses out of a recording, or list where they are.")
+        .args([
+            args::input(),
+            args::output()
+                .required(false)
+                .required_unless_present("list"),
+            Arg::new("list")
+                .long("list")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("output")
+                .help("Print the silences instead of removing them"),
+            Arg::new("threshold")
+                .long("threshold")
+                .default_value("-30")
+                .allow_negative_numbers(true)
+                .help("Audio quieter than this many dBFS counts as silence")
+                .value_parser(value_parser!(f64)),
+            Arg::new("min-duration")
+                .long("min-duration")
+                .default_value("0.5")
+                .help("Pauses shorter than this are kept")
+                .value_parser(args::timestamp),
+            Arg::new("padding")
+                .long("padding")
+                .default_value("0.1")
+                .help("How much silence is kept on either side of a cut")
+                .value_parser(args::timestamp),
+        ])
+        .args(transcode::option_args())
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let options = SilenceOptions {
+        threshold: *args.get_one::<f64>("threshold").unwrap(),
+        min_duration: *args.get_one::<Duration>("min-duration").unwrap(),
+        padding: *args.get_one::<Duration>("padding").unwrap(),
+        transcode: transcode::options(args)?,
+    };
+
+    let dry_run = settings::get().dry_run;
+    let Some(output) = args.get_one::<PathBuf>("output") else {
+        if dry_run {
+            return report::execute(&detect_silence_command(input, &options)?);
+        }
+        let silences = detect_silence(input, &options)?;
+        if report::json() {
+            let silences: Vec<Json> = silences.iter().map(TimeRange::to_json).collect();
+            report::field("silences", silences);
+        } else {
+            for silence in &silences {
+                println!(
+                    "{:.3} - {:.3} ({:.3}s)",
+                    silence.start.as_secs_f64(),
+                    silence.end.as_secs_f64(),
+                    silence.duration().as_secs_f64()
+                );
+            }
+        }
+        return Ok(());
+    };
+
+    // The cut needs the silences the detection finds, so nothing can be
+    // shown of it but where they go.
+    if dry_run {
+        for command in remove_silence_preview(input, output, &options)? {
+            report::execute(&command)?;
+        }
+        return Ok(());
+    }
+
+    let command = remove_silence_command(input, output, &options)?;
+    report::execute(&command)
+}
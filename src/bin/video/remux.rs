@@ -0,0 +1,44 @@
+use clap::{ArgMatches, Command};
+use std::path::PathBuf;
+use video::{Container, Error, StreamAction, StreamPlan, remux_command, remux_plan};
+
+use crate::{args, report};
+
+pub fn command() -> Command {
+    Command::new("remux")
+        .about("Move the streams into another container without re-encoding those it can hold.")
+        .args([args::input(), args::output()])
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let plans = remux_plan(input, output)?;
+    if report::json() {
+        report::field(
+            "streams",
+            plans.iter().map(StreamPlan::to_json).collect::<Vec<_>>(),
+        );
+    } else {
+        let container = Container::from_path(output).name();
+        for plan in &plans {
+            let what = format!(
+                "stream #{} ({} {})",
+                plan.index,
+                plan.kind.name(),
+                plan.codec
+            );
+            match &plan.action {
+                StreamAction::Copy => {}
+                StreamAction::Convert(encoder) => {
+                    eprintln!("{what}: converted with {encoder}, {container} cannot hold it")
+                }
+                StreamAction::Drop => eprintln!("{what}: dropped, {container} cannot hold it"),
+            }
+        }
+    }
+
+    let command = remux_command(input, output)?;
+    report::execute(&command)
+}
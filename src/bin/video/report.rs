@@ -0,0 +1,205 @@
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use video::json::Json;
+use video::{Error, FfmpegCommand, Progress};
+
+use crate::{notify, settings};
+
+/// What `--json` prints once the subcommand is done.
+static RUNS: Mutex<Vec<Json>> = Mutex::new(Vec::new());
+static FIELDS: Mutex<Vec<(String, Json)>> = Mutex::new(Vec::new());
+
+/// Runs the command, drawing a progress bar when stderr is a terminal.
+pub fn execute(command: &FfmpegCommand) -> Result<(), Error> {
+    let settings = settings::get();
+    let mut command = command.clone().overwrite_policy(settings.overwrite);
+    if let Some(tolerance) = settings.validate {
+        command = command.validate_outputs(tolerance);
+    }
+    let command = &command;
+    if settings.dry_run {
+        // Files ffmpeg itself would create, such as pass logs, don't exist yet.
+        let kept: Vec<_> = command
+            .persist_temp_files()
+            .into_iter()
+            .filter(|f| f.exists())
+            .collect();
+        if settings.json {
+            RUNS.lock().unwrap().push(Json::object([
+                ("command_line", Json::from(command.command_line())),
+                ("outputs", command.output_paths().into()),
+                ("temp_files", kept.into()),
+            ]));
+        } else {
+            for file in kept {
+                println!("# kept {}", file.display());
+            }
+            println!("{command}");
+        }
+        return Ok(());
+    }
+
+    let started = Instant::now();
+    let result = if settings.verbose > 0 {
+        command.clone().verbose(|line| eprintln!("{line}")).run()
+    } else if !settings.quiet && std::io::stderr().is_terminal() {
+        let result = command.run_with_progress(draw_progress);
+        eprintln!();
+        result
+    } else {
+        command.run()
+    };
+
+    let result = result?;
+    if settings.json || notify::enabled() {
+        RUNS.lock().unwrap().push(Json::object([
+            ("command_line", Json::from(result.command_line.as_str())),
+            ("exit_code", result.exit_code.into()),
+            ("success", result.success().into()),
+            ("elapsed", started.elapsed().into()),
+            ("duration", command.expected_duration().into()),
+            ("outputs", command.output_paths().into()),
+        ]));
+    }
+    result.check()?;
+    Ok(())
+}
+
+/// Runs a recording or live stream that ends when Ctrl-C is pressed. Ctrl-C
+/// is left to ffmpeg, which stops and finishes `output`, instead of ending
+/// this process first.
+pub fn execute_until_interrupted(
+    command: &FfmpegCommand,
+    output: Option<&Path>,
+) -> Result<(), Error> {
+    ignore_interrupts();
+    match execute(command) {
+        // ffmpeg exits with 255 when Ctrl-C stops it, after finishing the file.
+        Err(Error::ProcessFailed(failure))
+            if failure.exit_code == Some(255) && output.is_none_or(Path::is_file) =>
+        {
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+fn ignore_interrupts() {
+    #[cfg(unix)]
+    {
+        const SIGINT: i32 = 2;
+        const SIG_IGN: usize = 1;
+        unsafe extern "C" {
+            fn signal(signum: i32, handler: usize) -> usize;
+        }
+        // SAFETY: ignoring a signal installs no handler code to go wrong.
+        unsafe {
+            signal(SIGINT, SIG_IGN);
+        }
+    }
+}
+
+/// Whether results go to stdout as JSON instead of text.
+pub fn json() -> bool {
+    settings::get().json
+}
+
+/// Adds a subcommand specific result to the `--json` output.
+pub fn field(key: &str, value: impl Into<Json>) {
+    FIELDS.lock().unwrap().push((key.to_string(), value.into()));
+}
+
+/// Reports how the subcommand went, as JSON on stdout with `--json`, and
+/// to the webhook or command that wants to know.
+pub fn finish(subcommand: &str, result: &Result<(), Error>, elapsed: Duration) {
+    if !json()
+        && let Err(e) = result
+    {
+        error(e);
+    }
+    if !json() && !notify::enabled() {
+        return;
+    }
+
+    let mut entries = vec![
+        ("command".to_string(), Json::from(subcommand)),
+        ("success".to_string(), result.is_ok().into()),
+        ("elapsed".to_string(), elapsed.into()),
+        (
+            "runs".to_string(),
+            Json::Array(std::mem::take(&mut *RUNS.lock().unwrap())),
+        ),
+    ];
+    entries.append(&mut FIELDS.lock().unwrap());
+    if let Err(e) = result {
+        let tail = match e {
+            Error::ProcessFailed(failure) => failure.stderr_tail.clone(),
+            _ => Vec::new(),
+        };
+        entries.push((
+            "error".to_string(),
+            Json::object([
+                ("message", Json::from(e.to_string())),
+                ("stderr_tail", tail.into()),
+            ]),
+        ));
+    }
+    let summary = Json::Object(entries);
+    if json() {
+        println!("{summary}");
+    }
+    notify::send(&summary);
+}
+
+pub fn error(error: &Error) {
+    eprintln!("error: {error}");
+    if let Error::ProcessFailed(failure) = error {
+        for line in &failure.stderr_tail {
+            eprintln!("  {line}");
+        }
+    }
+}
+
+fn draw_progress(progress: &Progress) {
+    let line = progress_line(progress);
+    let mut stderr = std::io::stderr();
+    let _ = write!(stderr, "\r{line}\x1b[K");
+    let _ = stderr.flush();
+}
+
+/// The bar with the time, frame rate, speed and time left.
+pub fn progress_line(progress: &Progress) -> String {
+    const WIDTH: usize = 30;
+
+    let clock = |time: Duration| {
+        let secs = time.as_secs();
+        format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    };
+    let time = clock(progress.time);
+    let mut stats = String::new();
+    if let Some(fps) = progress.fps {
+        stats.push_str(&format!(" {fps:.0} fps"));
+    }
+    if let Some(speed) = progress.speed {
+        stats.push_str(&format!(" {speed:.2}x"));
+    }
+    if let Some(eta) = progress.eta
+        && !progress.done
+    {
+        stats.push_str(&format!(" ETA {}", clock(eta)));
+    }
+
+    match progress.percent {
+        Some(percent) => {
+            let filled = (percent / 100.0 * WIDTH as f64).round() as usize;
+            format!(
+                "[{}{}] {percent:5.1}% {time}{stats}",
+                "#".repeat(filled),
+                "-".repeat(WIDTH - filled.min(WIDTH))
+            )
+        }
+        None => format!("{time}{stats}"),
+    }
+}
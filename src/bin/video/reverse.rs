@@ -0,0 +1,36 @@
+use clap::{Arg, ArgMatches, Command};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::{Error, ReverseOptions, reverse_passes};
+
+use crate::{args, report, transcode};
+
+pub fn command() -> Command {
+    Command::new("reverse")
+        .about("Play a video and its audio backwards.")
+        .args([
+            args::input(),
+            args::output(),
+            Arg::new("segment-length")
+                .long("segment-length")
+                .default_value("30s")
+                .help("Longer inputs are reversed in pieces this long, to bound the memory used")
+                .value_parser(args::length),
+        ])
+        .args(transcode::option_args())
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let options = ReverseOptions {
+        segment_length: *args.get_one::<Duration>("segment-length").unwrap(),
+        transcode: transcode::options(args)?,
+    };
+
+    for command in reverse_passes(input, output, &options)? {
+        report::execute(&command)?;
+    }
+    Ok(())
+}
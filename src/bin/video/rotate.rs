@@ -0,0 +1,59 @@
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::path::PathBuf;
+use video::{Error, RotateMode, RotateOptions, Rotation, rotate_command};
+
+use crate::{args, report, transcode};
+
+pub fn command() -> Command {
+    Command::new("rotate")
+        .about("Rotate or flip a video.")
+        .args([
+            args::input(),
+            args::output(),
+            Arg::new("degrees")
+                .long("degrees")
+                .default_value("0")
+                .help("The clockwise rotation")
+                .value_parser(["0", "90", "180", "270"]),
+            Arg::new("hflip")
+                .long("hflip")
+                .action(ArgAction::SetTrue)
+                .help("Mirror the video left to right"),
+            Arg::new("vflip")
+                .long("vflip")
+                .action(ArgAction::SetTrue)
+                .help("Mirror the video top to bottom"),
+            Arg::new("reencode")
+                .long("reencode")
+                .action(ArgAction::SetTrue)
+                .help("Transform the frames instead of only setting the rotation metadata"),
+        ])
+        .args(
+            transcode::option_args()
+                .into_iter()
+                .map(|arg| arg.requires("reencode")),
+        )
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let mode = if args.get_flag("reencode") {
+        RotateMode::Reencode(transcode::options(args)?)
+    } else {
+        RotateMode::Metadata
+    };
+    let options = RotateOptions {
+        rotation: args
+            .get_one::<String>("degrees")
+            .unwrap()
+            .parse::<Rotation>()?,
+        flip_horizontal: args.get_flag("hflip"),
+        flip_vertical: args.get_flag("vflip"),
+        mode,
+    };
+
+    let command = rotate_command(input, output, &options)?;
+    report::execute(&command)
+}
@@ -0,0 +1,49 @@
+use clap::{Arg, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use video::{Error, ScaleFit, ScaleOptions, scale_command};
+
+use crate::{args, report, transcode};
+
+pub fn command() -> Command {
+    Command::new("scale")
+        .about("Resize a video, keeping the aspect ratio unless told otherwise.")
+        .args([
+            args::input(),
+            args::output(),
+            Arg::new("width")
+                .long("width")
+                .allow_negative_numbers(true)
+                .help("The width in pixels, negative to derive it from the height")
+                .value_parser(value_parser!(i32)),
+            Arg::new("height")
+                .long("height")
+                .allow_negative_numbers(true)
+                .help("The height in pixels, negative to derive it from the width")
+                .value_parser(value_parser!(i32)),
+            Arg::new("fit")
+                .long("fit")
+                .default_value("pad")
+                .help("How to fit a different aspect ratio when both sizes are set")
+                .value_parser(["pad", "crop", "stretch"]),
+        ])
+        .args(transcode::option_args())
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let size = |name| {
+        args.get_one::<i32>(name)
+            .and_then(|&v| u32::try_from(v).ok())
+    };
+    let options = ScaleOptions {
+        width: size("width"),
+        height: size("height"),
+        fit: args.get_one::<String>("fit").unwrap().parse::<ScaleFit>()?,
+        transcode: transcode::options(args)?,
+    };
+
+    let command = scale_command(input, output, &options)?;
+    report::execute(&command)
+}
@@ -0,0 +1,602 @@
+//! A small HTTP API for other services to run jobs through, one thread per
+//! connection and a fixed number of worker threads running the jobs.
+//!
+//! - `POST /jobs` with `{"operation": "transcode", "input": …, "output":
+//!   "name.mp4", "options": {…}}`, `"concat"` with `"inputs"` instead, or
+//!   `"probe"` with only an input, answers with the new job.
+//! - `GET /jobs` and `GET /jobs/ID` tell how far the jobs are.
+//! - `GET /jobs/ID/output` downloads the output of a finished job.
+//! - `DELETE /jobs/ID` cancels a job that hasn't finished.
+
+use clap::{Arg, ArgMatches, Command, value_parser};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use video::json::Json;
+use video::{
+    CancelToken, ConcatOptions, Error, FfmpegCommand, Progress, TranscodeOptions,
+    concat_files_command, probe, transcode_command,
+};
+
+use crate::{config, settings};
+
+/// Requests larger than this are refused.
+const MAX_BODY: usize = 1 << 20;
+
+/// Longer request and header lines are refused, and so are more headers.
+const MAX_LINE: usize = 8 << 10;
+const MAX_HEADERS: usize = 100;
+
+/// A client that sends or reads nothing for this long is dropped.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub fn command() -> Command {
+    Command::new("serve")
+        .about("Accept concat, transcode and probe jobs over HTTP.")
+        .args([
+            Arg::new("bind")
+                .long("bind")
+                .default_value("127.0.0.1:8080")
+                .help("The address to listen on; anyone who can reach it can run jobs")
+                .value_parser(value_parser!(String)),
+            Arg::new("dir")
+                .long("dir")
+                .default_value("jobs")
+                .help("The folder the outputs are written to, one subfolder per job")
+                .value_parser(value_parser!(PathBuf)),
+            config::default(
+                Arg::new("jobs")
+                    .short('j')
+                    .long("jobs")
+                    .default_value("1")
+                    .help("How many jobs run at once")
+                    .value_parser(value_parser!(usize)),
+                &config::get().jobs,
+            ),
+        ])
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Queued,
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl Status {
+    fn name(self) -> &'static str {
+        match self {
+            Status::Queued => "queued",
+            Status::Running => "running",
+            Status::Done => "done",
+            Status::Failed => "failed",
+            Status::Cancelled => "cancelled",
+        }
+    }
+
+    fn is_finished(self) -> bool {
+        matches!(self, Status::Done | Status::Failed | Status::Cancelled)
+    }
+}
+
+#[derive(Debug)]
+enum Task {
+    Transcode {
+        input: PathBuf,
+        options: TranscodeOptions,
+    },
+    Concat {
+        inputs: Vec<PathBuf>,
+        options: ConcatOptions,
+    },
+    Probe {
+        input: PathBuf,
+    },
+}
+
+impl Task {
+    fn name(&self) -> &'static str {
+        match self {
+            Task::Transcode { .. } => "transcode",
+            Task::Concat { .. } => "concat",
+            Task::Probe { .. } => "probe",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Job {
+    id: u64,
+    operation: &'static str,
+    status: Status,
+    percent: Option<f64>,
+    speed: Option<f64>,
+    eta: Option<Duration>,
+    error: Option<String>,
+    output: Option<PathBuf>,
+    /// What a probe found.
+    result: Option<Json>,
+    token: CancelToken,
+    submitted: Instant,
+    started: Option<Instant>,
+    finished: Option<Instant>,
+}
+
+impl Job {
+    fn to_json(&self) -> Json {
+        let seconds = |from: Option<Instant>, to: Option<Instant>| {
+            from.map(|from| to.unwrap_or_else(Instant::now).duration_since(from))
+        };
+        let download = (self.status == Status::Done && self.output.is_some())
+            .then(|| format!("/jobs/{}/output", self.id));
+        Json::object([
+            ("id", Json::from(self.id)),
+            ("operation", self.operation.into()),
+            ("status", self.status.name().into()),
+            ("percent", self.percent.into()),
+            ("speed", self.speed.into()),
+            ("eta_seconds", self.eta.into()),
+            ("error", self.error.clone().into()),
+            ("download", download.into()),
+            ("result", self.result.clone().unwrap_or(Json::Null)),
+            (
+                "queued_seconds",
+                seconds(Some(self.submitted), self.started).into(),
+            ),
+            ("run_seconds", seconds(self.started, self.finished).into()),
+        ])
+    }
+}
+
+#[derive(Debug, Default)]
+struct Jobs {
+    jobs: Mutex<Vec<Job>>,
+}
+
+impl Jobs {
+    fn update(&self, id: u64, change: impl FnOnce(&mut Job)) {
+        if let Some(job) = self
+            .jobs
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|job| job.id == id)
+        {
+            change(job);
+        }
+    }
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let bind = args.get_one::<String>("bind").unwrap();
+    let dir = args.get_one::<PathBuf>("dir").unwrap().clone();
+    let workers = (*args.get_one::<usize>("jobs").unwrap()).max(1);
+    std::fs::create_dir_all(&dir).map_err(Error::CreateOutputError)?;
+
+    let listener = TcpListener::bind(bind)
+        .map_err(|e| Error::InvalidArgument(format!("cannot listen on {bind}: {e}")))?;
+    if !settings::get().quiet {
+        eprintln!("Listening on http://{bind}, press Ctrl-C to stop");
+    }
+
+    let jobs = Arc::new(Jobs::default());
+    let (sender, receiver) = mpsc::channel::<(u64, Task, PathBuf)>();
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..workers {
+        let jobs = Arc::clone(&jobs);
+        let receiver = Arc::clone(&receiver);
+        std::thread::spawn(move || work(&jobs, &receiver));
+    }
+
+    let next_id = Arc::new(Mutex::new(1));
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        if stream.set_read_timeout(Some(IDLE_TIMEOUT)).is_err()
+            || stream.set_write_timeout(Some(IDLE_TIMEOUT)).is_err()
+        {
+            continue;
+        }
+        let jobs = Arc::clone(&jobs);
+        let sender = sender.clone();
+        let next_id = Arc::clone(&next_id);
+        let dir = dir.clone();
+        std::thread::spawn(move || {
+            let mut stream = stream;
+            let response = match read_request(&mut stream) {
+                Ok(request) => handle(request, &jobs, |operation, output| {
+                    let mut next = next_id.lock().unwrap();
+                    let id = *next;
+                    *next += 1;
+                    let output = output.map(|name| dir.join(id.to_string()).join(name));
+                    jobs.jobs.lock().unwrap().push(Job {
+                        id,
+                        operation: operation.name(),
+                        status: Status::Queued,
+                        percent: None,
+                        speed: None,
+                        eta: None,
+                        error: None,
+                        output: output.clone(),
+                        result: None,
+                        token: CancelToken::new(),
+                        submitted: Instant::now(),
+                        started: None,
+                        finished: None,
+                    });
+                    let _ = sender.send((id, operation, output.unwrap_or_default()));
+                    id
+                }),
+                Err(message) => Response::error(400, message),
+            };
+            let _ = response.write(&mut stream);
+        });
+    }
+    Ok(())
+}
+
+/// Runs queued jobs until the server stops.
+fn work(jobs: &Jobs, receiver: &Mutex<Receiver<(u64, Task, PathBuf)>>) {
+    loop {
+        let Ok((id, operation, output)) = receiver.lock().unwrap().recv() else {
+            return;
+        };
+        let mut token = None;
+        jobs.update(id, |job| {
+            if job.status == Status::Queued {
+                job.status = Status::Running;
+                job.started = Some(Instant::now());
+                token = Some(job.token.clone());
+            }
+        });
+        // Cancelled while it waited.
+        let Some(token) = token else { continue };
+
+        let result = run_job(&operation, &output, token, |progress| {
+            jobs.update(id, |job| {
+                job.percent = progress.percent;
+                job.speed = progress.speed;
+                job.eta = progress.eta;
+            })
+        });
+        jobs.update(id, |job| {
+            job.finished = Some(Instant::now());
+            match result {
+                Ok(found) => {
+                    job.status = Status::Done;
+                    job.percent = Some(100.0);
+                    job.eta = None;
+                    job.result = found;
+                }
+                Err(Error::Cancelled) => job.status = Status::Cancelled,
+                Err(e) => {
+                    job.status = Status::Failed;
+                    job.error = Some(e.to_string());
+                }
+            }
+        });
+    }
+}
+
+fn run_job(
+    operation: &Task,
+    output: &Path,
+    token: CancelToken,
+    on_progress: impl FnMut(&Progress),
+) -> Result<Option<Json>, Error> {
+    let command = match operation {
+        Task::Probe { input } => return Ok(Some(probe(input)?.to_json())),
+        Task::Transcode { input, options } => transcode_command(input, output, options)?,
+        Task::Concat { inputs, options } => concat_files_command(inputs, output, options)?,
+    };
+    if let Some(dir) = output.parent() {
+        std::fs::create_dir_all(dir).map_err(Error::CreateOutputError)?;
+    }
+    let command: FfmpegCommand = command.overwrite(true).cancel_token(token);
+    command.run_with_progress(on_progress)?.check()?;
+    Ok(None)
+}
+
+#[derive(Debug)]
+struct Request {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+}
+
+fn read_request(stream: &mut TcpStream) -> Result<Request, String> {
+    let mut reader = BufReader::new(stream);
+    let line = read_line(&mut reader)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err("malformed request line".to_string());
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+
+    let mut length = 0;
+    for count in 0.. {
+        let header = read_line(&mut reader)?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if count == MAX_HEADERS {
+            return Err(format!("there are over {MAX_HEADERS} headers"));
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            length = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid content length `{}`", value.trim()))?;
+        }
+    }
+    if length > MAX_BODY {
+        return Err(format!("the body is over {MAX_BODY} bytes"));
+    }
+    let mut body = vec![0; length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|e| format!("cannot read the body: {e}"))?;
+    Ok(Request { method, path, body })
+}
+
+/// Reads one line of the request head, of at most [`MAX_LINE`] bytes.
+fn read_line(reader: &mut impl BufRead) -> Result<String, String> {
+    let mut line = String::new();
+    reader
+        .take(MAX_LINE as u64 + 1)
+        .read_line(&mut line)
+        .map_err(|e| format!("cannot read the request: {e}"))?;
+    if line.len() > MAX_LINE {
+        return Err(format!("a line of the request is over {MAX_LINE} bytes"));
+    }
+    Ok(line)
+}
+
+fn handle(
+    request: Request,
+    jobs: &Jobs,
+    submit: impl FnOnce(Task, Option<String>) -> u64,
+) -> Response {
+    let path = request.path.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let id = segments.get(1).and_then(|id| id.parse::<u64>().ok());
+    let find = |id| {
+        let list = jobs.jobs.lock().unwrap();
+        list.iter().find(|job| job.id == id).map(Job::to_json)
+    };
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("POST", ["jobs"]) => {
+            let body = String::from_utf8_lossy(&request.body);
+            let json = match Json::parse(&body) {
+                Ok(json) => json,
+                Err(_) => return Response::error(400, "the body is not JSON".to_string()),
+            };
+            match parse_job(&json) {
+                Ok((operation, output)) => {
+                    let id = submit(operation, output);
+                    Response::json(202, find(id).unwrap_or(Json::Null))
+                }
+                Err(message) => Response::error(400, message),
+            }
+        }
+        ("GET", ["jobs"]) => {
+            let list = jobs.jobs.lock().unwrap();
+            Response::json(
+                200,
+                Json::object([("jobs", Json::Array(list.iter().map(Job::to_json).collect()))]),
+            )
+        }
+        ("GET", ["jobs", _]) => match id.and_then(find) {
+            Some(job) => Response::json(200, job),
+            None => Response::error(404, "no such job".to_string()),
+        },
+        ("GET", ["jobs", _, "output"]) => {
+            let list = jobs.jobs.lock().unwrap();
+            let Some(job) = id.and_then(|id| list.iter().find(|job| job.id == id)) else {
+                return Response::error(404, "no such job".to_string());
+            };
+            match (&job.output, job.status) {
+                (Some(output), Status::Done) => match File::open(output) {
+                    Ok(file) => Response::file(file, output),
+                    Err(e) => Response::error(500, format!("cannot read the output: {e}")),
+                },
+                (None, _) => Response::error(404, "the job has no output file".to_string()),
+                (Some(_), status) => Response::error(409, format!("the job is {}", status.name())),
+            }
+        }
+        ("DELETE", ["jobs", _]) => {
+            let mut list = jobs.jobs.lock().unwrap();
+            let Some(job) = id.and_then(|id| list.iter_mut().find(|job| job.id == id)) else {
+                return Response::error(404, "no such job".to_string());
+            };
+            if job.status.is_finished() {
+                return Response::error(409, format!("the job is {}", job.status.name()));
+            }
+            job.token.cancel();
+            if job.status == Status::Queued {
+                job.status = Status::Cancelled;
+                job.finished = Some(Instant::now());
+            }
+            Response::json(202, job.to_json())
+        }
+        (_, ["jobs", ..]) => Response::error(405, "method not allowed".to_string()),
+        _ => Response::error(404, "not found".to_string()),
+    }
+}
+
+/// The operation a `POST /jobs` body asks for and its output's file name.
+fn parse_job(json: &Json) -> Result<(Task, Option<String>), String> {
+    let text = |key: &str| json.get(key).and_then(Json::as_str);
+    let input = || {
+        text("input")
+            .map(PathBuf::from)
+            .ok_or_else(|| "`input` is missing".to_string())
+    };
+    let output = || {
+        let name = text("output").ok_or_else(|| "`output` is missing".to_string())?;
+        // The output stays in its job's folder, and its name goes in a header.
+        if name.is_empty()
+            || name == ".."
+            || name.starts_with('.')
+            || name.contains(['/', '\\'])
+            || name.contains(char::is_control)
+        {
+            return Err(format!("`output` must be a plain file name, not `{name}`"));
+        }
+        Ok(name.to_string())
+    };
+    let transcode = transcode_options(json.get("options"))?;
+
+    match text("operation") {
+        Some("transcode") => Ok((
+            Task::Transcode {
+                input: input()?,
+                options: transcode,
+            },
+            Some(output()?),
+        )),
+        Some("concat") => {
+            let inputs = json
+                .get("inputs")
+                .and_then(Json::as_array)
+                .ok_or_else(|| "`inputs` is missing".to_string())?
+                .iter()
+                .map(|input| input.as_str().map(PathBuf::from))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| "`inputs` must be a list of paths".to_string())?;
+            let options = ConcatOptions {
+                transcode,
+                ..ConcatOptions::default()
+            };
+            Ok((Task::Concat { inputs, options }, Some(output()?)))
+        }
+        Some("probe") => Ok((Task::Probe { input: input()? }, None)),
+        Some(other) => Err(format!(
+            "unknown operation `{other}`, expected transcode, concat or probe"
+        )),
+        None => Err("`operation` is missing".to_string()),
+    }
+}
+
+/// The `options` of a job, named like the transcode flags.
+fn transcode_options(json: Option<&Json>) -> Result<TranscodeOptions, String> {
+    let mut options = TranscodeOptions::default();
+    let Some(json) = json else {
+        return Ok(options);
+    };
+    let Json::Object(entries) = json else {
+        return Err("`options` must be an object".to_string());
+    };
+    for (key, value) in entries {
+        let text = || {
+            value
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| format!("`{key}` must be a string"))
+        };
+        match key.as_str() {
+            "vcodec" => options.video_codec = Some(text()?),
+            "acodec" => options.audio_codec = Some(text()?),
+            "preset" => options.preset = Some(text()?),
+            "vbitrate" => options.video_bitrate = Some(text()?),
+            "abitrate" => options.audio_bitrate = Some(text()?),
+            "crf" => {
+                options.crf = Some(
+                    value
+                        .as_u64()
+                        .filter(|crf| *crf <= 51)
+                        .ok_or_else(|| "`crf` must be a number from 0 to 51".to_string())?
+                        as u8,
+                )
+            }
+            "faststart" => match value {
+                Json::Bool(faststart) => options.faststart = *faststart,
+                _ => return Err("`faststart` must be true or false".to_string()),
+            },
+            key => return Err(format!("unknown option `{key}`")),
+        }
+    }
+    Ok(options)
+}
+
+enum Body {
+    Json(Json),
+    File(File, u64),
+}
+
+struct Response {
+    status: u16,
+    body: Body,
+    file_name: Option<String>,
+}
+
+impl Response {
+    fn json(status: u16, json: Json) -> Self {
+        Response {
+            status,
+            body: Body::Json(json),
+            file_name: None,
+        }
+    }
+
+    fn error(status: u16, message: String) -> Self {
+        Self::json(status, Json::object([("error", message)]))
+    }
+
+    fn file(file: File, path: &Path) -> Self {
+        let length = file.metadata().map_or(0, |metadata| metadata.len());
+        Response {
+            status: 200,
+            body: Body::File(file, length),
+            file_name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().replace('"', "")),
+        }
+    }
+
+    fn write(self, stream: &mut TcpStream) -> io::Result<()> {
+        let reason = match self.status {
+            200 => "OK",
+            202 => "Accepted",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            409 => "Conflict",
+            _ => "Internal Server Error",
+        };
+        let (content_type, length, body) = match self.body {
+            Body::Json(json) => {
+                let text = json.to_string();
+                ("application/json", text.len() as u64, Ok(text))
+            }
+            Body::File(file, length) => ("application/octet-stream", length, Err(file)),
+        };
+        let mut head = format!(
+            "HTTP/1.1 {} {reason}\r\nContent-Type: {content_type}\r\nContent-Length: {length}\r\nConnection: close\r\n",
+            self.status
+        );
+        if let Some(name) = &self.file_name {
+            head.push_str(&format!(
+                "Content-Disposition: attachment; filename=\"{name}\"\r\n"
+            ));
+        }
+        head.push_str("\r\n");
+        stream.write_all(head.as_bytes())?;
+        match body {
+            Ok(text) => stream.write_all(text.as_bytes())?,
+            Err(mut file) => {
+                io::copy(&mut file, stream)?;
+            }
+        }
+        stream.flush()
+    }
+}
@@ -0,0 +1,132 @@
+use clap::ArgMatches;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+use video::log::{self, Level};
+use video::{FfmpegCommand, NetworkOptions, OverwritePolicy, ResourceLimits, RetryPolicy, Tool};
+
+use crate::config;
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// Options given before the subcommand that apply to all of them.
+#[derive(Debug, Default)]
+pub struct Settings {
+    /// How many times `-v` was given. Any streams ffmpeg's own output
+    /// instead of a progress bar.
+    pub verbose: u8,
+    /// Neither progress nor log messages, only errors.
+    pub quiet: bool,
+    /// Print the ffmpeg command lines instead of running them.
+    pub dry_run: bool,
+    /// Print the results as JSON instead of text.
+    pub json: bool,
+    /// From `--overwrite`/`--no-overwrite`, else the config file.
+    pub overwrite: OverwritePolicy,
+    /// How long each ffmpeg run may take, also installed as the default of
+    /// every [`FfmpegCommand`].
+    pub timeout: Option<Duration>,
+    /// The duration tolerance outputs are validated with, if at all.
+    pub validate: Option<Duration>,
+    /// Where to report how the subcommand went, see [`crate::notify`].
+    pub notify_url: Option<String>,
+    pub notify_command: Option<String>,
+}
+
+/// Warns through the logger [`init`] installs, so `--quiet` silences it like
+/// the library's own warnings.
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        video::log::dispatch(video::log::Level::Warn, module_path!(), format_args!($($arg)*))
+    };
+}
+
+pub(crate) use warning;
+
+pub fn init(matches: &ArgMatches) {
+    let settings = Settings {
+        verbose: matches.get_count("verbose"),
+        quiet: matches.get_flag("quiet"),
+        dry_run: matches.get_flag("dry-run"),
+        json: matches.get_flag("json"),
+        overwrite: if matches.get_flag("overwrite") {
+            OverwritePolicy::Always
+        } else if matches.get_flag("no-overwrite") {
+            OverwritePolicy::Never
+        } else {
+            config::overwrite().unwrap_or_default()
+        },
+        timeout: matches.get_one::<Duration>("timeout").copied(),
+        validate: matches.get_one::<Duration>("validate").copied(),
+        notify_url: matches.get_one::<String>("notify-url").cloned(),
+        notify_command: matches.get_one::<String>("notify-command").cloned(),
+    };
+    log::set_max_level(match (settings.quiet, settings.verbose) {
+        (true, _) => Some(Level::Error),
+        (false, 0) => Some(Level::Warn),
+        (false, 1) => Some(Level::Info),
+        (false, 2) => Some(Level::Debug),
+        (false, _) => Some(Level::Trace),
+    });
+    log::set_logger(|record| eprintln!("{}: {}", record.level, record.message));
+    FfmpegCommand::install_timeout(settings.timeout);
+    let _ = SETTINGS.set(settings);
+
+    // Flags win over the environment, which `Tool::from_env` reads, and the
+    // environment over the config file.
+    let config = config::get();
+    let ffmpeg = matches
+        .get_one::<PathBuf>("ffmpeg")
+        .cloned()
+        .or_else(|| config.ffmpeg.as_ref().map(PathBuf::from));
+    let ffprobe = matches
+        .get_one::<PathBuf>("ffprobe")
+        .cloned()
+        .or_else(|| config.ffprobe.as_ref().map(PathBuf::from));
+    if ffmpeg.is_some() || ffprobe.is_some() {
+        let mut tool = ffmpeg.map_or_else(Tool::from_env, Tool::with_binary);
+        if let Some(ffprobe) = ffprobe {
+            tool = tool.with_ffprobe(ffprobe);
+        }
+        tool.install();
+    }
+
+    let mut network = NetworkOptions {
+        timeout: matches.get_one::<Duration>("network-timeout").copied(),
+        ..NetworkOptions::default()
+    };
+    for protocol in matches
+        .get_many::<String>("allow-protocol")
+        .into_iter()
+        .flatten()
+    {
+        let protocol = protocol.to_ascii_lowercase();
+        if !network.protocols.contains(&protocol) {
+            network.protocols.push(protocol);
+        }
+    }
+    network.install();
+
+    RetryPolicy {
+        retries: *matches.get_one::<u32>("retry").unwrap(),
+        delay: *matches.get_one::<Duration>("retry-delay").unwrap(),
+        ..RetryPolicy::default()
+    }
+    .install();
+
+    ResourceLimits {
+        nice: matches.get_one::<i32>("nice").copied(),
+        idle_io: matches.get_flag("idle-io"),
+        threads: matches.get_one::<u32>("threads").copied(),
+        cpu_limit: matches.get_one::<u32>("cpu-limit").copied(),
+        cpu_limiter: matches
+            .get_one::<String>("cpu-limiter")
+            .and_then(|limiter| limiter.parse().ok())
+            .unwrap_or_default(),
+    }
+    .install();
+}
+
+pub fn get() -> &'static Settings {
+    SETTINGS.get_or_init(Settings::default)
+}
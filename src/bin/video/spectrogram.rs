@@ -0,0 +1,42 @@
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use video::{Error, SpectrogramOptions, spectrogram_command};
+
+use crate::{args, report};
+
+pub fn command() -> Command {
+    Command::new("spectrogram")
+        .about("Draw the spectrum of an audio track over time as a PNG.")
+        .args([
+            args::input(),
+            args::output(),
+            Arg::new("size")
+                .long("size")
+                .default_value("1280x512")
+                .help("The size of the spectrum, without the legend")
+                .value_parser(args::size),
+            Arg::new("color")
+                .long("color")
+                .default_value("intensity")
+                .help("The color scheme, e.g. intensity, rainbow, fire, viridis or magma")
+                .value_parser(value_parser!(String)),
+            Arg::new("no-legend")
+                .long("no-legend")
+                .action(ArgAction::SetTrue)
+                .help("Leave out the time and frequency axes"),
+        ])
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let options = SpectrogramOptions {
+        size: *args.get_one::<(u32, u32)>("size").unwrap(),
+        color: args.get_one::<String>("color").unwrap().clone(),
+        legend: !args.get_flag("no-legend"),
+    };
+
+    let command = spectrogram_command(input, output, &options)?;
+    report::execute(&command)
+}
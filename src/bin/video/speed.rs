@@ -0,0 +1,38 @@
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use video::{Error, SpeedOptions, speed_command};
+
+use crate::{args, report, transcode};
+
+pub fn command() -> Command {
+    Command::new("speed")
+        .about("Change the playback rate for slow motion or a timelapse.")
+        .args([
+            args::input(),
+            args::output(),
+            Arg::new("factor")
+                .long("factor")
+                .required(true)
+                .help("The playback rate, e.g. 0.5 or 4")
+                .value_parser(value_parser!(f64)),
+            Arg::new("mute")
+                .long("mute")
+                .action(ArgAction::SetTrue)
+                .help("Drop the audio instead of changing its tempo"),
+        ])
+        .args(transcode::option_args())
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let options = SpeedOptions {
+        factor: *args.get_one::<f64>("factor").unwrap(),
+        mute: args.get_flag("mute"),
+        transcode: transcode::options(args)?,
+    };
+
+    let command = speed_command(input, output, &options)?;
+    report::execute(&command)
+}
@@ -0,0 +1,96 @@
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::time::format_timestamp;
+use video::{
+    Error, SplitOptions, SplitPoints, TrimMode, detect_scenes, split_command, split_preview,
+    written_segments,
+};
+
+use crate::{args, report, settings, transcode};
+
+pub fn command() -> Command {
+    Command::new("split")
+        .about("Split a video into fixed-length segments or at scene changes.")
+        .args([
+            args::input(),
+            args::output().help("The output pattern, e.g. out_%03d.mp4 or {stem}_{index}.{ext}"),
+            Arg::new("segment-time")
+                .long("segment-time")
+                .default_value("600")
+                .help("The length of each segment")
+                .value_parser(args::timestamp),
+            Arg::new("scenes")
+                .long("scenes")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("segment-time")
+                .help("Cut at shot boundaries instead of fixed lengths"),
+            Arg::new("threshold")
+                .long("threshold")
+                .requires("scenes")
+                .default_value("0.4")
+                .help("The scene change score (0-1) that counts as a cut")
+                .value_parser(value_parser!(f64)),
+            Arg::new("reencode")
+                .long("reencode")
+                .action(ArgAction::SetTrue)
+                .help("Re-encode to cut at the exact times instead of the next keyframe"),
+        ])
+        .args(
+            transcode::option_args()
+                .into_iter()
+                .map(|arg| arg.requires("reencode")),
+        )
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = &args::render_output(args.get_one::<PathBuf>("output").unwrap(), input)?;
+
+    let mode = if args.get_flag("reencode") {
+        TrimMode::Reencode(transcode::options(args)?)
+    } else {
+        TrimMode::Copy
+    };
+    let points = if args.get_flag("scenes") {
+        let threshold = *args.get_one::<f64>("threshold").unwrap();
+        // The split needs the scenes the detection finds, so nothing can be
+        // shown of it but where they go.
+        if settings::get().dry_run {
+            let options = SplitOptions {
+                points: SplitPoints::Scenes(threshold),
+                mode,
+            };
+            for command in split_preview(input, output, &options)? {
+                report::execute(&command)?;
+            }
+            return Ok(());
+        }
+        let cuts = detect_scenes(input, threshold)?;
+        if report::json() {
+            report::field("scenes", cuts.clone());
+        } else {
+            for cut in &cuts {
+                println!("scene at {}", format_timestamp(*cut));
+            }
+        }
+        SplitPoints::At(cuts)
+    } else {
+        SplitPoints::Every(*args.get_one::<Duration>("segment-time").unwrap())
+    };
+    let options = SplitOptions { points, mode };
+
+    let command = split_command(input, output, &options)?;
+    report::execute(&command)?;
+    if !settings::get().dry_run {
+        let segments = written_segments(output);
+        if report::json() {
+            report::field("segments", segments);
+        } else {
+            for segment in segments {
+                println!("{}", segment.display());
+            }
+        }
+    }
+    Ok(())
+}
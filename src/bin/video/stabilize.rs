@@ -0,0 +1,48 @@
+use clap::{Arg, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use video::{Error, StabilizeOptions, stabilize_passes};
+
+use crate::{args, report, transcode};
+
+pub fn command() -> Command {
+    Command::new("stabilize")
+        .about("Steady shaky handheld footage, analysing the camera motion in a first pass.")
+        .args([
+            args::input(),
+            args::output(),
+            Arg::new("shakiness")
+                .long("shakiness")
+                .default_value("5")
+                .help("How shaky the footage is, from 1 to 10")
+                .value_parser(value_parser!(u8).range(1..=10)),
+            Arg::new("smoothing")
+                .long("smoothing")
+                .default_value("10")
+                .help("How many frames on each side the motion is smoothed over")
+                .value_parser(value_parser!(u32)),
+            Arg::new("zoom")
+                .long("zoom")
+                .help(
+                    "Zoom in by this many percent to hide the borders, by default as far as needed",
+                )
+                .value_parser(value_parser!(f64)),
+        ])
+        .args(transcode::option_args())
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let options = StabilizeOptions {
+        shakiness: *args.get_one::<u8>("shakiness").unwrap(),
+        smoothing: *args.get_one::<u32>("smoothing").unwrap(),
+        zoom: args.get_one::<f64>("zoom").copied(),
+        transcode: transcode::options(args)?,
+    };
+
+    for command in stabilize_passes(input, output, &options)? {
+        report::execute(&command)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,136 @@
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::{
+    CaptureOptions, Error, RecordOptions, StreamOptions, StreamSource, is_connection_error,
+    stream_command,
+};
+
+use crate::{args, report, settings, transcode};
+
+pub fn command() -> Command {
+    Command::new("stream")
+        .about("Send a file, a camera or the screen live to an RTMP or SRT server.")
+        .args([
+            Arg::new("url")
+                .required(true)
+                .help("The server, e.g. rtmp://live.example.com/app/KEY or srt://host:9000")
+                .value_parser(value_parser!(String)),
+            args::input().required(false),
+            Arg::new("camera")
+                .long("camera")
+                .num_args(0..=1)
+                .default_missing_value("")
+                .help("Stream a camera, by default the first one")
+                .value_parser(value_parser!(String)),
+            Arg::new("screen")
+                .long("screen")
+                .num_args(0..=1)
+                .default_missing_value("")
+                .help("Stream the screen, by default the main one")
+                .value_parser(value_parser!(String)),
+            Arg::new("loop")
+                .long("loop")
+                .action(ArgAction::SetTrue)
+                .requires("input")
+                .help("Start the file over when it ends"),
+            Arg::new("size")
+                .long("size")
+                .requires("camera")
+                .help("The resolution to ask the camera for, as WxH")
+                .value_parser(args::size),
+            Arg::new("fps")
+                .long("fps")
+                .conflicts_with("input")
+                .help("Frames per second of the camera or screen")
+                .value_parser(value_parser!(f64)),
+            Arg::new("mic")
+                .long("mic")
+                .conflicts_with("input")
+                .help("Send this microphone along with the camera or screen")
+                .value_parser(value_parser!(String)),
+            Arg::new("keyframe-interval")
+                .long("keyframe-interval")
+                .default_value("2")
+                .help("Time between keyframes")
+                .value_parser(args::timestamp),
+            Arg::new("reconnect")
+                .long("reconnect")
+                .default_value("5")
+                .help("How often to reconnect after the connection drops")
+                .value_parser(value_parser!(u32)),
+            Arg::new("reconnect-delay")
+                .long("reconnect-delay")
+                .default_value("2")
+                .help("How long to wait before reconnecting")
+                .value_parser(args::timestamp),
+        ])
+        .group(
+            ArgGroup::new("source")
+                .args(["input", "camera", "screen"])
+                .required(true),
+        )
+        .args(transcode::option_args())
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let url = args.get_one::<String>("url").unwrap();
+    let size = args.get_one::<(u32, u32)>("size").copied();
+    let fps = args.get_one::<f64>("fps").copied();
+    let microphone = args.get_one::<String>("mic").cloned();
+    let chosen = |name: &str| {
+        args.get_one::<String>(name)
+            .filter(|value| !value.is_empty())
+            .cloned()
+    };
+
+    let source = if let Some(input) = args.get_one::<PathBuf>("input") {
+        StreamSource::File(input.clone())
+    } else if args.contains_id("camera") {
+        StreamSource::Camera(CaptureOptions {
+            device: chosen("camera"),
+            size,
+            fps,
+            microphone,
+            ..CaptureOptions::default()
+        })
+    } else {
+        let defaults = RecordOptions::default();
+        StreamSource::Screen(RecordOptions {
+            screen: chosen("screen"),
+            fps: fps.unwrap_or(defaults.fps),
+            microphone,
+            ..defaults
+        })
+    };
+    let options = StreamOptions {
+        source,
+        looping: args.get_flag("loop"),
+        keyframe_interval: *args.get_one::<Duration>("keyframe-interval").unwrap(),
+        reconnect_attempts: *args.get_one::<u32>("reconnect").unwrap(),
+        reconnect_delay: *args.get_one::<Duration>("reconnect-delay").unwrap(),
+        transcode: transcode::options(args)?,
+    };
+
+    let command = stream_command(url, &options)?;
+    let settings = settings::get();
+    if !settings.dry_run && !settings.quiet {
+        eprintln!("Streaming to {url}, press Ctrl-C to stop");
+    }
+    let mut attempt = 0;
+    loop {
+        match report::execute_until_interrupted(&command, None) {
+            Err(e) if attempt < options.reconnect_attempts && is_connection_error(&e) => {
+                attempt += 1;
+                if !settings.quiet {
+                    eprintln!(
+                        "{e}, reconnecting ({attempt}/{})",
+                        options.reconnect_attempts
+                    );
+                }
+                std::thread::sleep(options.reconnect_delay);
+            }
+            result => return result,
+        }
+    }
+}
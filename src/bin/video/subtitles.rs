@@ -0,0 +1,51 @@
+use clap::{Arg, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use video::{Error, SubtitleMode, SubtitleOptions, subtitles_command};
+
+use crate::{args, report};
+
+pub fn command() -> Command {
+    Command::new("subtitles")
+        .about("Burn in or add a subtitle track to a video.")
+        .args([
+            args::input(),
+            args::output(),
+            Arg::new("subtitles")
+                .short('s')
+                .long("subtitles")
+                .required(true)
+                .help("The SRT or ASS subtitle file")
+                .value_parser(value_parser!(PathBuf)),
+            Arg::new("mode")
+                .long("mode")
+                .default_value("soft")
+                .help("Burn the subtitles into the picture or add a soft track")
+                .value_parser(["burn", "soft"]),
+            Arg::new("language")
+                .long("language")
+                .help("The language of a soft track, e.g. eng")
+                .value_parser(value_parser!(String)),
+            Arg::new("title")
+                .long("title")
+                .help("The title of a soft track")
+                .value_parser(value_parser!(String)),
+        ])
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+    let subtitles = args.get_one::<PathBuf>("subtitles").unwrap();
+
+    let options = SubtitleOptions {
+        mode: args
+            .get_one::<String>("mode")
+            .unwrap()
+            .parse::<SubtitleMode>()?,
+        language: args.get_one::<String>("language").cloned(),
+        title: args.get_one::<String>("title").cloned(),
+    };
+
+    let command = subtitles_command(input, subtitles, output, &options)?;
+    report::execute(&command)
+}
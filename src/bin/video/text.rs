@@ -0,0 +1,100 @@
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::{Error, Position, TextContent, TextOptions, draw_text_command};
+
+use crate::{args, report, transcode};
+
+pub fn command() -> Command {
+    Command::new("text")
+        .about("Burn text, the file name or a running timecode into a video.")
+        .args([
+            args::input(),
+            args::output(),
+            Arg::new("text")
+                .long("text")
+                .help("The text to draw")
+                .value_parser(value_parser!(String)),
+            Arg::new("filename")
+                .long("filename")
+                .action(ArgAction::SetTrue)
+                .help("Draw the input's file name"),
+            Arg::new("timecode")
+                .long("timecode")
+                .num_args(0..=1)
+                .default_missing_value("00:00:00:00")
+                .help("Draw a running timecode, starting at hh:mm:ss:ff")
+                .value_parser(value_parser!(String)),
+            Arg::new("font")
+                .long("font")
+                .help("The font file, by default the system's default font")
+                .value_parser(value_parser!(PathBuf)),
+            Arg::new("font-size")
+                .long("font-size")
+                .default_value("32")
+                .help("The font size in pixels")
+                .value_parser(value_parser!(u32).range(1..)),
+            Arg::new("color")
+                .long("color")
+                .default_value("white")
+                .help("The text color, e.g. white or 0xffcc00")
+                .value_parser(value_parser!(String)),
+            args::position(),
+            Arg::new("margin")
+                .long("margin")
+                .default_value("20")
+                .help("The distance from the edges in pixels")
+                .value_parser(value_parser!(u32)),
+            Arg::new("box")
+                .long("box")
+                .num_args(0..=1)
+                .default_missing_value("black@0.5")
+                .help("Draw a box behind the text, by default half-transparent black")
+                .value_parser(value_parser!(String)),
+            Arg::new("start")
+                .long("start")
+                .help("Show the text from this timestamp")
+                .value_parser(args::timestamp),
+            Arg::new("end")
+                .long("end")
+                .help("Hide the text after this timestamp")
+                .value_parser(args::timestamp),
+        ])
+        .args(transcode::option_args())
+        .group(
+            ArgGroup::new("content")
+                .args(["text", "filename", "timecode"])
+                .required(true),
+        )
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let content = if let Some(text) = args.get_one::<String>("text") {
+        TextContent::Text(text.clone())
+    } else if args.get_flag("filename") {
+        TextContent::FileName
+    } else {
+        TextContent::Timecode(args.get_one::<String>("timecode").unwrap().clone())
+    };
+    let options = TextOptions {
+        content,
+        font: args.get_one::<PathBuf>("font").cloned(),
+        font_size: *args.get_one::<u32>("font-size").unwrap(),
+        color: args.get_one::<String>("color").unwrap().clone(),
+        position: args
+            .get_one::<String>("position")
+            .unwrap()
+            .parse::<Position>()?,
+        margin: *args.get_one::<u32>("margin").unwrap(),
+        box_color: args.get_one::<String>("box").cloned(),
+        start: args.get_one::<Duration>("start").copied(),
+        end: args.get_one::<Duration>("end").copied(),
+        transcode: transcode::options(args)?,
+    };
+
+    let command = draw_text_command(input, output, &options)?;
+    report::execute(&command)
+}
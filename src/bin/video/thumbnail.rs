@@ -0,0 +1,93 @@
+use clap::{Arg, ArgGroup, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::{
+    Error, FrameOptions, FrameSelection, ImageFormat, OutputTemplate, extract_frames_command,
+    written_frames,
+};
+
+use crate::{args, report};
+
+pub fn command() -> Command {
+    Command::new("thumbnail")
+        .about("Grab one or more frames of a video as images.")
+        .args([
+            args::input(),
+            args::output().help("The directory the images are written to"),
+        ])
+        .args(option_args())
+        .group(selection_group())
+}
+
+pub fn selection_group() -> ArgGroup {
+    ArgGroup::new("selection")
+        .args(["at", "count", "scenes"])
+        .required(true)
+}
+
+pub fn option_args() -> Vec<Arg> {
+    vec![
+        Arg::new("at")
+            .long("at")
+            .help("Grab a single frame at this timestamp")
+            .value_parser(args::timestamp),
+        Arg::new("count")
+            .long("count")
+            .help("Grab this many evenly spaced frames")
+            .value_parser(value_parser!(u32).range(1..)),
+        Arg::new("scenes")
+            .long("scenes")
+            .num_args(0..=1)
+            .default_missing_value("0.4")
+            .help("Grab a frame at every scene change above this score")
+            .value_parser(value_parser!(f64)),
+        Arg::new("format")
+            .long("format")
+            .default_value("png")
+            .help("The image format")
+            .value_parser(["png", "jpeg"]),
+        Arg::new("pattern")
+            .long("pattern")
+            .default_value("frame_%03d")
+            .help("The file name without extension, e.g. frame_%03d or {stem}_{index}")
+            .value_parser(value_parser!(String)),
+    ]
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let mut options = options(args)?;
+    options.pattern = OutputTemplate::new(options.pattern).render(input, None)?;
+    let command = extract_frames_command(input, output, &options)?;
+    report::execute(&command)?;
+    let frames = written_frames(output, &options);
+    if report::json() {
+        report::field("frames", frames);
+    } else {
+        for frame in frames {
+            println!("{}", frame.display());
+        }
+    }
+    Ok(())
+}
+
+pub fn options(args: &ArgMatches) -> Result<FrameOptions, Error> {
+    let selection = if let Some(at) = args.get_one::<Duration>("at") {
+        FrameSelection::At(*at)
+    } else if let Some(count) = args.get_one::<u32>("count") {
+        FrameSelection::Evenly(*count)
+    } else {
+        FrameSelection::SceneChange(*args.get_one::<f64>("scenes").unwrap())
+    };
+
+    Ok(FrameOptions {
+        selection,
+        format: args
+            .get_one::<String>("format")
+            .unwrap()
+            .parse::<ImageFormat>()?,
+        pattern: args.get_one::<String>("pattern").unwrap().clone(),
+    })
+}
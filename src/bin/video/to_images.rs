@@ -0,0 +1,69 @@
+use clap::{Arg, ArgGroup, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use video::{
+    Error, FrameOptions, FrameSelection, ImageFormat, extract_frames_command, written_frames,
+};
+
+use crate::{args, report, settings};
+
+pub fn command() -> Command {
+    Command::new("to-images")
+        .about("Write the frames of a video to numbered images.")
+        .args([
+            args::input(),
+            args::output().help("The directory the images are written to"),
+            Arg::new("every")
+                .long("every")
+                .help("Only keep every Nth frame")
+                .value_parser(value_parser!(u32).range(1..)),
+            Arg::new("fps")
+                .long("fps")
+                .help("Sample this many frames per second")
+                .value_parser(value_parser!(f64)),
+            Arg::new("format")
+                .long("format")
+                .default_value("png")
+                .help("The image format")
+                .value_parser(["png", "jpeg"]),
+            Arg::new("pattern")
+                .long("pattern")
+                .default_value("frame_%06d")
+                .help("The file name pattern without extension")
+                .value_parser(value_parser!(String)),
+        ])
+        .group(ArgGroup::new("selection").args(["every", "fps"]))
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let selection = if let Some(n) = args.get_one::<u32>("every") {
+        FrameSelection::EveryNth(*n)
+    } else if let Some(fps) = args.get_one::<f64>("fps") {
+        FrameSelection::Rate(*fps)
+    } else {
+        FrameSelection::All
+    };
+    let options = FrameOptions {
+        selection,
+        format: args
+            .get_one::<String>("format")
+            .unwrap()
+            .parse::<ImageFormat>()?,
+        pattern: args.get_one::<String>("pattern").unwrap().clone(),
+    };
+
+    let command = extract_frames_command(input, output, &options)?;
+    report::execute(&command)?;
+    if settings::get().dry_run {
+        return Ok(());
+    }
+    let frames = written_frames(output, &options);
+    if report::json() {
+        report::field("frames", frames);
+    } else {
+        println!("{} frames written to {}", frames.len(), output.display());
+    }
+    Ok(())
+}
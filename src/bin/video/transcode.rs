@@ -0,0 +1,369 @@
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::{
+    Deinterlacer, Denoiser, Error, Fades, HwAccel, StreamAction, StreamPlan, StreamSelection,
+    Strength, ToneMap, Tool, TranscodeOptions, VideoFilters, transcode_passes, transcode_plan,
+};
+
+use crate::{args, config, report};
+
+pub fn command() -> Command {
+    Command::new("transcode")
+        .about("Re-encode a video file with different codecs or quality.")
+        .args([
+            args::input(),
+            args::output(),
+            Arg::new("two-pass")
+                .long("two-pass")
+                .action(ArgAction::SetTrue)
+                .requires("vbitrate")
+                .help("Encode in two passes to hit the bitrate more closely"),
+            Arg::new("smart")
+                .long("smart")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["two-pass", "map"])
+                .help("Copy the streams that already have the wanted codec and re-encode only the rest"),
+            Arg::new("deinterlace")
+                .long("deinterlace")
+                .num_args(0..=1)
+                .default_missing_value("yadif")
+                .help("Deinterlace the video if the input is interlaced, with yadif or bwdif")
+                .value_parser(["yadif", "bwdif"]),
+            Arg::new("tonemap")
+                .long("tonemap")
+                .num_args(0..=1)
+                .default_missing_value("hable")
+                .help("Convert HDR10 or HLG video to SDR, if the input is HDR, along this curve")
+                .value_parser(["hable", "mobius", "reinhard", "clip"]),
+        ])
+        .args(option_args())
+        .args(stream_args())
+        .args(fade_args())
+        .args(filter_args())
+}
+
+pub fn option_args() -> Vec<Arg> {
+    let config = config::get();
+    vec![
+        config::default(
+            Arg::new("vcodec")
+                .long("vcodec")
+                .help("The video codec, e.g. libx264")
+                .value_parser(value_parser!(String)),
+            &config.vcodec,
+        ),
+        config::default(
+            Arg::new("acodec")
+                .long("acodec")
+                .help("The audio codec, e.g. aac")
+                .value_parser(value_parser!(String)),
+            &config.acodec,
+        ),
+        config::default(
+            Arg::new("crf")
+                .long("crf")
+                .help("The constant rate factor (0-51)")
+                .value_parser(value_parser!(u8).range(0..=51)),
+            &config.crf,
+        ),
+        config::default(
+            Arg::new("preset")
+                .long("preset")
+                .help("The encoder preset, e.g. medium, or an encoding preset: web-1080p, youtube, instagram, archival or one from the config file")
+                .value_parser(value_parser!(String)),
+            &config.preset,
+        ),
+        Arg::new("vbitrate")
+            .long("vbitrate")
+            .visible_alias("bitrate")
+            .help("The video bitrate, e.g. 4M")
+            .value_parser(value_parser!(String)),
+        Arg::new("abitrate")
+            .long("abitrate")
+            .help("The audio bitrate, e.g. 192k")
+            .value_parser(value_parser!(String)),
+        Arg::new("faststart")
+            .long("faststart")
+            .action(ArgAction::SetTrue)
+            .help("Put the MP4 index at the front so playback can start before the download ends"),
+        Arg::new("hwaccel")
+            .long("hwaccel")
+            .help("Encode the video on hardware, auto picks one the installed ffmpeg supports")
+            .value_parser(["auto", "videotoolbox", "nvenc", "qsv", "vaapi"]),
+    ]
+}
+
+/// Stream selection for the subcommands that honour
+/// [`TranscodeOptions::streams`].
+pub fn stream_args() -> Vec<Arg> {
+    vec![
+        Arg::new("map")
+            .long("map")
+            .action(ArgAction::Append)
+            .help("An ffmpeg stream specifier to keep, e.g. 0:a:1; may be repeated")
+            .value_parser(value_parser!(String)),
+        Arg::new("audio-track")
+            .long("audio-track")
+            .conflicts_with("map")
+            .help("Keep only this audio track, counting from 1")
+            .value_parser(value_parser!(u64).range(1..)),
+        Arg::new("drop-subs")
+            .long("drop-subs")
+            .action(ArgAction::SetTrue)
+            .help("Leave the subtitle streams out"),
+    ]
+}
+
+/// Fades for the subcommands that honour [`TranscodeOptions::fades`].
+pub fn fade_args() -> Vec<Arg> {
+    vec![
+        Arg::new("fade-in")
+            .long("fade-in")
+            .help("Fade in from black and silence over this long, e.g. 1s")
+            .value_parser(args::length),
+        Arg::new("fade-out")
+            .long("fade-out")
+            .help("Fade out to black and silence over this long at the end")
+            .value_parser(args::length),
+    ]
+}
+
+/// The cleanup and color filters only transcode has.
+fn filter_args() -> Vec<Arg> {
+    let strengths = ["light", "medium", "heavy"];
+    vec![
+        Arg::new("denoise")
+            .long("denoise")
+            .num_args(0..=1)
+            .default_missing_value("medium")
+            .help("Reduce the noise, by default at medium strength")
+            .value_parser(strengths),
+        Arg::new("denoiser")
+            .long("denoiser")
+            .requires("denoise")
+            .default_value("hqdn3d")
+            .help("hqdn3d is fast, nlmeans keeps more detail but is much slower")
+            .value_parser(["hqdn3d", "nlmeans"]),
+        Arg::new("sharpen")
+            .long("sharpen")
+            .num_args(0..=1)
+            .default_missing_value("medium")
+            .help("Sharpen with unsharp, by default at medium strength")
+            .value_parser(strengths),
+        Arg::new("brightness")
+            .long("brightness")
+            .allow_negative_numbers(true)
+            .help("Brighten or darken, from -1 to 1")
+            .value_parser(value_parser!(f64)),
+        Arg::new("contrast")
+            .long("contrast")
+            .allow_negative_numbers(true)
+            .help("Scale the contrast, 1 leaves it as it is")
+            .value_parser(value_parser!(f64)),
+        Arg::new("saturation")
+            .long("saturation")
+            .help("Scale the saturation from 0 (grey) to 3, 1 leaves it as it is")
+            .value_parser(value_parser!(f64)),
+        Arg::new("gamma")
+            .long("gamma")
+            .help("The gamma from 0.1 to 10, 1 leaves it as it is")
+            .value_parser(value_parser!(f64)),
+        Arg::new("lut")
+            .long("lut")
+            .help("Apply a 3D LUT, e.g. a .cube file")
+            .value_parser(value_parser!(PathBuf)),
+    ]
+}
+
+fn filters(args: &ArgMatches) -> Result<VideoFilters, Error> {
+    let strength = |id| {
+        args.get_one::<String>(id)
+            .map(|s| s.parse::<Strength>())
+            .transpose()
+    };
+    Ok(VideoFilters {
+        denoise: strength("denoise")?,
+        denoiser: args
+            .get_one::<String>("denoiser")
+            .unwrap()
+            .parse::<Denoiser>()?,
+        sharpen: strength("sharpen")?,
+        brightness: args.get_one::<f64>("brightness").copied(),
+        contrast: args.get_one::<f64>("contrast").copied(),
+        saturation: args.get_one::<f64>("saturation").copied(),
+        gamma: args.get_one::<f64>("gamma").copied(),
+        lut: args.get_one::<PathBuf>("lut").cloned(),
+    })
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let mut options = options(args)?;
+    options.two_pass = args.get_flag("two-pass");
+    options.deinterlace = args
+        .get_one::<String>("deinterlace")
+        .map(|name| name.parse::<Deinterlacer>())
+        .transpose()?;
+    options.tonemap = args
+        .get_one::<String>("tonemap")
+        .map(|name| name.parse::<ToneMap>())
+        .transpose()?;
+    options.filters = filters(args)?;
+    options.smart = args.get_flag("smart");
+    if options.smart {
+        report_plan(&transcode_plan(input, output, &options)?);
+    }
+    for command in transcode_passes(input, output, &options)? {
+        report::execute(&command)?;
+    }
+    Ok(())
+}
+
+/// Tells which streams a smart transcode copies.
+fn report_plan(plans: &[StreamPlan]) {
+    if report::json() {
+        report::field(
+            "streams",
+            plans.iter().map(StreamPlan::to_json).collect::<Vec<_>>(),
+        );
+        return;
+    }
+    for plan in plans {
+        let what = match &plan.action {
+            StreamAction::Copy => "copied".to_string(),
+            StreamAction::Convert(encoder) => format!("encoded with {encoder}"),
+            StreamAction::Drop => "dropped".to_string(),
+        };
+        eprintln!(
+            "stream #{} ({} {}): {what}",
+            plan.index,
+            plan.kind.name(),
+            plan.codec
+        );
+    }
+}
+
+pub fn options(args: &ArgMatches) -> Result<TranscodeOptions, Error> {
+    let hwaccel = match args.get_one::<String>("hwaccel").map(String::as_str) {
+        None => None,
+        Some("auto") => Some(
+            HwAccel::detect(&Tool::current())?
+                .into_iter()
+                .next()
+                .ok_or_else(|| Error::InvalidArgument("ffmpeg has no hardware encoders".into()))?,
+        ),
+        Some(name) => Some(name.parse::<HwAccel>()?),
+    };
+
+    // An encoding preset fills in what the command line leaves out, ahead of
+    // the config file's defaults.
+    let named = args
+        .get_one::<String>("preset")
+        .map(|name| named_preset(name))
+        .transpose()?
+        .flatten();
+    let encoder_preset = match &named {
+        Some(named) => named.preset.clone(),
+        None => args.get_one::<String>("preset").cloned(),
+    };
+    let named = named.unwrap_or_default();
+    let given = |id| args.value_source(id) == Some(ValueSource::CommandLine);
+    let text = |id, preset: Option<String>| {
+        let value = args.get_one::<String>(id).cloned();
+        if given(id) { value } else { preset.or(value) }
+    };
+
+    Ok(TranscodeOptions {
+        video_codec: text("vcodec", named.video_codec),
+        audio_codec: text("acodec", named.audio_codec),
+        crf: match args.get_one::<u8>("crf") {
+            Some(crf) if given("crf") => Some(*crf),
+            crf => named.crf.or(crf.copied()),
+        },
+        preset: encoder_preset,
+        video_bitrate: text("vbitrate", named.video_bitrate),
+        audio_bitrate: text("abitrate", named.audio_bitrate),
+        pixel_format: named.pixel_format,
+        faststart: args.get_flag("faststart") || named.faststart,
+        hwaccel,
+        two_pass: false,
+        streams: streams(args),
+        fades: fades(args),
+        deinterlace: None,
+        tonemap: None,
+        filters: VideoFilters::default(),
+        smart: false,
+    })
+}
+
+/// The [`fade_args`], which only some subcommands have.
+pub fn fades(args: &ArgMatches) -> Fades {
+    let length = |id| args.try_get_one::<Duration>(id).ok().flatten().copied();
+    Fades {
+        fade_in: length("fade-in"),
+        fade_out: length("fade-out"),
+    }
+}
+
+/// The built-in or user-defined encoding preset called `name`, `None` when
+/// it is an encoder preset such as `medium` instead. A preset that only sets
+/// the encoder preset is returned with the rest left empty.
+fn named_preset(name: &str) -> Result<Option<TranscodeOptions>, Error> {
+    let Some(preset) = config::preset(name) else {
+        return Ok(video::encoding_preset(name));
+    };
+    let invalid = |key: &str, value: &str| {
+        Error::InvalidArgument(format!("invalid {key} `{value}` in the preset {name}"))
+    };
+    let crf = match &preset.crf {
+        Some(crf) => Some(
+            crf.parse::<u8>()
+                .ok()
+                .filter(|crf| *crf <= 51)
+                .ok_or_else(|| invalid("crf", crf))?,
+        ),
+        None => None,
+    };
+    let faststart = match preset.faststart.as_deref() {
+        None | Some("false") => false,
+        Some("true") => true,
+        Some(value) => return Err(invalid("faststart", value)),
+    };
+    Ok(Some(TranscodeOptions {
+        video_codec: preset.vcodec.clone(),
+        audio_codec: preset.acodec.clone(),
+        crf,
+        preset: preset.preset.clone(),
+        video_bitrate: preset.vbitrate.clone(),
+        audio_bitrate: preset.abitrate.clone(),
+        pixel_format: preset.pix_fmt.clone(),
+        faststart,
+        ..Default::default()
+    }))
+}
+
+/// The [`stream_args`], which only some subcommands have.
+fn streams(args: &ArgMatches) -> StreamSelection {
+    StreamSelection {
+        maps: args
+            .try_get_many::<String>("map")
+            .ok()
+            .flatten()
+            .map(|maps| maps.cloned().collect())
+            .unwrap_or_default(),
+        audio_track: args
+            .try_get_one::<u64>("audio-track")
+            .ok()
+            .flatten()
+            .map(|track| *track as usize),
+        drop_subtitles: args
+            .try_get_one::<bool>("drop-subs")
+            .ok()
+            .flatten()
+            .is_some_and(|drop| *drop),
+    }
+}
@@ -0,0 +1,73 @@
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::{Error, TranscodeOptions, TrimMode, TrimOptions, trim_command};
+
+use crate::{args, report, transcode};
+
+pub fn command() -> Command {
+    Command::new("trim")
+        .about("Cut a section out of a video file.")
+        .args([args::input(), args::output()])
+        .args(option_args())
+}
+
+pub fn option_args() -> Vec<Arg> {
+    vec![
+        Arg::new("start")
+            .long("start")
+            .help("The start timestamp, e.g. 00:01:30 or 90.5")
+            .value_parser(args::timestamp),
+        Arg::new("end")
+            .long("end")
+            .help("The end timestamp, e.g. 00:05:00")
+            .value_parser(args::timestamp),
+        Arg::new("reencode")
+            .long("reencode")
+            .action(ArgAction::SetTrue)
+            .help("Re-encode for frame-accurate cuts instead of stream copy"),
+        Arg::new("vcodec")
+            .long("vcodec")
+            .requires("reencode")
+            .help("The video codec used with --reencode")
+            .value_parser(value_parser!(String)),
+        Arg::new("acodec")
+            .long("acodec")
+            .requires("reencode")
+            .help("The audio codec used with --reencode")
+            .value_parser(value_parser!(String)),
+    ]
+    .into_iter()
+    .chain(transcode::fade_args())
+    .collect()
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let options = options(args);
+    let command = trim_command(input, output, &options)?;
+    report::execute(&command)
+}
+
+pub fn options(args: &ArgMatches) -> TrimOptions {
+    // Fading needs the frames decoded, so it re-encodes too.
+    let fades = transcode::fades(args);
+    let mode = if args.get_flag("reencode") || !fades.is_empty() {
+        TrimMode::Reencode(TranscodeOptions {
+            video_codec: args.get_one::<String>("vcodec").cloned(),
+            audio_codec: args.get_one::<String>("acodec").cloned(),
+            fades,
+            ..Default::default()
+        })
+    } else {
+        TrimMode::Copy
+    };
+
+    TrimOptions {
+        start: args.get_one::<Duration>("start").copied(),
+        end: args.get_one::<Duration>("end").copied(),
+        mode,
+    }
+}
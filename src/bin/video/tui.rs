@@ -0,0 +1,630 @@
+//! A full-screen picker for those who'd rather not script: browse a folder,
+//! see what is in each file, pick clips in order and concat or transcode them
+//! while the progress is shown. Drawn with plain escape codes, with `stty`
+//! switching the terminal to reading single keys.
+
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use std::collections::HashMap;
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use video::ffmpeg::OverwritePolicy;
+use video::scan::{name_matches, natural_cmp, walk};
+use video::{
+    ConcatMode, ConcatOptions, Error, JobHandle, Progress, TranscodeOptions, concat_files_command,
+    probe, transcode_command,
+};
+
+use crate::{report, settings, transcode};
+
+const HELP: &str =
+    "space pick  [ ] reorder  a all  c concat  t transcode  x cancel  r rescan  q quit";
+
+pub fn command() -> Command {
+    Command::new("tui")
+        .about("Browse a folder, pick clips and concat or transcode them interactively.")
+        .long_about(
+            "Browse a folder, pick clips and concat or transcode them interactively.\n\n\
+             The arrow keys or j and k move through the files, the details of the \
+             highlighted one are shown beside the list. Space picks a clip, [ and ] move \
+             it earlier or later in the order they are joined in. c concats the picked \
+             clips, t transcodes the highlighted file, x cancels the running job and q \
+             quits.",
+        )
+        .args([
+            Arg::new("folder")
+                .short('f')
+                .long("folder")
+                .default_value(".")
+                .help("The folder to browse")
+                .value_parser(value_parser!(PathBuf)),
+            Arg::new("pattern")
+                .long("pattern")
+                .default_value("*")
+                .help("The glob the file names must match, e.g. \"*.MP4\"")
+                .value_parser(value_parser!(String)),
+            Arg::new("recursive")
+                .short('r')
+                .long("recursive")
+                .action(ArgAction::SetTrue)
+                .help("Also list the files in subfolders"),
+            Arg::new("reencode")
+                .long("reencode")
+                .action(ArgAction::SetTrue)
+                .help("Always re-encode when concatenating, not only when the clips don't match"),
+        ])
+        .args(transcode::option_args())
+        .args(transcode::stream_args())
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    if !cfg!(unix) {
+        return Err(Error::InvalidArgument(
+            "tui only runs in a unix terminal".to_string(),
+        ));
+    }
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return Err(Error::InvalidArgument("tui needs a terminal".to_string()));
+    }
+
+    let transcode = transcode::options(args)?;
+    let mut app = App {
+        folder: args.get_one::<PathBuf>("folder").unwrap().clone(),
+        pattern: args.get_one::<String>("pattern").unwrap().clone(),
+        recursive: args.get_flag("recursive"),
+        concat: ConcatOptions {
+            mode: if args.get_flag("reencode") {
+                ConcatMode::Reencode
+            } else {
+                ConcatMode::Auto
+            },
+            transcode: transcode.clone(),
+            ..ConcatOptions::default()
+        },
+        transcode,
+        files: Vec::new(),
+        cursor: 0,
+        top: 0,
+        picked: Vec::new(),
+        details: HashMap::new(),
+        mode: Mode::Browse,
+        status: String::new(),
+        job: None,
+    };
+    app.rescan()?;
+
+    let terminal = Terminal::open()?;
+    let result = app.run(&terminal);
+    if let Some(job) = app.job.take() {
+        job.handle.cancel();
+        let _ = job.handle.wait();
+    }
+    drop(terminal);
+    result
+}
+
+/// What the output path is asked for.
+enum Action {
+    Concat,
+    Transcode(PathBuf),
+}
+
+enum Mode {
+    Browse,
+    /// Typing the output path, starting from a suggestion.
+    Output {
+        action: Action,
+        path: String,
+    },
+    /// Asking whether the existing output may be replaced.
+    Overwrite {
+        action: Action,
+        output: PathBuf,
+    },
+}
+
+struct Job {
+    what: String,
+    output: PathBuf,
+    handle: JobHandle,
+    progress: Arc<Mutex<Option<Progress>>>,
+}
+
+struct App {
+    folder: PathBuf,
+    pattern: String,
+    recursive: bool,
+    concat: ConcatOptions,
+    transcode: TranscodeOptions,
+    files: Vec<PathBuf>,
+    cursor: usize,
+    /// The first file shown, once the list is longer than the screen.
+    top: usize,
+    /// The picked clips, in the order they are joined in.
+    picked: Vec<PathBuf>,
+    /// What `probe` prints for each file looked at so far.
+    details: HashMap<PathBuf, Vec<String>>,
+    mode: Mode,
+    status: String,
+    job: Option<Job>,
+}
+
+impl App {
+    fn run(&mut self, terminal: &Terminal) -> Result<(), Error> {
+        let mut stdin = std::io::stdin().lock();
+        let mut buffer = [0; 64];
+        let mut size = terminal.size();
+        let mut tick = 0u64;
+        loop {
+            // Resizes are noticed within a second.
+            tick += 1;
+            if tick.is_multiple_of(10) {
+                size = terminal.size();
+            }
+            self.finish_job();
+            terminal.draw(&self.render(size));
+
+            // Returns after a tenth of a second without a key.
+            let read = match stdin.read(&mut buffer) {
+                Ok(read) => read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => 0,
+                Err(e) => return Err(Error::InvalidArgument(format!("cannot read keys: {e}"))),
+            };
+            for key in keys(&String::from_utf8_lossy(&buffer[..read])) {
+                if !self.handle(key, size.0) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn rescan(&mut self) -> Result<(), Error> {
+        let mut files: Vec<PathBuf> = walk(&self.folder, self.recursive)?
+            .into_iter()
+            .filter(|path| {
+                name_matches(path, &self.pattern)
+                    && !path
+                        .file_name()
+                        .is_some_and(|name| name.as_encoded_bytes().starts_with(b"."))
+            })
+            .collect();
+        files.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+        self.picked.retain(|path| files.contains(path));
+        self.cursor = self.cursor.min(files.len().saturating_sub(1));
+        self.files = files;
+        Ok(())
+    }
+
+    /// Acts on `key`, returning false to quit.
+    fn handle(&mut self, key: Key, rows: usize) -> bool {
+        let page = rows.saturating_sub(4).max(1);
+        match std::mem::replace(&mut self.mode, Mode::Browse) {
+            Mode::Output { action, mut path } => match key {
+                Key::Enter => {
+                    let output = PathBuf::from(path.trim());
+                    if path.trim().is_empty() {
+                        self.status = "no output given".to_string();
+                    } else {
+                        self.confirm(action, output);
+                    }
+                }
+                Key::Escape | Key::Interrupt => self.status.clear(),
+                Key::Backspace => {
+                    path.pop();
+                    self.mode = Mode::Output { action, path };
+                }
+                Key::Char(c) => {
+                    path.push(c);
+                    self.mode = Mode::Output { action, path };
+                }
+                _ => self.mode = Mode::Output { action, path },
+            },
+            Mode::Overwrite { action, output } => match key {
+                Key::Char('y' | 'Y') => self.start(action, output, true),
+                _ => self.status = format!("{} was left as it is", output.display()),
+            },
+            Mode::Browse => match key {
+                Key::Up | Key::Char('k') => self.cursor = self.cursor.saturating_sub(1),
+                Key::Down | Key::Char('j') => self.move_to(self.cursor + 1),
+                Key::PageUp => self.cursor = self.cursor.saturating_sub(page),
+                Key::PageDown => self.move_to(self.cursor + page),
+                Key::Home | Key::Char('g') => self.cursor = 0,
+                Key::End | Key::Char('G') => self.move_to(usize::MAX),
+                Key::Char(' ') => self.toggle(),
+                Key::Char('[') => self.reorder(-1),
+                Key::Char(']') => self.reorder(1),
+                Key::Char('a') => {
+                    if self.picked.len() == self.files.len() {
+                        self.picked.clear();
+                    } else {
+                        let unpicked: Vec<PathBuf> = self
+                            .files
+                            .iter()
+                            .filter(|file| !self.picked.contains(file))
+                            .cloned()
+                            .collect();
+                        self.picked.extend(unpicked);
+                    }
+                }
+                Key::Char('c') => self.ask_output(Action::Concat),
+                Key::Char('t') => {
+                    if let Some(file) = self.files.get(self.cursor) {
+                        self.ask_output(Action::Transcode(file.clone()));
+                    }
+                }
+                Key::Char('x') => match &self.job {
+                    Some(job) => job.handle.cancel(),
+                    None => self.status = "nothing is running".to_string(),
+                },
+                Key::Char('r') => {
+                    if let Err(e) = self.rescan() {
+                        self.status = format!("error: {e}");
+                    }
+                }
+                Key::Char('q') | Key::Interrupt => return false,
+                _ => {}
+            },
+        }
+        true
+    }
+
+    fn move_to(&mut self, index: usize) {
+        self.cursor = index.min(self.files.len().saturating_sub(1));
+    }
+
+    fn toggle(&mut self) {
+        let Some(file) = self.files.get(self.cursor) else {
+            return;
+        };
+        match self.picked.iter().position(|picked| picked == file) {
+            Some(index) => {
+                self.picked.remove(index);
+            }
+            None => self.picked.push(file.clone()),
+        }
+        self.move_to(self.cursor + 1);
+    }
+
+    /// Moves the highlighted clip `by` places in the order.
+    fn reorder(&mut self, by: isize) {
+        let Some(file) = self.files.get(self.cursor) else {
+            return;
+        };
+        let Some(index) = self.picked.iter().position(|picked| picked == file) else {
+            self.status = "pick the clip first".to_string();
+            return;
+        };
+        if let Some(other) = index.checked_add_signed(by)
+            && other < self.picked.len()
+        {
+            self.picked.swap(index, other);
+        }
+    }
+
+    fn ask_output(&mut self, action: Action) {
+        if self.job.is_some() {
+            self.status = "wait for the running job or cancel it with x".to_string();
+            return;
+        }
+        let suggestion = match &action {
+            Action::Concat => {
+                if self.picked.len() < 2 {
+                    self.status = "pick at least two clips to concat".to_string();
+                    return;
+                }
+                let ext = extension(&self.picked[0]);
+                self.folder.join(format!("concat.{ext}"))
+            }
+            Action::Transcode(input) => {
+                let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+                input.with_file_name(format!("{stem}-transcoded.{}", extension(input)))
+            }
+        };
+        self.status.clear();
+        self.mode = Mode::Output {
+            action,
+            path: suggestion.display().to_string(),
+        };
+    }
+
+    /// Starts the job, once replacing an existing output is settled.
+    fn confirm(&mut self, action: Action, output: PathBuf) {
+        if !output.exists() {
+            return self.start(action, output, false);
+        }
+        match settings::get().overwrite {
+            OverwritePolicy::Always => self.start(action, output, true),
+            OverwritePolicy::Never => {
+                self.status = format!("{} already exists", output.display());
+            }
+            OverwritePolicy::Prompt => self.mode = Mode::Overwrite { action, output },
+        }
+    }
+
+    fn start(&mut self, action: Action, output: PathBuf, overwrite: bool) {
+        let settings = settings::get();
+        let (what, command) = match &action {
+            Action::Concat => (
+                format!("Concatenating {} clips", self.picked.len()),
+                concat_files_command(&self.picked, &output, &self.concat),
+            ),
+            Action::Transcode(input) => (
+                format!("Transcoding {}", self.name(input)),
+                transcode_command(input, &output, &self.transcode),
+            ),
+        };
+        let mut command = match command {
+            Ok(command) => command.overwrite(overwrite),
+            Err(e) => {
+                self.status = format!("error: {e}");
+                return;
+            }
+        };
+        if let Some(tolerance) = settings.validate {
+            command = command.validate_outputs(tolerance);
+        }
+        if settings.dry_run {
+            self.status = command.command_line();
+            return;
+        }
+
+        let progress = Arc::new(Mutex::new(None));
+        let latest = Arc::clone(&progress);
+        let handle = command.spawn_with_progress(move |update: &Progress| {
+            *latest.lock().unwrap() = Some(update.clone());
+        });
+        self.status = format!("{what} into {}", output.display());
+        self.job = Some(Job {
+            what,
+            output,
+            handle,
+            progress,
+        });
+    }
+
+    fn finish_job(&mut self) {
+        if !self
+            .job
+            .as_ref()
+            .is_some_and(|job| job.handle.is_finished())
+        {
+            return;
+        }
+        let job = self.job.take().unwrap();
+        self.status = match job.handle.wait().and_then(|result| result.check()) {
+            Ok(_) => format!("Wrote {}", job.output.display()),
+            Err(Error::Cancelled) => format!("{} was cancelled", job.what),
+            Err(e) => format!("error: {e}"),
+        };
+        // The output may have landed in the folder.
+        if let Err(e) = self.rescan() {
+            self.status = format!("error: {e}");
+        }
+    }
+
+    /// The file's path below the folder.
+    fn name(&self, file: &Path) -> String {
+        file.strip_prefix(&self.folder)
+            .unwrap_or(file)
+            .display()
+            .to_string()
+    }
+
+    /// The screen, one string per row.
+    fn render(&mut self, (rows, cols): (usize, usize)) -> Vec<String> {
+        let height = rows.saturating_sub(4).max(1);
+        if self.cursor < self.top {
+            self.top = self.cursor;
+        } else if self.cursor >= self.top + height {
+            self.top = self.cursor + 1 - height;
+        }
+        let left = (cols * 2 / 5).max(20).min(cols);
+        let right = cols.saturating_sub(left + 3);
+
+        let mut screen = vec![format!(
+            "\x1b[7m{}\x1b[0m",
+            fit(
+                &format!(
+                    " {}  {} files, {} picked",
+                    self.folder.display(),
+                    self.files.len(),
+                    self.picked.len()
+                ),
+                cols
+            )
+        )];
+
+        let mut details = Vec::new();
+        if let Some(file) = self.files.get(self.cursor).cloned() {
+            details = self
+                .details
+                .entry(file.clone())
+                .or_insert_with(|| match probe(&file) {
+                    Ok(info) => crate::probe::describe(&info),
+                    Err(e) => vec![format!("error: {e}")],
+                })
+                .clone();
+        }
+        if !self.picked.is_empty() {
+            details.push(String::new());
+            details.push("Order:".to_string());
+            for (i, file) in self.picked.iter().enumerate() {
+                details.push(format!("{:>3}. {}", i + 1, self.name(file)));
+            }
+        }
+
+        for row in 0..height {
+            let index = self.top + row;
+            let entry = match self.files.get(index) {
+                Some(file) => {
+                    let order = self
+                        .picked
+                        .iter()
+                        .position(|picked| picked == file)
+                        .map_or(String::new(), |i| (i + 1).to_string());
+                    let line = fit(&format!("{order:>3} {}", self.name(file)), left);
+                    if index == self.cursor {
+                        format!("\x1b[7m{line}\x1b[0m")
+                    } else {
+                        line
+                    }
+                }
+                None if index == 0 => fit("    no files match", left),
+                None => fit("", left),
+            };
+            let detail = details.get(row).map_or("", String::as_str);
+            screen.push(format!("{entry} │ {}", fit(detail, right)));
+        }
+
+        screen.push(match &self.job {
+            Some(job) => {
+                let progress = job.progress.lock().unwrap().clone().unwrap_or_default();
+                fit(
+                    &format!("{}  {}", job.what, report::progress_line(&progress)),
+                    cols,
+                )
+            }
+            None => String::new(),
+        });
+        screen.push(match &self.mode {
+            Mode::Browse => fit(&self.status, cols),
+            Mode::Output { path, .. } => fit(&format!("Output: {path}_"), cols),
+            Mode::Overwrite { output, .. } => fit(
+                &format!("{} exists, replace it? [y/N]", output.display()),
+                cols,
+            ),
+        });
+        screen.push(format!("\x1b[2m{}\x1b[0m", fit(HELP, cols)));
+        screen
+    }
+}
+
+fn extension(file: &Path) -> String {
+    file.extension()
+        .map_or("mp4".into(), |ext| ext.to_string_lossy().into_owned())
+}
+
+/// Cuts `text` to `width` characters, or pads it with spaces up to it.
+fn fit(text: &str, width: usize) -> String {
+    let mut line: String = text.chars().take(width).collect();
+    let len = line.chars().count();
+    line.extend(std::iter::repeat_n(' ', width - len));
+    line
+}
+
+enum Key {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Enter,
+    Escape,
+    Backspace,
+    Interrupt,
+    Char(char),
+}
+
+/// The keys in what the terminal sent, with the escape sequences of the
+/// arrow and paging keys decoded.
+fn keys(input: &str) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        keys.push(match c {
+            '\x1b' if matches!(chars.peek(), Some('[' | 'O')) => {
+                chars.next();
+                let mut code = String::new();
+                for c in chars.by_ref() {
+                    code.push(c);
+                    if !c.is_ascii_digit() && c != ';' {
+                        break;
+                    }
+                }
+                match code.as_str() {
+                    "A" => Key::Up,
+                    "B" => Key::Down,
+                    "H" | "1~" => Key::Home,
+                    "F" | "4~" => Key::End,
+                    "5~" => Key::PageUp,
+                    "6~" => Key::PageDown,
+                    _ => continue,
+                }
+            }
+            '\x1b' => Key::Escape,
+            '\r' | '\n' => Key::Enter,
+            '\x7f' | '\x08' => Key::Backspace,
+            '\x03' => Key::Interrupt,
+            c if c.is_control() => continue,
+            c => Key::Char(c),
+        });
+    }
+    keys
+}
+
+/// The terminal switched to single keys on the alternate screen, until it
+/// is dropped.
+struct Terminal {
+    saved: String,
+}
+
+impl Terminal {
+    fn open() -> Result<Self, Error> {
+        let saved = stty(&["-g"])?.trim().to_string();
+        // No echo, no line editing, and Ctrl-C as a key rather than a signal
+        // so the terminal is always restored. Reads give up after 0.1s.
+        stty(&["-icanon", "-echo", "-isig", "min", "0", "time", "1"])?;
+        let mut stdout = std::io::stdout();
+        let _ = write!(stdout, "\x1b[?1049h\x1b[?25l");
+        let _ = stdout.flush();
+        Ok(Self { saved })
+    }
+
+    /// The rows and columns.
+    fn size(&self) -> (usize, usize) {
+        let size = stty(&["size"]).unwrap_or_default();
+        let mut numbers = size.split_whitespace().map(str::parse::<usize>);
+        match (numbers.next(), numbers.next()) {
+            (Some(Ok(rows)), Some(Ok(cols))) if rows > 0 && cols > 0 => (rows, cols),
+            _ => (24, 80),
+        }
+    }
+
+    fn draw(&self, screen: &[String]) {
+        let mut frame = String::new();
+        for (row, line) in screen.iter().enumerate() {
+            frame.push_str(&format!("\x1b[{};1H{line}\x1b[K", row + 1));
+        }
+        frame.push_str("\x1b[J");
+        let mut stdout = std::io::stdout();
+        let _ = stdout.write_all(frame.as_bytes());
+        let _ = stdout.flush();
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let mut stdout = std::io::stdout();
+        let _ = write!(stdout, "\x1b[?25h\x1b[?1049l");
+        let _ = stdout.flush();
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+/// Runs `stty` on the terminal, returning what it printed.
+fn stty(args: &[&str]) -> Result<String, Error> {
+    let output = std::process::Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| Error::InvalidArgument(format!("cannot set up the terminal: {e}")))?;
+    if !output.status.success() {
+        return Err(Error::InvalidArgument(
+            "cannot set up the terminal".to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,125 @@
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::ffmpeg::OverwritePolicy;
+use video::json::Json;
+use video::{BatchOptions, CancelToken, Error, WatchOptions, batch_plan, watch};
+
+use crate::{args, batch, config, report, settings};
+
+pub fn command() -> Command {
+    Command::new("watch")
+        .about("Apply an operation to every file that appears in a folder.")
+        .subcommand_required(true)
+        .args([
+            Arg::new("folder")
+                .short('f')
+                .long("folder")
+                .required(true)
+                .help("The folder to watch")
+                .value_parser(value_parser!(PathBuf)),
+            config::default(
+                Arg::new("output")
+                    .short('o')
+                    .long("output")
+                    .required(config::get().output_dir.is_none())
+                    .help("The folder the outputs are written to")
+                    .value_parser(value_parser!(PathBuf)),
+                &config::get().output_dir,
+            ),
+            Arg::new("pattern")
+                .long("pattern")
+                .default_value("*")
+                .help("The glob the file names must match, e.g. \"*.MP4\"")
+                .value_parser(value_parser!(String)),
+            Arg::new("recursive")
+                .short('r')
+                .long("recursive")
+                .action(ArgAction::SetTrue)
+                .help("Also watch subfolders"),
+            Arg::new("template")
+                .long("template")
+                .help("The output name, with {stem}, {ext}, {name}, {index}, {date} and {resolution} of the input")
+                .value_parser(value_parser!(String)),
+            Arg::new("done")
+                .long("done")
+                .help("Move each input here once it is processed")
+                .value_parser(value_parser!(PathBuf)),
+            Arg::new("failed")
+                .long("failed")
+                .help("Move inputs that failed here, by default to --done")
+                .value_parser(value_parser!(PathBuf)),
+            Arg::new("poll")
+                .long("poll")
+                .default_value("2s")
+                .help("How often the folder is looked at")
+                .value_parser(args::length),
+            Arg::new("settle")
+                .long("settle")
+                .default_value("5s")
+                .help("How long a file must stay unchanged before it counts as complete")
+                .value_parser(args::length),
+            Arg::new("once")
+                .long("once")
+                .action(ArgAction::SetTrue)
+                .help("Stop once every file in the folder is processed"),
+        ])
+        .subcommands(batch::operations())
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let folder = args.get_one::<PathBuf>("folder").unwrap();
+    let (operation, default_template) = batch::operation(args)?;
+    let settings = settings::get();
+    let options = WatchOptions {
+        pattern: args.get_one::<String>("pattern").unwrap().clone(),
+        recursive: args.get_flag("recursive"),
+        output_dir: args.get_one::<PathBuf>("output").unwrap().clone(),
+        template: args
+            .get_one::<String>("template")
+            .cloned()
+            .unwrap_or_else(|| default_template.to_string()),
+        overwrite: settings.overwrite,
+        poll_interval: *args.get_one::<Duration>("poll").unwrap(),
+        settle_time: *args.get_one::<Duration>("settle").unwrap(),
+        done_dir: args.get_one::<PathBuf>("done").cloned(),
+        failed_dir: args.get_one::<PathBuf>("failed").cloned(),
+        stop_when_idle: args.get_flag("once"),
+    };
+
+    // What would run for the files there now.
+    if settings.dry_run {
+        let plan = batch_plan(
+            folder,
+            &BatchOptions {
+                pattern: options.pattern.clone(),
+                recursive: options.recursive,
+                output_dir: options.output_dir.clone(),
+                template: options.template.clone(),
+                overwrite: options.overwrite,
+                skip_existing: options.overwrite != OverwritePolicy::Always,
+                ..BatchOptions::default()
+            },
+        )?;
+        for (input, output) in plan {
+            report::execute(&operation.command(&input, &output)?)?;
+        }
+        return Ok(());
+    }
+
+    if !settings.quiet && !report::json() {
+        eprintln!("watching {}", folder.display());
+    }
+    let mut items = Vec::new();
+    watch(folder, &operation, &options, &CancelToken::new(), |item| {
+        if report::json() {
+            items.push(batch::item_json(item));
+        } else {
+            batch::print_item(item);
+        }
+    })?;
+    if report::json() {
+        report::field("items", Json::Array(items));
+    }
+    Ok(())
+}
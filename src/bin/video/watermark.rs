@@ -0,0 +1,59 @@
+use clap::{Arg, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use std::time::Duration;
+use video::{Error, OverlayOptions, Position, overlay_command};
+
+use crate::{args, report};
+
+pub fn command() -> Command {
+    Command::new("watermark")
+        .about("Composite an image onto a video.")
+        .args([
+            args::input(),
+            args::output(),
+            Arg::new("image")
+                .long("image")
+                .required(true)
+                .help("The image to overlay, PNG with alpha works best")
+                .value_parser(value_parser!(PathBuf)),
+            args::position(),
+            Arg::new("margin")
+                .long("margin")
+                .default_value("10")
+                .help("The distance from the edges in pixels")
+                .value_parser(value_parser!(u32)),
+            Arg::new("opacity")
+                .long("opacity")
+                .default_value("1.0")
+                .help("The opacity from 0.0 to 1.0")
+                .value_parser(value_parser!(f64)),
+            Arg::new("start")
+                .long("start")
+                .help("Show the image from this timestamp")
+                .value_parser(args::timestamp),
+            Arg::new("end")
+                .long("end")
+                .help("Hide the image after this timestamp")
+                .value_parser(args::timestamp),
+        ])
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+    let image = args.get_one::<PathBuf>("image").unwrap();
+
+    let options = OverlayOptions {
+        position: args
+            .get_one::<String>("position")
+            .unwrap()
+            .parse::<Position>()?,
+        margin: *args.get_one::<u32>("margin").unwrap(),
+        opacity: *args.get_one::<f64>("opacity").unwrap(),
+        start: args.get_one::<Duration>("start").copied(),
+        end: args.get_one::<Duration>("end").copied(),
+    };
+
+    let command = overlay_command(input, image, output, &options)?;
+    report::execute(&command)
+}
@@ -0,0 +1,50 @@
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use std::path::PathBuf;
+use video::{Error, WaveformOptions, waveform_command};
+
+use crate::{args, report};
+
+pub fn command() -> Command {
+    Command::new("waveform")
+        .about("Draw the waveform of an audio track as a PNG.")
+        .args([
+            args::input(),
+            args::output(),
+            Arg::new("size")
+                .long("size")
+                .default_value("1280x240")
+                .help("The image size")
+                .value_parser(args::size),
+            Arg::new("color")
+                .long("color")
+                .action(ArgAction::Append)
+                .help("The waveform color, e.g. white or 0x3080ff; repeat it for one color per channel")
+                .value_parser(value_parser!(String)),
+            Arg::new("background")
+                .long("background")
+                .help("Fill the image with this color instead of leaving it transparent")
+                .value_parser(value_parser!(String)),
+            Arg::new("split-channels")
+                .long("split-channels")
+                .action(ArgAction::SetTrue)
+                .help("Draw each channel in its own row"),
+        ])
+}
+
+pub fn run(args: &ArgMatches) -> Result<(), Error> {
+    let input = args.get_one::<PathBuf>("input").unwrap();
+    let output = args.get_one::<PathBuf>("output").unwrap();
+
+    let mut options = WaveformOptions {
+        size: *args.get_one::<(u32, u32)>("size").unwrap(),
+        background: args.get_one::<String>("background").cloned(),
+        split_channels: args.get_flag("split-channels"),
+        ..Default::default()
+    };
+    if let Some(colors) = args.get_many::<String>("color") {
+        options.colors = colors.cloned().collect();
+    }
+
+    let command = waveform_command(input, output, &options)?;
+    report::execute(&command)
+}
@@ -0,0 +1,249 @@
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, Input, ProcessResult};
+use crate::time::format_timestamp;
+use crate::tool::Tool;
+use crate::transcode::TranscodeOptions;
+
+/// The ffmpeg input device that reads cameras on this platform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraBackend {
+    /// Video4Linux2.
+    V4l2,
+    /// macOS.
+    AvFoundation,
+    /// DirectShow on Windows.
+    DirectShow,
+}
+
+impl CameraBackend {
+    pub fn current() -> Self {
+        if cfg!(target_os = "macos") {
+            CameraBackend::AvFoundation
+        } else if cfg!(windows) {
+            CameraBackend::DirectShow
+        } else {
+            CameraBackend::V4l2
+        }
+    }
+
+    pub fn format(&self) -> &'static str {
+        match self {
+            CameraBackend::V4l2 => "v4l2",
+            CameraBackend::AvFoundation => "avfoundation",
+            CameraBackend::DirectShow => "dshow",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceKind {
+    Video,
+    Audio,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptureDevice {
+    pub kind: DeviceKind,
+    /// What to pass as [`CaptureOptions::device`], e.g. `/dev/video0`, an
+    /// avfoundation index or a DirectShow name.
+    pub id: String,
+    pub name: String,
+}
+
+/// The cameras and microphones `backend` can open. Video4Linux2 only
+/// reports cameras, microphones come from PulseAudio there.
+pub fn list_devices(backend: CameraBackend) -> Result<Vec<CaptureDevice>, Error> {
+    if backend == CameraBackend::V4l2 {
+        return list_v4l2();
+    }
+
+    let dummy = if backend == CameraBackend::AvFoundation {
+        ""
+    } else {
+        "dummy"
+    };
+    // ffmpeg prints the list and then fails to open the dummy input.
+    let output = Command::new(Tool::current().ffmpeg())
+        .args(["-hide_banner", "-f", backend.format()])
+        .args(["-list_devices", "true", "-i", dummy])
+        .output()
+        .map_err(Error::CommandError)?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Ok(parse_device_list(&stderr))
+}
+
+fn list_v4l2() -> Result<Vec<CaptureDevice>, Error> {
+    let entries = match std::fs::read_dir("/dev") {
+        Ok(entries) => entries,
+        Err(e) => return Err(Error::AccessDenied(e)),
+    };
+    let mut devices: Vec<CaptureDevice> = entries
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name().into_string().ok()?;
+            name.strip_prefix("video")?.parse::<u32>().ok()?;
+            let description =
+                std::fs::read_to_string(format!("/sys/class/video4linux/{name}/name"))
+                    .map(|n| n.trim().to_string())
+                    .unwrap_or_else(|_| name.clone());
+            Some(CaptureDevice {
+                kind: DeviceKind::Video,
+                id: format!("/dev/{name}"),
+                name: description,
+            })
+        })
+        .collect();
+    devices.sort_by(|a, b| crate::scan::natural_cmp(&a.id, &b.id));
+    Ok(devices)
+}
+
+/// Reads the lists avfoundation (`[0] FaceTime HD Camera` under a
+/// `video devices:` heading) and DirectShow (`"Integrated Camera" (video)`,
+/// or quoted names under headings in older builds) print.
+fn parse_device_list(stderr: &str) -> Vec<CaptureDevice> {
+    let mut devices = Vec::new();
+    let mut kind = DeviceKind::Video;
+    for line in stderr.lines() {
+        // Drop the `[avfoundation @ 0x...]` context.
+        let line = match line.trim_start().strip_prefix('[') {
+            Some(rest) => rest.split_once("] ").map_or("", |(_, text)| text),
+            None => line,
+        }
+        .trim();
+
+        if line.ends_with("video devices:") || line.ends_with("video devices") {
+            kind = DeviceKind::Video;
+        } else if line.ends_with("audio devices:") || line.ends_with("audio devices") {
+            kind = DeviceKind::Audio;
+        } else if let Some(rest) = line.strip_prefix('[') {
+            let Some((index, name)) = rest.split_once("] ") else {
+                continue;
+            };
+            if index.parse::<u32>().is_ok() {
+                devices.push(CaptureDevice {
+                    kind,
+                    id: index.to_string(),
+                    name: name.to_string(),
+                });
+            }
+        } else if let Some(rest) = line.strip_prefix('"') {
+            let Some((name, tail)) = rest.split_once('"') else {
+                continue;
+            };
+            let kind = match tail.trim() {
+                "(video)" => DeviceKind::Video,
+                "(audio)" => DeviceKind::Audio,
+                "" => kind,
+                // e.g. `(none)` for devices that aren't usable.
+                _ => continue,
+            };
+            devices.push(CaptureDevice {
+                kind,
+                id: name.to_string(),
+                name: name.to_string(),
+            });
+        }
+    }
+    devices
+}
+
+#[derive(Debug, Clone)]
+pub struct CaptureOptions {
+    pub backend: CameraBackend,
+    /// The camera, by default the first one.
+    pub device: Option<String>,
+    /// The resolution to ask the camera for.
+    pub size: Option<(u32, u32)>,
+    /// The frame rate to ask the camera for.
+    pub fps: Option<f64>,
+    /// Record a microphone too, as listed by [`list_devices`] or a
+    /// PulseAudio source on Linux.
+    pub microphone: Option<String>,
+    /// Stop after this long instead of when interrupted.
+    pub duration: Option<Duration>,
+    pub transcode: TranscodeOptions,
+}
+
+impl Default for CaptureOptions {
+    fn default() -> Self {
+        Self {
+            backend: CameraBackend::current(),
+            device: None,
+            size: None,
+            fps: None,
+            microphone: None,
+            duration: None,
+            transcode: TranscodeOptions::default(),
+        }
+    }
+}
+
+/// Records a camera to `output` until ffmpeg is interrupted, e.g. with
+/// Ctrl-C, or `options.duration` has passed.
+pub fn capture_command(output: &Path, options: &CaptureOptions) -> Result<FfmpegCommand, Error> {
+    Ok(camera_command(options)?.output(output))
+}
+
+/// The camera input and its encoding, without an output.
+pub(crate) fn camera_command(options: &CaptureOptions) -> Result<FfmpegCommand, Error> {
+    let backend = options.backend;
+    let device = match (&options.device, backend) {
+        (Some(device), _) => device.clone(),
+        (None, CameraBackend::V4l2) => "/dev/video0".to_string(),
+        (None, CameraBackend::AvFoundation) => "0".to_string(),
+        (None, CameraBackend::DirectShow) => list_devices(backend)?
+            .into_iter()
+            .find(|d| d.kind == DeviceKind::Video)
+            .map(|d| d.id)
+            .ok_or_else(|| Error::invalid("no camera found"))?,
+    };
+    let name = match backend {
+        CameraBackend::V4l2 => device,
+        CameraBackend::AvFoundation => {
+            let audio = options.microphone.as_deref().unwrap_or("none");
+            format!("{device}:{audio}")
+        }
+        CameraBackend::DirectShow => match &options.microphone {
+            Some(microphone) => format!("video={device}:audio={microphone}"),
+            None => format!("video={device}"),
+        },
+    };
+
+    let mut camera = Input::new(name).format(backend.format());
+    // avfoundation refuses to guess a frame rate.
+    let fps = options
+        .fps
+        .or((backend == CameraBackend::AvFoundation).then_some(30.0));
+    if let Some(fps) = fps {
+        camera = camera.args(["-framerate", &fps.to_string()]);
+    }
+    if let Some((width, height)) = options.size {
+        camera = camera.args(["-video_size", &format!("{width}x{height}")]);
+    }
+
+    let mut command = FfmpegCommand::new().add_input(camera);
+    if let (Some(microphone), CameraBackend::V4l2) = (&options.microphone, backend) {
+        command = command.add_input(Input::new(microphone).format("pulse"));
+    }
+    if let Some(duration) = options.duration {
+        command = command
+            .args(["-t", &format_timestamp(duration)])
+            .duration(duration);
+    }
+    command = command.video_filter("format=yuv420p");
+    if options.transcode.video_encoder().is_none() {
+        command = command.video_codec("libx264");
+        if options.transcode.preset.is_none() {
+            command = command.args(["-preset", "veryfast"]);
+        }
+    }
+
+    Ok(options.transcode.apply(command))
+}
+
+pub fn capture(output: &Path, options: &CaptureOptions) -> Result<ProcessResult, Error> {
+    capture_command(output, options)?.run()?.check()
+}
@@ -0,0 +1,677 @@
+use std::ffi::OsStr;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, Input, ProcessResult};
+use crate::hwaccel::HwAccel;
+use crate::network::{self, NetworkOptions, check_input, is_url};
+use crate::pool::JobPool;
+use crate::probe::{MediaInfo, Stream, probe};
+use crate::scale::ScaleFit;
+use crate::scan::{DuplicateCheck, SortOrder, read_dir};
+use crate::space::SpaceCheck;
+use crate::temp::TempFile;
+use crate::transcode::TranscodeOptions;
+
+/// A property of one input that differs from the first input.
+#[derive(Debug, Clone)]
+pub struct Mismatch {
+    pub path: PathBuf,
+    pub property: &'static str,
+    pub expected: String,
+    pub found: String,
+}
+
+fn describe<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| "none".to_string(), |v| v.to_string())
+}
+
+fn stream_properties(stream: Option<&Stream>, video: bool) -> Vec<(&'static str, String)> {
+    let Some(stream) = stream else {
+        let name = if video {
+            "video stream"
+        } else {
+            "audio stream"
+        };
+        return vec![(name, "none".to_string())];
+    };
+
+    let mut properties = vec![
+        (
+            if video { "video codec" } else { "audio codec" },
+            stream.codec.clone(),
+        ),
+        (
+            if video {
+                "video time base"
+            } else {
+                "audio time base"
+            },
+            describe(stream.time_base),
+        ),
+    ];
+    if video {
+        properties.push((
+            "resolution",
+            describe(stream.resolution().map(|(w, h)| format!("{w}x{h}"))),
+        ));
+        properties.push(("pixel format", describe(stream.pixel_format.as_ref())));
+    } else {
+        properties.push(("sample rate", describe(stream.sample_rate)));
+        properties.push(("channels", describe(stream.channels)));
+    }
+    properties
+}
+
+fn properties(info: &MediaInfo) -> Vec<(&'static str, String)> {
+    let mut properties = stream_properties(info.video(), true);
+    if info.audio().is_some() || info.video().is_none() {
+        properties.extend(stream_properties(info.audio(), false));
+    }
+    properties
+}
+
+/// Compares the media properties the concat demuxer relies on.
+pub fn check_compatibility(inputs: &[MediaInfo]) -> Vec<Mismatch> {
+    let Some((first, rest)) = inputs.split_first() else {
+        return Vec::new();
+    };
+
+    let expected = properties(first);
+    let mut mismatches = Vec::new();
+    for info in rest {
+        let found = properties(info);
+        for (property, value) in &expected {
+            let other = found
+                .iter()
+                .find(|(p, _)| p == property)
+                .map_or("none", |(_, v)| v.as_str());
+            if other != value {
+                mismatches.push(Mismatch {
+                    path: info.path.clone(),
+                    property,
+                    expected: value.clone(),
+                    found: other.to_string(),
+                });
+            }
+        }
+    }
+    mismatches
+}
+
+fn probe_all<P: AsRef<Path>>(files: &[P]) -> Result<Vec<MediaInfo>, Error> {
+    JobPool::default()
+        .run(files.iter().map(AsRef::as_ref).collect(), probe)
+        .into_iter()
+        .collect()
+}
+
+pub fn validate_inputs<P: AsRef<Path>>(files: &[P]) -> Result<(), Error> {
+    let mismatches = check_compatibility(&probe_all(files)?);
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::IncompatibleInputs(mismatches))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConcatMode {
+    /// Join the streams as they are with the concat demuxer.
+    #[default]
+    Copy,
+    /// Decode every input and join them with the concat filter.
+    Reencode,
+    /// Copy when the inputs match, re-encode otherwise.
+    Auto,
+}
+
+impl FromStr for ConcatMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "copy" => Ok(ConcatMode::Copy),
+            "reencode" => Ok(ConcatMode::Reencode),
+            "auto" => Ok(ConcatMode::Auto),
+            _ => Err(Error::invalid(format!("unknown concat mode `{s}`"))),
+        }
+    }
+}
+
+/// An xfade effect between two clips.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TransitionKind {
+    #[default]
+    Fade,
+    FadeBlack,
+    Dissolve,
+    WipeLeft,
+    WipeRight,
+    WipeUp,
+    WipeDown,
+    SlideLeft,
+    SlideRight,
+    CircleOpen,
+}
+
+impl TransitionKind {
+    /// The name xfade knows the effect by.
+    pub fn name(self) -> &'static str {
+        match self {
+            TransitionKind::Fade => "fade",
+            TransitionKind::FadeBlack => "fadeblack",
+            TransitionKind::Dissolve => "dissolve",
+            TransitionKind::WipeLeft => "wipeleft",
+            TransitionKind::WipeRight => "wiperight",
+            TransitionKind::WipeUp => "wipeup",
+            TransitionKind::WipeDown => "wipedown",
+            TransitionKind::SlideLeft => "slideleft",
+            TransitionKind::SlideRight => "slideright",
+            TransitionKind::CircleOpen => "circleopen",
+        }
+    }
+}
+
+impl FromStr for TransitionKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fade" => Ok(TransitionKind::Fade),
+            "fadeblack" => Ok(TransitionKind::FadeBlack),
+            "dissolve" => Ok(TransitionKind::Dissolve),
+            "wipe" | "wipeleft" => Ok(TransitionKind::WipeLeft),
+            "wiperight" => Ok(TransitionKind::WipeRight),
+            "wipeup" => Ok(TransitionKind::WipeUp),
+            "wipedown" => Ok(TransitionKind::WipeDown),
+            "slideleft" => Ok(TransitionKind::SlideLeft),
+            "slideright" => Ok(TransitionKind::SlideRight),
+            "circleopen" => Ok(TransitionKind::CircleOpen),
+            _ => Err(Error::invalid(format!("unknown transition `{s}`"))),
+        }
+    }
+}
+
+/// Clips overlap by `duration` while one turns into the next, so the output
+/// is that much shorter per transition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transition {
+    pub kind: TransitionKind,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ConcatOptions {
+    pub sort: SortOrder,
+    /// Glob the file names must match as well, e.g. `cam1_*.MP4`.
+    pub pattern: Option<String>,
+    /// Gather files from subfolders too, e.g. footage sorted into date
+    /// folders.
+    pub recursive: bool,
+    pub mode: ConcatMode,
+    /// Frame size when re-encoding, by default the first input's.
+    pub size: Option<(u32, u32)>,
+    /// Frame rate when re-encoding, by default the first input's.
+    pub fps: Option<f64>,
+    /// Encoder settings when re-encoding.
+    pub transcode: TranscodeOptions,
+    /// Add a chapter at the start of every input.
+    pub chapters: bool,
+    /// Blend each clip into the next, which always re-encodes.
+    pub transition: Option<Transition>,
+    /// Whether to make sure the output, estimated as large as the inputs
+    /// together, fits on its disk.
+    pub space_check: SpaceCheck,
+    /// Whether to look for files in the folder that are copies of one
+    /// before them, and leave them out.
+    pub duplicates: DuplicateCheck,
+}
+
+pub fn concat_command(
+    path: &Path,
+    prefix: &str,
+    ext: &str,
+    output: &Path,
+    options: &ConcatOptions,
+) -> Result<FfmpegCommand, Error> {
+    let files = concat_inputs(path, prefix, ext, options)?;
+    concat_files_command(&files, output, options)
+}
+
+/// The files in `path` that [`concat_command`] joins, in the order it joins
+/// them, e.g. to review before passing them to [`concat_files_command`].
+pub fn concat_inputs(
+    path: &Path,
+    prefix: &str,
+    ext: &str,
+    options: &ConcatOptions,
+) -> Result<Vec<PathBuf>, Error> {
+    let files = read_dir(
+        path,
+        prefix,
+        ext,
+        options.pattern.as_deref(),
+        options.recursive,
+        options.sort,
+    )?;
+    options.duplicates.apply(files)
+}
+
+/// Like [`concat_command`] for files given in the order they are joined in.
+/// `options.sort`, `pattern` and `recursive` don't apply.
+pub fn concat_files_command<P: AsRef<Path>>(
+    files: &[P],
+    output: &Path,
+    options: &ConcatOptions,
+) -> Result<FfmpegCommand, Error> {
+    match files {
+        [] => return Err(Error::NoInputFiles),
+        [file] => return Err(Error::SingleInput(file.as_ref().to_path_buf())),
+        _ => {}
+    }
+    for file in files {
+        check_input(file.as_ref())?;
+    }
+    // URLs count as nothing, their size isn't known up front.
+    let needed = files
+        .iter()
+        .filter_map(|file| std::fs::metadata(file).ok())
+        .map(|meta| meta.len())
+        .sum();
+    options.space_check.check(output, needed)?;
+    let inputs = probe_all(files)?;
+
+    let overlap = options.transition.map_or(Duration::ZERO, |t| t.duration);
+    let mismatches = check_compatibility(&inputs);
+    let mut command = match options.mode {
+        _ if options.transition.is_some() => filter_command(&inputs, options)?,
+        ConcatMode::Copy if !mismatches.is_empty() => {
+            return Err(Error::IncompatibleInputs(mismatches));
+        }
+        ConcatMode::Copy => options.transcode.streams.apply(demuxer_command(files)?),
+        ConcatMode::Auto if mismatches.is_empty() => {
+            options.transcode.streams.apply(demuxer_command(files)?)
+        }
+        _ => filter_command(&inputs, options)?,
+    };
+    if options.chapters {
+        let chapters = chapters_file(&inputs, overlap)?;
+        let index = command.input_count().to_string();
+        command = command
+            .add_input(Input::new(chapters.path()).format("ffmetadata"))
+            .args(["-map_chapters", &index])
+            .temp_file(chapters);
+    }
+    command = command.output(output);
+    if let Some(total) = inputs.iter().map(|i| i.duration).sum::<Option<Duration>>() {
+        let overlaps = overlap * inputs.len().saturating_sub(1) as u32;
+        command = command.duration(total.saturating_sub(overlaps));
+    }
+
+    Ok(command)
+}
+
+pub(crate) fn demuxer_command<P: AsRef<Path>>(files: &[P]) -> Result<FfmpegCommand, Error> {
+    let (list, mut f) = TempFile::create("media_tools-concat", "txt")?;
+
+    // Relative entries would be resolved against the temp directory.
+    let mut urls = false;
+    let mut links = None;
+    for (i, file) in files.iter().enumerate() {
+        let file = file.as_ref();
+        let mut file = if is_url(file) {
+            urls = true;
+            file.to_path_buf()
+        } else {
+            std::path::absolute(file).map_err(Error::AccessDenied)?
+        };
+        if has_line_break(&file) {
+            file = link_without_line_breaks(&mut links, i, &file)?;
+        }
+        let mut entry = b"file ".to_vec();
+        entry.extend(quote_entry(file.as_os_str()));
+        entry.push(b'\n');
+        f.write_all(&entry).map_err(Error::WriteFileError)?;
+    }
+
+    // `-safe 0` lets the list name any file; the whitelist still limits
+    // which protocols it can reach.
+    let mut input = Input::new(list.path())
+        .format("concat")
+        .args(["-safe", "0"]);
+    if urls {
+        input = input.args([
+            "-protocol_whitelist".to_string(),
+            NetworkOptions::current().protocol_whitelist(),
+        ]);
+    }
+    let mut command = FfmpegCommand::new()
+        .add_input(input)
+        .codec_copy()
+        .temp_file(list);
+    if let Some(links) = links {
+        command = command.temp_file(links);
+    }
+    Ok(command)
+}
+
+/// Quotes `path` for a concat list: all of it in single quotes, with each
+/// quote in it closed, escaped and reopened, as in `'it'\''s.mp4'`. The
+/// bytes of the path are kept as they are, whatever their encoding.
+fn quote_entry(path: &OsStr) -> Vec<u8> {
+    let mut quoted = vec![b'\''];
+    for &byte in path.as_encoded_bytes() {
+        if byte == b'\'' {
+            quoted.extend(b"'\\''");
+        } else {
+            quoted.push(byte);
+        }
+    }
+    quoted.push(b'\'');
+    quoted
+}
+
+fn has_line_break(path: &Path) -> bool {
+    path.as_os_str()
+        .as_encoded_bytes()
+        .iter()
+        .any(|b| matches!(b, b'\n' | b'\r'))
+}
+
+/// A concat list entry ends at the line break, whatever the quoting, so a
+/// file with one in its path is named through a link in `dir`, which is
+/// created on first use.
+fn link_without_line_breaks(
+    dir: &mut Option<TempFile>,
+    index: usize,
+    file: &Path,
+) -> Result<PathBuf, Error> {
+    #[cfg(unix)]
+    {
+        if dir.is_none() {
+            *dir = Some(TempFile::create_dir("media_tools-links")?);
+        }
+        let mut name = PathBuf::from(index.to_string());
+        // ffmpeg may go by the extension to tell the format.
+        if let Some(ext) = file.extension()
+            && !has_line_break(Path::new(ext))
+        {
+            name.set_extension(ext);
+        }
+        let link = dir.as_ref().unwrap().path().join(name);
+        std::os::unix::fs::symlink(file, &link).map_err(Error::WriteFileError)?;
+        Ok(link)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (dir, index);
+        Err(Error::invalid(format!(
+            "cannot concat {}, its path has a line break",
+            file.display()
+        )))
+    }
+}
+
+/// Writes an ffmetadata file with a chapter per input, titled after its file
+/// name. Each chapter starts with the transition into its clip.
+fn chapters_file(inputs: &[MediaInfo], overlap: Duration) -> Result<TempFile, Error> {
+    let (file, mut f) = TempFile::create("media_tools-chapters", "txt")?;
+    let mut text = String::from(";FFMETADATA1\n");
+    let mut start = Duration::ZERO;
+    for (i, info) in inputs.iter().enumerate() {
+        let duration = info.duration.ok_or_else(|| {
+            Error::invalid(format!("unknown duration of {}", info.path.display()))
+        })?;
+        // The last clip plays out in full.
+        let end = if i + 1 == inputs.len() {
+            start + duration
+        } else {
+            start + duration.saturating_sub(overlap)
+        };
+        let title = info.path.file_stem().unwrap_or_default().to_string_lossy();
+        text.push_str(&format!(
+            "\n[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle={}\n",
+            start.as_millis(),
+            end.as_millis(),
+            escape_metadata(&title)
+        ));
+        start = end;
+    }
+    f.write_all(text.as_bytes())
+        .map_err(Error::WriteFileError)?;
+    Ok(file)
+}
+
+/// Escapes the characters ffmetadata files treat specially.
+fn escape_metadata(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if "=;#\\\n".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Scales, pads and resamples every input to one format before the concat
+/// filter, which needs identical streams.
+fn filter_command(inputs: &[MediaInfo], options: &ConcatOptions) -> Result<FfmpegCommand, Error> {
+    let first = inputs.first().and_then(MediaInfo::video);
+    let (width, height) = options
+        .size
+        .or_else(|| first.and_then(Stream::resolution))
+        .ok_or_else(|| Error::invalid("cannot tell the output size, set it explicitly"))?;
+    // yuv420p needs even dimensions.
+    let (width, height) = (width & !1, height & !1);
+    let fps = options
+        .fps
+        .or_else(|| first.and_then(|s| s.frame_rate).map(|r| r.as_f64()))
+        .unwrap_or(30.0);
+    let streams = &options.transcode.streams;
+    if !streams.maps.is_empty() {
+        return Err(Error::invalid(
+            "stream maps don't apply when re-encoding, choose an audio track instead",
+        ));
+    }
+    let track = streams.audio_track.map_or(0, |t| t.saturating_sub(1));
+    let audio = inputs
+        .iter()
+        .any(|i| i.audio_streams().nth(track).is_some());
+
+    let mut command = FfmpegCommand::new();
+    let mut graph = Vec::new();
+    let mut segments = String::new();
+    for (i, info) in inputs.iter().enumerate() {
+        command = command.add_input(network::input(&info.path));
+        graph.push(format!(
+            "[{i}:v:0]{},fps={fps},format=yuv420p[v{i}]",
+            ScaleFit::Pad.filter(width, height)
+        ));
+        segments.push_str(&format!("[v{i}]"));
+        if !audio {
+            continue;
+        }
+        if info.audio_streams().nth(track).is_some() {
+            graph.push(format!(
+                "[{i}:a:{track}]aresample=48000,aformat=sample_rates=48000:channel_layouts=stereo[a{i}]"
+            ));
+        } else {
+            // Keep the segments aligned with silence for inputs without audio.
+            let duration = info.duration.ok_or_else(|| {
+                Error::invalid(format!("unknown duration of {}", info.path.display()))
+            })?;
+            graph.push(format!(
+                "anullsrc=channel_layout=stereo:sample_rate=48000:d={}[a{i}]",
+                duration.as_secs_f64()
+            ));
+        }
+        segments.push_str(&format!("[a{i}]"));
+    }
+
+    let n = inputs.len();
+    let upload = options.transcode.hwaccel.and_then(HwAccel::upload_filter);
+    let video = if upload.is_some() { "[vc]" } else { "[v]" };
+    match options.transition {
+        Some(transition) if n > 1 => {
+            graph.extend(transition_graph(inputs, transition, video, audio)?);
+        }
+        _ => {
+            let (a, audio_out) = if audio { (1, "[a]") } else { (0, "") };
+            graph.push(format!(
+                "{segments}concat=n={n}:v=1:a={a}{video}{audio_out}"
+            ));
+        }
+    }
+    if let Some(upload) = upload {
+        graph.push(format!("[vc]{upload}[v]"));
+    }
+    command = command.filter_complex(graph.join(";")).map("[v]");
+    if audio {
+        command = command.map("[a]");
+    }
+
+    Ok(options.transcode.apply_codecs(command))
+}
+
+/// Chains xfade, and acrossfade for the audio, over the normalized `[vN]`
+/// and `[aN]` streams. Each transition starts where the output so far ends,
+/// less its duration.
+fn transition_graph(
+    inputs: &[MediaInfo],
+    transition: Transition,
+    video: &str,
+    audio: bool,
+) -> Result<Vec<String>, Error> {
+    let d = transition.duration.as_secs_f64();
+    if transition.duration.is_zero() {
+        return Err(Error::invalid("the transition duration must be positive"));
+    }
+
+    let mut durations = Vec::new();
+    for info in inputs {
+        match info.duration {
+            Some(duration) if duration > transition.duration => durations.push(duration),
+            _ => {
+                return Err(Error::invalid(format!(
+                    "{} must be longer than the transition",
+                    info.path.display()
+                )));
+            }
+        }
+    }
+
+    let mut graph = Vec::new();
+    let mut offset = Duration::ZERO;
+    let (mut v, mut a) = ("[v0]".to_string(), "[a0]".to_string());
+    for i in 1..inputs.len() {
+        offset += durations[i - 1] - transition.duration;
+        let (v_out, a_out) = if i + 1 == inputs.len() {
+            (video.to_string(), "[a]".to_string())
+        } else {
+            (format!("[vx{i}]"), format!("[ax{i}]"))
+        };
+        graph.push(format!(
+            "{v}[v{i}]xfade=transition={}:duration={d}:offset={}{v_out}",
+            transition.kind.name(),
+            offset.as_secs_f64()
+        ));
+        if audio {
+            graph.push(format!("{a}[a{i}]acrossfade=d={d}{a_out}"));
+        }
+        (v, a) = (v_out, a_out);
+    }
+    Ok(graph)
+}
+
+pub fn concat(
+    path: &Path,
+    prefix: &str,
+    ext: &str,
+    output: &Path,
+    options: &ConcatOptions,
+) -> Result<ProcessResult, Error> {
+    concat_command(path, prefix, ext, output, options)?
+        .run()?
+        .check()
+}
+
+pub fn concat_files<P: AsRef<Path>>(
+    files: &[P],
+    output: &Path,
+    options: &ConcatOptions,
+) -> Result<ProcessResult, Error> {
+    concat_files_command(files, output, options)?.run()?.check()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quote_entry_closes_and_reopens_around_quotes() {
+        assert_eq!(quote_entry(OsStr::new("it's")), b"'it'\\''s'");
+    }
+
+    #[test]
+    fn quote_entry_keeps_backslashes_and_dashes() {
+        assert_eq!(quote_entry(OsStr::new("a\\b.mp4")), b"'a\\b.mp4'");
+        assert_eq!(quote_entry(OsStr::new("-clip.mp4")), b"'-clip.mp4'");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn quote_entry_keeps_bytes_that_are_not_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+        let name = OsStr::from_bytes(b"caf\xe9.mp4");
+        assert_eq!(quote_entry(name), b"'caf\xe9.mp4'");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn files_with_line_breaks_are_listed_through_links() {
+        let dir = TempFile::create_dir("media_tools-test").unwrap();
+        let files: Vec<PathBuf> = ["line\nbreak.mp4", "carriage\rreturn.mp4", "plain.mp4"]
+            .iter()
+            .map(|name| dir.path().join(name))
+            .collect();
+        for file in &files {
+            std::fs::write(file, b"").unwrap();
+        }
+        assert!(has_line_break(&files[0]) && has_line_break(&files[1]));
+        assert!(!has_line_break(&files[2]));
+
+        let command = demuxer_command(&files).unwrap();
+        let args = command.build_args();
+        let list = args
+            .iter()
+            .position(|arg| arg == "-i")
+            .map(|i| PathBuf::from(&args[i + 1]))
+            .unwrap();
+        let text = std::fs::read(&list).unwrap();
+        assert!(!text.contains(&b'\r'));
+        let entries: Vec<&[u8]> = text
+            .split(|&byte| byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .collect();
+        assert_eq!(entries.len(), files.len());
+
+        for (entry, file) in entries.iter().zip(&files) {
+            let path = entry
+                .strip_prefix(b"file '")
+                .and_then(|rest| rest.strip_suffix(b"'"))
+                .unwrap();
+            let path = Path::new(std::str::from_utf8(path).unwrap());
+            if has_line_break(file) {
+                assert_ne!(path, file.as_path());
+                assert_eq!(std::fs::read_link(path).unwrap(), *file);
+            } else {
+                assert_eq!(path, file.as_path());
+            }
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use std::path::Path;
+
+use crate::ffmpeg::FfmpegCommand;
+use crate::json::Json;
+use crate::probe::{MediaInfo, Stream, StreamKind};
+
+/// An output container, told by the file extension, and the codecs it can
+/// hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Container {
+    Mp4,
+    Mov,
+    Matroska,
+    WebM,
+    MpegTs,
+    /// Anything else, trusted to take whatever it is given.
+    Other,
+}
+
+const TEXT_SUBTITLES: [&str; 6] = ["subrip", "ass", "ssa", "mov_text", "webvtt", "text"];
+
+impl Container {
+    pub fn from_path(path: &Path) -> Self {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase)
+            .unwrap_or_default();
+        match ext.as_str() {
+            "mp4" | "m4v" | "m4a" => Container::Mp4,
+            "mov" => Container::Mov,
+            "mkv" | "mka" => Container::Matroska,
+            "webm" => Container::WebM,
+            "ts" | "m2ts" | "mts" => Container::MpegTs,
+            _ => Container::Other,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Container::Mp4 => "mp4",
+            Container::Mov => "mov",
+            Container::Matroska => "matroska",
+            Container::WebM => "webm",
+            Container::MpegTs => "mpegts",
+            Container::Other => "other",
+        }
+    }
+
+    /// Whether a stream can be copied into the container as it is.
+    pub fn accepts(self, stream: &Stream) -> bool {
+        let codec = stream.codec.as_str();
+        let mp4_video = ["h264", "hevc", "av1", "vp9", "mpeg4", "mpeg2video", "mjpeg"];
+        let mp4_audio = ["aac", "mp3", "ac3", "eac3", "opus", "flac", "alac"];
+        match (self, stream.kind) {
+            (Container::Other, _) => true,
+            (_, StreamKind::Unknown) => false,
+
+            (Container::Mp4, StreamKind::Video) => mp4_video.contains(&codec),
+            (Container::Mov, StreamKind::Video) => {
+                mp4_video.contains(&codec) || codec == "prores" || codec == "dnxhd"
+            }
+            (Container::Mp4, StreamKind::Audio) => mp4_audio.contains(&codec),
+            (Container::Mov, StreamKind::Audio) => {
+                mp4_audio.contains(&codec) || codec.starts_with("pcm_")
+            }
+            (Container::Mp4 | Container::Mov, StreamKind::Subtitle) => codec == "mov_text",
+            (Container::Mp4 | Container::Mov, _) => false,
+
+            // Matroska holds nearly anything but MP4's own subtitle format.
+            (Container::Matroska, StreamKind::Subtitle) => codec != "mov_text",
+            (Container::Matroska, StreamKind::Data) => false,
+            (Container::Matroska, _) => true,
+
+            (Container::WebM, StreamKind::Video) => ["vp8", "vp9", "av1"].contains(&codec),
+            (Container::WebM, StreamKind::Audio) => ["opus", "vorbis"].contains(&codec),
+            (Container::WebM, StreamKind::Subtitle) => codec == "webvtt",
+            (Container::WebM, _) => false,
+
+            (Container::MpegTs, StreamKind::Video) => {
+                ["h264", "hevc", "mpeg2video", "mpeg1video", "av1"].contains(&codec)
+            }
+            (Container::MpegTs, StreamKind::Audio) => {
+                ["aac", "mp3", "mp2", "ac3", "eac3", "opus"].contains(&codec)
+            }
+            (Container::MpegTs, StreamKind::Subtitle) => {
+                ["dvb_subtitle", "dvb_teletext"].contains(&codec)
+            }
+            (Container::MpegTs, _) => false,
+        }
+    }
+
+    /// The encoder a stream the container doesn't accept is converted with,
+    /// `None` when it is dropped instead. Only text subtitles convert to
+    /// other subtitle formats.
+    pub fn fallback(self, stream: &Stream) -> Option<&'static str> {
+        let text = TEXT_SUBTITLES.contains(&stream.codec.as_str());
+        match (self, stream.kind) {
+            (Container::Mp4 | Container::Mov | Container::MpegTs, StreamKind::Video) => {
+                Some("libx264")
+            }
+            (Container::Mp4 | Container::Mov | Container::MpegTs, StreamKind::Audio) => Some("aac"),
+            (Container::Mp4 | Container::Mov, StreamKind::Subtitle) if text => Some("mov_text"),
+            (Container::WebM, StreamKind::Video) => Some("libvpx-vp9"),
+            (Container::WebM, StreamKind::Audio) => Some("libopus"),
+            (Container::WebM, StreamKind::Subtitle) if text => Some("webvtt"),
+            (Container::Matroska, StreamKind::Subtitle) if text => Some("srt"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamAction {
+    Copy,
+    /// Re-encoded with this encoder.
+    Convert(String),
+    Drop,
+}
+
+/// What happens to one input stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamPlan {
+    pub index: u32,
+    pub kind: StreamKind,
+    pub codec: String,
+    pub action: StreamAction,
+}
+
+impl StreamPlan {
+    pub fn to_json(&self) -> Json {
+        let (action, encoder) = match &self.action {
+            StreamAction::Copy => ("copy", None),
+            StreamAction::Convert(encoder) => ("convert", Some(encoder.as_str())),
+            StreamAction::Drop => ("drop", None),
+        };
+        Json::object([
+            ("index", Json::from(self.index)),
+            ("kind", self.kind.name().into()),
+            ("codec", self.codec.as_str().into()),
+            ("action", action.into()),
+            ("encoder", encoder.into()),
+        ])
+    }
+}
+
+/// Copies every stream the container takes and converts or drops the rest.
+pub fn plan_streams(info: &MediaInfo, container: Container) -> Vec<StreamPlan> {
+    plan_transcode(info, container, None, None)
+}
+
+/// Like [`plan_streams`], but video and audio streams are only copied when
+/// they already have the codec the given encoders produce, e.g. `h264` for
+/// `libx264`, and converted with those encoders otherwise.
+pub fn plan_transcode(
+    info: &MediaInfo,
+    container: Container,
+    video_encoder: Option<&str>,
+    audio_encoder: Option<&str>,
+) -> Vec<StreamPlan> {
+    info.streams
+        .iter()
+        .map(|stream| {
+            let encoder = match stream.kind {
+                StreamKind::Video => video_encoder,
+                StreamKind::Audio => audio_encoder,
+                _ => None,
+            };
+            let copy = container.accepts(stream)
+                && encoder.is_none_or(|encoder| encoded_codec(encoder) == stream.codec);
+            let action = match encoder {
+                _ if copy => StreamAction::Copy,
+                Some(encoder) => StreamAction::Convert(encoder.to_string()),
+                None => container
+                    .fallback(stream)
+                    .map_or(StreamAction::Drop, |encoder| {
+                        StreamAction::Convert(encoder.to_string())
+                    }),
+            };
+            StreamPlan {
+                index: stream.index,
+                kind: stream.kind,
+                codec: stream.codec.clone(),
+                action,
+            }
+        })
+        .collect()
+}
+
+/// The codec name ffprobe reports for what an encoder writes.
+fn encoded_codec(encoder: &str) -> &str {
+    match encoder {
+        "libx264" | "libopenh264" => "h264",
+        "libx265" => "hevc",
+        "libvpx" => "vp8",
+        "libvpx-vp9" => "vp9",
+        "libaom-av1" | "libsvtav1" | "librav1e" => "av1",
+        "libmp3lame" => "mp3",
+        "libopus" => "opus",
+        "libvorbis" => "vorbis",
+        "libfdk_aac" => "aac",
+        // Hardware encoders are named after their codec, e.g. h264_nvenc.
+        encoder => ["_nvenc", "_qsv", "_vaapi", "_videotoolbox", "_amf"]
+            .iter()
+            .find_map(|accel| encoder.strip_suffix(accel))
+            .unwrap_or(encoder),
+    }
+}
+
+/// Maps the streams of input 0 that are kept and sets their codecs, by
+/// their position in the output.
+pub(crate) fn apply_plan(mut command: FfmpegCommand, plans: &[StreamPlan]) -> FfmpegCommand {
+    let kept = plans
+        .iter()
+        .filter(|plan| plan.action != StreamAction::Drop);
+    for (output, plan) in kept.enumerate() {
+        let codec = match &plan.action {
+            StreamAction::Convert(encoder) => encoder.as_str(),
+            _ => "copy",
+        };
+        command = command
+            .map(&format!("0:{}", plan.index))
+            .args([format!("-c:{output}"), codec.to_string()]);
+    }
+    command
+}
@@ -0,0 +1,198 @@
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, Input, ProcessResult};
+use crate::probe::probe;
+use crate::time::format_timestamp;
+use crate::transcode::TranscodeOptions;
+
+/// A crop rectangle, centred when no offset is given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CropArea {
+    pub width: u32,
+    pub height: u32,
+    pub x: Option<u32>,
+    pub y: Option<u32>,
+}
+
+impl CropArea {
+    /// Parses cropdetect's `w:h:x:y` value.
+    fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.split(':').map(|p| p.parse::<u32>().ok());
+        Some(Self {
+            width: parts.next()??,
+            height: parts.next()??,
+            x: Some(parts.next()??),
+            y: Some(parts.next()??),
+        })
+    }
+}
+
+impl fmt::Display for CropArea {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.width, self.height)?;
+        if self.x.is_some() || self.y.is_some() {
+            // crop's own defaults centre the axis that is left out.
+            let x = self
+                .x
+                .map_or_else(|| "(iw-ow)/2".to_string(), |x| x.to_string());
+            let y = self
+                .y
+                .map_or_else(|| "(ih-oh)/2".to_string(), |y| y.to_string());
+            write!(f, ":{x}:{y}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum CropMode {
+    Manual(CropArea),
+    /// Detect black bars over a window of the input.
+    Auto {
+        /// Where the window starts, a tenth into the input by default to
+        /// skip black intros.
+        start: Option<Duration>,
+        window: Duration,
+    },
+}
+
+impl Default for CropMode {
+    fn default() -> Self {
+        CropMode::Auto {
+            start: None,
+            window: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CropOptions {
+    pub mode: CropMode,
+    pub transcode: TranscodeOptions,
+}
+
+/// The cropdetect pass over `window` from `start`, which prints its
+/// suggestions to stderr.
+pub fn detect_crop_command(
+    input: &Path,
+    start: Duration,
+    window: Duration,
+) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+
+    Ok(FfmpegCommand::new()
+        .add_input(Input::new(input).args(["-ss", &format_timestamp(start)]))
+        .args(["-t", &format_timestamp(window)])
+        .video_filter("cropdetect")
+        .arg("-an")
+        .format("null")
+        .output("-"))
+}
+
+/// Runs cropdetect over `window` from `start` and returns the most common
+/// suggestion.
+pub fn detect_crop(input: &Path, start: Duration, window: Duration) -> Result<CropArea, Error> {
+    let result = detect_crop_command(input, start, window)?.run()?.check()?;
+
+    let mut counts: Vec<(CropArea, usize)> = Vec::new();
+    for area in result
+        .stderr
+        .lines()
+        .filter_map(|line| line.rsplit_once("crop="))
+        .filter_map(|(_, value)| CropArea::parse(value.trim()))
+    {
+        match counts.iter_mut().find(|(a, _)| *a == area) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((area, 1)),
+        }
+    }
+
+    counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(area, _)| area)
+        .ok_or(Error::StreamNotFound)
+}
+
+pub fn crop_command(
+    input: &Path,
+    output: &Path,
+    options: &CropOptions,
+) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+
+    let duration = probe(input).ok().and_then(|info| info.duration);
+    let area = match options.mode {
+        CropMode::Manual(area) => area,
+        CropMode::Auto { start, window } => {
+            let start = start.unwrap_or_else(|| duration.unwrap_or_default() / 10);
+            detect_crop(input, start, window)?
+        }
+    };
+    if area.width == 0 || area.height == 0 {
+        return Err(Error::invalid("the crop size must be positive"));
+    }
+    Ok(crop_pass(
+        input,
+        output,
+        options,
+        &area.to_string(),
+        duration,
+    ))
+}
+
+/// For a dry run: with [`CropMode::Auto`] the cropdetect pass, and the crop
+/// with `DETECTED` where the area it finds goes, as nothing is run to find
+/// it. A manual crop is the same one command as [`crop_command`].
+pub fn crop_preview(
+    input: &Path,
+    output: &Path,
+    options: &CropOptions,
+) -> Result<Vec<FfmpegCommand>, Error> {
+    let CropMode::Auto { start, window } = options.mode else {
+        return Ok(vec![crop_command(input, output, options)?]);
+    };
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+
+    let duration = probe(input).ok().and_then(|info| info.duration);
+    let start = start.unwrap_or_else(|| duration.unwrap_or_default() / 10);
+    Ok(vec![
+        detect_crop_command(input, start, window)?,
+        crop_pass(input, output, options, "DETECTED", duration),
+    ])
+}
+
+/// Crops to `area`, given as the crop filter takes it.
+fn crop_pass(
+    input: &Path,
+    output: &Path,
+    options: &CropOptions,
+    area: &str,
+    duration: Option<Duration>,
+) -> FfmpegCommand {
+    let mut command = FfmpegCommand::new()
+        .input(input)
+        .video_filter(format!("crop={area}"));
+    if let Some(duration) = duration {
+        command = command.duration(duration);
+    }
+    let transcode = &options.transcode;
+    if transcode.audio_codec.is_none() && transcode.audio_bitrate.is_none() {
+        command = command.audio_codec("copy");
+    }
+
+    transcode.apply(command).output(output)
+}
+
+pub fn crop(input: &Path, output: &Path, options: &CropOptions) -> Result<ProcessResult, Error> {
+    crop_command(input, output, options)?.run()?.check()
+}
@@ -0,0 +1,104 @@
+use std::path::Path;
+use std::time::Duration;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, ProcessResult};
+use crate::probe::probe;
+use crate::rendition::{Rendition, map_renditions};
+use crate::time::format_timestamp;
+use crate::trim::TrimMode;
+
+#[derive(Debug, Clone)]
+pub struct DashOptions {
+    pub segment_duration: Duration,
+    /// The manifest file name inside the output directory.
+    pub manifest: String,
+    /// Encode one representation per entry. When empty, the input streams
+    /// are packaged as a single representation according to `mode`.
+    pub representations: Vec<Rendition>,
+    /// For multiple representations only the codecs and preset are used.
+    pub mode: TrimMode,
+}
+
+impl Default for DashOptions {
+    fn default() -> Self {
+        Self {
+            segment_duration: Duration::from_secs(4),
+            manifest: "manifest.mpd".to_string(),
+            representations: Vec::new(),
+            mode: TrimMode::Copy,
+        }
+    }
+}
+
+pub fn package_dash_command(
+    input: &Path,
+    output_dir: &Path,
+    options: &DashOptions,
+) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+    if options.segment_duration.is_zero() {
+        return Err(Error::invalid("the segment duration must be positive"));
+    }
+    std::fs::create_dir_all(output_dir).map_err(Error::CreateOutputError)?;
+
+    let segment_duration = format_timestamp(options.segment_duration);
+    // Multiple representations need to know which streams exist.
+    let info = if options.representations.is_empty() {
+        probe(input).ok()
+    } else {
+        Some(probe(input)?)
+    };
+
+    let mut command = FfmpegCommand::new().input(input);
+    if let Some(duration) = info.as_ref().and_then(|info| info.duration) {
+        command = command.duration(duration);
+    }
+    if options.representations.is_empty() {
+        command = command.map("0:v:0?").map("0:a:0?");
+        command = match &options.mode {
+            TrimMode::Copy => command.codec_copy(),
+            TrimMode::Reencode(transcode) => transcode.apply(command),
+        };
+    } else if let Some(info) = info {
+        if info.video().is_none() {
+            return Err(Error::StreamNotFound);
+        }
+        let audio = info.audio().is_some();
+        let transcode = match &options.mode {
+            TrimMode::Copy => Default::default(),
+            TrimMode::Reencode(transcode) => transcode.clone(),
+        };
+        command =
+            map_renditions(command, &options.representations, &transcode, audio, false).args([
+                "-force_key_frames",
+                &format!("expr:gte(t,n_forced*{segment_duration})"),
+            ]);
+
+        // Keep the video representations switchable in one adaptation set.
+        let sets = if audio {
+            "id=0,streams=v id=1,streams=a"
+        } else {
+            "id=0,streams=v"
+        };
+        command = command.args(["-adaptation_sets", sets]);
+    }
+
+    Ok(command
+        .format("dash")
+        .args(["-seg_duration", &segment_duration])
+        .args(["-use_template", "1", "-use_timeline", "1"])
+        .output(output_dir.join(&options.manifest)))
+}
+
+pub fn package_dash(
+    input: &Path,
+    output_dir: &Path,
+    options: &DashOptions,
+) -> Result<ProcessResult, Error> {
+    package_dash_command(input, output_dir, options)?
+        .run()?
+        .check()
+}
@@ -0,0 +1,35 @@
+use std::str::FromStr;
+
+use crate::Error;
+
+/// A deinterlacing filter. Both output one frame per frame, keeping the
+/// frame rate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Deinterlacer {
+    /// Fast and widely available.
+    #[default]
+    Yadif,
+    /// Sharper than yadif, at some extra cost.
+    Bwdif,
+}
+
+impl Deinterlacer {
+    pub fn name(self) -> &'static str {
+        match self {
+            Deinterlacer::Yadif => "yadif",
+            Deinterlacer::Bwdif => "bwdif",
+        }
+    }
+}
+
+impl FromStr for Deinterlacer {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "yadif" => Ok(Deinterlacer::Yadif),
+            "bwdif" => Ok(Deinterlacer::Bwdif),
+            _ => Err(Error::invalid(format!("unknown deinterlacer `{s}`"))),
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::path::Path;
+use std::time::Duration;
+
+use crate::Error;
+use crate::ffmpeg::FfmpegCommand;
+use crate::json::Json;
+use crate::probe::probe;
+use crate::time::TimeRange;
+
+#[derive(Debug, Clone)]
+pub struct DetectOptions {
+    pub black: bool,
+    pub freeze: bool,
+    /// Shorter stretches of black or frozen video are not reported.
+    pub min_duration: Duration,
+    /// How dark a pixel must be to count as black, from 0 to 1.
+    pub black_threshold: f64,
+    /// How much two frames may differ, in dB, and still count as frozen.
+    pub freeze_noise: f64,
+}
+
+impl Default for DetectOptions {
+    fn default() -> Self {
+        Self {
+            black: true,
+            freeze: true,
+            min_duration: Duration::from_secs(2),
+            black_threshold: 0.1,
+            freeze_noise: -60.0,
+        }
+    }
+}
+
+/// Where the video is black or frozen.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Detections {
+    pub black: Vec<TimeRange>,
+    pub freeze: Vec<TimeRange>,
+}
+
+impl Detections {
+    pub fn is_empty(&self) -> bool {
+        self.black.is_empty() && self.freeze.is_empty()
+    }
+
+    pub fn to_json(&self) -> Json {
+        let ranges =
+            |ranges: &[TimeRange]| Json::Array(ranges.iter().map(TimeRange::to_json).collect());
+        Json::object([
+            ("black", ranges(&self.black)),
+            ("freeze", ranges(&self.freeze)),
+        ])
+    }
+}
+
+/// Decodes the first video stream through blackdetect and freezedetect.
+pub fn detect_command(input: &Path, options: &DetectOptions) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+    if !options.black && !options.freeze {
+        return Err(Error::invalid("nothing to detect"));
+    }
+    if !(0.0..=1.0).contains(&options.black_threshold) {
+        return Err(Error::invalid(
+            "the black threshold must be between 0 and 1",
+        ));
+    }
+    if !options.freeze_noise.is_finite() {
+        return Err(Error::invalid("the freeze noise must be a number"));
+    }
+
+    let min_duration = options.min_duration.as_secs_f64();
+    let mut filters = Vec::new();
+    if options.black {
+        filters.push(format!(
+            "blackdetect=d={min_duration}:pix_th={}",
+            options.black_threshold
+        ));
+    }
+    if options.freeze {
+        filters.push(format!(
+            "freezedetect=n={}dB:d={min_duration}",
+            options.freeze_noise
+        ));
+    }
+
+    let mut command = FfmpegCommand::new()
+        .input(input)
+        .map("0:v:0")
+        .video_filter(filters.join(","))
+        .format("null")
+        .output("-");
+    if let Some(duration) = probe(input).ok().and_then(|info| info.duration) {
+        command = command.duration(duration);
+    }
+    Ok(command)
+}
+
+pub fn detect(input: &Path, options: &DetectOptions) -> Result<Detections, Error> {
+    let command = detect_command(input, options)?;
+    let duration = command.expected_duration();
+    let result = command.run()?.check()?;
+    Ok(Detections {
+        black: detected_ranges(&result.stderr, "black_start", "black_end", duration),
+        // freezedetect logs its findings as frame metadata.
+        freeze: detected_ranges(
+            &result.stderr,
+            "lavfi.freezedetect.freeze_start",
+            "lavfi.freezedetect.freeze_end",
+            duration,
+        ),
+    })
+}
+
+/// Pairs up the `start_key: 1.5` and `end_key: 2.5` values a detection
+/// filter logs. A range still open at the end runs to `duration`.
+pub(crate) fn detected_ranges(
+    stderr: &str,
+    start_key: &str,
+    end_key: &str,
+    duration: Option<Duration>,
+) -> Vec<TimeRange> {
+    let mut ranges = Vec::new();
+    let mut start = None;
+    for line in stderr.lines() {
+        if let Some(time) = value_after(line, start_key) {
+            start = Some(time);
+        }
+        if let Some(end) = value_after(line, end_key)
+            && let Some(start) = start.take()
+        {
+            ranges.push(TimeRange { start, end });
+        }
+    }
+    if let (Some(start), Some(end)) = (start, duration)
+        && end > start
+    {
+        ranges.push(TimeRange { start, end });
+    }
+    ranges
+}
+
+fn value_after(line: &str, key: &str) -> Option<Duration> {
+    let (_, rest) = line.split_once(&format!("{key}:"))?;
+    rest.trim_start()
+        .split(|c: char| c.is_whitespace() || c == '|')
+        .next()?
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| secs.is_finite())
+        // silencedetect reports a start a little before 0 for files that
+        // open with silence.
+        .map(|secs| Duration::from_secs_f64(secs.max(0.0)))
+}
@@ -0,0 +1,203 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::concat::Mismatch;
+use crate::ffmpeg::ProcessFailure;
+use crate::space::format_bytes;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    FolderNotFound(PathBuf),
+    AccessDenied(io::Error),
+    FileNotFound(PathBuf),
+
+    CreateOutputError(io::Error),
+    /// The output exists and may not be overwritten.
+    OutputExists(PathBuf),
+    WriteFileError(io::Error),
+    /// The output would need more room than its disk has, in bytes.
+    InsufficientSpace {
+        needed: u64,
+        available: u64,
+    },
+    /// ffmpeg or ffprobe could not be started or waited on.
+    CommandError(io::Error),
+    ProcessFailed(ProcessFailure),
+    /// A [`crate::CancelToken`] stopped the command.
+    Cancelled,
+    /// ffmpeg ran longer than [`crate::FfmpegCommand::timeout`] allows.
+    Timeout(Duration),
+    /// ffmpeg succeeded but what it wrote is unusable, see
+    /// [`crate::validate_output`].
+    OutputValidationFailed {
+        path: PathBuf,
+        reason: String,
+    },
+    /// ffprobe could not read the file, with its explanation.
+    ProbeError(String),
+    ParseError,
+    StreamNotFound,
+
+    IncompatibleInputs(Vec<Mismatch>),
+    /// Nothing to concatenate, e.g. no file in the folder matched.
+    NoInputFiles,
+    /// Only this file was given to concatenate.
+    SingleInput(PathBuf),
+
+    InvalidArgument(String),
+    /// A [`crate::FilterGraph`] whose pads don't connect up.
+    InvalidFilterGraph(String),
+    InvalidTimestamp,
+    InvalidTimeRange,
+}
+
+/// What ffmpeg prints when the connection to a server failed.
+pub(crate) const CONNECTION_MESSAGES: [&str; 7] = [
+    "Connection refused",
+    "Connection reset",
+    "Broken pipe",
+    "Connection timed out",
+    "Network is unreachable",
+    "Input/output error",
+    "Connection to tcp",
+];
+
+/// Other messages of failures that may pass by themselves: a device another
+/// program holds, a name server or web server having trouble.
+const TRANSIENT_MESSAGES: [&str; 8] = [
+    "Device or resource busy",
+    "Resource temporarily unavailable",
+    "Temporary failure in name resolution",
+    "Server returned 5XX",
+    "HTTP error 429",
+    "HTTP error 502",
+    "HTTP error 503",
+    "HTTP error 504",
+];
+
+/// Whether trying an operation again could make it succeed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// The network or a device failed for now, see [`crate::RetryPolicy`].
+    Transient,
+    /// The same inputs and settings fail the same way again.
+    Permanent,
+}
+
+impl Error {
+    pub(crate) fn invalid(message: impl Into<String>) -> Self {
+        Error::InvalidArgument(message.into())
+    }
+
+    pub fn class(&self) -> ErrorClass {
+        let transient = |text: &str| {
+            CONNECTION_MESSAGES
+                .iter()
+                .chain(&TRANSIENT_MESSAGES)
+                .any(|message| text.contains(message))
+        };
+        let is_transient = match self {
+            Error::ProcessFailed(failure) => failure.stderr_tail.iter().any(|line| transient(line)),
+            Error::ProbeError(message) => transient(message),
+            Error::AccessDenied(e)
+            | Error::CreateOutputError(e)
+            | Error::WriteFileError(e)
+            | Error::CommandError(e) => matches!(
+                e.kind(),
+                io::ErrorKind::Interrupted
+                    | io::ErrorKind::WouldBlock
+                    | io::ErrorKind::ResourceBusy
+                    | io::ErrorKind::TimedOut
+            ),
+            _ => false,
+        };
+        if is_transient {
+            ErrorClass::Transient
+        } else {
+            ErrorClass::Permanent
+        }
+    }
+
+    pub fn is_transient(&self) -> bool {
+        self.class() == ErrorClass::Transient
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::FolderNotFound(path) => write!(f, "folder not found: {}", path.display()),
+            Error::AccessDenied(e) => write!(f, "access denied: {e}"),
+            Error::FileNotFound(path) => write!(f, "file not found: {}", path.display()),
+            Error::CreateOutputError(e) => write!(f, "could not create output: {e}"),
+            Error::OutputExists(path) => write!(f, "output already exists: {}", path.display()),
+            Error::InsufficientSpace { needed, available } => write!(
+                f,
+                "not enough disk space: the output needs about {}, {} are free",
+                format_bytes(*needed),
+                format_bytes(*available)
+            ),
+            Error::WriteFileError(e) => write!(f, "could not write file: {e}"),
+            Error::CommandError(e) => write!(f, "could not run ffmpeg: {e}"),
+            Error::ProcessFailed(failure) => {
+                match failure.exit_code {
+                    Some(code) => write!(f, "ffmpeg exited with code {code}")?,
+                    None => write!(f, "ffmpeg was terminated by a signal")?,
+                }
+                match failure.stderr_tail.last() {
+                    Some(line) => write!(f, ": {line}"),
+                    None => Ok(()),
+                }
+            }
+            Error::Cancelled => write!(f, "cancelled"),
+            Error::Timeout(timeout) => {
+                write!(f, "ffmpeg timed out after {}s", timeout.as_secs_f64())
+            }
+            Error::OutputValidationFailed { path, reason } => {
+                write!(f, "{} is not a valid output: {reason}", path.display())
+            }
+            Error::ProbeError(message) => write!(f, "ffprobe failed: {message}"),
+            Error::ParseError => write!(f, "could not parse the ffmpeg or ffprobe output"),
+            Error::StreamNotFound => write!(f, "the input has no matching stream"),
+            Error::NoInputFiles => write!(f, "no input files to join"),
+            Error::SingleInput(path) => write!(
+                f,
+                "{} is the only input, joining needs at least two",
+                path.display()
+            ),
+            Error::IncompatibleInputs(mismatches) => {
+                write!(f, "inputs cannot be concatenated without re-encoding")?;
+                for m in mismatches {
+                    write!(
+                        f,
+                        "\n  {}: {} is {}, expected {}",
+                        m.path.display(),
+                        m.property,
+                        m.found,
+                        m.expected
+                    )?;
+                }
+                Ok(())
+            }
+            Error::InvalidArgument(message) => write!(f, "invalid argument: {message}"),
+            Error::InvalidFilterGraph(message) => write!(f, "invalid filter graph: {message}"),
+            Error::InvalidTimestamp => write!(f, "invalid timestamp"),
+            Error::InvalidTimeRange => write!(f, "the end must come after the start"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::AccessDenied(e)
+            | Error::CreateOutputError(e)
+            | Error::WriteFileError(e)
+            | Error::CommandError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use crate::Error;
+use crate::ffmpeg::FfmpegCommand;
+
+/// Fades from and to black and silence at the ends of the output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Fades {
+    pub fade_in: Option<Duration>,
+    /// Needs the output duration, so the input must be probeable.
+    pub fade_out: Option<Duration>,
+}
+
+impl Fades {
+    pub fn is_empty(&self) -> bool {
+        self.fade_in.is_none() && self.fade_out.is_none()
+    }
+
+    /// Adds `fade` and, with `audio`, `afade` to the filter chains. Has to
+    /// come before any hardware upload, which works on the frames after it.
+    pub(crate) fn apply(
+        &self,
+        mut command: FfmpegCommand,
+        audio: bool,
+    ) -> Result<FfmpegCommand, Error> {
+        let mut fades = Vec::new();
+        if let Some(length) = self.fade_in {
+            fades.push(format!("t=in:st=0:d={:.3}", length.as_secs_f64()));
+        }
+        if let Some(length) = self.fade_out {
+            let total = command.expected_duration().ok_or_else(|| {
+                Error::invalid("a fade-out needs the duration, which cannot be probed")
+            })?;
+            if length > total {
+                return Err(Error::invalid("the fade-out is longer than the output"));
+            }
+            fades.push(format!(
+                "t=out:st={:.3}:d={:.3}",
+                (total - length).as_secs_f64(),
+                length.as_secs_f64()
+            ));
+        }
+
+        for fade in fades {
+            command = command.video_filter(format!("fade={fade}"));
+            if audio {
+                command = command.audio_filter(format!("afade={fade}"));
+            }
+        }
+        Ok(command)
+    }
+}
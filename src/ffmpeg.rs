@@ -0,0 +1,867 @@
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::io::{BufRead, BufReader, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::str::FromStr;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::Error;
+use crate::job::CancelToken;
+use crate::limits::ResourceLimits;
+use crate::log::{debug, info, warning};
+use crate::progress::{Progress, ProgressParser};
+use crate::retry::RetryPolicy;
+use crate::temp::TempFile;
+use crate::tool::Tool;
+use crate::validate;
+
+static DEFAULT_TIMEOUT: RwLock<Option<Duration>> = RwLock::new(None);
+
+/// An input file together with the options that must precede its `-i`.
+#[derive(Debug, Clone)]
+pub struct Input {
+    args: Vec<OsString>,
+    path: OsString,
+}
+
+impl Input {
+    pub fn new(path: impl AsRef<OsStr>) -> Self {
+        Self {
+            args: Vec::new(),
+            path: path.as_ref().to_os_string(),
+        }
+    }
+
+    pub fn arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.args
+            .extend(args.into_iter().map(|a| a.as_ref().to_os_string()));
+        self
+    }
+
+    pub fn format(self, format: &str) -> Self {
+        self.args(["-f", format])
+    }
+}
+
+/// What happens when an output file already exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverwritePolicy {
+    /// Fail instead of touching it (`-n`).
+    Never,
+    /// Ask on the terminal before running ffmpeg. Without a terminal to ask
+    /// on this behaves like [`OverwritePolicy::Never`].
+    #[default]
+    Prompt,
+    /// Replace it (`-y`).
+    Always,
+}
+
+impl FromStr for OverwritePolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "never" => Ok(OverwritePolicy::Never),
+            "prompt" => Ok(OverwritePolicy::Prompt),
+            "always" => Ok(OverwritePolicy::Always),
+            _ => Err(Error::invalid(format!("unknown overwrite policy `{s}`"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Output {
+    args: Vec<OsString>,
+    video_filters: Vec<String>,
+    audio_filters: Vec<String>,
+    path: OsString,
+}
+
+impl Output {
+    fn is_empty(&self) -> bool {
+        self.args.is_empty() && self.video_filters.is_empty() && self.audio_filters.is_empty()
+    }
+
+    fn push_args(&self, args: &mut Vec<OsString>) {
+        args.extend(self.args.iter().cloned());
+        if !self.video_filters.is_empty() {
+            args.push("-vf".into());
+            args.push(self.video_filters.join(",").into());
+        }
+        if !self.audio_filters.is_empty() {
+            args.push("-af".into());
+            args.push(self.audio_filters.join(",").into());
+        }
+    }
+}
+
+/// Builder for an ffmpeg invocation.
+///
+/// Output options (codecs, filters, maps, ...) apply to the next call to
+/// [`FfmpegCommand::output`], mirroring ffmpeg's own argument order, so a
+/// single command can write several outputs.
+#[derive(Debug, Clone)]
+pub struct FfmpegCommand {
+    program: OsString,
+    global_args: Vec<OsString>,
+    overwrite: OverwritePolicy,
+    inputs: Vec<Input>,
+    filter_complex: Vec<String>,
+    outputs: Vec<Output>,
+    pending: Output,
+    duration: Option<Duration>,
+    temp_files: Vec<Arc<TempFile>>,
+    logger: Option<LineLogger>,
+    cancel: Option<CancelToken>,
+    timeout: Option<Duration>,
+    validate: Option<Duration>,
+    retry: RetryPolicy,
+    limits: ResourceLimits,
+}
+
+impl Default for FfmpegCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FfmpegCommand {
+    /// Starts a command for the ffmpeg of [`Tool::current`].
+    pub fn new() -> Self {
+        Self {
+            program: Tool::current().ffmpeg().into(),
+            global_args: Vec::new(),
+            overwrite: OverwritePolicy::default(),
+            inputs: Vec::new(),
+            filter_complex: Vec::new(),
+            outputs: Vec::new(),
+            pending: Output::default(),
+            duration: None,
+            temp_files: Vec::new(),
+            logger: None,
+            cancel: None,
+            timeout: Self::default_timeout(),
+            validate: None,
+            retry: RetryPolicy::current(),
+            limits: ResourceLimits::current(),
+        }
+    }
+
+    /// Streams ffmpeg's diagnostics to `logger` as they are printed. They are
+    /// still captured in the [`ProcessResult`].
+    pub fn verbose<F>(mut self, logger: F) -> Self
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.logger = Some(LineLogger(Arc::new(logger)));
+        self
+    }
+
+    /// Lets `token` kill the command while it runs. The partial outputs are
+    /// removed then.
+    pub fn cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    pub fn get_cancel_token(&self) -> Option<&CancelToken> {
+        self.cancel.as_ref()
+    }
+
+    /// Kills ffmpeg when it runs longer than `timeout`, e.g. because it hangs
+    /// on a corrupt input, and fails with [`Error::Timeout`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn get_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Makes `timeout` the one new commands start with, including those the
+    /// library runs itself to measure or detect something.
+    pub fn install_timeout(timeout: Option<Duration>) {
+        *DEFAULT_TIMEOUT.write().unwrap() = timeout;
+    }
+
+    /// The installed timeout, or none when nothing was installed.
+    pub fn default_timeout() -> Option<Duration> {
+        *DEFAULT_TIMEOUT.read().unwrap()
+    }
+
+    /// Runs ffmpeg again when it fails for a reason that may pass, such as
+    /// a dropped connection or a busy device, by default as
+    /// [`RetryPolicy::current`] says. Outputs are written from scratch each
+    /// time.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    pub fn get_retry(&self) -> &RetryPolicy {
+        &self.retry
+    }
+
+    /// Lowers ffmpeg's priority and caps its threads and CPU time, by
+    /// default as [`ResourceLimits::current`] says.
+    pub fn limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn get_limits(&self) -> &ResourceLimits {
+        &self.limits
+    }
+
+    /// Checks each output with [`crate::validate_output`] after ffmpeg
+    /// succeeds, allowing its duration to differ from the expected one by
+    /// `tolerance`. Numbered patterns, pipes and URLs are not checked.
+    pub fn validate_outputs(mut self, tolerance: Duration) -> Self {
+        self.validate = Some(tolerance);
+        self
+    }
+
+    /// Keeps a temp file (e.g. a concat list) alive for as long as the
+    /// command, or any clone of it, exists.
+    pub fn temp_file(mut self, file: TempFile) -> Self {
+        self.temp_files.push(Arc::new(file));
+        self
+    }
+
+    /// Keeps the command's temp files after it is dropped, e.g. so a printed
+    /// dry-run command line can still be executed by hand.
+    pub fn persist_temp_files(&self) -> Vec<PathBuf> {
+        self.temp_files
+            .iter()
+            .map(|file| {
+                file.persist();
+                file.path().to_path_buf()
+            })
+            .collect()
+    }
+
+    /// The expected output duration, used to compute [`Progress::percent`].
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub fn expected_duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    pub fn get_program(&self) -> &OsStr {
+        &self.program
+    }
+
+    pub fn program(mut self, program: impl AsRef<OsStr>) -> Self {
+        self.program = program.as_ref().to_os_string();
+        self
+    }
+
+    pub fn global_arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+        self.global_args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    pub fn global_args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.global_args
+            .extend(args.into_iter().map(|a| a.as_ref().to_os_string()));
+        self
+    }
+
+    pub fn overwrite(self, overwrite: bool) -> Self {
+        self.overwrite_policy(if overwrite {
+            OverwritePolicy::Always
+        } else {
+            OverwritePolicy::Never
+        })
+    }
+
+    pub fn overwrite_policy(mut self, policy: OverwritePolicy) -> Self {
+        self.overwrite = policy;
+        self
+    }
+
+    pub fn input(self, path: impl AsRef<OsStr>) -> Self {
+        self.add_input(Input::new(path))
+    }
+
+    pub fn add_input(mut self, input: Input) -> Self {
+        self.inputs.push(input);
+        self
+    }
+
+    /// The number of inputs so far, which is the index of the next one.
+    pub fn input_count(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn filter_complex(mut self, graph: impl Into<String>) -> Self {
+        self.filter_complex.push(graph.into());
+        self
+    }
+
+    pub fn arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+        self.pending.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.pending
+            .args
+            .extend(args.into_iter().map(|a| a.as_ref().to_os_string()));
+        self
+    }
+
+    pub fn video_codec(self, codec: &str) -> Self {
+        self.args(["-c:v", codec])
+    }
+
+    pub fn audio_codec(self, codec: &str) -> Self {
+        self.args(["-c:a", codec])
+    }
+
+    pub fn codec_copy(self) -> Self {
+        self.args(["-c", "copy"])
+    }
+
+    /// Appends a filter to the video filter chain (`-vf`) of the next output.
+    pub fn video_filter(mut self, filter: impl Into<String>) -> Self {
+        self.pending.video_filters.push(filter.into());
+        self
+    }
+
+    /// Appends a filter to the audio filter chain (`-af`) of the next output.
+    pub fn audio_filter(mut self, filter: impl Into<String>) -> Self {
+        self.pending.audio_filters.push(filter.into());
+        self
+    }
+
+    /// Whether the next output has a video filter chain.
+    pub(crate) fn has_video_filters(&self) -> bool {
+        !self.pending.video_filters.is_empty()
+    }
+
+    pub(crate) fn has_audio_filters(&self) -> bool {
+        !self.pending.audio_filters.is_empty()
+    }
+
+    pub fn map(self, spec: &str) -> Self {
+        self.args(["-map", spec])
+    }
+
+    pub fn format(self, format: &str) -> Self {
+        self.args(["-f", format])
+    }
+
+    pub fn output(mut self, path: impl AsRef<OsStr>) -> Self {
+        let mut output = std::mem::take(&mut self.pending);
+        output.path = path.as_ref().to_os_string();
+        self.outputs.push(output);
+        self
+    }
+
+    /// The files the command writes, in order.
+    pub fn output_paths(&self) -> Vec<PathBuf> {
+        self.outputs
+            .iter()
+            .map(|o| PathBuf::from(&o.path))
+            .collect()
+    }
+
+    /// The arguments ffmpeg would be invoked with, without the program
+    /// itself. Together with the `*_command` builders of every operation this
+    /// is a dry run: nothing is executed.
+    pub fn build_args(&self) -> Vec<OsString> {
+        let mut args = self.global_args.clone();
+        if let Some(threads) = self.limits.threads {
+            args.extend(["-filter_threads".into(), threads.to_string().into()]);
+        }
+        match self.overwrite {
+            OverwritePolicy::Never => args.push("-n".into()),
+            OverwritePolicy::Always => args.push("-y".into()),
+            OverwritePolicy::Prompt => {}
+        }
+
+        for input in &self.inputs {
+            args.extend(input.args.iter().cloned());
+            args.push("-i".into());
+            args.push(input.path.clone());
+        }
+
+        if !self.filter_complex.is_empty() {
+            args.push("-filter_complex".into());
+            args.push(self.filter_complex.join(";").into());
+        }
+
+        for output in &self.outputs {
+            output.push_args(&mut args);
+            if let Some(threads) = self.limits.threads {
+                args.extend(["-threads".into(), threads.to_string().into()]);
+            }
+            args.push(output.path.clone());
+        }
+
+        // Options given after the last output still reach ffmpeg, which
+        // warns about them instead of us dropping them silently.
+        if !self.pending.is_empty() {
+            self.pending.push_args(&mut args);
+        }
+
+        args
+    }
+
+    /// The process to start, which is ffmpeg itself unless
+    /// [`ResourceLimits`] have it started through other programs.
+    pub fn to_command(&self) -> Command {
+        let invocation = self.invocation(true);
+        let mut command = Command::new(&invocation[0]);
+        command.args(&invocation[1..]);
+        self.limits.apply(&mut command);
+        command
+    }
+
+    /// The full command line, quoted so it can be pasted into a shell.
+    pub fn command_line(&self) -> String {
+        self.invocation(false)
+            .iter()
+            .map(|arg| quote(&arg.to_string_lossy()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn invocation(&self, warn: bool) -> Vec<OsString> {
+        let mut invocation = self.limits.wrappers(warn);
+        invocation.push(self.program.clone());
+        invocation.extend(self.build_args());
+        invocation
+    }
+
+    pub fn status(&self) -> Result<ExitStatus, Error> {
+        self.to_command().status().map_err(Error::CommandError)
+    }
+
+    /// Runs ffmpeg to completion, capturing its stdout and stderr.
+    pub fn run(&self) -> Result<ProcessResult, Error> {
+        self.execute(None)
+    }
+
+    /// Runs ffmpeg with `-progress pipe:1`, calling `on_progress` for every
+    /// update it reports.
+    pub fn run_with_progress<F>(&self, mut on_progress: F) -> Result<ProcessResult, Error>
+    where
+        F: FnMut(&Progress),
+    {
+        self.execute(Some(&mut on_progress))
+    }
+
+    fn execute(
+        &self,
+        mut on_progress: Option<&mut dyn FnMut(&Progress)>,
+    ) -> Result<ProcessResult, Error> {
+        // Asked once, retries keep the answer.
+        let mut command = self.clone();
+        if command.overwrite == OverwritePolicy::Prompt {
+            command.overwrite = self.confirm_overwrite()?;
+        }
+        let existing: Vec<PathBuf> = self
+            .output_paths()
+            .into_iter()
+            .filter(|path| validate::is_plain_output(path) && path.exists())
+            .collect();
+
+        let mut retry = 0;
+        loop {
+            let result = command.execute_once(on_progress.as_deref_mut());
+            // A failed run only becomes an error when the caller checks it.
+            let reason = match &result {
+                Ok(result) if !result.success() => result
+                    .clone()
+                    .check()
+                    .err()
+                    .filter(Error::is_transient)
+                    .map(|e| e.to_string()),
+                Ok(_) => None,
+                Err(e) => e.is_transient().then(|| e.to_string()),
+            };
+            match reason {
+                Some(reason) if retry < self.retry.retries => {
+                    retry += 1;
+                    self.retry.wait(retry, &reason);
+                    // What the failed attempt wrote would otherwise count as
+                    // an existing output.
+                    for path in self.output_paths() {
+                        if validate::is_plain_output(&path) && !existing.contains(&path) {
+                            let _ = std::fs::remove_file(path);
+                        }
+                    }
+                }
+                _ => return result,
+            }
+        }
+    }
+
+    fn execute_once(
+        &self,
+        mut on_progress: Option<&mut (dyn FnMut(&Progress) + '_)>,
+    ) -> Result<ProcessResult, Error> {
+        let reports_progress = on_progress.is_some();
+        let command = if reports_progress {
+            self.clone()
+                .global_args(["-progress", "pipe:1", "-nostats"])
+        } else {
+            self.clone()
+        };
+        if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+            return Err(Error::Cancelled);
+        }
+
+        debug!("running {}", command.command_line());
+        let started = Instant::now();
+        let mut child = command
+            .to_command()
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(Error::CommandError)?;
+        info!(
+            "spawned {} (pid {})",
+            self.program.to_string_lossy(),
+            child.id()
+        );
+        let limiter = self.limits.watch(child.id());
+
+        let stderr = child.stderr.take().ok_or_else(not_captured)?;
+        let logger = self.logger.clone();
+        let stderr_reader = thread::spawn(move || {
+            let mut text = String::new();
+            read_lines(stderr, |line| {
+                if let Some(logger) = &logger {
+                    (logger.0)(line);
+                }
+                text.push_str(line);
+                text.push('\n');
+            });
+            text
+        });
+
+        let stdout = child.stdout.take().ok_or_else(not_captured)?;
+        let (tx, rx) = mpsc::channel();
+        let total = self.duration;
+        let stdout_reader = thread::spawn(move || {
+            let mut text = String::new();
+            if reports_progress {
+                let mut parser = ProgressParser::new(total);
+                read_lines(stdout, |line| {
+                    if let Some(progress) = parser.feed(line) {
+                        let _ = tx.send(progress);
+                    }
+                });
+            } else {
+                let mut buf = Vec::new();
+                let mut stdout = stdout;
+                let _ = stdout.read_to_end(&mut buf);
+                text = String::from_utf8_lossy(&buf).into_owned();
+            }
+            text
+        });
+
+        // From here on the token owns the process, so it can kill it. A
+        // timeout needs one too, only to kill it.
+        let token = match (&self.cancel, self.timeout) {
+            (Some(token), _) => Some(token.clone()),
+            (None, Some(_)) => Some(CancelToken::new()),
+            (None, None) => None,
+        };
+        let mut child = match &token {
+            Some(token) => {
+                token.attach(child);
+                None
+            }
+            None => Some(child),
+        };
+        let (finished, done) = mpsc::channel::<()>();
+        let watchdog = self.timeout.zip(token.clone()).map(|(timeout, token)| {
+            thread::spawn(move || {
+                let expired = done.recv_timeout(timeout) == Err(RecvTimeoutError::Timeout);
+                if expired {
+                    token.kill();
+                }
+                expired
+            })
+        });
+
+        for progress in rx {
+            if let Some(on_progress) = on_progress.as_mut() {
+                on_progress(&progress);
+            }
+        }
+
+        let status = match (child.as_mut(), &token) {
+            (Some(child), _) => child.wait().map_err(Error::CommandError)?,
+            (None, Some(token)) => token.wait()?,
+            (None, None) => unreachable!(),
+        };
+        drop(finished);
+        if let Some(mut limiter) = limiter {
+            let _ = limiter.kill();
+            let _ = limiter.wait();
+        }
+        let timed_out = watchdog.is_some_and(|watchdog| watchdog.join().unwrap_or(false));
+        let stdout = stdout_reader.join().unwrap_or_default();
+        let stderr = stderr_reader.join().unwrap_or_default();
+
+        let error = if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+            Some(Error::Cancelled)
+        } else if timed_out {
+            self.timeout.map(Error::Timeout)
+        } else {
+            None
+        };
+        if let Some(error) = error {
+            warning!(
+                "stopped after {:.2}s: {error}",
+                started.elapsed().as_secs_f64()
+            );
+            for path in self.output_paths() {
+                let _ = std::fs::remove_file(path);
+            }
+            return Err(error);
+        }
+
+        let elapsed = started.elapsed();
+        if status.success() {
+            info!("finished in {:.2}s", elapsed.as_secs_f64());
+        } else {
+            warning!("failed with {status} after {:.2}s", elapsed.as_secs_f64());
+        }
+
+        if status.success()
+            && let Some(tolerance) = self.validate
+        {
+            for path in self.output_paths() {
+                if validate::is_plain_output(&path) {
+                    debug!("validating {}", path.display());
+                    validate::check(&self.program, &path, self.duration, tolerance)?;
+                }
+            }
+        }
+
+        Ok(ProcessResult {
+            command_line: command.command_line(),
+            exit_code: status.code(),
+            stdout,
+            stderr,
+        })
+    }
+
+    /// Asks whether each existing output may be replaced. ffmpeg can't ask
+    /// itself, its stdin is not the terminal.
+    fn confirm_overwrite(&self) -> Result<OverwritePolicy, Error> {
+        let existing: Vec<PathBuf> = self
+            .output_paths()
+            .into_iter()
+            .filter(|path| path.is_file())
+            .collect();
+        if existing.is_empty() {
+            return Ok(OverwritePolicy::Prompt);
+        }
+        if !std::io::stdin().is_terminal() {
+            return Ok(OverwritePolicy::Never);
+        }
+
+        // Commands run from a pool must not ask at the same time.
+        static PROMPT: Mutex<()> = Mutex::new(());
+        let _guard = PROMPT.lock().unwrap_or_else(|e| e.into_inner());
+
+        for path in existing {
+            if !ask(&path).map_err(Error::CommandError)? {
+                return Err(Error::OutputExists(path));
+            }
+        }
+        Ok(OverwritePolicy::Always)
+    }
+
+    /// Like [`FfmpegCommand::run_with_progress`], sending updates to a channel.
+    pub fn run_with_channel(&self, sender: Sender<Progress>) -> Result<ProcessResult, Error> {
+        self.run_with_progress(|progress| {
+            let _ = sender.send(progress.clone());
+        })
+    }
+}
+
+impl fmt::Display for FfmpegCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.command_line())
+    }
+}
+
+/// Escapes a value for use as a filter option inside a filtergraph, e.g. a
+/// file path given to the `subtitles` filter. Both escaping levels described
+/// in ffmpeg-filters(1) are applied.
+pub fn escape_filter_arg(value: &str) -> String {
+    let escape = |value: &str, special: &str| {
+        let mut escaped = String::with_capacity(value.len());
+        for c in value.chars() {
+            if special.contains(c) {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
+    };
+    escape(&escape(value, "\\':"), "\\'[],;")
+}
+
+fn ask(path: &Path) -> std::io::Result<bool> {
+    let mut stderr = std::io::stderr();
+    write!(
+        stderr,
+        "{} already exists. Overwrite? [y/N] ",
+        path.display()
+    )?;
+    stderr.flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes" | "Yes"))
+}
+
+fn not_captured() -> Error {
+    Error::CommandError(std::io::Error::other("output was not captured"))
+}
+
+/// Calls `f` for every line, treating the `\r` ffmpeg uses to redraw its
+/// status line as a line break too.
+fn read_lines(reader: impl Read, mut f: impl FnMut(&str)) {
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    loop {
+        let (consumed, done) = match reader.fill_buf() {
+            Ok([]) | Err(_) => break,
+            Ok(buf) => match buf.iter().position(|b| *b == b'\n' || *b == b'\r') {
+                Some(i) => {
+                    line.extend_from_slice(&buf[..i]);
+                    (i + 1, true)
+                }
+                None => {
+                    line.extend_from_slice(buf);
+                    (buf.len(), false)
+                }
+            },
+        };
+        reader.consume(consumed);
+        if done {
+            if !line.is_empty() {
+                f(&String::from_utf8_lossy(&line));
+            }
+            line.clear();
+        }
+    }
+    if !line.is_empty() {
+        f(&String::from_utf8_lossy(&line));
+    }
+}
+
+fn quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=+,%@".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// The outcome of an ffmpeg run.
+#[derive(Debug, Clone)]
+pub struct ProcessResult {
+    /// The command line that was executed.
+    pub command_line: String,
+    /// The exit code, `None` when the process was killed by a signal.
+    pub exit_code: Option<i32>,
+    /// What ffmpeg wrote to stdout, empty when progress was reported.
+    pub stdout: String,
+    /// Everything ffmpeg wrote to stderr.
+    pub stderr: String,
+}
+
+impl ProcessResult {
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+
+    /// The last `n` non-empty lines of stderr, which usually hold the error.
+    pub fn stderr_tail(&self, n: usize) -> Vec<&str> {
+        let lines = self
+            .stderr
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .collect::<Vec<_>>();
+        lines[lines.len().saturating_sub(n)..].to_vec()
+    }
+
+    /// Turns a non-zero exit into [`Error::ProcessFailed`].
+    pub fn check(self) -> Result<Self, Error> {
+        if self.success() {
+            return Ok(self);
+        }
+
+        Err(Error::ProcessFailed(ProcessFailure {
+            stderr_tail: self
+                .stderr_tail(STDERR_TAIL_LINES)
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            command_line: self.command_line,
+            exit_code: self.exit_code,
+        }))
+    }
+}
+
+/// How many lines of stderr a [`ProcessFailure`] keeps.
+pub const STDERR_TAIL_LINES: usize = 20;
+
+/// Why ffmpeg exited unsuccessfully.
+#[derive(Debug, Clone)]
+pub struct ProcessFailure {
+    pub command_line: String,
+    pub exit_code: Option<i32>,
+    /// The last [`STDERR_TAIL_LINES`] lines ffmpeg printed.
+    pub stderr_tail: Vec<String>,
+}
+
+/// Receives ffmpeg's stderr line by line while it runs.
+#[derive(Clone)]
+pub struct LineLogger(Arc<dyn Fn(&str) + Send + Sync>);
+
+impl fmt::Debug for LineLogger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("LineLogger")
+    }
+}
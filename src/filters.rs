@@ -0,0 +1,126 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, escape_filter_arg};
+
+/// How hard a cleanup filter works.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strength {
+    Light,
+    Medium,
+    Heavy,
+}
+
+impl FromStr for Strength {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "light" => Ok(Strength::Light),
+            "medium" => Ok(Strength::Medium),
+            "heavy" => Ok(Strength::Heavy),
+            _ => Err(Error::invalid(format!("unknown strength `{s}`"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Denoiser {
+    /// Fast, smooths over space and time.
+    #[default]
+    Hqdn3d,
+    /// Non-local means, keeps more detail but is many times slower.
+    Nlmeans,
+}
+
+impl FromStr for Denoiser {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hqdn3d" => Ok(Denoiser::Hqdn3d),
+            "nlmeans" => Ok(Denoiser::Nlmeans),
+            _ => Err(Error::invalid(format!("unknown denoiser `{s}`"))),
+        }
+    }
+}
+
+/// Cleanup and color filters, applied in a fixed order: denoising first,
+/// then the LUT and the color adjustments, and sharpening last so the noise
+/// isn't sharpened too.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VideoFilters {
+    pub denoise: Option<Strength>,
+    pub denoiser: Denoiser,
+    pub sharpen: Option<Strength>,
+    /// From -1 to 1, 0 leaves it as it is.
+    pub brightness: Option<f64>,
+    /// From -1000 to 1000, 1 leaves it as it is.
+    pub contrast: Option<f64>,
+    /// From 0 (grey) to 3, 1 leaves it as it is.
+    pub saturation: Option<f64>,
+    /// From 0.1 to 10, 1 leaves it as it is.
+    pub gamma: Option<f64>,
+    /// A 3D LUT such as a `.cube` file.
+    pub lut: Option<PathBuf>,
+}
+
+impl VideoFilters {
+    /// The eq filter for the color adjustments, if there are any.
+    fn eq(&self) -> Result<Option<String>, Error> {
+        let adjustments = [
+            ("brightness", self.brightness, -1.0..=1.0),
+            ("contrast", self.contrast, -1000.0..=1000.0),
+            ("saturation", self.saturation, 0.0..=3.0),
+            ("gamma", self.gamma, 0.1..=10.0),
+        ];
+        let mut options = Vec::new();
+        for (name, value, range) in adjustments {
+            let Some(value) = value else { continue };
+            if !range.contains(&value) {
+                return Err(Error::invalid(format!(
+                    "the {name} must be between {} and {}",
+                    range.start(),
+                    range.end()
+                )));
+            }
+            options.push(format!("{name}={value}"));
+        }
+        Ok((!options.is_empty()).then(|| format!("eq={}", options.join(":"))))
+    }
+
+    pub(crate) fn apply(&self, mut command: FfmpegCommand) -> Result<FfmpegCommand, Error> {
+        if let Some(strength) = self.denoise {
+            command = command.video_filter(match (self.denoiser, strength) {
+                // hqdn3d's own defaults are the medium strength.
+                (Denoiser::Hqdn3d, Strength::Light) => "hqdn3d=2:1.5:3:2.25",
+                (Denoiser::Hqdn3d, Strength::Medium) => "hqdn3d=4:3:6:4.5",
+                (Denoiser::Hqdn3d, Strength::Heavy) => "hqdn3d=8:6:12:9",
+                (Denoiser::Nlmeans, Strength::Light) => "nlmeans=s=2",
+                (Denoiser::Nlmeans, Strength::Medium) => "nlmeans=s=4",
+                (Denoiser::Nlmeans, Strength::Heavy) => "nlmeans=s=8",
+            });
+        }
+        if let Some(lut) = &self.lut {
+            if !lut.is_file() {
+                return Err(Error::FileNotFound(lut.clone()));
+            }
+            let lut = lut
+                .to_str()
+                .ok_or_else(|| Error::invalid("the LUT path is not valid UTF-8"))?;
+            command = command.video_filter(format!("lut3d=file={}", escape_filter_arg(lut)));
+        }
+        if let Some(eq) = self.eq()? {
+            command = command.video_filter(eq);
+        }
+        if let Some(strength) = self.sharpen {
+            command = command.video_filter(match strength {
+                Strength::Light => "unsharp=5:5:0.5:5:5:0",
+                Strength::Medium => "unsharp=5:5:1.0:5:5:0",
+                Strength::Heavy => "unsharp=5:5:1.5:5:5:0",
+            });
+        }
+        Ok(command)
+    }
+}
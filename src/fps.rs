@@ -0,0 +1,89 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, ProcessResult};
+use crate::probe::probe;
+use crate::transcode::TranscodeOptions;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FpsMode {
+    /// Drop or repeat frames with the fps filter, fast but motion stutters
+    /// when the rate goes up.
+    #[default]
+    Simple,
+    /// Synthesize the new frames from motion estimation with minterpolate,
+    /// e.g. for smooth 30 to 60 fps. Slow, and fast motion can smear.
+    Interpolate,
+}
+
+impl FromStr for FpsMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "simple" => Ok(FpsMode::Simple),
+            "interpolate" => Ok(FpsMode::Interpolate),
+            _ => Err(Error::invalid(format!("unknown frame rate mode `{s}`"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FpsOptions {
+    /// The output frame rate, e.g. 60 or 29.97.
+    pub fps: f64,
+    pub mode: FpsMode,
+    pub transcode: TranscodeOptions,
+}
+
+impl Default for FpsOptions {
+    fn default() -> Self {
+        Self {
+            fps: 30.0,
+            mode: FpsMode::Simple,
+            transcode: TranscodeOptions::default(),
+        }
+    }
+}
+
+/// Converts the video to a new frame rate, copying the audio, which keeps
+/// its timing.
+pub fn convert_fps_command(
+    input: &Path,
+    output: &Path,
+    options: &FpsOptions,
+) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+    if !(options.fps.is_finite() && options.fps > 0.0) {
+        return Err(Error::invalid("the frame rate must be positive"));
+    }
+
+    let fps = options.fps;
+    let filter = match options.mode {
+        FpsMode::Simple => format!("fps={fps}"),
+        FpsMode::Interpolate => {
+            format!("minterpolate=fps={fps}:mi_mode=mci:mc_mode=aobmc:me_mode=bidir:vsbmc=1")
+        }
+    };
+    let mut command = FfmpegCommand::new().input(input).video_filter(filter);
+    if let Some(duration) = probe(input).ok().and_then(|info| info.duration) {
+        command = command.duration(duration);
+    }
+    let transcode = &options.transcode;
+    if transcode.audio_codec.is_none() && transcode.audio_bitrate.is_none() {
+        command = command.audio_codec("copy");
+    }
+
+    Ok(transcode.apply(command).output(output))
+}
+
+pub fn convert_fps(
+    input: &Path,
+    output: &Path,
+    options: &FpsOptions,
+) -> Result<ProcessResult, Error> {
+    convert_fps_command(input, output, options)?.run()?.check()
+}
@@ -0,0 +1,234 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, Input, ProcessResult};
+use crate::probe::probe;
+use crate::time::format_timestamp;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameSelection {
+    /// A single frame at the given timestamp.
+    At(Duration),
+    /// `n` frames spread evenly over the whole file.
+    Evenly(u32),
+    /// One frame at every scene change whose score exceeds the threshold
+    /// (0.0-1.0, around 0.3-0.4 works for most footage).
+    SceneChange(f64),
+    /// Every frame of the video.
+    All,
+    /// Every `n`th frame, starting with the first.
+    EveryNth(u32),
+    /// Frames sampled at this many per second.
+    Rate(f64),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ImageFormat {
+    #[default]
+    Png,
+    Jpeg,
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+        }
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "png" => Ok(ImageFormat::Png),
+            "jpg" | "jpeg" => Ok(ImageFormat::Jpeg),
+            _ => Err(Error::invalid(format!("unknown image format `{s}`"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FrameOptions {
+    pub selection: FrameSelection,
+    pub format: ImageFormat,
+    /// File name without extension, with a printf-style counter such as
+    /// `frame_%03d`. A counter is appended when several frames are written
+    /// and the pattern has none.
+    pub pattern: String,
+}
+
+impl Default for FrameOptions {
+    fn default() -> Self {
+        Self {
+            selection: FrameSelection::At(Duration::ZERO),
+            format: ImageFormat::default(),
+            pattern: "frame_%03d".to_string(),
+        }
+    }
+}
+
+impl FrameOptions {
+    fn file_pattern(&self) -> String {
+        let pattern = if self.pattern.contains('%') || self.is_single() {
+            self.pattern.clone()
+        } else {
+            format!("{}_%03d", self.pattern)
+        };
+        format!("{pattern}.{}", self.format.extension())
+    }
+
+    fn is_single(&self) -> bool {
+        matches!(self.selection, FrameSelection::At(_))
+    }
+}
+
+/// Expands a printf-style `%d` / `%0Nd` counter the way ffmpeg's image2
+/// muxer does.
+pub fn render_pattern(pattern: &str, index: u64) -> String {
+    let Some(start) = pattern.find('%') else {
+        return pattern.to_string();
+    };
+    let rest = &pattern[start + 1..];
+    let Some(end) = rest.find('d') else {
+        return pattern.to_string();
+    };
+    let spec = &rest[..end];
+    if !spec.chars().all(|c| c.is_ascii_digit()) {
+        return pattern.to_string();
+    }
+    let width = spec.parse::<usize>().unwrap_or(0);
+
+    format!(
+        "{}{index:0width$}{}",
+        &pattern[..start],
+        &rest[end + 1..],
+        width = width
+    )
+}
+
+pub fn extract_frames_command(
+    input: &Path,
+    output_dir: &Path,
+    options: &FrameOptions,
+) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+    std::fs::create_dir_all(output_dir).map_err(Error::CreateOutputError)?;
+
+    let mut source = Input::new(input);
+    let mut command = match options.selection {
+        FrameSelection::At(at) => {
+            if !at.is_zero() {
+                source = source.args(["-ss", &format_timestamp(at)]);
+            }
+            FfmpegCommand::new()
+                .add_input(source)
+                .args(["-frames:v", "1"])
+        }
+        FrameSelection::Evenly(count) => {
+            if count == 0 {
+                return Err(Error::invalid("the frame count must be positive"));
+            }
+            let duration = probe(input)?
+                .duration
+                .ok_or_else(|| Error::ProbeError("unknown duration".to_string()))?;
+            FfmpegCommand::new()
+                .add_input(source)
+                .video_filter(format!("fps={count}/{}", format_timestamp(duration)))
+                .args(["-frames:v", &count.to_string()])
+                .duration(duration)
+        }
+        FrameSelection::SceneChange(threshold) => {
+            if !(0.0..=1.0).contains(&threshold) {
+                return Err(Error::invalid(
+                    "the scene threshold must be between 0 and 1",
+                ));
+            }
+            FfmpegCommand::new()
+                .add_input(source)
+                .video_filter(format!("select='gt(scene,{threshold})'"))
+                .args(["-fps_mode", "vfr"])
+        }
+        FrameSelection::All => FfmpegCommand::new()
+            .add_input(source)
+            .args(["-fps_mode", "passthrough"]),
+        FrameSelection::EveryNth(n) => {
+            if n == 0 {
+                return Err(Error::invalid("the frame step must be positive"));
+            }
+            FfmpegCommand::new()
+                .add_input(source)
+                .video_filter(format!("select='not(mod(n,{n}))'"))
+                .args(["-fps_mode", "vfr"])
+        }
+        FrameSelection::Rate(fps) => {
+            if !(fps.is_finite() && fps > 0.0) {
+                return Err(Error::invalid("the frame rate must be positive"));
+            }
+            FfmpegCommand::new()
+                .add_input(source)
+                .video_filter(format!("fps={fps}"))
+        }
+    };
+    // These read the whole file, so progress is worth reporting.
+    let whole_file = !matches!(
+        options.selection,
+        FrameSelection::At(_) | FrameSelection::Evenly(_)
+    );
+    if whole_file && let Some(duration) = probe(input).ok().and_then(|info| info.duration) {
+        command = command.duration(duration);
+    }
+
+    if options.format == ImageFormat::Jpeg {
+        command = command.args(["-q:v", "2"]);
+    }
+    if options.is_single() && !options.pattern.contains('%') {
+        command = command.args(["-update", "1"]);
+    }
+
+    Ok(command.output(output_dir.join(options.file_pattern())))
+}
+
+/// The frames an extraction wrote to `output_dir`, in order.
+pub fn written_frames(output_dir: &Path, options: &FrameOptions) -> Vec<PathBuf> {
+    let pattern = options.file_pattern();
+    if !pattern.contains('%') {
+        let path = output_dir.join(pattern);
+        return if path.is_file() {
+            vec![path]
+        } else {
+            Vec::new()
+        };
+    }
+
+    (1..)
+        .map(|i| output_dir.join(render_pattern(&pattern, i)))
+        .take_while(|path| path.is_file())
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct FramesOutput {
+    pub result: ProcessResult,
+    pub frames: Vec<PathBuf>,
+}
+
+pub fn extract_frames(
+    input: &Path,
+    output_dir: &Path,
+    options: &FrameOptions,
+) -> Result<FramesOutput, Error> {
+    let result = extract_frames_command(input, output_dir, options)?
+        .run()?
+        .check()?;
+    Ok(FramesOutput {
+        result,
+        frames: written_frames(output_dir, options),
+    })
+}
@@ -0,0 +1,130 @@
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, Input, ProcessResult};
+use crate::time::format_timestamp;
+use crate::transcode::TranscodeOptions;
+use crate::visualize::check_color;
+
+/// A synthetic picture from one of ffmpeg's lavfi sources.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TestPattern {
+    /// Moving color patterns with a frame counter, handy for spotting
+    /// dropped or repeated frames.
+    TestSrc,
+    /// SMPTE HD color bars.
+    Bars,
+    /// A solid color, e.g. for a placeholder.
+    Color(String),
+}
+
+impl FromStr for TestPattern {
+    type Err = Error;
+
+    /// `testsrc`, `bars` or any color name.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "testsrc" => Ok(TestPattern::TestSrc),
+            "bars" => Ok(TestPattern::Bars),
+            color => {
+                check_color(color)?;
+                Ok(TestPattern::Color(color.to_string()))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TestTone {
+    /// A sine tone at this frequency in Hz.
+    Sine(f64),
+    Silence,
+}
+
+#[derive(Debug, Clone)]
+pub struct GenerateOptions {
+    /// `None` for an audio-only file.
+    pub video: Option<TestPattern>,
+    /// `None` for a silent video without an audio track.
+    pub audio: Option<TestTone>,
+    pub duration: Duration,
+    pub size: (u32, u32),
+    pub fps: f64,
+    pub sample_rate: u32,
+    pub transcode: TranscodeOptions,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        Self {
+            video: Some(TestPattern::TestSrc),
+            audio: Some(TestTone::Sine(1000.0)),
+            duration: Duration::from_secs(10),
+            size: (1280, 720),
+            fps: 30.0,
+            sample_rate: 48000,
+            transcode: TranscodeOptions::default(),
+        }
+    }
+}
+
+/// Writes test or placeholder media generated by ffmpeg itself, so no
+/// input file is needed.
+pub fn generate_command(output: &Path, options: &GenerateOptions) -> Result<FfmpegCommand, Error> {
+    if options.video.is_none() && options.audio.is_none() {
+        return Err(Error::invalid("the output needs video, audio or both"));
+    }
+    if options.duration.is_zero() {
+        return Err(Error::invalid("the duration must be positive"));
+    }
+
+    let mut command = FfmpegCommand::new();
+    if let Some(pattern) = &options.video {
+        let (width, height) = options.size;
+        if width == 0 || height == 0 {
+            return Err(Error::invalid("the size must be positive"));
+        }
+        if !(options.fps.is_finite() && options.fps > 0.0) {
+            return Err(Error::invalid("the frame rate must be positive"));
+        }
+        let frames = format!("s={width}x{height}:r={}", options.fps);
+        let source = match pattern {
+            TestPattern::TestSrc => format!("testsrc2={frames}"),
+            TestPattern::Bars => format!("smptehdbars={frames}"),
+            TestPattern::Color(color) => {
+                check_color(color)?;
+                format!("color=c={color}:{frames}")
+            }
+        };
+        command = command.add_input(Input::new(source).format("lavfi"));
+    }
+    if let Some(tone) = options.audio {
+        if options.sample_rate == 0 {
+            return Err(Error::invalid("the sample rate must be positive"));
+        }
+        let rate = options.sample_rate;
+        let source = match tone {
+            TestTone::Sine(frequency) if !(frequency.is_finite() && frequency > 0.0) => {
+                return Err(Error::invalid("the frequency must be positive"));
+            }
+            TestTone::Sine(frequency) => format!("sine=f={frequency}:r={rate}"),
+            TestTone::Silence => format!("anullsrc=r={rate}:cl=stereo"),
+        };
+        command = command.add_input(Input::new(source).format("lavfi"));
+    }
+
+    // The sources are endless, and many players only take 4:2:0 video.
+    command = command
+        .args(["-t", &format_timestamp(options.duration)])
+        .duration(options.duration);
+    if options.video.is_some() && options.transcode.hwaccel.is_none() {
+        command = command.args(["-pix_fmt", "yuv420p"]);
+    }
+    Ok(options.transcode.apply(command).output(output))
+}
+
+pub fn generate(output: &Path, options: &GenerateOptions) -> Result<ProcessResult, Error> {
+    generate_command(output, options)?.run()?.check()
+}
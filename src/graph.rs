@@ -0,0 +1,266 @@
+//! Typed filter graphs for [`FfmpegCommand::filter_graph`], as an
+//! alternative to writing `-filter_complex` strings by hand.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, escape_filter_arg};
+
+/// One filter and its options, which are escaped when the graph is written
+/// out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    name: String,
+    /// Positional options have no key.
+    options: Vec<(Option<String>, String)>,
+}
+
+impl Filter {
+    pub fn new(name: &str) -> Self {
+        Filter {
+            name: name.to_string(),
+            options: Vec::new(),
+        }
+    }
+
+    /// Adds an option by position, e.g. the width and height of `scale`.
+    pub fn arg(mut self, value: impl fmt::Display) -> Self {
+        self.options.push((None, value.to_string()));
+        self
+    }
+
+    /// Adds an option by name, e.g. `x` of `overlay`.
+    pub fn option(mut self, key: &str, value: impl fmt::Display) -> Self {
+        self.options
+            .push((Some(key.to_string()), value.to_string()));
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        for (i, (key, value)) in self.options.iter().enumerate() {
+            f.write_str(if i == 0 { "=" } else { ":" })?;
+            if let Some(key) = key {
+                write!(f, "{key}=")?;
+            }
+            f.write_str(&escape_filter_arg(value))?;
+        }
+        Ok(())
+    }
+}
+
+/// Filters applied one after the other, between labeled input and output
+/// pads. A label is either a stream of an input such as `0:v` or `1:a:0`,
+/// or a name linking the output of one chain to the input of another.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilterChain {
+    inputs: Vec<String>,
+    filters: Vec<Filter>,
+    outputs: Vec<String>,
+}
+
+impl FilterChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn input(mut self, label: &str) -> Self {
+        self.inputs.push(label.to_string());
+        self
+    }
+
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// Names an output pad. Pads no other chain reads end up as outputs of
+    /// the graph, to be picked with [`FfmpegCommand::map`] as `[label]`.
+    pub fn output(mut self, label: &str) -> Self {
+        self.outputs.push(label.to_string());
+        self
+    }
+}
+
+impl fmt::Display for FilterChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for label in &self.inputs {
+            write!(f, "[{label}]")?;
+        }
+        for (i, filter) in self.filters.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{filter}")?;
+        }
+        for label in &self.outputs {
+            write!(f, "[{label}]")?;
+        }
+        Ok(())
+    }
+}
+
+/// A whole `-filter_complex` graph.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilterGraph {
+    chains: Vec<FilterChain>,
+}
+
+impl FilterGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn chain(mut self, chain: FilterChain) -> Self {
+        self.chains.push(chain);
+        self
+    }
+
+    /// The labels of the pads no chain reads, which the command can map.
+    pub fn outputs(&self) -> Vec<&str> {
+        let read: Vec<&str> = self.inputs().map(|(_, label)| label).collect();
+        self.chains
+            .iter()
+            .flat_map(|chain| &chain.outputs)
+            .map(String::as_str)
+            .filter(|label| !read.contains(label))
+            .collect()
+    }
+
+    fn inputs(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.chains
+            .iter()
+            .enumerate()
+            .flat_map(|(i, chain)| chain.inputs.iter().map(move |label| (i, label.as_str())))
+    }
+
+    /// Checks that the pads connect up for a command with `input_count`
+    /// inputs: every stream label names an existing input, every other
+    /// input label is the output of exactly one chain and is read only once,
+    /// and no chain feeds back into itself. Unlabeled pads, which ffmpeg
+    /// connects by itself, are not checked.
+    pub fn validate(&self, input_count: usize) -> Result<(), Error> {
+        let invalid = |message: String| Err(Error::InvalidFilterGraph(message));
+        if self.chains.is_empty() {
+            return invalid("the graph has no filters".to_string());
+        }
+
+        let mut producers = HashMap::new();
+        for (i, chain) in self.chains.iter().enumerate() {
+            if chain.filters.is_empty() {
+                return invalid(format!("chain {} has no filters", i + 1));
+            }
+            for filter in &chain.filters {
+                if !is_name(&filter.name) {
+                    return invalid(format!("invalid filter name `{}`", filter.name));
+                }
+                for key in filter.options.iter().filter_map(|(key, _)| key.as_ref()) {
+                    if !is_name(key) {
+                        return invalid(format!("invalid option `{key}` of {}", filter.name));
+                    }
+                }
+            }
+            for label in chain.inputs.iter().chain(&chain.outputs) {
+                if !is_label(label) {
+                    return invalid(format!("invalid pad label `{label}`"));
+                }
+            }
+            for label in &chain.outputs {
+                if stream_input(label).is_some() {
+                    return invalid(format!("the output `{label}` looks like an input stream"));
+                }
+                if producers.insert(label.as_str(), i).is_some() {
+                    return invalid(format!("more than one chain writes to `{label}`"));
+                }
+            }
+        }
+
+        // Each chain's index, with the chains that read from it.
+        let mut consumers = vec![Vec::new(); self.chains.len()];
+        let mut read = Vec::new();
+        for (i, label) in self.inputs() {
+            if let Some(index) = stream_input(label) {
+                if index >= input_count {
+                    return invalid(format!(
+                        "`{label}` reads input {index}, but there are only {input_count}"
+                    ));
+                }
+                continue;
+            }
+            let Some(&producer) = producers.get(label) else {
+                return invalid(format!("no chain writes to `{label}`"));
+            };
+            if read.contains(&label) {
+                return invalid(format!("`{label}` is read more than once, split it first"));
+            }
+            read.push(label);
+            consumers[producer].push(i);
+        }
+
+        // A depth-first walk that finds its way back to a chain on the
+        // current path has found a loop.
+        fn visit(chain: usize, consumers: &[Vec<usize>], state: &mut [u8]) -> bool {
+            match state[chain] {
+                1 => return true,
+                2 => return false,
+                _ => {}
+            }
+            state[chain] = 1;
+            let looped = consumers[chain]
+                .iter()
+                .any(|&next| visit(next, consumers, state));
+            state[chain] = 2;
+            looped
+        }
+        let mut state = vec![0; self.chains.len()];
+        if (0..self.chains.len()).any(|chain| visit(chain, &consumers, &mut state)) {
+            return invalid("the chains form a loop".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for FilterGraph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, chain) in self.chains.iter().enumerate() {
+            if i > 0 {
+                f.write_str(";")?;
+            }
+            write!(f, "{chain}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FfmpegCommand {
+    /// Sets the `-filter_complex` graph after [`FilterGraph::validate`]
+    /// accepts it for the inputs added so far.
+    pub fn filter_graph(self, graph: &FilterGraph) -> Result<Self, Error> {
+        graph.validate(self.input_count())?;
+        Ok(self.filter_complex(graph.to_string()))
+    }
+}
+
+/// The index of the input a label such as `0:v` or `1` reads from.
+fn stream_input(label: &str) -> Option<usize> {
+    let index = label.split(':').next()?;
+    index.parse().ok()
+}
+
+fn is_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn is_label(label: &str) -> bool {
+    !label.is_empty()
+        && label
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_:.-".contains(c))
+}
@@ -0,0 +1,131 @@
+use std::path::Path;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, ProcessResult};
+use crate::hwaccel::HwAccel;
+use crate::probe::{Stream, probe};
+use crate::scale::ScaleFit;
+use crate::transcode::TranscodeOptions;
+
+#[derive(Debug, Clone)]
+pub struct GridOptions {
+    /// Defaults to as many as make the grid square.
+    pub columns: Option<u32>,
+    /// The size every input is scaled and padded to, by default the first
+    /// input's.
+    pub cell_size: Option<(u32, u32)>,
+    /// The input whose audio is kept, counted from 0, or `None` for a
+    /// silent output.
+    pub audio: Option<usize>,
+    pub transcode: TranscodeOptions,
+}
+
+impl Default for GridOptions {
+    fn default() -> Self {
+        Self {
+            columns: None,
+            cell_size: None,
+            audio: Some(0),
+            transcode: TranscodeOptions::default(),
+        }
+    }
+}
+
+/// Tiles 2 to 9 videos into one, row by row, ending with the shortest.
+pub fn grid_command<P: AsRef<Path>>(
+    inputs: &[P],
+    output: &Path,
+    options: &GridOptions,
+) -> Result<FfmpegCommand, Error> {
+    let n = inputs.len();
+    if !(2..=9).contains(&n) {
+        return Err(Error::invalid("a grid takes 2 to 9 videos"));
+    }
+    for input in inputs {
+        if !input.as_ref().is_file() {
+            return Err(Error::FileNotFound(input.as_ref().to_path_buf()));
+        }
+    }
+    if let Some(audio) = options.audio
+        && audio >= n
+    {
+        return Err(Error::invalid(format!("there is no input {}", audio + 1)));
+    }
+    let infos = inputs
+        .iter()
+        .map(|input| probe(input.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let columns = match options.columns {
+        Some(0) => return Err(Error::invalid("the grid needs at least one column")),
+        Some(columns) => (columns as usize).min(n),
+        None => (1..).find(|c| c * c >= n).unwrap_or(n),
+    };
+    let rows = n.div_ceil(columns);
+    let (width, height) = options
+        .cell_size
+        .or_else(|| infos[0].video().and_then(Stream::resolution))
+        .ok_or_else(|| Error::invalid("cannot tell the cell size, set it explicitly"))?;
+    // yuv420p needs even dimensions.
+    let (width, height) = (width & !1, height & !1);
+
+    let mut command = FfmpegCommand::new();
+    let mut graph = Vec::new();
+    let mut cells = String::new();
+    for (i, info) in infos.iter().enumerate() {
+        if info.video().is_none() {
+            return Err(Error::invalid(format!(
+                "{} has no video",
+                info.path.display()
+            )));
+        }
+        command = command.input(&info.path);
+        graph.push(format!(
+            "[{i}:v:0]{},format=yuv420p[c{i}]",
+            ScaleFit::Pad.filter(width, height)
+        ));
+        cells.push_str(&format!("[c{i}]"));
+    }
+
+    let upload = options.transcode.hwaccel.and_then(HwAccel::upload_filter);
+    let video = if upload.is_some() { "[vg]" } else { "[v]" };
+    let stack = if rows == 1 {
+        format!("hstack=inputs={n}:shortest=1")
+    } else if columns == 1 {
+        format!("vstack=inputs={n}:shortest=1")
+    } else {
+        // Cells left over in the last row stay black.
+        let layout = (0..n)
+            .map(|i| {
+                format!(
+                    "{}_{}",
+                    i % columns * width as usize,
+                    i / columns * height as usize
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("|");
+        format!("xstack=inputs={n}:layout={layout}:fill=black:shortest=1")
+    };
+    graph.push(format!("{cells}{stack}{video}"));
+    if let Some(upload) = upload {
+        graph.push(format!("[vg]{upload}[v]"));
+    }
+    command = command.filter_complex(graph.join(";")).map("[v]");
+    if let Some(audio) = options.audio {
+        command = command.map(&format!("{audio}:a:0?"));
+    }
+    if let Some(shortest) = infos.iter().filter_map(|info| info.duration).min() {
+        command = command.duration(shortest);
+    }
+
+    Ok(options.transcode.apply_codecs(command).output(output))
+}
+
+pub fn grid<P: AsRef<Path>>(
+    inputs: &[P],
+    output: &Path,
+    options: &GridOptions,
+) -> Result<ProcessResult, Error> {
+    grid_command(inputs, output, options)?.run()?.check()
+}
@@ -0,0 +1,181 @@
+use std::ffi::OsString;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, ProcessResult};
+use crate::probe::probe;
+use crate::time::format_timestamp;
+use crate::trim::TrimMode;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SegmentFormat {
+    /// MPEG-TS segments, playable everywhere.
+    #[default]
+    Ts,
+    /// Fragmented MP4 segments with a shared init segment.
+    Fmp4,
+}
+
+impl SegmentFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            SegmentFormat::Ts => "ts",
+            SegmentFormat::Fmp4 => "m4s",
+        }
+    }
+}
+
+impl FromStr for SegmentFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ts" => Ok(SegmentFormat::Ts),
+            "fmp4" => Ok(SegmentFormat::Fmp4),
+            _ => Err(Error::invalid(format!("unknown segment format `{s}`"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PlaylistType {
+    /// A complete playlist of every segment.
+    #[default]
+    Vod,
+    /// A playlist that only grows while the input is being written.
+    Event,
+    /// A sliding window of the latest segments, older ones are deleted.
+    Live { list_size: u32 },
+}
+
+impl FromStr for PlaylistType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "vod" => Ok(PlaylistType::Vod),
+            "event" => Ok(PlaylistType::Event),
+            "live" => Ok(PlaylistType::Live { list_size: 6 }),
+            _ => Err(Error::invalid(format!("unknown playlist type `{s}`"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HlsOptions {
+    pub segment_duration: Duration,
+    pub segment_format: SegmentFormat,
+    pub playlist_type: PlaylistType,
+    /// The playlist file name inside the output directory.
+    pub playlist: String,
+    /// The segment file name pattern inside the output directory, without
+    /// the extension.
+    pub segment_pattern: String,
+    /// Segments need H.264/HEVC and AAC for most players, set a re-encode
+    /// when the input has other codecs.
+    pub mode: TrimMode,
+}
+
+impl Default for HlsOptions {
+    fn default() -> Self {
+        Self {
+            segment_duration: Duration::from_secs(6),
+            segment_format: SegmentFormat::Ts,
+            playlist_type: PlaylistType::Vod,
+            playlist: "index.m3u8".to_string(),
+            segment_pattern: "segment_%05d".to_string(),
+            mode: TrimMode::Copy,
+        }
+    }
+}
+
+impl HlsOptions {
+    /// The hls muxer arguments shared by single and multi-rendition output.
+    pub(crate) fn muxer_args(&self, output_dir: &Path) -> Vec<OsString> {
+        let segments = output_dir.join(format!(
+            "{}.{}",
+            self.segment_pattern,
+            self.segment_format.extension()
+        ));
+        let mut args: Vec<OsString> = vec![
+            "-f".into(),
+            "hls".into(),
+            "-hls_time".into(),
+            format_timestamp(self.segment_duration).into(),
+            "-hls_segment_filename".into(),
+            segments.into_os_string(),
+        ];
+        if self.segment_format == SegmentFormat::Fmp4 {
+            args.extend(["-hls_segment_type".into(), "fmp4".into()]);
+        }
+        match self.playlist_type {
+            PlaylistType::Vod => {
+                args.extend(
+                    ["-hls_playlist_type", "vod", "-hls_list_size", "0"].map(OsString::from),
+                );
+            }
+            PlaylistType::Event => {
+                args.extend(
+                    ["-hls_playlist_type", "event", "-hls_list_size", "0"].map(OsString::from),
+                );
+            }
+            PlaylistType::Live { list_size } => {
+                args.extend([
+                    "-hls_list_size".into(),
+                    list_size.to_string().into(),
+                    "-hls_flags".into(),
+                    "delete_segments".into(),
+                ]);
+            }
+        }
+        args
+    }
+}
+
+pub fn package_hls_command(
+    input: &Path,
+    output_dir: &Path,
+    options: &HlsOptions,
+) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+    if options.segment_duration.is_zero() {
+        return Err(Error::invalid("the segment duration must be positive"));
+    }
+    std::fs::create_dir_all(output_dir).map_err(Error::CreateOutputError)?;
+
+    let mut command = FfmpegCommand::new()
+        .input(input)
+        .map("0:v:0?")
+        .map("0:a:0?");
+    if let Some(duration) = probe(input).ok().and_then(|info| info.duration) {
+        command = command.duration(duration);
+    }
+    command = match &options.mode {
+        TrimMode::Copy => command.codec_copy(),
+        TrimMode::Reencode(transcode) => {
+            // Keyframes on the segment boundaries keep segments the same length.
+            let time = format_timestamp(options.segment_duration);
+            transcode
+                .apply(command)
+                .args(["-force_key_frames", &format!("expr:gte(t,n_forced*{time})")])
+        }
+    };
+
+    Ok(command
+        .args(options.muxer_args(output_dir))
+        .output(output_dir.join(&options.playlist)))
+}
+
+pub fn package_hls(
+    input: &Path,
+    output_dir: &Path,
+    options: &HlsOptions,
+) -> Result<ProcessResult, Error> {
+    package_hls_command(input, output_dir, options)?
+        .run()?
+        .check()
+}
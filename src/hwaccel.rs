@@ -0,0 +1,96 @@
+use std::str::FromStr;
+
+use crate::Error;
+use crate::tool::Tool;
+
+/// A hardware encoder family.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HwAccel {
+    VideoToolbox,
+    Nvenc,
+    Qsv,
+    Vaapi,
+}
+
+impl HwAccel {
+    pub const ALL: [HwAccel; 4] = [
+        HwAccel::VideoToolbox,
+        HwAccel::Nvenc,
+        HwAccel::Qsv,
+        HwAccel::Vaapi,
+    ];
+
+    /// The name used on the command line and in encoder names.
+    pub fn name(self) -> &'static str {
+        match self {
+            HwAccel::VideoToolbox => "videotoolbox",
+            HwAccel::Nvenc => "nvenc",
+            HwAccel::Qsv => "qsv",
+            HwAccel::Vaapi => "vaapi",
+        }
+    }
+
+    /// The hardware encoder for a software codec name, H.264 for anything
+    /// other than HEVC.
+    pub fn encoder(self, codec: Option<&str>) -> String {
+        let family = match codec {
+            Some("libx265" | "hevc" | "h265") => "hevc",
+            _ => "h264",
+        };
+        format!("{family}_{}", self.name())
+    }
+
+    /// The encoder option that takes a constant quality value.
+    pub(crate) fn quality_arg(self) -> &'static str {
+        match self {
+            HwAccel::VideoToolbox => "-q:v",
+            HwAccel::Nvenc => "-cq",
+            HwAccel::Qsv => "-global_quality",
+            HwAccel::Vaapi => "-qp",
+        }
+    }
+
+    /// VAAPI encoders take frames in GPU memory.
+    pub(crate) fn upload_filter(self) -> Option<&'static str> {
+        match self {
+            HwAccel::Vaapi => Some("format=nv12,hwupload"),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn global_args(self) -> &'static [&'static str] {
+        match self {
+            HwAccel::Vaapi => &["-vaapi_device", "/dev/dri/renderD128"],
+            _ => &[],
+        }
+    }
+
+    /// The accelerators the given ffmpeg has an H.264 encoder for. Whether
+    /// the hardware is present is only known once encoding starts.
+    pub fn detect(tool: &Tool) -> Result<Vec<HwAccel>, Error> {
+        let encoders = tool.encoders()?;
+        Ok(HwAccel::ALL
+            .into_iter()
+            .filter(|accel| {
+                let name = accel.encoder(None);
+                encoders.iter().any(|e| e.name == name)
+            })
+            .collect())
+    }
+}
+
+impl FromStr for HwAccel {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "videotoolbox" => Ok(HwAccel::VideoToolbox),
+            "nvenc" => Ok(HwAccel::Nvenc),
+            "qsv" => Ok(HwAccel::Qsv),
+            "vaapi" => Ok(HwAccel::Vaapi),
+            _ => Err(Error::invalid(format!(
+                "unknown hardware accelerator `{s}`"
+            ))),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::process::{Child, ExitStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, ProcessResult};
+use crate::log::info;
+use crate::progress::Progress;
+
+#[derive(Debug, Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    child: Mutex<Option<Child>>,
+}
+
+/// Stops the commands it is given to with [`FfmpegCommand::cancel_token`].
+/// Clones share one state, so any of them can cancel from another thread.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<CancelState>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Kills the running ffmpeg, if any, and keeps later commands from
+    /// starting. They return [`Error::Cancelled`].
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::SeqCst);
+        if let Some(child) = self.0.child.lock().unwrap().as_mut() {
+            info!("cancelling pid {}", child.id());
+            let _ = child.kill();
+        }
+    }
+
+    /// Kills the running ffmpeg without cancelling anything else.
+    pub(crate) fn kill(&self) {
+        if let Some(child) = self.0.child.lock().unwrap().as_mut() {
+            let _ = child.kill();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Takes charge of a spawned process so [`cancel`](Self::cancel) can
+    /// kill it.
+    pub(crate) fn attach(&self, mut child: Child) {
+        let mut slot = self.0.child.lock().unwrap();
+        // cancel() may have come between the check before spawning and now.
+        if self.is_cancelled() {
+            let _ = child.kill();
+        }
+        *slot = Some(child);
+    }
+
+    /// Waits for the attached process without holding on to it, so it can
+    /// still be killed meanwhile.
+    pub(crate) fn wait(&self) -> Result<ExitStatus, Error> {
+        loop {
+            let mut slot = self.0.child.lock().unwrap();
+            let child = slot.as_mut().expect("no process attached");
+            if let Some(status) = child.try_wait().map_err(Error::CommandError)? {
+                *slot = None;
+                return Ok(status);
+            }
+            drop(slot);
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+}
+
+/// A command running on its own thread, see [`FfmpegCommand::spawn`].
+#[derive(Debug)]
+pub struct JobHandle {
+    token: CancelToken,
+    thread: JoinHandle<Result<ProcessResult, Error>>,
+}
+
+impl JobHandle {
+    /// Kills ffmpeg and removes what it wrote so far. [`wait`](Self::wait)
+    /// then returns [`Error::Cancelled`].
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// A token that cancels this job, for handing to another thread.
+    pub fn token(&self) -> CancelToken {
+        self.token.clone()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    pub fn wait(self) -> Result<ProcessResult, Error> {
+        self.thread.join().unwrap_or(Err(Error::Cancelled))
+    }
+}
+
+impl FfmpegCommand {
+    /// Runs the command on a new thread and returns at once.
+    pub fn spawn(self) -> JobHandle {
+        self.spawn_with_progress(|_| {})
+    }
+
+    /// Like [`FfmpegCommand::spawn`], calling `on_progress` on the job's
+    /// thread for every update.
+    pub fn spawn_with_progress<F>(self, on_progress: F) -> JobHandle
+    where
+        F: FnMut(&Progress) + Send + 'static,
+    {
+        let token = self.get_cancel_token().cloned().unwrap_or_default();
+        let command = self.cancel_token(token.clone());
+        let thread = thread::spawn(move || command.run_with_progress(on_progress));
+        JobHandle { token, thread }
+    }
+}
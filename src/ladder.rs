@@ -0,0 +1,150 @@
+use std::path::{Path, PathBuf};
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, ProcessResult};
+use crate::hls::HlsOptions;
+use crate::probe::probe;
+use crate::rendition::{Rendition, map_renditions, split_graph};
+use crate::time::format_timestamp;
+use crate::transcode::TranscodeOptions;
+
+#[derive(Debug, Clone)]
+pub struct LadderOptions {
+    pub renditions: Vec<Rendition>,
+    /// Keep renditions taller than the source instead of dropping them.
+    pub upscale: bool,
+    /// Codecs and preset, the bitrates come from the renditions.
+    pub transcode: TranscodeOptions,
+    /// Package the renditions as HLS variants under a master playlist
+    /// instead of writing one file each.
+    pub hls: Option<HlsOptions>,
+}
+
+impl Default for LadderOptions {
+    fn default() -> Self {
+        Self {
+            renditions: Rendition::standard_ladder(),
+            upscale: false,
+            transcode: TranscodeOptions::default(),
+            hls: None,
+        }
+    }
+}
+
+/// The name of the HLS variant and its subdirectory, e.g. `720p`.
+fn variant_name(rendition: &Rendition) -> String {
+    format!("{}p", rendition.height)
+}
+
+/// Where a rendition of `output` is written without HLS: `out.mp4` becomes
+/// `out_720p.mp4`.
+pub fn rendition_path(output: &Path, rendition: &Rendition) -> PathBuf {
+    let mut name = output.file_stem().unwrap_or_default().to_os_string();
+    name.push(format!("_{}", variant_name(rendition)));
+    if let Some(ext) = output.extension() {
+        name.push(".");
+        name.push(ext);
+    }
+    output.with_file_name(name)
+}
+
+/// `output` is a file name the rendition heights are added to, or the output
+/// directory with [`LadderOptions::hls`].
+pub fn ladder_command(
+    input: &Path,
+    output: &Path,
+    options: &LadderOptions,
+) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+
+    let info = probe(input)?;
+    let source = info.video().ok_or(Error::StreamNotFound)?;
+    let audio = info.audio().is_some();
+    let renditions: Vec<Rendition> = match source.height {
+        Some(height) if !options.upscale => options
+            .renditions
+            .iter()
+            .filter(|r| r.height <= height)
+            .cloned()
+            .collect(),
+        _ => options.renditions.clone(),
+    };
+    if renditions.is_empty() {
+        return Err(Error::invalid("no rendition fits the source size"));
+    }
+
+    let mut command = FfmpegCommand::new().input(input);
+    if let Some(duration) = info.duration {
+        command = command.duration(duration);
+    }
+
+    let Some(hls) = &options.hls else {
+        command = command.filter_complex(split_graph(&renditions, &options.transcode));
+        if let Some(accel) = options.transcode.hwaccel {
+            command = command.global_args(accel.global_args());
+        }
+        let video_codec = options
+            .transcode
+            .video_encoder()
+            .unwrap_or_else(|| "libx264".to_string());
+        let audio_codec = options.transcode.audio_codec.as_deref().unwrap_or("aac");
+        for (i, rendition) in renditions.iter().enumerate() {
+            command = command
+                .map(&format!("[v{i}]"))
+                .video_codec(&video_codec)
+                .args(["-b:v", &rendition.video_bitrate]);
+            if let Some(preset) = &options.transcode.preset {
+                command = command.args(["-preset", preset]);
+            }
+            if audio {
+                command = command
+                    .map("0:a:0")
+                    .audio_codec(audio_codec)
+                    .args(["-b:a", &rendition.audio_bitrate]);
+            }
+            command = command.output(rendition_path(output, rendition));
+        }
+        return Ok(command);
+    };
+
+    std::fs::create_dir_all(output).map_err(Error::CreateOutputError)?;
+    for rendition in &renditions {
+        std::fs::create_dir_all(output.join(variant_name(rendition)))
+            .map_err(Error::CreateOutputError)?;
+    }
+
+    let variants = renditions
+        .iter()
+        .enumerate()
+        .map(|(i, rendition)| {
+            let name = variant_name(rendition);
+            if audio {
+                format!("v:{i},a:{i},name:{name}")
+            } else {
+                format!("v:{i},name:{name}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    // Aligned keyframes let players switch variants at segment boundaries.
+    let time = format_timestamp(hls.segment_duration);
+    Ok(
+        map_renditions(command, &renditions, &options.transcode, audio, true)
+            .args(["-force_key_frames", &format!("expr:gte(t,n_forced*{time})")])
+            .args(hls.muxer_args(&output.join("%v")))
+            .args(["-var_stream_map", &variants])
+            .args(["-master_pl_name", "master.m3u8"])
+            .output(output.join("%v").join(&hls.playlist)),
+    )
+}
+
+pub fn ladder(
+    input: &Path,
+    output: &Path,
+    options: &LadderOptions,
+) -> Result<ProcessResult, Error> {
+    ladder_command(input, output, options)?.run()?.check()
+}
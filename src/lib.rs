@@ -1,73 +1,181 @@
-use std::fs::File;
-use std::io::Write;
-use std::path::Path;
-use std::process::Command;
+mod analyze;
+mod animation;
+mod audio;
+mod batch;
+mod capture;
+mod concat;
+mod container;
+mod crop;
+mod dash;
+mod deinterlace;
+mod detect;
+mod error;
+mod fade;
+pub mod ffmpeg;
+mod filters;
+mod fps;
+mod frames;
+mod generate;
+mod graph;
+mod grid;
+mod hls;
+mod hwaccel;
+mod job;
+pub mod json;
+mod ladder;
+mod limits;
+pub mod log;
+mod looping;
+mod metadata;
+mod mux;
+mod network;
+mod normalize;
+mod offset;
+mod optimize;
+mod overlay;
+mod pipeline;
+mod pool;
+mod presets;
+pub mod probe;
+mod progress;
+mod quality;
+mod queue;
+mod record;
+mod remux;
+mod rendition;
+mod retry;
+mod reverse;
+mod rotate;
+mod scale;
+pub mod scan;
+mod sequence;
+mod silence;
+mod space;
+mod speed;
+mod split;
+mod stabilize;
+mod stats;
+mod stream;
+mod streams;
+mod subtitles;
+#[cfg(feature = "async")]
+mod task;
+pub mod temp;
+mod template;
+mod text;
+pub mod time;
+mod tonemap;
+mod tool;
+mod transcode;
+mod trim;
+mod validate;
+mod visualize;
+mod watch;
 
-#[derive(Debug)]
-pub enum Error {
-    FolderNotFound,
-    AccessDenied,
-    FileNotFound,
-
-    CreateOutputError,
-    WriteFileError,
-    CommandError,
-}
-
-fn read_dir(path: &Path, prefix: &str, ext: &str) -> Result<Vec<String>, Error> {
-    if !path.exists() {
-        return Err(Error::FolderNotFound);
-    }
-
-    let entries = std::fs::read_dir(path).map_err(|_| Error::AccessDenied)?;
-    let mut paths = entries
-        .filter_map(|entry| {
-            entry.ok().and_then(|e| {
-                let path = e.path();
-                if path.is_file()
-                    && path
-                        .extension()
-                        .is_some_and(|e| e.eq_ignore_ascii_case(ext))
-                    && path
-                        .file_name()
-                        .and_then(|e| e.to_str())
-                        .is_some_and(|s| s.starts_with(prefix))
-                {
-                    Some(path.to_str().unwrap().to_string())
-                } else {
-                    None
-                }
-            })
-        })
-        .collect::<Vec<_>>();
-
-    paths.sort_unstable();
-    Ok(paths)
-}
-
-pub fn concat(path: &Path, prefix: &str, ext: &str, output: &Path) -> Result<bool, Error> {
-    let tmp_path = "/tmp/file_list.txt";
-    let mut f = File::create(tmp_path).map_err(|_| Error::CreateOutputError)?;
-
-    for file in read_dir(path, prefix, ext)? {
-        writeln!(f, "file '{}'", file).map_err(|_| Error::WriteFileError)?;
-    }
-
-    // Step 2: run ffmpeg concat
-    let status = Command::new("ffmpeg")
-        .args([
-            "-f",
-            "concat",
-            "-safe",
-            "0",
-            "-i",
-            tmp_path,
-            "-c",
-            "copy",
-            output.as_os_str().to_str().unwrap(),
-        ])
-        .status()
-        .map_err(|_| Error::CommandError)?;
-
-    Ok(status.success())
-}
+pub use analyze::{AnalyzeOptions, FrameTypes, StreamAnalysis, analyze};
+pub use animation::{AnimationFormat, AnimationOptions, animation, animation_command};
+pub use audio::{
+    AudioFormat, ConcatAudioOptions, ConvertAudioOptions, ExtractAudioOptions, concat_audio,
+    concat_audio_command, convert_audio, convert_audio_command, extract_audio,
+    extract_audio_command,
+};
+pub use batch::{
+    BatchItem, BatchOperation, BatchOptions, BatchReport, batch, batch_plan, batch_with,
+};
+pub use capture::{
+    CameraBackend, CaptureDevice, CaptureOptions, DeviceKind, capture, capture_command,
+    list_devices,
+};
+pub use concat::{
+    ConcatMode, ConcatOptions, Mismatch, Transition, TransitionKind, check_compatibility, concat,
+    concat_command, concat_files, concat_files_command, concat_inputs, validate_inputs,
+};
+pub use container::{Container, StreamAction, StreamPlan, plan_streams, plan_transcode};
+pub use crop::{
+    CropArea, CropMode, CropOptions, crop, crop_command, crop_preview, detect_crop,
+    detect_crop_command,
+};
+pub use dash::{DashOptions, package_dash, package_dash_command};
+pub use deinterlace::Deinterlacer;
+pub use detect::{DetectOptions, Detections, detect, detect_command};
+pub use error::{Error, ErrorClass};
+pub use fade::Fades;
+pub use ffmpeg::{
+    FfmpegCommand, Input, LineLogger, OverwritePolicy, ProcessFailure, ProcessResult,
+};
+pub use filters::{Denoiser, Strength, VideoFilters};
+pub use fps::{FpsMode, FpsOptions, convert_fps, convert_fps_command};
+pub use frames::{
+    FrameOptions, FrameSelection, FramesOutput, ImageFormat, extract_frames,
+    extract_frames_command, written_frames,
+};
+pub use generate::{GenerateOptions, TestPattern, TestTone, generate, generate_command};
+pub use graph::{Filter, FilterChain, FilterGraph};
+pub use grid::{GridOptions, grid, grid_command};
+pub use hls::{HlsOptions, PlaylistType, SegmentFormat, package_hls, package_hls_command};
+pub use hwaccel::HwAccel;
+pub use job::{CancelToken, JobHandle};
+pub use ladder::{LadderOptions, ladder, ladder_command, rendition_path};
+pub use limits::{CpuLimiter, ResourceLimits};
+pub use looping::{LoopLength, LoopOptions, loop_video, loop_video_command};
+pub use metadata::{Metadata, write_metadata, write_metadata_command};
+pub use mux::{MuxOptions, mux, mux_command};
+pub use network::{NetworkOptions, is_url};
+pub use normalize::{
+    Loudness, NormalizeAudioOptions, measure_loudness, measure_loudness_command, normalize_audio,
+    normalize_audio_command, normalize_audio_preview,
+};
+pub use offset::{OffsetOptions, offset, offset_command};
+pub use optimize::{WebOptimizeOptions, optimize_web, optimize_web_command};
+pub use overlay::{
+    OverlayOptions, PipOptions, Position, Watermark, overlay, overlay_command, pip, pip_command,
+};
+pub use pipeline::{Operation, Pipeline, Stage};
+pub use pool::{JobFailure, JobPool};
+pub use presets::{ENCODING_PRESETS, encoding_preset};
+pub use probe::{MediaInfo, probe};
+pub use progress::Progress;
+pub use quality::{QualityMetrics, QualityOptions, quality_metrics, quality_metrics_command};
+pub use queue::{JobQueue, JobStatus, QueuedJob, WorkerGuard};
+pub use record::{RecordOptions, Region, ScreenGrabber, record, record_command};
+pub use remux::{remux, remux_command, remux_plan};
+pub use rendition::Rendition;
+pub use retry::RetryPolicy;
+pub use reverse::{ReverseOptions, reverse, reverse_passes};
+pub use rotate::{RotateMode, RotateOptions, Rotation, rotate, rotate_command};
+pub use scale::{ScaleFit, ScaleOptions, scale, scale_command};
+pub use scan::{DuplicateCheck, SortOrder};
+pub use sequence::{
+    ImageInput, ImageSequenceOptions, images_to_video, images_to_video_command, link_sequence,
+};
+pub use silence::{
+    SilenceOptions, detect_silence, detect_silence_command, remove_silence, remove_silence_command,
+    remove_silence_preview,
+};
+pub use space::{SpaceCheck, available_space};
+pub use speed::{SpeedOptions, speed, speed_command};
+pub use split::{
+    SplitOptions, SplitOutput, SplitPoints, detect_scenes, detect_scenes_command, split,
+    split_command, split_preview, written_segments,
+};
+pub use stabilize::{StabilizeOptions, stabilize, stabilize_passes};
+pub use stats::{AudioStats, audio_stats, audio_stats_command};
+pub use stream::{StreamOptions, StreamSource, is_connection_error, stream, stream_command};
+pub use streams::StreamSelection;
+pub use subtitles::{SubtitleMode, SubtitleOptions, subtitles, subtitles_command};
+#[cfg(feature = "async")]
+pub use task::{Task, concat_async, probe_async, transcode_async, trim_async};
+pub use template::OutputTemplate;
+pub use text::{TextContent, TextOptions, draw_text, draw_text_command};
+pub use tonemap::ToneMap;
+pub use tool::{Encoder, EncoderKind, FFMPEG_ENV, FFPROBE_ENV, Tool};
+pub use transcode::{
+    TranscodeOptions, transcode, transcode_command, transcode_passes, transcode_plan,
+};
+pub use trim::{TrimMode, TrimOptions, trim, trim_command};
+pub use validate::validate_output;
+pub use visualize::{
+    SpectrogramOptions, WaveformOptions, spectrogram, spectrogram_command, waveform,
+    waveform_command,
+};
+pub use watch::{WatchOptions, watch};
@@ -0,0 +1,167 @@
+//! Keeping background encodes from starving everything else on the machine.
+//! Priorities and CPU limits are applied by running ffmpeg through `nice`,
+//! `ionice` and `systemd-run`, which all hand over to ffmpeg itself, so
+//! cancelling and timeouts still reach it.
+
+use std::ffi::OsString;
+use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
+use std::sync::RwLock;
+
+use crate::Error;
+use crate::log::warning;
+use crate::tool::Tool;
+
+static DEFAULT: RwLock<Option<ResourceLimits>> = RwLock::new(None);
+
+/// How CPU time is capped on Linux.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CpuLimiter {
+    /// A `CPUQuota` on a transient cgroup, through `systemd-run --user`.
+    #[default]
+    Cgroup,
+    /// The `cpulimit` tool, which pauses ffmpeg whenever it uses too much.
+    /// Also works without systemd and on other unixes.
+    Cpulimit,
+}
+
+impl CpuLimiter {
+    pub fn name(self) -> &'static str {
+        match self {
+            CpuLimiter::Cgroup => "cgroup",
+            CpuLimiter::Cpulimit => "cpulimit",
+        }
+    }
+}
+
+impl FromStr for CpuLimiter {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cgroup" => Ok(CpuLimiter::Cgroup),
+            "cpulimit" => Ok(CpuLimiter::Cpulimit),
+            _ => Err(Error::invalid(format!("unknown CPU limiter `{s}`"))),
+        }
+    }
+}
+
+/// How much of the machine an ffmpeg run may use. Nothing is limited by
+/// default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// How far to lower the CPU priority, from 1 to 19 as with `nice`. On
+    /// Windows ffmpeg runs below normal priority, or idle from 15 on.
+    pub nice: Option<i32>,
+    /// Only use the disk when nothing else does (Linux).
+    pub idle_io: bool,
+    /// How many threads the encoders and filters use, ffmpeg's `-threads`
+    /// and `-filter_threads`.
+    pub threads: Option<u32>,
+    /// The CPU time ffmpeg may use in percent of one core, e.g. 200 for two
+    /// cores.
+    pub cpu_limit: Option<u32>,
+    pub cpu_limiter: CpuLimiter,
+}
+
+impl ResourceLimits {
+    /// Makes these the limits new commands start with.
+    pub fn install(self) {
+        *DEFAULT.write().unwrap() = Some(self);
+    }
+
+    /// The installed limits, or none when nothing was installed.
+    pub fn current() -> Self {
+        DEFAULT.read().unwrap().clone().unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The programs ffmpeg is started through, each followed by its own
+    /// arguments, e.g. `nice -n 10 ionice -c 3 ffmpeg ...`. Programs that
+    /// aren't installed are left out, with a warning when `warn` is set.
+    pub(crate) fn wrappers(&self, warn: bool) -> Vec<OsString> {
+        let mut args: Vec<OsString> = Vec::new();
+        let mut wrap = |program: &str, program_args: Vec<String>| {
+            if Tool::resolve(program.as_ref()).is_none() {
+                if warn {
+                    warning!("{program} is not installed, running ffmpeg without it");
+                }
+                return;
+            }
+            args.push(program.into());
+            args.extend(program_args.into_iter().map(OsString::from));
+        };
+
+        if let Some(percent) = self.cpu_limit
+            && self.cpu_limiter == CpuLimiter::Cgroup
+        {
+            if cfg!(target_os = "linux") {
+                wrap(
+                    "systemd-run",
+                    vec![
+                        "--user".into(),
+                        "--scope".into(),
+                        "--quiet".into(),
+                        "--collect".into(),
+                        "-p".into(),
+                        format!("CPUQuota={percent}%"),
+                        "--".into(),
+                    ],
+                );
+            } else if warn {
+                warning!("CPU limits through cgroups only work on Linux, try cpulimit");
+            }
+        }
+        if let Some(nice) = self.nice
+            && cfg!(unix)
+        {
+            wrap("nice", vec!["-n".into(), nice.to_string()]);
+        }
+        if self.idle_io {
+            if cfg!(target_os = "linux") {
+                wrap("ionice", vec!["-c".into(), "3".into()]);
+            } else if warn {
+                warning!("idle disk priority only works on Linux");
+            }
+        }
+        args
+    }
+
+    /// Sets what can't be done through a wrapper on `command` itself.
+    pub(crate) fn apply(&self, command: &mut Command) {
+        #[cfg(windows)]
+        if let Some(nice) = self.nice {
+            use std::os::windows::process::CommandExt;
+            const IDLE_PRIORITY_CLASS: u32 = 0x40;
+            const BELOW_NORMAL_PRIORITY_CLASS: u32 = 0x4000;
+            command.creation_flags(if nice >= 15 {
+                IDLE_PRIORITY_CLASS
+            } else {
+                BELOW_NORMAL_PRIORITY_CLASS
+            });
+        }
+        #[cfg(not(windows))]
+        let _ = command;
+    }
+
+    /// Starts `cpulimit` watching the process `pid`, when that is how CPU
+    /// time is limited. It quits by itself once the process is gone.
+    pub(crate) fn watch(&self, pid: u32) -> Option<Child> {
+        let percent = self.cpu_limit?;
+        if self.cpu_limiter != CpuLimiter::Cpulimit || !cfg!(unix) {
+            return None;
+        }
+        Command::new("cpulimit")
+            .args(["--pid", &pid.to_string(), "--limit", &percent.to_string()])
+            .arg("--lazy")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .inspect_err(|e| warning!("could not start cpulimit: {e}"))
+            .ok()
+    }
+}
@@ -0,0 +1,92 @@
+//! A minimal logging facade. Install a logger with [`set_logger`] to receive
+//! what the library does: commands being run, their exit status and timing.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, RwLock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        })
+    }
+}
+
+pub struct Record<'a> {
+    pub level: Level,
+    /// The module the message comes from, e.g. `video::ffmpeg`.
+    pub target: &'static str,
+    pub message: fmt::Arguments<'a>,
+}
+
+type Logger = Arc<dyn Fn(&Record) + Send + Sync>;
+
+static LOGGER: RwLock<Option<Logger>> = RwLock::new(None);
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(Level::Trace as u8);
+
+/// Sends every record up to the maximum level to `logger`, replacing any
+/// previous one.
+pub fn set_logger<F>(logger: F)
+where
+    F: Fn(&Record) + Send + Sync + 'static,
+{
+    *LOGGER.write().unwrap() = Some(Arc::new(logger));
+}
+
+/// The most detailed level passed on, `None` turns logging off.
+pub fn set_max_level(level: Option<Level>) {
+    MAX_LEVEL.store(level.map_or(0, |l| l as u8), Ordering::Relaxed);
+}
+
+pub fn enabled(level: Level) -> bool {
+    level as u8 <= MAX_LEVEL.load(Ordering::Relaxed) && LOGGER.read().unwrap().is_some()
+}
+
+#[doc(hidden)]
+pub fn dispatch(level: Level, target: &'static str, message: fmt::Arguments<'_>) {
+    if level as u8 > MAX_LEVEL.load(Ordering::Relaxed) {
+        return;
+    }
+    let logger = LOGGER.read().unwrap().clone();
+    if let Some(logger) = logger {
+        logger(&Record {
+            level,
+            target,
+            message,
+        });
+    }
+}
+
+macro_rules! record {
+    ($level:expr, $($arg:tt)*) => {
+        $crate::log::dispatch($level, module_path!(), format_args!($($arg)*))
+    };
+}
+
+macro_rules! warning {
+    ($($arg:tt)*) => { $crate::log::record!($crate::log::Level::Warn, $($arg)*) };
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => { $crate::log::record!($crate::log::Level::Info, $($arg)*) };
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => { $crate::log::record!($crate::log::Level::Debug, $($arg)*) };
+}
+
+pub(crate) use {debug, info, record, warning};
@@ -0,0 +1,78 @@
+use std::path::Path;
+use std::time::Duration;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, Input, ProcessResult};
+use crate::probe::probe;
+use crate::time::format_timestamp;
+use crate::transcode::TranscodeOptions;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopLength {
+    /// Play the input this many times in total.
+    Times(u32),
+    /// Repeat the input until the output is this long, cutting the last
+    /// repetition short.
+    Duration(Duration),
+}
+
+#[derive(Debug, Clone)]
+pub struct LoopOptions {
+    pub length: LoopLength,
+    /// Re-encode with these instead of copying the streams, e.g. when a
+    /// copied duration cut would snap to a keyframe.
+    pub transcode: Option<TranscodeOptions>,
+}
+
+impl Default for LoopOptions {
+    fn default() -> Self {
+        Self {
+            length: LoopLength::Times(2),
+            transcode: None,
+        }
+    }
+}
+
+/// Repeats `input` with `-stream_loop`, which reads it again from the start
+/// without decoding when the streams are copied.
+pub fn loop_video_command(
+    input: &Path,
+    output: &Path,
+    options: &LoopOptions,
+) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+    let once = probe(input).ok().and_then(|info| info.duration);
+
+    let (loops, duration) = match options.length {
+        LoopLength::Times(0) => return Err(Error::invalid("the clip must play at least once")),
+        LoopLength::Times(times) => ((times - 1).to_string(), once.map(|once| once * times)),
+        LoopLength::Duration(duration) if duration.is_zero() => {
+            return Err(Error::invalid("the duration must be positive"));
+        }
+        LoopLength::Duration(duration) => ("-1".to_string(), Some(duration)),
+    };
+    let mut command =
+        FfmpegCommand::new().add_input(Input::new(input).args(["-stream_loop", &loops]));
+    if let LoopLength::Duration(duration) = options.length {
+        command = command.args(["-t", &format_timestamp(duration)]);
+    }
+    if let Some(duration) = duration {
+        command = command.duration(duration);
+    }
+
+    command = match &options.transcode {
+        Some(transcode) => transcode.apply(command),
+        None => command.codec_copy(),
+    };
+    Ok(command.output(output))
+}
+
+pub fn loop_video(
+    input: &Path,
+    output: &Path,
+    options: &LoopOptions,
+) -> Result<ProcessResult, Error> {
+    loop_video_command(input, output, options)?.run()?.check()
+}
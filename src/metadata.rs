@@ -0,0 +1,139 @@
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, ProcessResult};
+use crate::probe::{MediaInfo, probe};
+use crate::time::{format_datetime, parse_datetime};
+
+/// The container tags of a file. Keys compare case-insensitively, because
+/// ffprobe reports them the way each container spells them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata {
+    tags: Vec<(String, String)>,
+}
+
+impl Metadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the tags of `path` with ffprobe.
+    pub fn read(path: &Path) -> Result<Self, Error> {
+        probe(path).map(|info| Self::from(&info))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn set(&mut self, key: &str, value: impl Into<String>) {
+        let value = value.into();
+        match self
+            .tags
+            .iter_mut()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        {
+            Some((_, v)) => *v = value,
+            None => self.tags.push((key.to_string(), value)),
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let i = self
+            .tags
+            .iter()
+            .position(|(k, _)| k.eq_ignore_ascii_case(key))?;
+        Some(self.tags.remove(i).1)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.tags.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.get("title")
+    }
+
+    pub fn set_title(&mut self, title: impl Into<String>) {
+        self.set("title", title);
+    }
+
+    pub fn artist(&self) -> Option<&str> {
+        self.get("artist")
+    }
+
+    pub fn set_artist(&mut self, artist: impl Into<String>) {
+        self.set("artist", artist);
+    }
+
+    pub fn comment(&self) -> Option<&str> {
+        self.get("comment")
+    }
+
+    pub fn set_comment(&mut self, comment: impl Into<String>) {
+        self.set("comment", comment);
+    }
+
+    /// `None` when the tag is missing or not a date ffmpeg would write.
+    pub fn creation_time(&self) -> Option<SystemTime> {
+        self.get("creation_time")
+            .and_then(|value| parse_datetime(value).ok())
+    }
+
+    pub fn set_creation_time(&mut self, time: SystemTime) {
+        self.set("creation_time", format_datetime(time));
+    }
+}
+
+impl From<&MediaInfo> for Metadata {
+    fn from(info: &MediaInfo) -> Self {
+        Self {
+            tags: info.tags.clone(),
+        }
+    }
+}
+
+/// Copies `input` to `output` with its tags replaced by `metadata`. Tags
+/// `metadata` doesn't have are cleared; the streams are not re-encoded.
+pub fn write_metadata_command(
+    input: &Path,
+    output: &Path,
+    metadata: &Metadata,
+) -> Result<FfmpegCommand, Error> {
+    let info = probe(input)?;
+    let current = Metadata::from(&info);
+
+    let mut command = FfmpegCommand::new().input(input).map("0").codec_copy();
+    if let Some(duration) = info.duration {
+        command = command.duration(duration);
+    }
+    for (key, value) in metadata.iter() {
+        if current.get(key) != Some(value) {
+            command = command.args(["-metadata".to_string(), format!("{key}={value}")]);
+        }
+    }
+    for (key, _) in current.iter() {
+        if metadata.get(key).is_none() {
+            command = command.args(["-metadata".to_string(), format!("{key}=")]);
+        }
+    }
+    Ok(command.output(output))
+}
+
+pub fn write_metadata(
+    input: &Path,
+    output: &Path,
+    metadata: &Metadata,
+) -> Result<ProcessResult, Error> {
+    write_metadata_command(input, output, metadata)?
+        .run()?
+        .check()
+}
@@ -0,0 +1,72 @@
+use std::path::Path;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, Input, ProcessResult};
+use crate::probe::probe;
+use crate::transcode::TranscodeOptions;
+
+#[derive(Debug, Clone, Default)]
+pub struct MuxOptions {
+    /// Milliseconds to delay the audio by, negative to start it earlier.
+    pub audio_offset_ms: i64,
+    /// End the output with the shorter of the video and the audio.
+    pub shortest: bool,
+    /// Add the audio as another track instead of replacing the video's own.
+    pub keep_original_audio: bool,
+    /// The streams are copied unless codecs are set here.
+    pub transcode: TranscodeOptions,
+}
+
+/// Puts the audio of `audio` onto the video of `video`.
+pub fn mux_command(
+    video: &Path,
+    audio: &Path,
+    output: &Path,
+    options: &MuxOptions,
+) -> Result<FfmpegCommand, Error> {
+    for input in [video, audio] {
+        if !input.is_file() {
+            return Err(Error::FileNotFound(input.to_path_buf()));
+        }
+    }
+
+    let mut audio_input = Input::new(audio);
+    if options.audio_offset_ms != 0 {
+        audio_input = audio_input.args([
+            "-itsoffset".to_string(),
+            format!("{:.3}", options.audio_offset_ms as f64 / 1000.0),
+        ]);
+    }
+
+    let mut command = FfmpegCommand::new()
+        .input(video)
+        .add_input(audio_input)
+        .map("0:v:0")
+        .map("1:a:0");
+    if options.keep_original_audio {
+        command = command.map("0:a?");
+    }
+    if let Some(duration) = probe(video).ok().and_then(|info| info.duration) {
+        command = command.duration(duration);
+    }
+    if options.transcode.video_encoder().is_none() {
+        command = command.video_codec("copy");
+    }
+    if options.transcode.audio_codec.is_none() && options.transcode.audio_bitrate.is_none() {
+        command = command.audio_codec("copy");
+    }
+    if options.shortest {
+        command = command.arg("-shortest");
+    }
+
+    Ok(options.transcode.apply(command).output(output))
+}
+
+pub fn mux(
+    video: &Path,
+    audio: &Path,
+    output: &Path,
+    options: &MuxOptions,
+) -> Result<ProcessResult, Error> {
+    mux_command(video, audio, output, options)?.run()?.check()
+}
@@ -0,0 +1,100 @@
+use std::path::Path;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use crate::Error;
+use crate::ffmpeg::Input;
+
+static DEFAULT: RwLock<Option<NetworkOptions>> = RwLock::new(None);
+
+/// How inputs given as URLs are read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkOptions {
+    /// Give up when the server sends nothing for this long.
+    pub timeout: Option<Duration>,
+    /// The URL schemes inputs may use, `http` and `https` by default.
+    pub protocols: Vec<String>,
+}
+
+impl Default for NetworkOptions {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            protocols: vec!["http".to_string(), "https".to_string()],
+        }
+    }
+}
+
+impl NetworkOptions {
+    /// Makes these the options URL inputs are read with.
+    pub fn install(self) {
+        *DEFAULT.write().unwrap() = Some(self);
+    }
+
+    /// The installed options, or the defaults when none were installed.
+    pub fn current() -> Self {
+        DEFAULT.read().unwrap().clone().unwrap_or_default()
+    }
+
+    /// The options ffmpeg and ffprobe read a URL input with.
+    pub(crate) fn input_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(timeout) = self.timeout {
+            args.extend(["-rw_timeout".to_string(), timeout.as_micros().to_string()]);
+        }
+        args
+    }
+
+    /// The `-protocol_whitelist` for demuxers that open URLs named inside
+    /// another file, such as concat lists. The connections https and
+    /// friends are built on have to be allowed too.
+    pub(crate) fn protocol_whitelist(&self) -> String {
+        let mut protocols = vec!["file".to_string()];
+        for protocol in &self.protocols {
+            if !protocols.contains(protocol) {
+                protocols.push(protocol.clone());
+            }
+        }
+        protocols.extend(["tcp", "tls", "crypto"].map(str::to_string));
+        protocols.join(",")
+    }
+}
+
+/// The scheme of a URL such as `https://example.com/a.mp4`, lower cased.
+fn scheme(path: &Path) -> Option<String> {
+    let (scheme, _) = path.to_str()?.split_once("://")?;
+    let valid = scheme.len() > 1
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+    valid.then(|| scheme.to_ascii_lowercase())
+}
+
+/// Whether ffmpeg would read `path` from the network rather than disk.
+pub fn is_url(path: &Path) -> bool {
+    scheme(path).is_some()
+}
+
+/// Fails unless `path` is a file, or a URL with an allowed protocol.
+pub(crate) fn check_input(path: &Path) -> Result<(), Error> {
+    match scheme(path) {
+        Some(scheme) if NetworkOptions::current().protocols.contains(&scheme) => Ok(()),
+        Some(scheme) => Err(Error::invalid(format!(
+            "`{scheme}` URLs are not allowed as input: {}",
+            path.display()
+        ))),
+        None if path.is_file() => Ok(()),
+        None => Err(Error::FileNotFound(path.to_path_buf())),
+    }
+}
+
+/// `path` as an ffmpeg input, with the network options when it is a URL.
+pub(crate) fn input(path: &Path) -> Input {
+    let input = Input::new(path);
+    if is_url(path) {
+        input.args(NetworkOptions::current().input_args())
+    } else {
+        input
+    }
+}
@@ -0,0 +1,165 @@
+use std::path::Path;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, ProcessResult};
+use crate::json::Json;
+use crate::probe::probe;
+use crate::transcode::TranscodeOptions;
+
+#[derive(Debug, Clone)]
+pub struct NormalizeAudioOptions {
+    /// Integrated loudness target in LUFS.
+    pub integrated: f64,
+    /// Maximum true peak in dBTP.
+    pub true_peak: f64,
+    /// Loudness range target in LU.
+    pub range: f64,
+    /// The video is copied unless a video codec is set.
+    pub transcode: TranscodeOptions,
+}
+
+impl Default for NormalizeAudioOptions {
+    /// The EBU R128 broadcast targets.
+    fn default() -> Self {
+        Self {
+            integrated: -23.0,
+            true_peak: -1.0,
+            range: 7.0,
+            transcode: TranscodeOptions::default(),
+        }
+    }
+}
+
+impl NormalizeAudioOptions {
+    fn targets(&self) -> String {
+        format!(
+            "loudnorm=I={}:TP={}:LRA={}",
+            self.integrated, self.true_peak, self.range
+        )
+    }
+}
+
+/// What the first loudnorm pass measured.
+#[derive(Debug, Clone, Copy)]
+pub struct Loudness {
+    pub integrated: f64,
+    pub true_peak: f64,
+    pub range: f64,
+    pub threshold: f64,
+    pub offset: f64,
+}
+
+/// The measuring pass of loudnorm over the whole input, which prints its
+/// stats to stderr.
+pub fn measure_loudness_command(
+    input: &Path,
+    options: &NormalizeAudioOptions,
+) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+
+    Ok(FfmpegCommand::new()
+        .input(input)
+        .map("0:a:0")
+        .audio_filter(format!("{}:print_format=json", options.targets()))
+        .format("null")
+        .output("-"))
+}
+
+/// Runs the measuring pass of loudnorm over the whole input.
+pub fn measure_loudness(input: &Path, options: &NormalizeAudioOptions) -> Result<Loudness, Error> {
+    let result = measure_loudness_command(input, options)?.run()?.check()?;
+
+    // The stats are the last JSON object loudnorm prints to stderr.
+    let start = result.stderr.rfind('{').ok_or(Error::ParseError)?;
+    let end = result.stderr[start..].find('}').ok_or(Error::ParseError)?;
+    let json = Json::parse(&result.stderr[start..=start + end])?;
+    let value = |key| {
+        json.get(key)
+            .and_then(Json::as_f64)
+            .ok_or(Error::ParseError)
+    };
+
+    Ok(Loudness {
+        integrated: value("input_i")?,
+        true_peak: value("input_tp")?,
+        range: value("input_lra")?,
+        threshold: value("input_thresh")?,
+        offset: value("target_offset")?,
+    })
+}
+
+pub fn normalize_audio_command(
+    input: &Path,
+    output: &Path,
+    options: &NormalizeAudioOptions,
+) -> Result<FfmpegCommand, Error> {
+    let measured = measure_loudness(input, options)?;
+    if !measured.integrated.is_finite() {
+        return Err(Error::invalid(format!("{} is silent", input.display())));
+    }
+    let measured = format!(
+        "measured_I={}:measured_TP={}:measured_LRA={}:measured_thresh={}:offset={}",
+        measured.integrated,
+        measured.true_peak,
+        measured.range,
+        measured.threshold,
+        measured.offset
+    );
+    second_pass(input, output, options, &measured)
+}
+
+/// For a dry run: the measuring pass, and the second pass with `MEASURED_*`
+/// where the values it measures go, as nothing is run to measure them.
+pub fn normalize_audio_preview(
+    input: &Path,
+    output: &Path,
+    options: &NormalizeAudioOptions,
+) -> Result<[FfmpegCommand; 2], Error> {
+    let measured = "measured_I=MEASURED_I:measured_TP=MEASURED_TP:measured_LRA=MEASURED_LRA:\
+                    measured_thresh=MEASURED_THRESH:offset=MEASURED_OFFSET";
+    Ok([
+        measure_loudness_command(input, options)?,
+        second_pass(input, output, options, measured)?,
+    ])
+}
+
+/// The loudnorm pass that applies `measured`, the stats of the first one.
+fn second_pass(
+    input: &Path,
+    output: &Path,
+    options: &NormalizeAudioOptions,
+    measured: &str,
+) -> Result<FfmpegCommand, Error> {
+    let info = probe(input).ok();
+    // loudnorm resamples to 192 kHz internally, so ask for the input rate back.
+    let sample_rate = info
+        .as_ref()
+        .and_then(|info| info.audio())
+        .and_then(|stream| stream.sample_rate)
+        .unwrap_or(48000);
+
+    let mut command = FfmpegCommand::new()
+        .input(input)
+        .audio_filter(format!("{}:{measured}:linear=true", options.targets()))
+        .args(["-ar", &sample_rate.to_string()]);
+    if options.transcode.video_encoder().is_none() {
+        command = command.video_codec("copy");
+    }
+    if let Some(duration) = info.and_then(|info| info.duration) {
+        command = command.duration(duration);
+    }
+
+    Ok(options.transcode.apply(command).output(output))
+}
+
+pub fn normalize_audio(
+    input: &Path,
+    output: &Path,
+    options: &NormalizeAudioOptions,
+) -> Result<ProcessResult, Error> {
+    normalize_audio_command(input, output, options)?
+        .run()?
+        .check()
+}
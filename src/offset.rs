@@ -0,0 +1,52 @@
+use std::path::Path;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, Input, ProcessResult};
+use crate::probe::probe;
+use crate::transcode::TranscodeOptions;
+
+#[derive(Debug, Clone, Default)]
+pub struct OffsetOptions {
+    /// Milliseconds to delay the audio by, negative to make it earlier.
+    pub audio_delay_ms: i64,
+    /// The streams are copied unless codecs are set here.
+    pub transcode: TranscodeOptions,
+}
+
+/// Shifts the audio of `input` against its video. The file is read twice,
+/// once for the video and once, offset, for the audio.
+pub fn offset_command(
+    input: &Path,
+    output: &Path,
+    options: &OffsetOptions,
+) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+
+    let delay = format!("{:.3}", options.audio_delay_ms as f64 / 1000.0);
+    let mut command = FfmpegCommand::new()
+        .input(input)
+        .add_input(Input::new(input).args(["-itsoffset", &delay]))
+        .map("0:v")
+        .map("1:a");
+    if let Some(duration) = probe(input).ok().and_then(|info| info.duration) {
+        command = command.duration(duration);
+    }
+    if options.transcode.video_encoder().is_none() {
+        command = command.video_codec("copy");
+    }
+    if options.transcode.audio_codec.is_none() && options.transcode.audio_bitrate.is_none() {
+        command = command.audio_codec("copy");
+    }
+
+    Ok(options.transcode.apply(command).output(output))
+}
+
+pub fn offset(
+    input: &Path,
+    output: &Path,
+    options: &OffsetOptions,
+) -> Result<ProcessResult, Error> {
+    offset_command(input, output, options)?.run()?.check()
+}
@@ -0,0 +1,61 @@
+use std::path::Path;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, ProcessResult};
+use crate::probe::probe;
+
+#[derive(Debug, Clone, Default)]
+pub struct WebOptimizeOptions {
+    /// Write a fragmented MP4 instead, which needs no index at all and can
+    /// be played while it is still being written, at some cost in players
+    /// that seek poorly in it.
+    pub fragmented: bool,
+}
+
+/// Remuxes an MP4 or MOV file with its index at the front, without
+/// re-encoding, so playback can start before the whole file is downloaded.
+pub fn optimize_web_command(
+    input: &Path,
+    output: &Path,
+    options: &WebOptimizeOptions,
+) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+    let mp4 = output
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            ["mp4", "m4v", "m4a", "mov"]
+                .iter()
+                .any(|mp4| ext.eq_ignore_ascii_case(mp4))
+        });
+    if !mp4 {
+        return Err(Error::invalid(
+            "web optimization only applies to MP4 and MOV outputs",
+        ));
+    }
+
+    let flags = if options.fragmented {
+        "+frag_keyframe+empty_moov+default_base_moof"
+    } else {
+        "+faststart"
+    };
+    let mut command = FfmpegCommand::new()
+        .input(input)
+        .map("0")
+        .codec_copy()
+        .args(["-movflags", flags]);
+    if let Some(duration) = probe(input).ok().and_then(|info| info.duration) {
+        command = command.duration(duration);
+    }
+    Ok(command.output(output))
+}
+
+pub fn optimize_web(
+    input: &Path,
+    output: &Path,
+    options: &WebOptimizeOptions,
+) -> Result<ProcessResult, Error> {
+    optimize_web_command(input, output, options)?.run()?.check()
+}
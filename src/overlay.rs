@@ -0,0 +1,306 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, ProcessResult};
+use crate::pipeline::{Operation, Stage};
+use crate::probe::probe;
+use crate::time::format_timestamp;
+use crate::visualize::check_color;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Position {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+    Center,
+}
+
+impl Position {
+    /// The `x:y` arguments of the overlay filter, where `W`/`H` is the main
+    /// video size and `w`/`h` the overlay size.
+    pub fn overlay_expr(&self, margin: u32) -> String {
+        match self {
+            Position::TopLeft => format!("{margin}:{margin}"),
+            Position::TopRight => format!("W-w-{margin}:{margin}"),
+            Position::BottomLeft => format!("{margin}:H-h-{margin}"),
+            Position::BottomRight => format!("W-w-{margin}:H-h-{margin}"),
+            Position::Center => "(W-w)/2:(H-h)/2".to_string(),
+        }
+    }
+
+    /// The `x` and `y` options of drawtext, which names the text size
+    /// `tw`/`th`.
+    pub fn drawtext_expr(&self, margin: u32) -> String {
+        match self {
+            Position::TopLeft => format!("x={margin}:y={margin}"),
+            Position::TopRight => format!("x=w-tw-{margin}:y={margin}"),
+            Position::BottomLeft => format!("x={margin}:y=h-th-{margin}"),
+            Position::BottomRight => format!("x=w-tw-{margin}:y=h-th-{margin}"),
+            Position::Center => "x=(w-tw)/2:y=(h-th)/2".to_string(),
+        }
+    }
+}
+
+impl FromStr for Position {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "top-left" => Ok(Position::TopLeft),
+            "top-right" => Ok(Position::TopRight),
+            "bottom-left" => Ok(Position::BottomLeft),
+            "bottom-right" => Ok(Position::BottomRight),
+            "center" => Ok(Position::Center),
+            _ => Err(Error::invalid(format!("unknown position `{s}`"))),
+        }
+    }
+}
+
+/// The `enable` expression limiting a filter to a time range.
+pub(crate) fn enable_expr(start: Option<Duration>, end: Option<Duration>) -> Option<String> {
+    match (start, end) {
+        (Some(s), Some(e)) => Some(format!(
+            "between(t,{},{})",
+            format_timestamp(s),
+            format_timestamp(e)
+        )),
+        (Some(s), None) => Some(format!("gte(t,{})", format_timestamp(s))),
+        (None, Some(e)) => Some(format!("lte(t,{})", format_timestamp(e))),
+        (None, None) => None,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OverlayOptions {
+    pub position: Position,
+    /// Distance from the edges in pixels.
+    pub margin: u32,
+    /// 0.0 (invisible) to 1.0 (opaque).
+    pub opacity: f64,
+    pub start: Option<Duration>,
+    pub end: Option<Duration>,
+}
+
+impl Default for OverlayOptions {
+    fn default() -> Self {
+        Self {
+            position: Position::default(),
+            margin: 10,
+            opacity: 1.0,
+            start: None,
+            end: None,
+        }
+    }
+}
+
+impl OverlayOptions {
+    fn check(&self) -> Result<(), Error> {
+        if !(0.0..=1.0).contains(&self.opacity) {
+            return Err(Error::invalid("the opacity must be between 0 and 1"));
+        }
+        if let (Some(start), Some(end)) = (self.start, self.end)
+            && end <= start
+        {
+            return Err(Error::InvalidTimeRange);
+        }
+        Ok(())
+    }
+
+    /// The filters fading the image read from `image` and laying it over
+    /// the video.
+    fn filters(&self, image: &str) -> (String, String) {
+        let mut overlay = format!("overlay={}", self.position.overlay_expr(self.margin));
+        if let Some(enable) = enable_expr(self.start, self.end) {
+            overlay.push_str(&format!(":enable='{enable}'"));
+        }
+        let image = format!("[{image}]format=rgba,colorchannelmixer=aa={}", self.opacity);
+        (image, overlay)
+    }
+}
+
+/// [`overlay_command`] as a [`Pipeline`](crate::Pipeline) step.
+#[derive(Debug, Clone)]
+pub struct Watermark {
+    pub image: PathBuf,
+    pub options: OverlayOptions,
+}
+
+impl Operation for Watermark {
+    fn name(&self) -> &'static str {
+        "watermark"
+    }
+
+    fn inputs(&self) -> Vec<&Path> {
+        vec![&self.image]
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        self.options.check()
+    }
+
+    fn build_args(&self, input: &Path, output: &Path) -> Result<FfmpegCommand, Error> {
+        overlay_command(input, &self.image, output, &self.options)
+    }
+
+    fn fuse(&self, stage: &mut Stage) -> Result<bool, Error> {
+        self.options.check()?;
+        let index = stage.input(&self.image);
+        let (mark, overlay) = self.options.filters(&format!("{index}:v"));
+        let mark = stage.chain(mark);
+        stage.video_with(&[mark], overlay);
+        Ok(true)
+    }
+}
+
+pub fn overlay_command(
+    input: &Path,
+    image: &Path,
+    output: &Path,
+    options: &OverlayOptions,
+) -> Result<FfmpegCommand, Error> {
+    for path in [input, image] {
+        if !path.is_file() {
+            return Err(Error::FileNotFound(path.to_path_buf()));
+        }
+    }
+    options.check()?;
+
+    let (mark, overlay) = options.filters("1:v");
+    let mut command = FfmpegCommand::new()
+        .input(input)
+        .input(image)
+        .filter_complex(format!("{mark}[wm];[0:v][wm]{overlay}[v]"))
+        .map("[v]")
+        .map("0:a?")
+        .audio_codec("copy");
+    if let Some(duration) = probe(input).ok().and_then(|info| info.duration) {
+        command = command.duration(duration);
+    }
+
+    Ok(command.output(output))
+}
+
+pub fn overlay(
+    input: &Path,
+    image: &Path,
+    output: &Path,
+    options: &OverlayOptions,
+) -> Result<ProcessResult, Error> {
+    overlay_command(input, image, output, options)?
+        .run()?
+        .check()
+}
+
+#[derive(Debug, Clone)]
+pub struct PipOptions {
+    pub position: Position,
+    /// Distance from the edges in pixels.
+    pub margin: u32,
+    /// The width of the inset as a fraction of the main video's, which
+    /// keeps its aspect ratio.
+    pub scale: f64,
+    /// The width of a frame around the inset in pixels, 0 for none.
+    pub border: u32,
+    pub border_color: String,
+    /// The inset starts playing from its beginning here.
+    pub start: Option<Duration>,
+    pub end: Option<Duration>,
+}
+
+impl Default for PipOptions {
+    fn default() -> Self {
+        Self {
+            position: Position::default(),
+            margin: 20,
+            scale: 0.25,
+            border: 0,
+            border_color: "white".to_string(),
+            start: None,
+            end: None,
+        }
+    }
+}
+
+/// Overlays `inset` scaled down into a corner of `input`, keeping the audio
+/// of `input`.
+pub fn pip_command(
+    input: &Path,
+    inset: &Path,
+    output: &Path,
+    options: &PipOptions,
+) -> Result<FfmpegCommand, Error> {
+    for path in [input, inset] {
+        if !path.is_file() {
+            return Err(Error::FileNotFound(path.to_path_buf()));
+        }
+    }
+    if !(options.scale > 0.0 && options.scale <= 1.0) {
+        return Err(Error::invalid("the inset scale must be between 0 and 1"));
+    }
+    if let (Some(start), Some(end)) = (options.start, options.end)
+        && end <= start
+    {
+        return Err(Error::InvalidTimeRange);
+    }
+    check_color(&options.border_color)?;
+
+    let info = probe(input)?;
+    let (width, _) = info
+        .video()
+        .and_then(|video| video.resolution())
+        .ok_or(Error::StreamNotFound)?;
+    // yuv420p needs even dimensions.
+    let inset_width = ((width as f64 * options.scale) as u32 & !1).max(2);
+
+    let mut inset_chain = format!("[1:v]scale={inset_width}:-2");
+    if let Some(start) = options.start {
+        inset_chain.push_str(&format!(
+            ",setpts=PTS-STARTPTS+{}/TB",
+            format_timestamp(start)
+        ));
+    }
+    if options.border > 0 {
+        let border = options.border;
+        inset_chain.push_str(&format!(
+            ",pad=iw+{}:ih+{}:{border}:{border}:color={}",
+            border * 2,
+            border * 2,
+            options.border_color
+        ));
+    }
+
+    // Once the inset ends the main video carries on alone.
+    let mut overlay = format!(
+        "overlay={}:eof_action=pass",
+        options.position.overlay_expr(options.margin)
+    );
+    if let Some(enable) = enable_expr(options.start, options.end) {
+        overlay.push_str(&format!(":enable='{enable}'"));
+    }
+
+    let mut command = FfmpegCommand::new()
+        .input(input)
+        .input(inset)
+        .filter_complex(format!("{inset_chain}[pip];[0:v][pip]{overlay}[v]"))
+        .map("[v]")
+        .map("0:a?")
+        .audio_codec("copy");
+    if let Some(duration) = info.duration {
+        command = command.duration(duration);
+    }
+
+    Ok(command.output(output))
+}
+
+pub fn pip(
+    input: &Path,
+    inset: &Path,
+    output: &Path,
+    options: &PipOptions,
+) -> Result<ProcessResult, Error> {
+    pip_command(input, inset, output, options)?.run()?.check()
+}
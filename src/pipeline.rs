@@ -0,0 +1,334 @@
+//! Operations chained one after the other, e.g. trim → scale → watermark →
+//! encode. Steps that only add filters share one ffmpeg run, the others run
+//! on their own with the files in between kept in a temp directory.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, Input, ProcessResult};
+use crate::hwaccel::HwAccel;
+use crate::probe::probe;
+use crate::temp::TempFile;
+use crate::transcode::TranscodeOptions;
+
+/// One step of a [`Pipeline`], reading a video and writing another.
+pub trait Operation: fmt::Debug + Send + Sync {
+    /// A short name for messages, e.g. `scale`.
+    fn name(&self) -> &'static str;
+
+    /// The files the step reads besides its input, e.g. a watermark image.
+    fn inputs(&self) -> Vec<&Path> {
+        Vec::new()
+    }
+
+    /// The encoding settings, for steps that encode.
+    fn options(&self) -> Option<&TranscodeOptions> {
+        None
+    }
+
+    /// Checks the options, without looking at any file.
+    fn validate(&self) -> Result<(), Error>;
+
+    /// The command running the step on its own.
+    fn build_args(&self, input: &Path, output: &Path) -> Result<FfmpegCommand, Error>;
+
+    /// Adds the step to a run shared with the steps around it, or returns
+    /// `false`, leaving the stage as it is, when it needs a run of its own.
+    fn fuse(&self, _stage: &mut Stage) -> Result<bool, Error> {
+        Ok(false)
+    }
+}
+
+/// The filters of one ffmpeg run shared by several steps. Filters go on the
+/// end of the video and audio as the steps left them, so each step sees the
+/// output of the one before.
+#[derive(Debug)]
+pub struct Stage {
+    input: PathBuf,
+    input_args: Vec<String>,
+    /// Extra inputs, numbered from 1.
+    inputs: Vec<PathBuf>,
+    /// Finished chains of the `-filter_complex` graph.
+    chains: Vec<String>,
+    /// The labels the pending video filters read.
+    video: Vec<String>,
+    video_filters: Vec<String>,
+    audio: Option<String>,
+    audio_filters: Vec<String>,
+    duration: Option<Duration>,
+    options: Option<TranscodeOptions>,
+    labels: usize,
+    steps: usize,
+}
+
+impl Stage {
+    fn new(input: &Path) -> Self {
+        // The input a run reads may not be written yet, e.g. for a dry run.
+        let info = probe(input).ok();
+        let has_audio = info.as_ref().is_none_or(|info| info.audio().is_some());
+        Stage {
+            input: input.to_path_buf(),
+            input_args: Vec::new(),
+            inputs: Vec::new(),
+            chains: Vec::new(),
+            video: vec!["0:v".to_string()],
+            video_filters: Vec::new(),
+            audio: has_audio.then(|| "0:a".to_string()),
+            audio_filters: Vec::new(),
+            duration: info.and_then(|info| info.duration),
+            options: None,
+            labels: 0,
+            steps: 0,
+        }
+    }
+
+    /// Whether no step has been added yet.
+    pub fn is_empty(&self) -> bool {
+        self.steps == 0
+    }
+
+    /// Adds options for the main input, such as `-ss`, which only the first
+    /// step of a run may do.
+    pub fn input_args<I, S>(&mut self, args: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        if !self.is_empty() {
+            return Err(Error::invalid(
+                "only the first step of a run can set input options",
+            ));
+        }
+        self.input_args.extend(args.into_iter().map(Into::into));
+        Ok(())
+    }
+
+    /// Adds another input and returns its index, for labels such as `1:v`.
+    pub fn input(&mut self, path: &Path) -> usize {
+        self.inputs.push(path.to_path_buf());
+        self.inputs.len()
+    }
+
+    /// Adds a chain of its own, e.g. `[1:v]format=rgba` preparing an extra
+    /// input, and returns the label of its output.
+    pub fn chain(&mut self, chain: impl Into<String>) -> String {
+        let label = self.label("p");
+        self.chains.push(format!("{}[{label}]", chain.into()));
+        label
+    }
+
+    /// Filters the video, e.g. with `scale=1280:-2`.
+    pub fn video(&mut self, filter: impl Into<String>) {
+        self.video_filters.push(filter.into());
+    }
+
+    /// Filters the video together with the outputs of other chains, e.g.
+    /// with `overlay`, which reads the video first and then `others`.
+    pub fn video_with(&mut self, others: &[String], filter: impl Into<String>) {
+        let video = self.finish_video();
+        self.video = std::iter::once(video)
+            .chain(others.iter().cloned())
+            .collect();
+        self.video_filters.push(filter.into());
+    }
+
+    /// Filters the audio, e.g. with `atempo=2`. Does nothing when there is
+    /// no audio.
+    pub fn audio(&mut self, filter: impl Into<String>) {
+        if self.audio.is_some() {
+            self.audio_filters.push(filter.into());
+        }
+    }
+
+    /// How long the output is so far, if known.
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    pub fn set_duration(&mut self, duration: Option<Duration>) {
+        self.duration = duration;
+    }
+
+    fn label(&mut self, prefix: &str) -> String {
+        self.labels += 1;
+        format!("{prefix}{}", self.labels)
+    }
+
+    /// Ends the pending video filters in a chain of their own and returns
+    /// the label of the result.
+    fn finish_video(&mut self) -> String {
+        if self.video_filters.is_empty() && self.video.len() == 1 {
+            return self.video[0].clone();
+        }
+        let label = self.label("v");
+        self.chains.push(format!(
+            "{}{}[{label}]",
+            pads(&self.video),
+            self.video_filters.join(",")
+        ));
+        self.video = vec![label.clone()];
+        self.video_filters.clear();
+        label
+    }
+
+    fn command(&mut self, output: &Path) -> Result<FfmpegCommand, Error> {
+        let options = self.options.take().unwrap_or_default();
+        if let Some(filter) = options.hwaccel.and_then(HwAccel::upload_filter) {
+            self.video(filter);
+        }
+
+        let mut command =
+            FfmpegCommand::new().add_input(Input::new(&self.input).args(&self.input_args));
+        for input in &self.inputs {
+            command = command.input(input);
+        }
+        if self.chains.is_empty() {
+            if !self.video_filters.is_empty() {
+                command = command.video_filter(self.video_filters.join(","));
+            }
+            if !self.audio_filters.is_empty() {
+                command = command.audio_filter(self.audio_filters.join(","));
+            }
+        } else {
+            let video = self.finish_video();
+            let mut graph = self.chains.join(";");
+            let audio = match &self.audio {
+                Some(audio) if !self.audio_filters.is_empty() => {
+                    graph.push_str(&format!(";[{audio}]{}[a]", self.audio_filters.join(",")));
+                    "[a]".to_string()
+                }
+                _ => "0:a?".to_string(),
+            };
+            command = command
+                .filter_complex(graph)
+                .map(&format!("[{video}]"))
+                .map(&audio);
+        }
+        if self.audio_filters.is_empty()
+            && options.audio_codec.is_none()
+            && options.audio_bitrate.is_none()
+        {
+            command = command.audio_codec("copy");
+        }
+        if let Some(duration) = self.duration {
+            command = command.duration(duration);
+        }
+
+        Ok(options.apply_codecs(command).output(output))
+    }
+}
+
+fn pads(labels: &[String]) -> String {
+    labels.iter().map(|label| format!("[{label}]")).collect()
+}
+
+/// Steps run in order on one input. A run of steps that can
+/// [fuse](Operation::fuse) is one ffmpeg process, encoding with the settings
+/// of the last of them that has any. Such runs copy the audio they leave
+/// alone unless those settings name an audio codec or bitrate.
+#[derive(Debug, Default)]
+pub struct Pipeline {
+    steps: Vec<Box<dyn Operation>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a step after the others.
+    pub fn then(mut self, step: impl Operation + 'static) -> Self {
+        self.steps.push(Box::new(step));
+        self
+    }
+
+    pub fn steps(&self) -> &[Box<dyn Operation>] {
+        &self.steps
+    }
+
+    /// Checks every step, naming the one whose options are wrong.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.steps.is_empty() {
+            return Err(Error::invalid("the pipeline has no steps"));
+        }
+        for (i, step) in self.steps.iter().enumerate() {
+            step.validate().map_err(|e| match e {
+                Error::InvalidArgument(message) => {
+                    Error::invalid(format!("step {} ({}): {message}", i + 1, step.name()))
+                }
+                e => e,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Runs the steps and returns the result of the last ffmpeg run.
+    pub fn run(&self, input: &Path, output: &Path) -> Result<ProcessResult, Error> {
+        let mut result = None;
+        self.run_with(input, output, |command| {
+            result = Some(command.run()?.check()?);
+            Ok(())
+        })?;
+        Ok(result.expect("at least one run"))
+    }
+
+    /// Like [`run`](Self::run), handing each command to `execute` instead.
+    /// Commands are built only once the ones before have been executed, as
+    /// a step may need to look at the file it reads.
+    pub fn run_with<F>(&self, input: &Path, output: &Path, mut execute: F) -> Result<(), Error>
+    where
+        F: FnMut(&FfmpegCommand) -> Result<(), Error>,
+    {
+        self.validate()?;
+        if !input.is_file() {
+            return Err(Error::FileNotFound(input.to_path_buf()));
+        }
+        for step in &self.steps {
+            for path in step.inputs() {
+                if !path.is_file() {
+                    return Err(Error::FileNotFound(path.to_path_buf()));
+                }
+            }
+        }
+
+        let dir = TempFile::create_dir("media_tools-pipeline")?;
+        let mut written = 0;
+        let mut intermediate = || {
+            written += 1;
+            dir.path().join(format!("step-{written:02}.mkv"))
+        };
+
+        let mut current = input.to_path_buf();
+        let mut stage = Stage::new(&current);
+        for (i, step) in self.steps.iter().enumerate() {
+            let last = i + 1 == self.steps.len();
+            if step.fuse(&mut stage)? {
+                stage.steps += 1;
+                if let Some(options) = step.options() {
+                    stage.options = Some(options.clone());
+                }
+                if last {
+                    execute(&stage.command(output)?)?;
+                }
+                continue;
+            }
+
+            if !stage.is_empty() {
+                let target = intermediate();
+                execute(&stage.command(&target)?)?;
+                current = target;
+            }
+            let target = if last {
+                output.to_path_buf()
+            } else {
+                intermediate()
+            };
+            execute(&step.build_args(&current, &target)?)?;
+            current = target;
+            stage = Stage::new(&current);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+use std::sync::Mutex;
+use std::thread;
+
+use crate::Error;
+use crate::log::debug;
+
+/// A job that failed in [`JobPool::try_run`], with its position in the input.
+#[derive(Debug)]
+pub struct JobFailure {
+    pub index: usize,
+    pub error: Error,
+}
+
+/// Runs jobs on a bounded number of threads, e.g. to keep a fixed number of
+/// ffmpeg processes busy.
+#[derive(Debug, Clone, Copy)]
+pub struct JobPool {
+    jobs: usize,
+}
+
+impl Default for JobPool {
+    /// One job per available CPU.
+    fn default() -> Self {
+        Self::new(thread::available_parallelism().map_or(1, |n| n.get()))
+    }
+}
+
+impl JobPool {
+    pub fn new(jobs: usize) -> Self {
+        Self { jobs: jobs.max(1) }
+    }
+
+    pub fn jobs(&self) -> usize {
+        self.jobs
+    }
+
+    /// Runs `job` for every item, returning the results in input order.
+    pub fn run<T, R, F>(&self, items: Vec<T>, job: F) -> Vec<R>
+    where
+        T: Send,
+        R: Send,
+        F: Fn(T) -> R + Sync,
+    {
+        let count = items.len();
+        debug!("running {count} jobs on up to {} threads", self.jobs);
+        let queue = Mutex::new(items.into_iter().enumerate());
+        let results = Mutex::new(Vec::with_capacity(count));
+
+        thread::scope(|scope| {
+            for _ in 0..self.jobs.min(count) {
+                scope.spawn(|| {
+                    loop {
+                        let Some((index, item)) = queue.lock().unwrap().next() else {
+                            break;
+                        };
+                        let result = job(item);
+                        results.lock().unwrap().push((index, result));
+                    }
+                });
+            }
+        });
+
+        let mut results = results.into_inner().unwrap();
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    /// Runs every job to completion and returns all results, or every
+    /// failure when at least one job failed.
+    pub fn try_run<T, R, F>(&self, items: Vec<T>, job: F) -> Result<Vec<R>, Vec<JobFailure>>
+    where
+        T: Send,
+        R: Send,
+        F: Fn(T) -> Result<R, Error> + Sync,
+    {
+        let mut values = Vec::new();
+        let mut failures = Vec::new();
+        for (index, result) in self.run(items, job).into_iter().enumerate() {
+            match result {
+                Ok(value) => values.push(value),
+                Err(error) => failures.push(JobFailure { index, error }),
+            }
+        }
+
+        if failures.is_empty() {
+            Ok(values)
+        } else {
+            Err(failures)
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use crate::transcode::TranscodeOptions;
+
+/// The names [`encoding_preset`] knows.
+pub const ENCODING_PRESETS: [&str; 4] = ["web-1080p", "youtube", "instagram", "archival"];
+
+/// Codec, quality, pixel format and audio settings for a common target.
+/// These don't scale the video, the input should already have the size the
+/// target expects.
+pub fn encoding_preset(name: &str) -> Option<TranscodeOptions> {
+    let h264 = |crf: Option<u8>, bitrate: Option<&str>, audio: &str| TranscodeOptions {
+        video_codec: Some("libx264".to_string()),
+        audio_codec: Some("aac".to_string()),
+        crf,
+        preset: Some("medium".to_string()),
+        video_bitrate: bitrate.map(str::to_string),
+        audio_bitrate: Some(audio.to_string()),
+        pixel_format: Some("yuv420p".to_string()),
+        faststart: true,
+        ..Default::default()
+    };
+    let options = match name {
+        // Streams well on most connections and plays everywhere.
+        "web-1080p" => h264(None, Some("5M"), "128k"),
+        // YouTube re-encodes everything, so it gets as much quality as is
+        // practical to upload.
+        "youtube" => TranscodeOptions {
+            preset: Some("slow".to_string()),
+            ..h264(Some(18), None, "384k")
+        },
+        "instagram" => h264(None, Some("3500k"), "128k"),
+        // Visually lossless HEVC with lossless audio, best kept in Matroska.
+        "archival" => TranscodeOptions {
+            video_codec: Some("libx265".to_string()),
+            audio_codec: Some("flac".to_string()),
+            crf: Some(18),
+            preset: Some("slow".to_string()),
+            pixel_format: Some("yuv420p10le".to_string()),
+            ..Default::default()
+        },
+        _ => return None,
+    };
+    Some(options)
+}
@@ -0,0 +1,314 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use crate::Error;
+use crate::json::Json;
+use crate::log::{debug, warning};
+use crate::network::{NetworkOptions, check_input, is_url};
+use crate::retry::RetryPolicy;
+use crate::tool::Tool;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamKind {
+    Video,
+    Audio,
+    Subtitle,
+    Data,
+    Attachment,
+    Unknown,
+}
+
+impl StreamKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            StreamKind::Video => "video",
+            StreamKind::Audio => "audio",
+            StreamKind::Subtitle => "subtitle",
+            StreamKind::Data => "data",
+            StreamKind::Attachment => "attachment",
+            StreamKind::Unknown => "unknown",
+        }
+    }
+
+    fn from_codec_type(codec_type: &str) -> Self {
+        match codec_type {
+            "video" => StreamKind::Video,
+            "audio" => StreamKind::Audio,
+            "subtitle" => StreamKind::Subtitle,
+            "data" => StreamKind::Data,
+            "attachment" => StreamKind::Attachment,
+            _ => StreamKind::Unknown,
+        }
+    }
+}
+
+/// A fraction such as a frame rate (`30000/1001`) or a time base (`1/90000`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rational {
+    pub num: u64,
+    pub den: u64,
+}
+
+impl Rational {
+    pub fn parse(value: &str) -> Option<Self> {
+        let (num, den) = value.split_once('/')?;
+        let rational = Rational {
+            num: num.trim().parse().ok()?,
+            den: den.trim().parse().ok()?,
+        };
+        (rational.den != 0).then_some(rational)
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.num, self.den)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Stream {
+    pub index: u32,
+    pub kind: StreamKind,
+    pub codec: String,
+    pub profile: Option<String>,
+    pub time_base: Option<Rational>,
+    pub duration: Option<Duration>,
+    pub bit_rate: Option<u64>,
+    pub language: Option<String>,
+
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub pixel_format: Option<String>,
+    pub frame_rate: Option<Rational>,
+    /// `progressive`, or which field comes first in interlaced video, e.g.
+    /// `tt` for top field first.
+    pub field_order: Option<String>,
+    /// The transfer characteristics, e.g. `bt709`, `smpte2084` for HDR10 or
+    /// `arib-std-b67` for HLG.
+    pub color_transfer: Option<String>,
+    pub color_primaries: Option<String>,
+
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    pub channel_layout: Option<String>,
+}
+
+impl Stream {
+    pub fn to_json(&self) -> Json {
+        let rational = |r: Option<Rational>| r.map(|r| r.to_string());
+        Json::object([
+            ("index", Json::from(self.index)),
+            ("kind", self.kind.name().into()),
+            ("codec", self.codec.as_str().into()),
+            ("profile", self.profile.clone().into()),
+            ("time_base", rational(self.time_base).into()),
+            ("duration", self.duration.into()),
+            ("bit_rate", self.bit_rate.into()),
+            ("language", self.language.clone().into()),
+            ("width", self.width.into()),
+            ("height", self.height.into()),
+            ("pixel_format", self.pixel_format.clone().into()),
+            ("frame_rate", rational(self.frame_rate).into()),
+            ("field_order", self.field_order.clone().into()),
+            ("color_transfer", self.color_transfer.clone().into()),
+            ("color_primaries", self.color_primaries.clone().into()),
+            ("sample_rate", self.sample_rate.into()),
+            ("channels", self.channels.into()),
+            ("channel_layout", self.channel_layout.clone().into()),
+        ])
+    }
+
+    fn from_json(value: &Json) -> Option<Self> {
+        let text = |key: &str| value.get(key).and_then(Json::as_str).map(str::to_string);
+        let number = |key: &str| value.get(key).and_then(Json::as_u64);
+        let rational = |key: &str| {
+            value
+                .get(key)
+                .and_then(Json::as_str)
+                .and_then(Rational::parse)
+                .filter(|r| r.num != 0)
+        };
+
+        Some(Stream {
+            index: number("index")? as u32,
+            kind: StreamKind::from_codec_type(value.get("codec_type")?.as_str()?),
+            codec: text("codec_name").unwrap_or_default(),
+            profile: text("profile"),
+            time_base: rational("time_base"),
+            duration: seconds(value.get("duration")),
+            bit_rate: number("bit_rate"),
+            language: value
+                .get("tags")
+                .and_then(|tags| tags.get("language"))
+                .and_then(Json::as_str)
+                .map(str::to_string),
+            width: number("width").map(|n| n as u32),
+            height: number("height").map(|n| n as u32),
+            pixel_format: text("pix_fmt"),
+            frame_rate: rational("avg_frame_rate").or_else(|| rational("r_frame_rate")),
+            field_order: text("field_order"),
+            color_transfer: text("color_transfer"),
+            color_primaries: text("color_primaries"),
+            sample_rate: number("sample_rate").map(|n| n as u32),
+            channels: number("channels").map(|n| n as u32),
+            channel_layout: text("channel_layout"),
+        })
+    }
+
+    pub fn resolution(&self) -> Option<(u32, u32)> {
+        Some((self.width?, self.height?))
+    }
+
+    /// Whether the container flags the video as interlaced. Unflagged
+    /// video counts as progressive.
+    pub fn is_interlaced(&self) -> bool {
+        matches!(self.field_order.as_deref(), Some("tt" | "bb" | "tb" | "bt"))
+    }
+
+    /// Whether the video uses an HDR transfer, PQ (HDR10) or HLG.
+    pub fn is_hdr(&self) -> bool {
+        matches!(
+            self.color_transfer.as_deref(),
+            Some("smpte2084" | "arib-std-b67")
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MediaInfo {
+    pub path: PathBuf,
+    /// The demuxer names, e.g. `mov,mp4,m4a,3gp,3g2,mj2`.
+    pub container: String,
+    pub duration: Option<Duration>,
+    pub bit_rate: Option<u64>,
+    pub size: Option<u64>,
+    /// The container tags, e.g. `title` or `creation_time`.
+    pub tags: Vec<(String, String)>,
+    pub streams: Vec<Stream>,
+}
+
+impl MediaInfo {
+    pub fn to_json(&self) -> Json {
+        Json::object([
+            ("path", Json::from(self.path.as_path())),
+            ("container", self.container.as_str().into()),
+            ("duration", self.duration.into()),
+            ("bit_rate", self.bit_rate.into()),
+            ("size", self.size.into()),
+            (
+                "tags",
+                Json::object(self.tags.iter().map(|(k, v)| (k.as_str(), v.as_str()))),
+            ),
+            (
+                "streams",
+                Json::Array(self.streams.iter().map(Stream::to_json).collect()),
+            ),
+        ])
+    }
+
+    pub fn parse(path: &Path, output: &str) -> Result<Self, Error> {
+        let json = Json::parse(output)?;
+        let format = json.get("format").ok_or(Error::ParseError)?;
+
+        Ok(MediaInfo {
+            path: path.to_path_buf(),
+            container: format
+                .get("format_name")
+                .and_then(Json::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            duration: seconds(format.get("duration")),
+            bit_rate: format.get("bit_rate").and_then(Json::as_u64),
+            size: format.get("size").and_then(Json::as_u64),
+            tags: match format.get("tags") {
+                Some(Json::Object(tags)) => tags
+                    .iter()
+                    .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                    .collect(),
+                _ => Vec::new(),
+            },
+            streams: json
+                .get("streams")
+                .and_then(Json::as_array)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(Stream::from_json)
+                .collect(),
+        })
+    }
+
+    pub fn video_streams(&self) -> impl Iterator<Item = &Stream> {
+        self.streams.iter().filter(|s| s.kind == StreamKind::Video)
+    }
+
+    pub fn audio_streams(&self) -> impl Iterator<Item = &Stream> {
+        self.streams.iter().filter(|s| s.kind == StreamKind::Audio)
+    }
+
+    /// The first video stream, which is what ffmpeg picks by default.
+    pub fn video(&self) -> Option<&Stream> {
+        self.video_streams().next()
+    }
+
+    pub fn audio(&self) -> Option<&Stream> {
+        self.audio_streams().next()
+    }
+}
+
+fn seconds(value: Option<&Json>) -> Option<Duration> {
+    value
+        .and_then(Json::as_f64)
+        .filter(|s| s.is_finite() && *s >= 0.0)
+        .map(Duration::from_secs_f64)
+}
+
+/// Probes `path` with the ffprobe of [`Tool::current`].
+pub fn probe(path: &Path) -> Result<MediaInfo, Error> {
+    Tool::current().probe(path)
+}
+
+pub(crate) fn probe_with(ffprobe: &Path, path: &Path) -> Result<MediaInfo, Error> {
+    check_input(path)?;
+    // A local file that can't be read now won't be readable in a second.
+    if is_url(path) {
+        RetryPolicy::current().run(|| probe_once(ffprobe, path))
+    } else {
+        probe_once(ffprobe, path)
+    }
+}
+
+fn probe_once(ffprobe: &Path, path: &Path) -> Result<MediaInfo, Error> {
+    debug!("probing {}", path.display());
+    let output = Command::new(ffprobe)
+        .args([
+            "-v",
+            "error",
+            "-show_format",
+            "-show_streams",
+            "-of",
+            "json",
+        ])
+        .args(if is_url(path) {
+            NetworkOptions::current().input_args()
+        } else {
+            Vec::new()
+        })
+        .arg(path)
+        .output()
+        .map_err(Error::CommandError)?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        warning!("ffprobe failed on {}: {}", path.display(), stderr.trim());
+        return Err(Error::ProbeError(stderr.trim().to_string()));
+    }
+
+    MediaInfo::parse(path, &String::from_utf8_lossy(&output.stdout))
+}
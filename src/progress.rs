@@ -0,0 +1,85 @@
+use std::time::{Duration, Instant};
+
+/// A progress update parsed from ffmpeg's `-progress` output.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Progress {
+    /// Position in the output timeline.
+    pub time: Duration,
+    pub frame: Option<u64>,
+    /// Frames encoded per second.
+    pub fps: Option<f64>,
+    /// Encoding speed as a multiple of realtime, from ffmpeg or else worked
+    /// out from `time` and `elapsed`.
+    pub speed: Option<f64>,
+    /// Known only when the expected output duration is known.
+    pub percent: Option<f64>,
+    /// How long ffmpeg has been running.
+    pub elapsed: Duration,
+    /// How much longer ffmpeg will take at its speed so far. Known only when
+    /// the expected output duration is known.
+    pub eta: Option<Duration>,
+    /// Set on the final update ffmpeg sends before exiting.
+    pub done: bool,
+}
+
+/// Accumulates `key=value` lines until ffmpeg closes a block with `progress=`.
+#[derive(Debug, Default)]
+pub(crate) struct ProgressParser {
+    current: Progress,
+    total: Option<Duration>,
+    started: Option<Instant>,
+}
+
+impl ProgressParser {
+    pub(crate) fn new(total: Option<Duration>) -> Self {
+        Self {
+            current: Progress::default(),
+            total: total.filter(|t| !t.is_zero()),
+            started: Some(Instant::now()),
+        }
+    }
+
+    pub(crate) fn feed(&mut self, line: &str) -> Option<Progress> {
+        let (key, value) = line.trim().split_once('=')?;
+        let value = value.trim();
+        match key {
+            "frame" => self.current.frame = value.parse().ok(),
+            "fps" => self.current.fps = value.parse().ok().filter(|fps: &f64| *fps > 0.0),
+            // Despite the name, `out_time_ms` is in microseconds as well.
+            "out_time_us" | "out_time_ms" => {
+                if let Ok(us) = value.parse::<u64>() {
+                    self.current.time = Duration::from_micros(us);
+                }
+            }
+            "speed" => self.current.speed = value.trim_end_matches('x').trim().parse().ok(),
+            "progress" => {
+                let mut progress = self.current.clone();
+                progress.done = value == "end";
+                progress.elapsed = self.started.map(|s| s.elapsed()).unwrap_or_default();
+                let elapsed = progress.elapsed.as_secs_f64();
+                if progress.speed.is_none_or(|speed| speed <= 0.0) && elapsed > 0.0 {
+                    progress.speed =
+                        Some(progress.time.as_secs_f64() / elapsed).filter(|speed| *speed > 0.0);
+                }
+                progress.percent = self.total.map(|total| {
+                    if progress.done {
+                        100.0
+                    } else {
+                        (progress.time.as_secs_f64() / total.as_secs_f64() * 100.0).min(100.0)
+                    }
+                });
+                progress.eta = match (self.total, progress.speed) {
+                    _ if progress.done => Some(Duration::ZERO),
+                    (Some(total), Some(speed)) => {
+                        let left = total.saturating_sub(progress.time).as_secs_f64();
+                        Duration::try_from_secs_f64(left / speed).ok()
+                    }
+                    _ => None,
+                };
+                return Some(progress);
+            }
+            _ => {}
+        }
+        None
+    }
+}
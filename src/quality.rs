@@ -0,0 +1,142 @@
+use std::path::Path;
+
+use crate::Error;
+use crate::ffmpeg::FfmpegCommand;
+use crate::json::Json;
+use crate::probe::probe;
+use crate::tool::Tool;
+
+#[derive(Debug, Clone)]
+pub struct QualityOptions {
+    pub psnr: bool,
+    pub ssim: bool,
+    /// Skipped when ffmpeg was built without libvmaf.
+    pub vmaf: bool,
+}
+
+impl Default for QualityOptions {
+    fn default() -> Self {
+        Self {
+            psnr: true,
+            ssim: true,
+            vmaf: true,
+        }
+    }
+}
+
+/// How closely an encode matches its reference, `None` for metrics that
+/// were not computed.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct QualityMetrics {
+    /// The average PSNR over all planes in dB, infinite for identical
+    /// videos.
+    pub psnr: Option<f64>,
+    /// The SSIM over all planes, 1.0 for identical videos.
+    pub ssim: Option<f64>,
+    /// The VMAF score, 0-100.
+    pub vmaf: Option<f64>,
+}
+
+impl QualityMetrics {
+    /// Reads the summaries the psnr, ssim and libvmaf filters print to
+    /// stderr when a [`quality_metrics_command`] finishes.
+    pub fn parse(stderr: &str) -> Self {
+        let mut metrics = QualityMetrics::default();
+        for line in stderr.lines() {
+            if line.contains("Parsed_psnr") {
+                metrics.psnr = value_after(line, "average:");
+            } else if line.contains("Parsed_ssim") {
+                metrics.ssim = value_after(line, "All:");
+            } else if line.contains("Parsed_libvmaf") && line.contains("VMAF score") {
+                metrics.vmaf = value_after(line, "VMAF score:");
+            }
+        }
+        metrics
+    }
+
+    pub fn to_json(&self) -> Json {
+        // JSON has no infinity, identical inputs report a PSNR of null.
+        let finite = |value: Option<f64>| value.filter(|v| v.is_finite());
+        Json::object([
+            ("psnr", finite(self.psnr)),
+            ("ssim", finite(self.ssim)),
+            ("vmaf", finite(self.vmaf)),
+        ])
+    }
+}
+
+fn value_after(line: &str, key: &str) -> Option<f64> {
+    let (_, rest) = line.split_once(key)?;
+    match rest.split_whitespace().next()? {
+        "inf" => Some(f64::INFINITY),
+        value => value.parse().ok(),
+    }
+}
+
+/// Compares the first video stream of `distorted` to that of `reference`
+/// frame by frame. `distorted` is scaled to the reference's size first.
+pub fn quality_metrics_command(
+    reference: &Path,
+    distorted: &Path,
+    options: &QualityOptions,
+) -> Result<FfmpegCommand, Error> {
+    let info = probe(reference)?;
+    probe(distorted)?;
+    let (width, height) = info
+        .video()
+        .and_then(|video| Some((video.width?, video.height?)))
+        .ok_or(Error::StreamNotFound)?;
+
+    let mut filters = Vec::new();
+    if options.psnr {
+        filters.push("psnr");
+    }
+    if options.ssim {
+        filters.push("ssim");
+    }
+    if options.vmaf && Tool::current().has_filter("libvmaf")? {
+        filters.push("libvmaf");
+    }
+    if filters.is_empty() {
+        return Err(Error::invalid("no quality metric to compute"));
+    }
+
+    // Every metric needs its own copy of both videos, starting at zero.
+    let n = filters.len();
+    let labels = |prefix: &str| (0..n).map(|i| format!("[{prefix}{i}]")).collect::<String>();
+    let mut graph = vec![
+        format!(
+            "[0:v:0]scale={width}:{height}:flags=bicubic,settb=AVTB,setpts=PTS-STARTPTS,split={n}{}",
+            labels("d")
+        ),
+        format!(
+            "[1:v:0]settb=AVTB,setpts=PTS-STARTPTS,split={n}{}",
+            labels("r")
+        ),
+    ];
+    for (i, filter) in filters.iter().enumerate() {
+        graph.push(format!("[d{i}][r{i}]{filter}"));
+    }
+
+    let mut command = FfmpegCommand::new()
+        .input(distorted)
+        .input(reference)
+        .filter_complex(graph.join(";"))
+        .format("null")
+        .output("-");
+    if let Some(duration) = info.duration {
+        command = command.duration(duration);
+    }
+    Ok(command)
+}
+
+pub fn quality_metrics(
+    reference: &Path,
+    distorted: &Path,
+    options: &QualityOptions,
+) -> Result<QualityMetrics, Error> {
+    let result = quality_metrics_command(reference, distorted, options)?
+        .run()?
+        .check()?;
+    Ok(QualityMetrics::parse(&result.stderr))
+}
@@ -0,0 +1,389 @@
+//! A job queue kept in a file, so queued work survives restarts. Each job is
+//! a command line for the `video` binary; the queue only orders them and
+//! keeps count of the attempts.
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::Error;
+use crate::json::Json;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Done,
+    /// Failed as many times as it may.
+    Failed,
+}
+
+impl JobStatus {
+    pub fn name(self) -> &'static str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Running => "running",
+            JobStatus::Done => "done",
+            JobStatus::Failed => "failed",
+        }
+    }
+}
+
+impl FromStr for JobStatus {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(JobStatus::Pending),
+            "running" => Ok(JobStatus::Running),
+            "done" => Ok(JobStatus::Done),
+            "failed" => Ok(JobStatus::Failed),
+            _ => Err(Error::invalid(format!("unknown job status `{s}`"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueuedJob {
+    pub id: u64,
+    /// The arguments to run `video` with, e.g. `["transcode", "-i", …]`.
+    pub args: Vec<String>,
+    /// The working directory to run in, against which relative paths in
+    /// the arguments are resolved.
+    pub dir: PathBuf,
+    /// Higher runs first, jobs of the same priority in the order they came.
+    pub priority: i32,
+    /// How many times a failed job is tried again.
+    pub retries: u32,
+    pub attempts: u32,
+    pub status: JobStatus,
+    /// Why the last attempt failed.
+    pub error: Option<String>,
+    /// When the job was queued, in seconds since the Unix epoch.
+    pub queued_at: u64,
+}
+
+impl QueuedJob {
+    pub fn to_json(&self) -> Json {
+        Json::object([
+            ("id", Json::from(self.id)),
+            ("args", self.args.clone().into()),
+            ("dir", self.dir.as_path().into()),
+            ("priority", self.priority.into()),
+            ("retries", self.retries.into()),
+            ("attempts", self.attempts.into()),
+            ("status", self.status.name().into()),
+            ("error", self.error.clone().into()),
+            ("queued_at", self.queued_at.into()),
+        ])
+    }
+
+    fn from_json(json: &Json) -> Option<Self> {
+        let number = |key| json.get(key).and_then(Json::as_f64);
+        Some(QueuedJob {
+            id: json.get("id")?.as_u64()?,
+            args: json
+                .get("args")?
+                .as_array()?
+                .iter()
+                .map(|arg| arg.as_str().map(str::to_string))
+                .collect::<Option<_>>()?,
+            dir: json.get("dir")?.as_str()?.into(),
+            priority: number("priority")? as i32,
+            retries: number("retries")? as u32,
+            attempts: number("attempts")? as u32,
+            status: json.get("status")?.as_str()?.parse().ok()?,
+            error: json.get("error").and_then(Json::as_str).map(str::to_string),
+            queued_at: json.get("queued_at")?.as_u64()?,
+        })
+    }
+}
+
+/// The queue in one JSON-lines file, rewritten whole on every change and
+/// replaced in one rename so a crash leaves either the old or the new
+/// version. Its first line holds the next id, so ids aren't given out again
+/// once their jobs are removed. Changes hold a lock on a file next to it,
+/// which the system releases when a process dies, so several processes can
+/// share the queue.
+#[derive(Debug, Clone)]
+pub struct JobQueue {
+    path: PathBuf,
+}
+
+impl JobQueue {
+    /// The queue in `path`, created with its directory when a job is first
+    /// added.
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        JobQueue { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn jobs(&self) -> Result<Vec<QueuedJob>, Error> {
+        let _lock = self.lock("lock")?;
+        Ok(self.read()?.jobs)
+    }
+
+    /// Adds a job and returns its id. The file holds text, so jobs can't
+    /// name files or run in a directory whose path isn't valid Unicode.
+    pub fn push(
+        &self,
+        args: Vec<String>,
+        dir: PathBuf,
+        priority: i32,
+        retries: u32,
+    ) -> Result<u64, Error> {
+        if args.is_empty() {
+            return Err(Error::invalid("a job needs a command"));
+        }
+        if dir.to_str().is_none() {
+            return Err(Error::invalid(format!(
+                "{} isn't valid Unicode, which the queue can't keep",
+                dir.display()
+            )));
+        }
+        self.update_file(|queue| {
+            let id = queue.next_id;
+            queue.next_id += 1;
+            let queued_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs());
+            queue.jobs.push(QueuedJob {
+                id,
+                args,
+                dir,
+                priority,
+                retries,
+                attempts: 0,
+                status: JobStatus::Pending,
+                error: None,
+                queued_at,
+            });
+            Ok(id)
+        })
+    }
+
+    /// Marks the next pending job as running and returns it.
+    pub fn claim(&self) -> Result<Option<QueuedJob>, Error> {
+        self.update(|jobs| {
+            // Sorted by id, so the first of the highest priority is the oldest.
+            let Some(job) = jobs
+                .iter_mut()
+                .filter(|job| job.status == JobStatus::Pending)
+                .rev()
+                .max_by_key(|job| job.priority)
+            else {
+                return Ok(None);
+            };
+            job.status = JobStatus::Running;
+            job.attempts += 1;
+            Ok(Some(job.clone()))
+        })
+    }
+
+    /// Records how a claimed job went. A failed job goes back in the queue
+    /// while it has retries left.
+    pub fn finish(&self, id: u64, result: Result<(), String>) -> Result<JobStatus, Error> {
+        self.update(|jobs| {
+            let job = find(jobs, id)?;
+            job.status = match result {
+                Ok(()) => {
+                    job.error = None;
+                    JobStatus::Done
+                }
+                Err(error) => {
+                    job.error = Some(error);
+                    if job.attempts <= job.retries {
+                        JobStatus::Pending
+                    } else {
+                        JobStatus::Failed
+                    }
+                }
+            };
+            Ok(job.status)
+        })
+    }
+
+    /// Queues a failed job again, with its attempts counted from zero.
+    pub fn retry(&self, id: u64) -> Result<(), Error> {
+        self.update(|jobs| {
+            let job = find(jobs, id)?;
+            if job.status != JobStatus::Failed {
+                return Err(Error::invalid(format!(
+                    "job {id} is {}, not failed",
+                    job.status.name()
+                )));
+            }
+            job.status = JobStatus::Pending;
+            job.attempts = 0;
+            Ok(())
+        })
+    }
+
+    /// Removes a job that is not running.
+    pub fn remove(&self, id: u64) -> Result<(), Error> {
+        self.update(|jobs| {
+            if find(jobs, id)?.status == JobStatus::Running {
+                return Err(Error::invalid(format!("job {id} is running")));
+            }
+            jobs.retain(|job| job.id != id);
+            Ok(())
+        })
+    }
+
+    /// Removes the finished jobs and returns how many there were.
+    pub fn clear_done(&self) -> Result<usize, Error> {
+        self.update(|jobs| {
+            let before = jobs.len();
+            jobs.retain(|job| job.status != JobStatus::Done);
+            Ok(before - jobs.len())
+        })
+    }
+
+    /// Makes this process the queue's only worker until the guard is
+    /// dropped. Jobs still marked as running belonged to a worker that
+    /// stopped halfway, e.g. at a reboot, so they are queued again without
+    /// counting that attempt.
+    pub fn start_worker(&self) -> Result<WorkerGuard, Error> {
+        let lock = self.lock_file("worker")?;
+        match lock.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                return Err(Error::invalid(format!(
+                    "another worker is processing {}",
+                    self.path.display()
+                )));
+            }
+            Err(TryLockError::Error(e)) => return Err(Error::AccessDenied(e)),
+        }
+        self.update(|jobs| {
+            for job in jobs
+                .iter_mut()
+                .filter(|job| job.status == JobStatus::Running)
+            {
+                job.status = JobStatus::Pending;
+                job.attempts = job.attempts.saturating_sub(1);
+            }
+            Ok(())
+        })?;
+        Ok(WorkerGuard { _lock: lock })
+    }
+
+    fn read(&self) -> Result<QueueFile, Error> {
+        let text = match std::fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                return Ok(QueueFile {
+                    next_id: 1,
+                    jobs: Vec::new(),
+                });
+            }
+            Err(e) => return Err(Error::AccessDenied(e)),
+        };
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(number, line)| (number, Json::parse(line).ok()))
+            .peekable();
+        // Queues written before the header started after their highest id.
+        let header = lines
+            .next_if(|(_, json)| json.as_ref().is_some_and(|json| json.get("id").is_none()))
+            .and_then(|(_, json)| json?.get("next_id")?.as_u64());
+        let jobs = lines
+            .map(|(number, json)| {
+                json.as_ref().and_then(QueuedJob::from_json).ok_or_else(|| {
+                    Error::invalid(format!(
+                        "{} line {}: not a job",
+                        self.path.display(),
+                        number + 1
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let after_jobs = jobs.iter().map(|job| job.id).max().unwrap_or(0) + 1;
+        Ok(QueueFile {
+            next_id: header.unwrap_or(1).max(after_jobs),
+            jobs,
+        })
+    }
+
+    fn update<T>(
+        &self,
+        change: impl FnOnce(&mut Vec<QueuedJob>) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        self.update_file(|queue| change(&mut queue.jobs))
+    }
+
+    fn update_file<T>(
+        &self,
+        change: impl FnOnce(&mut QueueFile) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let _lock = self.lock("lock")?;
+        let mut queue = self.read()?;
+        let result = change(&mut queue)?;
+
+        let mut text = Json::object([("next_id", Json::from(queue.next_id))]).to_string();
+        text.push('\n');
+        for job in &queue.jobs {
+            text.push_str(&job.to_json().to_string());
+            text.push('\n');
+        }
+        let temp = self.sibling("tmp");
+        let mut file = File::create(&temp).map_err(Error::WriteFileError)?;
+        file.write_all(text.as_bytes())
+            .and_then(|()| file.sync_all())
+            .map_err(Error::WriteFileError)?;
+        std::fs::rename(&temp, &self.path).map_err(Error::WriteFileError)?;
+        Ok(result)
+    }
+
+    fn lock(&self, suffix: &str) -> Result<File, Error> {
+        let file = self.lock_file(suffix)?;
+        file.lock().map_err(Error::AccessDenied)?;
+        Ok(file)
+    }
+
+    fn lock_file(&self, suffix: &str) -> Result<File, Error> {
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(Error::CreateOutputError)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.sibling(suffix))
+            .map_err(Error::CreateOutputError)
+    }
+
+    /// `queue.jsonl` becomes `queue.jsonl.<suffix>`.
+    fn sibling(&self, suffix: &str) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(".");
+        name.push(suffix);
+        name.into()
+    }
+}
+
+/// What the queue file holds.
+struct QueueFile {
+    next_id: u64,
+    jobs: Vec<QueuedJob>,
+}
+
+/// Keeps other workers off the queue while alive, see
+/// [`JobQueue::start_worker`].
+#[derive(Debug)]
+pub struct WorkerGuard {
+    _lock: File,
+}
+
+fn find(jobs: &mut [QueuedJob], id: u64) -> Result<&mut QueuedJob, Error> {
+    jobs.iter_mut()
+        .find(|job| job.id == id)
+        .ok_or_else(|| Error::invalid(format!("there is no job {id}")))
+}
@@ -0,0 +1,181 @@
+use std::path::Path;
+use std::time::Duration;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, Input, ProcessResult};
+use crate::time::format_timestamp;
+use crate::transcode::TranscodeOptions;
+
+/// A rectangle of the screen, in pixels from the top left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// The ffmpeg input device that grabs the screen on this platform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScreenGrabber {
+    /// X11 on Linux and the BSDs.
+    X11,
+    /// macOS.
+    AvFoundation,
+    /// Windows.
+    Gdi,
+}
+
+impl ScreenGrabber {
+    pub fn current() -> Self {
+        if cfg!(target_os = "macos") {
+            ScreenGrabber::AvFoundation
+        } else if cfg!(windows) {
+            ScreenGrabber::Gdi
+        } else {
+            ScreenGrabber::X11
+        }
+    }
+
+    pub fn format(&self) -> &'static str {
+        match self {
+            ScreenGrabber::X11 => "x11grab",
+            ScreenGrabber::AvFoundation => "avfoundation",
+            ScreenGrabber::Gdi => "gdigrab",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordOptions {
+    pub grabber: ScreenGrabber,
+    /// The screen to grab: an X display such as `:0.0`, an avfoundation
+    /// device such as `Capture screen 0`, or `desktop` on Windows. By
+    /// default `$DISPLAY`, the first screen or the whole desktop.
+    pub screen: Option<String>,
+    /// Only grab this part of the screen.
+    pub region: Option<Region>,
+    pub fps: f64,
+    /// Record a microphone too: a PulseAudio source such as `default`, an
+    /// avfoundation audio device index or a DirectShow device name.
+    pub microphone: Option<String>,
+    /// Stop after this long instead of when interrupted.
+    pub duration: Option<Duration>,
+    pub transcode: TranscodeOptions,
+}
+
+impl Default for RecordOptions {
+    fn default() -> Self {
+        Self {
+            grabber: ScreenGrabber::current(),
+            screen: None,
+            region: None,
+            fps: 30.0,
+            microphone: None,
+            duration: None,
+            transcode: TranscodeOptions::default(),
+        }
+    }
+}
+
+/// Records the screen to `output` until ffmpeg is interrupted, e.g. with
+/// Ctrl-C, or `options.duration` has passed. An interrupted ffmpeg still
+/// finishes the file but exits with code 255.
+pub fn record_command(output: &Path, options: &RecordOptions) -> Result<FfmpegCommand, Error> {
+    Ok(grab_command(options)?.output(output))
+}
+
+/// The screen grab and its encoding, without an output.
+pub(crate) fn grab_command(options: &RecordOptions) -> Result<FfmpegCommand, Error> {
+    if !(options.fps.is_finite() && options.fps > 0.0) {
+        return Err(Error::invalid("the frame rate must be positive"));
+    }
+
+    let fps = options.fps.to_string();
+    let size = options.region.map(|r| format!("{}x{}", r.width, r.height));
+    let mut command = FfmpegCommand::new();
+    let mut screen = Input::new(match options.grabber {
+        ScreenGrabber::X11 => {
+            let display = options
+                .screen
+                .clone()
+                .or_else(|| std::env::var("DISPLAY").ok())
+                .unwrap_or_else(|| ":0.0".to_string());
+            match options.region {
+                Some(r) => format!("{display}+{},{}", r.x, r.y),
+                None => display,
+            }
+        }
+        ScreenGrabber::AvFoundation => {
+            let screen = options.screen.as_deref().unwrap_or("Capture screen 0");
+            match &options.microphone {
+                Some(microphone) => format!("{screen}:{microphone}"),
+                None => format!("{screen}:none"),
+            }
+        }
+        ScreenGrabber::Gdi => options
+            .screen
+            .clone()
+            .unwrap_or_else(|| "desktop".to_string()),
+    })
+    .format(options.grabber.format())
+    .args(["-framerate", &fps]);
+
+    match (options.grabber, options.region) {
+        (ScreenGrabber::X11, Some(_)) => {
+            screen = screen.args(["-video_size", size.as_deref().unwrap()]);
+        }
+        (ScreenGrabber::Gdi, Some(r)) => {
+            screen = screen.args([
+                "-offset_x".to_string(),
+                r.x.to_string(),
+                "-offset_y".to_string(),
+                r.y.to_string(),
+                "-video_size".to_string(),
+                size.clone().unwrap(),
+            ]);
+        }
+        // avfoundation always grabs the whole screen.
+        (ScreenGrabber::AvFoundation, Some(r)) => {
+            command =
+                command.video_filter(format!("crop={}:{}:{}:{}", r.width, r.height, r.x, r.y));
+        }
+        (_, None) => {}
+    }
+    if options.grabber == ScreenGrabber::AvFoundation {
+        screen = screen.args(["-capture_cursor", "1"]);
+    }
+    command = command.add_input(screen);
+
+    if let Some(microphone) = &options.microphone {
+        command = match options.grabber {
+            ScreenGrabber::X11 => command.add_input(Input::new(microphone).format("pulse")),
+            ScreenGrabber::Gdi => {
+                command.add_input(Input::new(format!("audio={microphone}")).format("dshow"))
+            }
+            // Both come from the same avfoundation input.
+            ScreenGrabber::AvFoundation => command,
+        };
+    }
+
+    if let Some(duration) = options.duration {
+        command = command
+            .args(["-t", &format_timestamp(duration)])
+            .duration(duration);
+    }
+    // Grabbed frames are RGB, which most players can't show once encoded.
+    command = command.video_filter("format=yuv420p");
+    // Encoding has to keep up with the screen.
+    if options.transcode.video_encoder().is_none() {
+        command = command.video_codec("libx264");
+        if options.transcode.preset.is_none() {
+            command = command.args(["-preset", "ultrafast"]);
+        }
+    }
+
+    Ok(options.transcode.apply(command))
+}
+
+pub fn record(output: &Path, options: &RecordOptions) -> Result<ProcessResult, Error> {
+    record_command(output, options)?.run()?.check()
+}
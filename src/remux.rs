@@ -0,0 +1,38 @@
+use std::path::Path;
+
+use crate::Error;
+use crate::container::{Container, StreamAction, StreamPlan, apply_plan, plan_streams};
+use crate::ffmpeg::{FfmpegCommand, ProcessResult};
+use crate::probe::probe;
+
+/// What [`remux_command`] does with each stream of `input`.
+pub fn remux_plan(input: &Path, output: &Path) -> Result<Vec<StreamPlan>, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+    Ok(plan_streams(&probe(input)?, Container::from_path(output)))
+}
+
+/// Moves `input` into the container `output`'s extension names, copying
+/// every stream it can hold. The others are converted, text subtitles to
+/// the container's own format for instance, or dropped when they cannot be.
+pub fn remux_command(input: &Path, output: &Path) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+    let info = probe(input)?;
+    let plans = plan_streams(&info, Container::from_path(output));
+    if plans.iter().all(|plan| plan.action == StreamAction::Drop) {
+        return Err(Error::StreamNotFound);
+    }
+
+    let mut command = apply_plan(FfmpegCommand::new().input(input), &plans);
+    if let Some(duration) = info.duration {
+        command = command.duration(duration);
+    }
+    Ok(command.output(output))
+}
+
+pub fn remux(input: &Path, output: &Path) -> Result<ProcessResult, Error> {
+    remux_command(input, output)?.run()?.check()
+}
@@ -0,0 +1,104 @@
+use crate::ffmpeg::FfmpegCommand;
+use crate::hwaccel::HwAccel;
+use crate::transcode::TranscodeOptions;
+
+/// One output of an adaptive bitrate ladder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rendition {
+    /// Frame height, the width follows the aspect ratio.
+    pub height: u32,
+    pub video_bitrate: String,
+    pub audio_bitrate: String,
+}
+
+impl Rendition {
+    pub fn new(height: u32, video_bitrate: &str) -> Self {
+        Self {
+            height,
+            video_bitrate: video_bitrate.to_string(),
+            audio_bitrate: "128k".to_string(),
+        }
+    }
+
+    /// 1080p, 720p and 480p at common streaming bitrates.
+    pub fn standard_ladder() -> Vec<Rendition> {
+        vec![
+            Rendition::new(1080, "5000k"),
+            Rendition::new(720, "2800k"),
+            Rendition::new(480, "1400k"),
+        ]
+    }
+}
+
+/// Splits the first video stream into `[v0]`, `[v1]`, ... scaled to each
+/// rendition.
+pub(crate) fn split_graph(renditions: &[Rendition], transcode: &TranscodeOptions) -> String {
+    let upload = transcode
+        .hwaccel
+        .and_then(HwAccel::upload_filter)
+        .map(|filter| format!(",{filter}"))
+        .unwrap_or_default();
+    let count = renditions.len();
+    let mut graph = format!("[0:v:0]split={count}");
+    for i in 0..count {
+        graph.push_str(&format!("[s{i}]"));
+    }
+    for (i, rendition) in renditions.iter().enumerate() {
+        // -2 keeps the width even for the encoder.
+        graph.push_str(&format!(
+            ";[s{i}]scale=-2:{}{upload}[v{i}]",
+            rendition.height
+        ));
+    }
+    graph
+}
+
+/// Splits the first video stream into one scaled stream per rendition and
+/// maps them with their bitrates. With `audio_per_rendition` the first audio
+/// stream is mapped once for every rendition, otherwise once overall.
+pub(crate) fn map_renditions(
+    mut command: FfmpegCommand,
+    renditions: &[Rendition],
+    transcode: &TranscodeOptions,
+    audio: bool,
+    audio_per_rendition: bool,
+) -> FfmpegCommand {
+    command = command.filter_complex(split_graph(renditions, transcode));
+    if let Some(accel) = transcode.hwaccel {
+        command = command.global_args(accel.global_args());
+    }
+
+    let video_codec = transcode
+        .video_encoder()
+        .unwrap_or_else(|| "libx264".to_string());
+    for (i, rendition) in renditions.iter().enumerate() {
+        command = command.map(&format!("[v{i}]")).args([
+            format!("-c:v:{i}"),
+            video_codec.clone(),
+            format!("-b:v:{i}"),
+            rendition.video_bitrate.clone(),
+        ]);
+    }
+    if let Some(preset) = &transcode.preset {
+        command = command.args(["-preset", preset]);
+    }
+
+    if audio {
+        let audio_codec = transcode.audio_codec.as_deref().unwrap_or("aac");
+        let streams = if audio_per_rendition {
+            renditions
+        } else {
+            &renditions[..1]
+        };
+        for (i, rendition) in streams.iter().enumerate() {
+            command = command.map("0:a:0").args([
+                format!("-c:a:{i}"),
+                audio_codec.to_string(),
+                format!("-b:a:{i}"),
+                rendition.audio_bitrate.clone(),
+            ]);
+        }
+    }
+
+    command
+}
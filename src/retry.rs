@@ -0,0 +1,84 @@
+use std::sync::RwLock;
+use std::time::Duration;
+
+use crate::Error;
+use crate::log::warning;
+
+static DEFAULT: RwLock<Option<RetryPolicy>> = RwLock::new(None);
+
+/// How often a run that failed for a passing reason, see
+/// [`Error::is_transient`], is tried again. The delay grows by `backoff`
+/// after each attempt, up to `max_delay`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// How many times to try again, none by default.
+    pub retries: u32,
+    /// How long to wait before the first retry.
+    pub delay: Duration,
+    /// What the delay is multiplied by for each further retry.
+    pub backoff: f64,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 0,
+            delay: Duration::from_secs(1),
+            backoff: 2.0,
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Tries `retries` more times with the default delays.
+    pub fn new(retries: u32) -> Self {
+        Self {
+            retries,
+            ..Self::default()
+        }
+    }
+
+    /// Makes this the policy new commands and probes of URLs start with.
+    pub fn install(self) {
+        *DEFAULT.write().unwrap() = Some(self);
+    }
+
+    /// The installed policy, or the default of no retries.
+    pub fn current() -> Self {
+        DEFAULT.read().unwrap().unwrap_or_default()
+    }
+
+    /// How long to wait before retry number `retry`, counted from 1.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = self.backoff.max(1.0).powi(retry.saturating_sub(1) as i32);
+        Duration::try_from_secs_f64(self.delay.as_secs_f64() * factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+
+    /// Calls `attempt` until it succeeds, fails permanently or runs out of
+    /// retries, and returns its last result.
+    pub fn run<T>(&self, mut attempt: impl FnMut() -> Result<T, Error>) -> Result<T, Error> {
+        let mut retry = 0;
+        loop {
+            match attempt() {
+                Err(e) if retry < self.retries && e.is_transient() => {
+                    retry += 1;
+                    self.wait(retry, &e.to_string());
+                }
+                result => return result,
+            }
+        }
+    }
+
+    pub(crate) fn wait(&self, retry: u32, reason: &str) {
+        let delay = self.delay(retry);
+        warning!(
+            "{reason}, retrying in {:.1}s ({retry}/{})",
+            delay.as_secs_f64(),
+            self.retries
+        );
+        std::thread::sleep(delay);
+    }
+}
@@ -0,0 +1,113 @@
+use std::path::Path;
+use std::time::Duration;
+
+use crate::Error;
+use crate::concat::demuxer_command;
+use crate::ffmpeg::{FfmpegCommand, Input, ProcessResult};
+use crate::log::warning;
+use crate::probe::probe;
+use crate::temp::TempFile;
+use crate::time::format_timestamp;
+use crate::transcode::TranscodeOptions;
+
+#[derive(Debug, Clone)]
+pub struct ReverseOptions {
+    /// reverse and areverse hold every frame in memory, so longer inputs are
+    /// reversed in pieces this long and joined back to front.
+    pub segment_length: Duration,
+    pub transcode: TranscodeOptions,
+}
+
+impl Default for ReverseOptions {
+    fn default() -> Self {
+        Self {
+            segment_length: Duration::from_secs(30),
+            transcode: TranscodeOptions::default(),
+        }
+    }
+}
+
+/// The commands to run in order: one for a short input, otherwise one per
+/// segment and a last one joining them. The segments are removed once the
+/// last command is dropped.
+pub fn reverse_passes(
+    input: &Path,
+    output: &Path,
+    options: &ReverseOptions,
+) -> Result<Vec<FfmpegCommand>, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+    if options.segment_length.is_zero() {
+        return Err(Error::invalid("the segment length must be positive"));
+    }
+    let info = probe(input)?;
+    let reverse = |mut command: FfmpegCommand| {
+        if info.video().is_some() {
+            command = command.video_filter("reverse");
+        }
+        if info.audio().is_some() {
+            command = command.audio_filter("areverse");
+        }
+        options.transcode.apply(command)
+    };
+
+    let Some(total) = info
+        .duration
+        .filter(|total| *total > options.segment_length)
+    else {
+        if info.duration.is_none() {
+            warning!(
+                "cannot tell how long {} is, reversing it in one piece, all in memory",
+                input.display()
+            );
+        }
+        let mut command = reverse(FfmpegCommand::new().input(input));
+        if let Some(duration) = info.duration {
+            command = command.duration(duration);
+        }
+        return Ok(vec![command.output(output)]);
+    };
+
+    let dir = TempFile::create_dir("media_tools-reverse")?;
+    let length = options.segment_length;
+    let count = (total.as_secs_f64() / length.as_secs_f64()).ceil() as u32;
+    let mut commands = Vec::new();
+    let mut segments = Vec::new();
+    for i in 0..count {
+        let start = length * i;
+        let duration = length.min(total.saturating_sub(start));
+        if duration.is_zero() {
+            break;
+        }
+        let segment = dir.path().join(format!("segment-{i:04}.mkv"));
+        let source = Input::new(input).args(["-ss", &format_timestamp(start)]);
+        let command = FfmpegCommand::new()
+            .add_input(source)
+            .args(["-t", &format_timestamp(duration)])
+            .duration(duration);
+        commands.push(reverse(command).output(&segment));
+        segments.push(segment);
+    }
+
+    // The last segment holds the end of the input, which comes first now.
+    segments.reverse();
+    let join = demuxer_command(&segments)?
+        .duration(total)
+        .temp_file(dir)
+        .output(output);
+    commands.push(join);
+    Ok(commands)
+}
+
+pub fn reverse(
+    input: &Path,
+    output: &Path,
+    options: &ReverseOptions,
+) -> Result<ProcessResult, Error> {
+    let mut result = None;
+    for command in reverse_passes(input, output, options)? {
+        result = Some(command.run()?.check()?);
+    }
+    Ok(result.expect("at least one pass"))
+}
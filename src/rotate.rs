@@ -0,0 +1,171 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, Input, ProcessResult};
+use crate::pipeline::{Operation, Stage};
+use crate::probe::probe;
+use crate::transcode::TranscodeOptions;
+
+/// A clockwise rotation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Rotation {
+    #[default]
+    None,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+impl Rotation {
+    pub fn degrees(self) -> u32 {
+        match self {
+            Rotation::None => 0,
+            Rotation::Rotate90 => 90,
+            Rotation::Rotate180 => 180,
+            Rotation::Rotate270 => 270,
+        }
+    }
+
+    fn filters(self) -> &'static [&'static str] {
+        match self {
+            Rotation::None => &[],
+            Rotation::Rotate90 => &["transpose=clock"],
+            Rotation::Rotate180 => &["hflip", "vflip"],
+            Rotation::Rotate270 => &["transpose=cclock"],
+        }
+    }
+}
+
+impl FromStr for Rotation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "0" => Ok(Rotation::None),
+            "90" => Ok(Rotation::Rotate90),
+            "180" => Ok(Rotation::Rotate180),
+            "270" => Ok(Rotation::Rotate270),
+            _ => Err(Error::invalid(format!("unknown rotation `{s}`"))),
+        }
+    }
+}
+
+// Like `TrimMode`, built once per command.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, Default)]
+pub enum RotateMode {
+    /// Only set the display matrix, fast but relies on the player honouring it.
+    #[default]
+    Metadata,
+    /// Transform the frames with filters.
+    Reencode(TranscodeOptions),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RotateOptions {
+    pub rotation: Rotation,
+    /// Mirror left to right, applied after the rotation.
+    pub flip_horizontal: bool,
+    /// Mirror top to bottom, applied after the rotation.
+    pub flip_vertical: bool,
+    pub mode: RotateMode,
+}
+
+impl RotateOptions {
+    /// The filters turning the frames themselves.
+    fn filter(&self) -> Result<String, Error> {
+        let mut filters = self.rotation.filters().to_vec();
+        if self.flip_horizontal {
+            filters.push("hflip");
+        }
+        if self.flip_vertical {
+            filters.push("vflip");
+        }
+        if filters.is_empty() {
+            return Err(Error::invalid("nothing to rotate or flip"));
+        }
+        Ok(filters.join(","))
+    }
+}
+
+impl Operation for RotateOptions {
+    fn name(&self) -> &'static str {
+        "rotate"
+    }
+
+    fn options(&self) -> Option<&TranscodeOptions> {
+        match &self.mode {
+            RotateMode::Metadata => None,
+            RotateMode::Reencode(transcode) => Some(transcode),
+        }
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        match self.mode {
+            RotateMode::Metadata => Ok(()),
+            RotateMode::Reencode(_) => self.filter().map(drop),
+        }
+    }
+
+    fn build_args(&self, input: &Path, output: &Path) -> Result<FfmpegCommand, Error> {
+        rotate_command(input, output, self)
+    }
+
+    fn fuse(&self, stage: &mut Stage) -> Result<bool, Error> {
+        if matches!(self.mode, RotateMode::Metadata) {
+            return Ok(false);
+        }
+        stage.video(self.filter()?);
+        Ok(true)
+    }
+}
+
+pub fn rotate_command(
+    input: &Path,
+    output: &Path,
+    options: &RotateOptions,
+) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+
+    let mut command = match &options.mode {
+        RotateMode::Metadata => {
+            // The display matrix is counter-clockwise and replaces any
+            // rotation the input already had.
+            let degrees = (360 - options.rotation.degrees()) % 360;
+            let mut source =
+                Input::new(input).args(["-display_rotation:v:0", &degrees.to_string()]);
+            if options.flip_horizontal {
+                source = source.args(["-display_hflip:v:0"]);
+            }
+            if options.flip_vertical {
+                source = source.args(["-display_vflip:v:0"]);
+            }
+            FfmpegCommand::new().add_input(source).codec_copy()
+        }
+        RotateMode::Reencode(transcode) => {
+            let mut command = FfmpegCommand::new()
+                .input(input)
+                .video_filter(options.filter()?);
+            if transcode.audio_codec.is_none() && transcode.audio_bitrate.is_none() {
+                command = command.audio_codec("copy");
+            }
+            transcode.apply(command)
+        }
+    };
+    if let Some(duration) = probe(input).ok().and_then(|info| info.duration) {
+        command = command.duration(duration);
+    }
+
+    Ok(command.output(output))
+}
+
+pub fn rotate(
+    input: &Path,
+    output: &Path,
+    options: &RotateOptions,
+) -> Result<ProcessResult, Error> {
+    rotate_command(input, output, options)?.run()?.check()
+}
@@ -0,0 +1,122 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, ProcessResult};
+use crate::pipeline::{Operation, Stage};
+use crate::probe::probe;
+use crate::transcode::TranscodeOptions;
+
+/// How a frame is fitted into a size with a different aspect ratio.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScaleFit {
+    /// Scale down to fit and letterbox the rest.
+    #[default]
+    Pad,
+    /// Scale up to fill and cut off what sticks out.
+    Crop,
+    /// Scale to the exact size, distorting the picture.
+    Stretch,
+}
+
+impl ScaleFit {
+    pub(crate) fn filter(self, width: u32, height: u32) -> String {
+        match self {
+            ScaleFit::Pad => format!(
+                "scale={width}:{height}:force_original_aspect_ratio=decrease,\
+                 pad={width}:{height}:(ow-iw)/2:(oh-ih)/2,setsar=1"
+            ),
+            ScaleFit::Crop => format!(
+                "scale={width}:{height}:force_original_aspect_ratio=increase,\
+                 crop={width}:{height},setsar=1"
+            ),
+            ScaleFit::Stretch => format!("scale={width}:{height},setsar=1"),
+        }
+    }
+}
+
+impl FromStr for ScaleFit {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pad" => Ok(ScaleFit::Pad),
+            "crop" => Ok(ScaleFit::Crop),
+            "stretch" => Ok(ScaleFit::Stretch),
+            _ => Err(Error::invalid(format!("unknown fit `{s}`"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ScaleOptions {
+    /// Output width, derived from the height and aspect ratio when unset.
+    pub width: Option<u32>,
+    /// Output height, derived from the width and aspect ratio when unset.
+    pub height: Option<u32>,
+    /// Only used when both the width and height are set.
+    pub fit: ScaleFit,
+    pub transcode: TranscodeOptions,
+}
+
+impl ScaleOptions {
+    fn filter(&self) -> Result<String, Error> {
+        Ok(match (self.width, self.height) {
+            (None, None) => return Err(Error::invalid("set a width, a height or both")),
+            (Some(0), _) | (_, Some(0)) => return Err(Error::invalid("the size must be positive")),
+            // -2 keeps the aspect ratio and rounds to the even sizes encoders need.
+            (Some(width), None) => format!("scale={width}:-2"),
+            (None, Some(height)) => format!("scale=-2:{height}"),
+            (Some(width), Some(height)) => self.fit.filter(width, height),
+        })
+    }
+}
+
+impl Operation for ScaleOptions {
+    fn name(&self) -> &'static str {
+        "scale"
+    }
+
+    fn options(&self) -> Option<&TranscodeOptions> {
+        Some(&self.transcode)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        self.filter().map(drop)
+    }
+
+    fn build_args(&self, input: &Path, output: &Path) -> Result<FfmpegCommand, Error> {
+        scale_command(input, output, self)
+    }
+
+    fn fuse(&self, stage: &mut Stage) -> Result<bool, Error> {
+        stage.video(self.filter()?);
+        Ok(true)
+    }
+}
+
+pub fn scale_command(
+    input: &Path,
+    output: &Path,
+    options: &ScaleOptions,
+) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+
+    let mut command = FfmpegCommand::new()
+        .input(input)
+        .video_filter(options.filter()?);
+    if let Some(duration) = probe(input).ok().and_then(|info| info.duration) {
+        command = command.duration(duration);
+    }
+    if options.transcode.audio_codec.is_none() && options.transcode.audio_bitrate.is_none() {
+        command = command.audio_codec("copy");
+    }
+
+    Ok(options.transcode.apply(command).output(output))
+}
+
+pub fn scale(input: &Path, output: &Path, options: &ScaleOptions) -> Result<ProcessResult, Error> {
+    scale_command(input, output, options)?.run()?.check()
+}
@@ -0,0 +1,459 @@
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, Metadata};
+use std::hash::Hasher;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use crate::Error;
+use crate::log::warning;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Plain string order, `part10` sorts before `part2`.
+    #[default]
+    Lexical,
+    /// Numbers inside names compare by value, `part2` sorts before `part10`.
+    Natural,
+    /// Oldest modification time first.
+    Modified,
+    /// Oldest creation time first, falling back to modification time on
+    /// filesystems that do not record it.
+    Created,
+}
+
+impl FromStr for SortOrder {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lexical" => Ok(SortOrder::Lexical),
+            "natural" => Ok(SortOrder::Natural),
+            "mtime" => Ok(SortOrder::Modified),
+            "ctime" => Ok(SortOrder::Created),
+            _ => Err(Error::invalid(format!("unknown sort order `{s}`"))),
+        }
+    }
+}
+
+/// What to do about files with the same content as one before them, such as
+/// clips copied off a camera card twice.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateCheck {
+    /// Don't look.
+    #[default]
+    Off,
+    /// Log a warning naming each copy and keep it.
+    Warn,
+    /// Leave the copies out, with a warning naming each.
+    Skip,
+}
+
+impl FromStr for DuplicateCheck {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(DuplicateCheck::Off),
+            "warn" => Ok(DuplicateCheck::Warn),
+            "skip" => Ok(DuplicateCheck::Skip),
+            _ => Err(Error::invalid(format!("unknown duplicate check `{s}`"))),
+        }
+    }
+}
+
+impl DuplicateCheck {
+    /// Warns about the copies in `paths` and, when skipping, removes them.
+    pub(crate) fn apply(self, paths: Vec<PathBuf>) -> Result<Vec<PathBuf>, Error> {
+        if self == DuplicateCheck::Off {
+            return Ok(paths);
+        }
+        let copies = duplicates(&paths)?;
+        for (copy, original) in &copies {
+            warning!(
+                "{} has the same content as {}{}",
+                paths[*copy].display(),
+                paths[*original].display(),
+                if self == DuplicateCheck::Skip {
+                    ", leaving it out"
+                } else {
+                    ""
+                }
+            );
+        }
+        if self == DuplicateCheck::Warn {
+            return Ok(paths);
+        }
+        Ok(paths
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| !copies.iter().any(|(copy, _)| copy == i))
+            .map(|(_, path)| path)
+            .collect())
+    }
+}
+
+/// The files in `paths` with the same content as an earlier one, as pairs of
+/// the copy's index and the first one's. Only files of the same size are
+/// read and hashed, and files whose hashes match are compared byte for byte;
+/// URLs and anything else that isn't a file are skipped.
+pub fn duplicates<P: AsRef<Path>>(paths: &[P]) -> Result<Vec<(usize, usize)>, Error> {
+    let mut by_size: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (i, path) in paths.iter().enumerate() {
+        if let Ok(meta) = std::fs::metadata(path)
+            && meta.is_file()
+        {
+            by_size.entry(meta.len()).or_default().push(i);
+        }
+    }
+
+    let mut copies = Vec::new();
+    for group in by_size.values().filter(|group| group.len() > 1) {
+        // The first file of each distinct content, by hash.
+        let mut first: HashMap<u64, Vec<usize>> = HashMap::new();
+        for &i in group {
+            let hash = hash_file(paths[i].as_ref())?;
+            let originals = first.entry(hash).or_default();
+            let mut original = None;
+            for &j in originals.iter() {
+                if same_content(paths[i].as_ref(), paths[j].as_ref())? {
+                    original = Some(j);
+                    break;
+                }
+            }
+            match original {
+                Some(original) => copies.push((i, original)),
+                None => originals.push(i),
+            }
+        }
+    }
+    copies.sort_unstable();
+    Ok(copies)
+}
+
+fn hash_file(path: &Path) -> Result<u64, Error> {
+    let mut file = File::open(path).map_err(Error::AccessDenied)?;
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = vec![0; 1 << 16];
+    loop {
+        let read = file.read(&mut buffer).map_err(Error::AccessDenied)?;
+        if read == 0 {
+            return Ok(hasher.finish());
+        }
+        hasher.write(&buffer[..read]);
+    }
+}
+
+/// Whether two files hold the same bytes.
+fn same_content(a: &Path, b: &Path) -> Result<bool, Error> {
+    let mut a = File::open(a).map_err(Error::AccessDenied)?;
+    let mut b = File::open(b).map_err(Error::AccessDenied)?;
+    let mut a_buffer = vec![0; 1 << 16];
+    let mut b_buffer = vec![0; 1 << 16];
+    loop {
+        let a_read = fill(&mut a, &mut a_buffer).map_err(Error::AccessDenied)?;
+        let b_read = fill(&mut b, &mut b_buffer).map_err(Error::AccessDenied)?;
+        if a_buffer[..a_read] != b_buffer[..b_read] {
+            return Ok(false);
+        }
+        if a_read == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+/// Reads until `buffer` is full or the file ends, as `read` may stop short.
+fn fill(file: &mut File, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match file.read(&mut buffer[filled..])? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+    Ok(filled)
+}
+
+/// Compares strings treating runs of digits as numbers.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        match (a_chars.peek(), b_chars.peek()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x = take_digits(&mut a_chars);
+                let y = take_digits(&mut b_chars);
+                let ordering = x
+                    .trim_start_matches('0')
+                    .len()
+                    .cmp(&y.trim_start_matches('0').len())
+                    .then_with(|| x.trim_start_matches('0').cmp(y.trim_start_matches('0')));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+        digits.push(c);
+    }
+    digits
+}
+
+fn timestamp(path: &Path, get: fn(&Metadata) -> std::io::Result<SystemTime>) -> Option<SystemTime> {
+    let metadata = std::fs::metadata(path).ok()?;
+    get(&metadata).or_else(|_| metadata.modified()).ok()
+}
+
+/// Sorts `paths` in place. Paths that aren't valid Unicode sort by their
+/// bytes, and naturally as if their odd bytes were replacement characters.
+pub fn sort_paths(paths: &mut [PathBuf], order: SortOrder) {
+    match order {
+        SortOrder::Lexical => paths.sort_unstable_by(|a, b| a.as_os_str().cmp(b.as_os_str())),
+        SortOrder::Natural => {
+            paths.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()))
+        }
+        SortOrder::Modified => paths
+            .sort_by_cached_key(|p| (timestamp(p, Metadata::modified), p.as_os_str().to_owned())),
+        SortOrder::Created => paths
+            .sort_by_cached_key(|p| (timestamp(p, Metadata::created), p.as_os_str().to_owned())),
+    }
+}
+
+/// Whether the file name of `path` matches the glob `pattern`. Parts of the
+/// name that aren't valid Unicode only match wildcards.
+pub fn name_matches(path: &Path, pattern: &str) -> bool {
+    path.file_name()
+        .is_some_and(|name| glob_match(pattern, &name.to_string_lossy()))
+}
+
+/// Lists the files in `path` whose name starts with `prefix`, has the
+/// extension `ext` and matches the glob `pattern`. An empty prefix or
+/// extension matches any file.
+pub(crate) fn read_dir(
+    path: &Path,
+    prefix: &str,
+    ext: &str,
+    pattern: Option<&str>,
+    recursive: bool,
+    order: SortOrder,
+) -> Result<Vec<PathBuf>, Error> {
+    let mut paths = walk(path, recursive)?
+        .into_iter()
+        .filter(|path| {
+            (ext.is_empty()
+                || path
+                    .extension()
+                    .is_some_and(|e| e.eq_ignore_ascii_case(ext)))
+                && path
+                    .file_name()
+                    .is_some_and(|name| name.as_encoded_bytes().starts_with(prefix.as_bytes()))
+                && pattern.is_none_or(|pattern| name_matches(path, pattern))
+        })
+        .collect::<Vec<_>>();
+
+    sort_paths(&mut paths, order);
+    Ok(paths)
+}
+
+/// Matches a file name against a shell-style glob supporting `*`, `?` and
+/// `[...]` character classes (`[!...]` negates).
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    glob_match_at(&pattern, &name)
+}
+
+fn glob_match_at(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|skip| glob_match_at(&pattern[1..], &name[skip..])),
+        Some('?') => !name.is_empty() && glob_match_at(&pattern[1..], &name[1..]),
+        Some('[') => {
+            let Some((matched, rest)) = name.first().and_then(|c| match_class(&pattern[1..], *c))
+            else {
+                return false;
+            };
+            matched && glob_match_at(rest, &name[1..])
+        }
+        Some(c) => name.first() == Some(c) && glob_match_at(&pattern[1..], &name[1..]),
+    }
+}
+
+/// Matches `c` against the class after a `[`, returning whether it matched
+/// and the pattern following the closing `]`.
+fn match_class(pattern: &[char], c: char) -> Option<(bool, &[char])> {
+    let (negated, mut i) = match pattern.first() {
+        Some('!') | Some('^') => (true, 1),
+        _ => (false, 0),
+    };
+
+    let mut matched = false;
+    let mut first = true;
+    while i < pattern.len() {
+        if pattern[i] == ']' && !first {
+            return Some((matched != negated, &pattern[i + 1..]));
+        }
+        if i + 2 < pattern.len() && pattern[i + 1] == '-' && pattern[i + 2] != ']' {
+            matched |= (pattern[i]..=pattern[i + 2]).contains(&c);
+            i += 3;
+        } else {
+            matched |= pattern[i] == c;
+            i += 1;
+        }
+        first = false;
+    }
+    None
+}
+
+/// Lists the files under `dir`, descending into subdirectories when
+/// `recursive` is set. Links to directories are followed once, so a link
+/// back up the tree doesn't loop. The result is sorted.
+pub fn walk(dir: &Path, recursive: bool) -> Result<Vec<PathBuf>, Error> {
+    if !dir.is_dir() {
+        return Err(Error::FolderNotFound(dir.to_path_buf()));
+    }
+
+    let mut files = Vec::new();
+    let mut visited = HashSet::new();
+    visited.insert(dir.canonicalize().map_err(Error::AccessDenied)?);
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir).map_err(Error::AccessDenied)? {
+            let Ok(entry) = entry else { continue };
+            let path = entry.path();
+            if path.is_dir() {
+                if recursive
+                    && let Ok(real) = path.canonicalize()
+                    && visited.insert(real)
+                {
+                    pending.push(path);
+                }
+            } else if path.is_file() {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort_unstable();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::TempFile;
+
+    #[test]
+    fn same_size_files_that_differ_are_not_duplicates() {
+        let dir = TempFile::create_dir("media_tools-test").unwrap();
+        let paths = ["a.mp4", "b.mp4", "c.mp4"].map(|name| dir.path().join(name));
+        std::fs::write(&paths[0], b"first clip").unwrap();
+        std::fs::write(&paths[1], b"other clip").unwrap();
+        std::fs::write(&paths[2], b"first clip").unwrap();
+
+        assert_eq!(duplicates(&paths).unwrap(), [(2, 0)]);
+    }
+
+    #[test]
+    fn natural_cmp_orders_digit_runs_as_numbers() {
+        assert_eq!(natural_cmp("clip2.mp4", "clip10.mp4"), Ordering::Less);
+        assert_eq!(natural_cmp("10", "9"), Ordering::Greater);
+        assert_eq!(natural_cmp("part1b", "part1a"), Ordering::Greater);
+        // More digits than a u64 holds.
+        assert_eq!(
+            natural_cmp("x99999999999999999999", "x100000000000000000000"),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn natural_cmp_breaks_ties_on_leading_zeros_and_case() {
+        assert_eq!(natural_cmp("a01", "a1"), Ordering::Less);
+        assert_eq!(natural_cmp("a007", "a7"), Ordering::Less);
+        assert_eq!(natural_cmp("a", "B"), Ordering::Less);
+        assert_eq!(natural_cmp("B", "b"), Ordering::Less);
+        assert_eq!(natural_cmp("clip", "clip1"), Ordering::Less);
+        assert_eq!(natural_cmp("", ""), Ordering::Equal);
+    }
+
+    #[test]
+    fn glob_match_handles_wildcards_at_either_end() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*", "a.mp4"));
+        assert!(glob_match("*.mp4", "a.mp4"));
+        assert!(!glob_match("*.mp4", "a.mp4.part"));
+        assert!(glob_match("clip*", "clip"));
+        assert!(glob_match("*clip*", "my clip 2"));
+        assert!(glob_match("?.mp4", "a.mp4"));
+        assert!(!glob_match("?.mp4", ".mp4"));
+        assert!(glob_match("clip??", "clip01"));
+        assert!(!glob_match("clip?", "clip"));
+    }
+
+    #[test]
+    fn glob_match_handles_empty_patterns_and_classes() {
+        assert!(glob_match("", ""));
+        assert!(!glob_match("", "a"));
+        assert!(glob_match("[a-c]x", "bx"));
+        assert!(!glob_match("[a-c]x", "dx"));
+        assert!(glob_match("[!a]x", "bx"));
+        assert!(!glob_match("[^a]x", "ax"));
+        assert!(glob_match("[]]", "]"));
+        assert!(glob_match("[a-]", "-"));
+        // An unclosed class matches nothing.
+        assert!(!glob_match("[abc", "a"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn walk_follows_a_link_back_up_the_tree_once() {
+        let dir = TempFile::create_dir("media_tools-test").unwrap();
+        let root = dir.path();
+        std::fs::create_dir(root.join("sub")).unwrap();
+        std::fs::write(root.join("a.mp4"), b"").unwrap();
+        std::fs::write(root.join("sub/b.mp4"), b"").unwrap();
+        std::os::unix::fs::symlink(root, root.join("sub/up")).unwrap();
+
+        assert_eq!(
+            walk(root, true).unwrap(),
+            [root.join("a.mp4"), root.join("sub/b.mp4")]
+        );
+    }
+
+    #[test]
+    fn same_content_compares_every_byte() {
+        let dir = TempFile::create_dir("media_tools-test").unwrap();
+        let a = dir.path().join("a");
+        let b = dir.path().join("b");
+        let mut data = vec![7; (1 << 16) + 10];
+        std::fs::write(&a, &data).unwrap();
+        std::fs::write(&b, &data).unwrap();
+        assert!(same_content(&a, &b).unwrap());
+
+        *data.last_mut().unwrap() = 8;
+        std::fs::write(&b, &data).unwrap();
+        assert!(!same_content(&a, &b).unwrap());
+        std::fs::write(&b, &data[..data.len() - 1]).unwrap();
+        assert!(!same_content(&a, &b).unwrap());
+    }
+}
@@ -0,0 +1,131 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, Input, ProcessResult};
+use crate::temp::TempFile;
+use crate::transcode::TranscodeOptions;
+
+/// Where the frames of [`images_to_video`] come from.
+#[derive(Debug, Clone)]
+pub enum ImageInput {
+    /// A numbered sequence such as `frames/frame_%04d.png`.
+    Pattern(PathBuf),
+    /// Images in the order they are shown, whatever their names. They are
+    /// linked into a numbered sequence in a temp folder first, so they must
+    /// share one format.
+    Files(Vec<PathBuf>),
+}
+
+#[derive(Debug, Clone)]
+pub struct ImageSequenceOptions {
+    /// Frames per second, the images are shown for `1 / fps` each.
+    pub fps: f64,
+    /// The number of the first image of a [`ImageInput::Pattern`], by
+    /// default the lowest ffmpeg finds.
+    pub start_number: Option<u32>,
+    pub transcode: TranscodeOptions,
+}
+
+impl Default for ImageSequenceOptions {
+    fn default() -> Self {
+        Self {
+            fps: 30.0,
+            start_number: None,
+            transcode: TranscodeOptions::default(),
+        }
+    }
+}
+
+/// Links `files` as `000000.ext`, `000001.ext`, ... into a temp folder,
+/// falling back to copies where links can't be made. Returns the folder and
+/// the sequence pattern in it.
+pub fn link_sequence<P: AsRef<Path>>(files: &[P]) -> Result<(TempFile, PathBuf), Error> {
+    let first = files
+        .first()
+        .ok_or_else(|| Error::invalid("no images to make a video from"))?
+        .as_ref();
+    let ext = first
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let dir = TempFile::create_dir("media_tools-images")?;
+    for (i, file) in files.iter().enumerate() {
+        let file = std::path::absolute(file.as_ref()).map_err(Error::AccessDenied)?;
+        if !file.is_file() {
+            return Err(Error::FileNotFound(file));
+        }
+        let link = dir.path().join(format!("{i:06}.{ext}"));
+        link_file(&file, &link).map_err(Error::CreateOutputError)?;
+    }
+
+    let pattern = dir.path().join(format!("%06d.{ext}"));
+    Ok((dir, pattern))
+}
+
+fn link_file(file: &Path, link: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    if std::os::unix::fs::symlink(file, link).is_ok() {
+        return Ok(());
+    }
+    if std::fs::hard_link(file, link).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(file, link).map(|_| ())
+}
+
+pub fn images_to_video_command(
+    images: &ImageInput,
+    output: &Path,
+    options: &ImageSequenceOptions,
+) -> Result<FfmpegCommand, Error> {
+    if !(options.fps.is_finite() && options.fps > 0.0) {
+        return Err(Error::invalid("the frame rate must be positive"));
+    }
+
+    let (pattern, start_number, frames, dir) = match images {
+        ImageInput::Pattern(pattern) => {
+            let folder = pattern.parent().filter(|p| !p.as_os_str().is_empty());
+            if let Some(folder) = folder.filter(|f| !f.is_dir()) {
+                return Err(Error::FolderNotFound(folder.to_path_buf()));
+            }
+            (pattern.clone(), options.start_number, None, None)
+        }
+        ImageInput::Files(files) => {
+            let (dir, pattern) = link_sequence(files)?;
+            (pattern, Some(0), Some(files.len()), Some(dir))
+        }
+    };
+
+    let mut input = Input::new(&pattern)
+        .format("image2")
+        .args(["-framerate", &options.fps.to_string()]);
+    if let Some(number) = start_number {
+        input = input.args(["-start_number", &number.to_string()]);
+    }
+
+    // Most players can't show the 4:4:4 or RGB video encoders make from
+    // still images.
+    let mut command = FfmpegCommand::new()
+        .add_input(input)
+        .video_filter("format=yuv420p");
+    if let Some(dir) = dir {
+        command = command.temp_file(dir);
+    }
+    if let Some(frames) = frames {
+        command = command.duration(Duration::from_secs_f64(frames as f64 / options.fps));
+    }
+
+    Ok(options.transcode.apply(command).output(output))
+}
+
+pub fn images_to_video(
+    images: &ImageInput,
+    output: &Path,
+    options: &ImageSequenceOptions,
+) -> Result<ProcessResult, Error> {
+    images_to_video_command(images, output, options)?
+        .run()?
+        .check()
+}
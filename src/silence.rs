@@ -0,0 +1,182 @@
+use std::path::Path;
+use std::time::Duration;
+
+use crate::Error;
+use crate::detect::detected_ranges;
+use crate::ffmpeg::{FfmpegCommand, ProcessResult};
+use crate::probe::{MediaInfo, probe};
+use crate::time::TimeRange;
+use crate::transcode::TranscodeOptions;
+
+#[derive(Debug, Clone)]
+pub struct SilenceOptions {
+    /// Audio quieter than this, in dBFS, counts as silence.
+    pub threshold: f64,
+    /// Shorter pauses are left alone.
+    pub min_duration: Duration,
+    /// How much of each silence is kept on either side of a cut, so speech
+    /// doesn't start or stop abruptly.
+    pub padding: Duration,
+    pub transcode: TranscodeOptions,
+}
+
+impl Default for SilenceOptions {
+    fn default() -> Self {
+        Self {
+            threshold: -30.0,
+            min_duration: Duration::from_millis(500),
+            padding: Duration::from_millis(100),
+            transcode: TranscodeOptions::default(),
+        }
+    }
+}
+
+/// Runs silencedetect over the first audio stream.
+pub fn detect_silence(input: &Path, options: &SilenceOptions) -> Result<Vec<TimeRange>, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+    let duration = probe(input)?.duration;
+    silences(input, options, duration)
+}
+
+/// The silencedetect pass over the first audio stream, which prints the
+/// silences to stderr.
+pub fn detect_silence_command(
+    input: &Path,
+    options: &SilenceOptions,
+) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+    if !options.threshold.is_finite() {
+        return Err(Error::invalid("the silence threshold must be a number"));
+    }
+
+    Ok(FfmpegCommand::new()
+        .input(input)
+        .map("0:a:0")
+        .audio_filter(format!(
+            "silencedetect=noise={}dB:d={}",
+            options.threshold,
+            options.min_duration.as_secs_f64()
+        ))
+        .format("null")
+        .output("-"))
+}
+
+fn silences(
+    input: &Path,
+    options: &SilenceOptions,
+    duration: Option<Duration>,
+) -> Result<Vec<TimeRange>, Error> {
+    let result = detect_silence_command(input, options)?.run()?.check()?;
+
+    Ok(detected_ranges(
+        &result.stderr,
+        "silence_start",
+        "silence_end",
+        duration,
+    ))
+}
+
+/// Cuts every silence out of `input`, re-encoding both audio and video.
+pub fn remove_silence_command(
+    input: &Path,
+    output: &Path,
+    options: &SilenceOptions,
+) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+    let info = probe(input)?;
+    if info.audio().is_none() {
+        return Err(Error::StreamNotFound);
+    }
+
+    // Padding isn't needed where there is nothing to lead into.
+    let end = info.duration.unwrap_or(Duration::MAX);
+    let cuts: Vec<TimeRange> = silences(input, options, info.duration)?
+        .into_iter()
+        .map(|silence| TimeRange {
+            start: if silence.start.is_zero() {
+                silence.start
+            } else {
+                silence.start + options.padding
+            },
+            end: if silence.end >= end {
+                silence.end
+            } else {
+                silence.end.saturating_sub(options.padding)
+            },
+        })
+        .filter(|cut| cut.end > cut.start)
+        .collect();
+
+    let silent = cuts
+        .iter()
+        .map(|cut| {
+            format!(
+                "between(t,{},{})",
+                cut.start.as_secs_f64(),
+                cut.end.as_secs_f64()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("+");
+    let removed = cuts.iter().map(TimeRange::duration).sum();
+    Ok(cut_pass(input, output, options, &info, &silent, removed))
+}
+
+/// For a dry run: the silencedetect pass, and the cut with `DETECTED` where
+/// the silences it finds go, as nothing is run to find them.
+pub fn remove_silence_preview(
+    input: &Path,
+    output: &Path,
+    options: &SilenceOptions,
+) -> Result<[FfmpegCommand; 2], Error> {
+    let detect = detect_silence_command(input, options)?;
+    let info = probe(input)?;
+    if info.audio().is_none() {
+        return Err(Error::StreamNotFound);
+    }
+    Ok([
+        detect,
+        cut_pass(input, output, options, &info, "DETECTED", Duration::ZERO),
+    ])
+}
+
+/// Leaves out what `silent`, a sum of `between` expressions, is true for,
+/// `removed` in all.
+fn cut_pass(
+    input: &Path,
+    output: &Path,
+    options: &SilenceOptions,
+    info: &MediaInfo,
+    silent: &str,
+    removed: Duration,
+) -> FfmpegCommand {
+    let mut command = FfmpegCommand::new().input(input);
+    if !silent.is_empty() {
+        let kept = format!("not({silent})");
+        if info.video().is_some() {
+            command = command.video_filter(format!("select='{kept}',setpts=N/FRAME_RATE/TB"));
+        }
+        command = command.audio_filter(format!("aselect='{kept}',asetpts=N/SR/TB"));
+    }
+    if let Some(duration) = info.duration {
+        command = command.duration(duration.saturating_sub(removed));
+    }
+
+    options.transcode.apply(command).output(output)
+}
+
+pub fn remove_silence(
+    input: &Path,
+    output: &Path,
+    options: &SilenceOptions,
+) -> Result<ProcessResult, Error> {
+    remove_silence_command(input, output, options)?
+        .run()?
+        .check()
+}
@@ -0,0 +1,186 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::Error;
+use crate::log::warning;
+
+/// What to do when an output may not fit on its disk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SpaceCheck {
+    /// Fail with [`Error::InsufficientSpace`] before starting.
+    #[default]
+    Refuse,
+    /// Log a warning and start anyway.
+    Warn,
+    /// Don't look.
+    Off,
+}
+
+impl FromStr for SpaceCheck {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "refuse" => Ok(SpaceCheck::Refuse),
+            "warn" => Ok(SpaceCheck::Warn),
+            "off" => Ok(SpaceCheck::Off),
+            _ => Err(Error::invalid(format!("unknown space check `{s}`"))),
+        }
+    }
+}
+
+impl SpaceCheck {
+    /// Checks that `needed` bytes fit where `output` is written. Nothing is
+    /// checked when the free space can't be found out.
+    pub(crate) fn check(self, output: &Path, needed: u64) -> Result<(), Error> {
+        if self == SpaceCheck::Off {
+            return Ok(());
+        }
+        let Some(available) = available_space(output) else {
+            return Ok(());
+        };
+        if needed <= available {
+            return Ok(());
+        }
+        let error = Error::InsufficientSpace { needed, available };
+        match self {
+            SpaceCheck::Refuse => Err(error),
+            _ => {
+                warning!("{error}");
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The bytes that can be written to the filesystem `path` is, or will be
+/// created, on. `None` where the platform can't tell.
+pub fn available_space(path: &Path) -> Option<u64> {
+    let absolute = std::path::absolute(path).ok()?;
+    let dir = absolute.ancestors().find(|dir| dir.is_dir())?;
+    free_bytes(dir)
+}
+
+#[cfg(all(
+    any(target_os = "linux", target_os = "android"),
+    target_pointer_width = "64"
+))]
+fn free_bytes(dir: &Path) -> Option<u64> {
+    #[repr(C)]
+    struct StatVfs {
+        f_bsize: u64,
+        f_frsize: u64,
+        f_blocks: u64,
+        f_bfree: u64,
+        f_bavail: u64,
+        f_files: u64,
+        f_ffree: u64,
+        f_favail: u64,
+        f_fsid: u64,
+        f_flag: u64,
+        f_namemax: u64,
+        spare: [i32; 6],
+    }
+    unsafe extern "C" {
+        fn statvfs(path: *const std::ffi::c_char, buf: *mut StatVfs) -> i32;
+    }
+
+    let path = std::ffi::CString::new(dir.as_os_str().as_encoded_bytes()).ok()?;
+    let mut stat = std::mem::MaybeUninit::<StatVfs>::uninit();
+    // SAFETY: `path` is NUL-terminated and `stat` is as large as the C struct.
+    let stat = unsafe {
+        if statvfs(path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return None;
+        }
+        stat.assume_init()
+    };
+    Some(stat.f_bavail.saturating_mul(stat.f_frsize))
+}
+
+#[cfg(target_os = "macos")]
+fn free_bytes(dir: &Path) -> Option<u64> {
+    // Darwin's block counts are 32 bits wide.
+    #[repr(C)]
+    struct StatVfs {
+        f_bsize: u64,
+        f_frsize: u64,
+        f_blocks: u32,
+        f_bfree: u32,
+        f_bavail: u32,
+        f_files: u32,
+        f_ffree: u32,
+        f_favail: u32,
+        f_fsid: u64,
+        f_flag: u64,
+        f_namemax: u64,
+    }
+    unsafe extern "C" {
+        fn statvfs(path: *const std::ffi::c_char, buf: *mut StatVfs) -> i32;
+    }
+
+    let path = std::ffi::CString::new(dir.as_os_str().as_encoded_bytes()).ok()?;
+    let mut stat = std::mem::MaybeUninit::<StatVfs>::uninit();
+    // SAFETY: `path` is NUL-terminated and `stat` is as large as the C struct.
+    let stat = unsafe {
+        if statvfs(path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return None;
+        }
+        stat.assume_init()
+    };
+    Some(u64::from(stat.f_bavail).saturating_mul(stat.f_frsize))
+}
+
+#[cfg(windows)]
+fn free_bytes(dir: &Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+
+    unsafe extern "system" {
+        fn GetDiskFreeSpaceExW(
+            directory: *const u16,
+            available: *mut u64,
+            total: *mut u64,
+            free: *mut u64,
+        ) -> i32;
+    }
+
+    let wide: Vec<u16> = dir.as_os_str().encode_wide().chain([0]).collect();
+    let mut available = 0u64;
+    // SAFETY: `wide` is NUL-terminated and the sizes may be null.
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    (ok != 0).then_some(available)
+}
+
+#[cfg(not(any(
+    all(
+        any(target_os = "linux", target_os = "android"),
+        target_pointer_width = "64"
+    ),
+    target_os = "macos",
+    windows
+)))]
+fn free_bytes(_dir: &Path) -> Option<u64> {
+    None
+}
+
+/// Formats a byte count for messages, e.g. `1.5 GB`.
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
@@ -0,0 +1,117 @@
+use std::path::Path;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, ProcessResult};
+use crate::pipeline::{Operation, Stage};
+use crate::probe::probe;
+use crate::transcode::TranscodeOptions;
+
+#[derive(Debug, Clone)]
+pub struct SpeedOptions {
+    /// Playback rate, e.g. 0.5 for slow motion or 4 for a timelapse.
+    pub factor: f64,
+    /// Drop the audio instead of changing its tempo.
+    pub mute: bool,
+    pub transcode: TranscodeOptions,
+}
+
+impl Default for SpeedOptions {
+    fn default() -> Self {
+        Self {
+            factor: 1.0,
+            mute: false,
+            transcode: TranscodeOptions::default(),
+        }
+    }
+}
+
+impl SpeedOptions {
+    fn check(&self) -> Result<(), Error> {
+        if !self.factor.is_finite() || self.factor <= 0.0 {
+            return Err(Error::invalid("the speed factor must be positive"));
+        }
+        Ok(())
+    }
+}
+
+impl Operation for SpeedOptions {
+    fn name(&self) -> &'static str {
+        "speed"
+    }
+
+    fn options(&self) -> Option<&TranscodeOptions> {
+        Some(&self.transcode)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        self.check()
+    }
+
+    fn build_args(&self, input: &Path, output: &Path) -> Result<FfmpegCommand, Error> {
+        speed_command(input, output, self)
+    }
+
+    /// Dropping the audio takes a run of its own.
+    fn fuse(&self, stage: &mut Stage) -> Result<bool, Error> {
+        if self.mute {
+            return Ok(false);
+        }
+        self.check()?;
+        let factor = self.factor;
+        stage.video(format!("setpts=PTS/{factor}"));
+        stage.audio(atempo_chain(factor));
+        stage.set_duration(stage.duration().map(|duration| duration.div_f64(factor)));
+        Ok(true)
+    }
+}
+
+/// atempo only accepts 0.5 to 2.0, so larger changes are split into steps
+/// that multiply to the factor.
+fn atempo_chain(mut factor: f64) -> String {
+    let mut steps = Vec::new();
+    while factor > 2.0 {
+        steps.push(2.0);
+        factor /= 2.0;
+    }
+    while factor < 0.5 {
+        steps.push(0.5);
+        factor /= 0.5;
+    }
+    steps.push(factor);
+
+    steps
+        .iter()
+        .map(|step| format!("atempo={step}"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub fn speed_command(
+    input: &Path,
+    output: &Path,
+    options: &SpeedOptions,
+) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+    options.check()?;
+
+    let factor = options.factor;
+    let mut command = FfmpegCommand::new()
+        .input(input)
+        .video_filter(format!("setpts=PTS/{factor}"));
+    command = if options.mute {
+        command.arg("-an")
+    } else {
+        command.audio_filter(atempo_chain(factor))
+    };
+    if let Some(duration) = probe(input).ok().and_then(|info| info.duration) {
+        command = command.duration(duration.div_f64(factor));
+    }
+
+    Ok(options.transcode.apply(command).output(output))
+}
+
+pub fn speed(input: &Path, output: &Path, options: &SpeedOptions) -> Result<ProcessResult, Error> {
+    speed_command(input, output, options)?.run()?.check()
+}
@@ -0,0 +1,227 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, ProcessResult};
+use crate::frames::render_pattern;
+use crate::probe::probe;
+use crate::time::format_timestamp;
+use crate::trim::TrimMode;
+
+/// Where a split cuts the input.
+#[derive(Debug, Clone)]
+pub enum SplitPoints {
+    /// Segments of a fixed length.
+    Every(Duration),
+    /// Cuts at the given times.
+    At(Vec<Duration>),
+    /// Cuts at shot boundaries whose scene score exceeds the threshold (0-1).
+    Scenes(f64),
+}
+
+#[derive(Debug, Clone)]
+pub struct SplitOptions {
+    pub points: SplitPoints,
+    /// With stream copy the cuts snap to the next keyframe, re-encoding
+    /// places keyframes at the exact boundaries.
+    pub mode: TrimMode,
+}
+
+impl Default for SplitOptions {
+    fn default() -> Self {
+        Self {
+            points: SplitPoints::Every(Duration::from_secs(600)),
+            mode: TrimMode::Copy,
+        }
+    }
+}
+
+/// The scene detection pass, which prints the frames whose scene change
+/// score exceeds `threshold` to stderr.
+pub fn detect_scenes_command(input: &Path, threshold: f64) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(Error::invalid(
+            "the scene threshold must be between 0 and 1",
+        ));
+    }
+
+    Ok(FfmpegCommand::new()
+        .input(input)
+        .video_filter(format!("select='gt(scene,{threshold})',showinfo"))
+        .arg("-an")
+        .format("null")
+        .output("-"))
+}
+
+/// The times of the frames whose scene change score exceeds `threshold`.
+pub fn detect_scenes(input: &Path, threshold: f64) -> Result<Vec<Duration>, Error> {
+    let result = detect_scenes_command(input, threshold)?.run()?.check()?;
+
+    Ok(result
+        .stderr
+        .lines()
+        .filter(|line| line.contains("Parsed_showinfo"))
+        .filter_map(|line| line.split_once("pts_time:"))
+        .filter_map(|(_, rest)| rest.split_whitespace().next()?.parse::<f64>().ok())
+        .filter(|secs| *secs > 0.0)
+        .map(Duration::from_secs_f64)
+        .collect())
+}
+
+/// `output` is a pattern such as `out_%03d.mp4`, numbered from 0.
+pub fn split_command(
+    input: &Path,
+    output: &Path,
+    options: &SplitOptions,
+) -> Result<FfmpegCommand, Error> {
+    check_split(input, output)?;
+    let points = match &options.points {
+        SplitPoints::Scenes(threshold) => SplitPoints::At(detect_scenes(input, *threshold)?),
+        points => points.clone(),
+    };
+    let (segment_args, keyframes) = match &points {
+        SplitPoints::Every(length) if length.is_zero() => {
+            return Err(Error::invalid("the segment time must be positive"));
+        }
+        SplitPoints::Every(length) => {
+            let length = format_timestamp(*length);
+            (
+                ["-segment_time".to_string(), length.clone()],
+                format!("expr:gte(t,n_forced*{length})"),
+            )
+        }
+        SplitPoints::At(times) if times.is_empty() => {
+            return Err(Error::invalid("there are no points to split at"));
+        }
+        SplitPoints::At(times) => {
+            let times = times
+                .iter()
+                .map(|t| format_timestamp(*t))
+                .collect::<Vec<_>>()
+                .join(",");
+            (["-segment_times".to_string(), times.clone()], times)
+        }
+        SplitPoints::Scenes(_) => unreachable!(),
+    };
+    Ok(segment_pass(
+        input,
+        output,
+        options,
+        segment_args,
+        &keyframes,
+    ))
+}
+
+/// For a dry run: with [`SplitPoints::Scenes`] the scene detection pass,
+/// and the split with `DETECTED` where the times it finds go, as nothing is
+/// run to find them. Other splits are the same one command as
+/// [`split_command`].
+pub fn split_preview(
+    input: &Path,
+    output: &Path,
+    options: &SplitOptions,
+) -> Result<Vec<FfmpegCommand>, Error> {
+    let SplitPoints::Scenes(threshold) = options.points else {
+        return Ok(vec![split_command(input, output, options)?]);
+    };
+    check_split(input, output)?;
+    let times = "DETECTED".to_string();
+    Ok(vec![
+        detect_scenes_command(input, threshold)?,
+        segment_pass(
+            input,
+            output,
+            options,
+            ["-segment_times".to_string(), times.clone()],
+            &times,
+        ),
+    ])
+}
+
+fn check_split(input: &Path, output: &Path) -> Result<(), Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+    if !output.to_string_lossy().contains('%') {
+        return Err(Error::invalid(
+            "the output needs a number pattern such as out_%03d.mp4",
+        ));
+    }
+    Ok(())
+}
+
+/// Writes the segments `segment_args` ask for, forcing `keyframes` where they
+/// start when re-encoding.
+fn segment_pass(
+    input: &Path,
+    output: &Path,
+    options: &SplitOptions,
+    segment_args: [String; 2],
+    keyframes: &str,
+) -> FfmpegCommand {
+    let mut command = FfmpegCommand::new().input(input).map("0");
+    if let Some(duration) = probe(input).ok().and_then(|info| info.duration) {
+        command = command.duration(duration);
+    }
+    command = match &options.mode {
+        TrimMode::Copy => command.codec_copy(),
+        TrimMode::Reencode(transcode) => transcode
+            .apply(command)
+            .args(["-force_key_frames", keyframes]),
+    };
+
+    command
+        .format("segment")
+        .args(segment_args)
+        .args(["-reset_timestamps", "1"])
+        .output(output)
+}
+
+/// The segments a split wrote for the `output` pattern, in order.
+pub fn written_segments(output: &Path) -> Vec<PathBuf> {
+    let Some(pattern) = output.file_name().and_then(|name| name.to_str()) else {
+        return Vec::new();
+    };
+    (0..)
+        .map(|i| output.with_file_name(render_pattern(pattern, i)))
+        .take_while(|path| path.is_file())
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct SplitOutput {
+    pub result: ProcessResult,
+    pub segments: Vec<PathBuf>,
+    /// The requested cut times, detected ones for [`SplitPoints::Scenes`].
+    pub cuts: Vec<Duration>,
+}
+
+pub fn split(input: &Path, output: &Path, options: &SplitOptions) -> Result<SplitOutput, Error> {
+    let mut options = options.clone();
+    if let SplitPoints::Scenes(threshold) = options.points {
+        options.points = SplitPoints::At(detect_scenes(input, threshold)?);
+    }
+
+    let command = split_command(input, output, &options)?;
+    let cuts = match &options.points {
+        SplitPoints::At(times) => times.clone(),
+        SplitPoints::Every(length) => match command.expected_duration() {
+            Some(total) => (1..)
+                .map(|i| *length * i)
+                .take_while(|t| *t < total)
+                .collect(),
+            None => Vec::new(),
+        },
+        SplitPoints::Scenes(_) => unreachable!(),
+    };
+
+    let result = command.run()?.check()?;
+    Ok(SplitOutput {
+        result,
+        segments: written_segments(output),
+        cuts,
+    })
+}
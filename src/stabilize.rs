@@ -0,0 +1,103 @@
+use std::path::Path;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, ProcessResult, escape_filter_arg};
+use crate::probe::probe;
+use crate::temp::TempFile;
+use crate::transcode::TranscodeOptions;
+
+#[derive(Debug, Clone)]
+pub struct StabilizeOptions {
+    /// How shaky the footage is, from 1 (a little) to 10 (very).
+    pub shakiness: u8,
+    /// How many frames before and after each frame the camera path is
+    /// smoothed over; more gives a steadier but less responsive picture.
+    pub smoothing: u32,
+    /// Zoom in by this many percent to hide the borders the moving frame
+    /// leaves, or `None` to zoom as far as each frame needs.
+    pub zoom: Option<f64>,
+    pub transcode: TranscodeOptions,
+}
+
+impl Default for StabilizeOptions {
+    fn default() -> Self {
+        Self {
+            shakiness: 5,
+            smoothing: 10,
+            zoom: None,
+            transcode: TranscodeOptions::default(),
+        }
+    }
+}
+
+/// The two commands to run in order: vidstabdetect writes the camera
+/// motion to a temporary file, which vidstabtransform then smooths out.
+/// The file is removed once both commands are dropped.
+pub fn stabilize_passes(
+    input: &Path,
+    output: &Path,
+    options: &StabilizeOptions,
+) -> Result<Vec<FfmpegCommand>, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+    if !(1..=10).contains(&options.shakiness) {
+        return Err(Error::invalid("the shakiness must be between 1 and 10"));
+    }
+    if options
+        .zoom
+        .is_some_and(|zoom| !(0.0..=100.0).contains(&zoom))
+    {
+        return Err(Error::invalid("the zoom must be between 0 and 100 percent"));
+    }
+
+    let (transforms, _) = TempFile::create("media_tools-stabilize", "trf")?;
+    let path = escape_filter_arg(
+        transforms
+            .path()
+            .to_str()
+            .ok_or_else(|| Error::invalid("the temp folder's path is not valid UTF-8"))?,
+    );
+    let mut base = FfmpegCommand::new().input(input).temp_file(transforms);
+    if let Some(duration) = probe(input).ok().and_then(|info| info.duration) {
+        base = base.duration(duration);
+    }
+
+    // Written to a pipe, which ffmpeg doesn't ask about overwriting.
+    let detect = base
+        .clone()
+        .video_filter(format!(
+            "vidstabdetect=shakiness={}:result={path}",
+            options.shakiness
+        ))
+        .arg("-an")
+        .format("null")
+        .output("-");
+
+    let zoom = match options.zoom {
+        Some(zoom) => format!("zoom={zoom}"),
+        None => "optzoom=2".to_string(),
+    };
+    let mut transform = base.video_filter(format!(
+        "vidstabtransform=input={path}:smoothing={}:{zoom},unsharp=5:5:0.8:3:3:0.4",
+        options.smoothing
+    ));
+    let transcode = &options.transcode;
+    if transcode.audio_codec.is_none() && transcode.audio_bitrate.is_none() {
+        transform = transform.audio_codec("copy");
+    }
+
+    Ok(vec![detect, transcode.apply(transform).output(output)])
+}
+
+pub fn stabilize(
+    input: &Path,
+    output: &Path,
+    options: &StabilizeOptions,
+) -> Result<ProcessResult, Error> {
+    let mut result = None;
+    for command in stabilize_passes(input, output, options)? {
+        result = Some(command.run()?.check()?);
+    }
+    Ok(result.expect("two passes"))
+}
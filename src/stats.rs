@@ -0,0 +1,101 @@
+use std::path::Path;
+
+use crate::Error;
+use crate::ffmpeg::FfmpegCommand;
+use crate::json::Json;
+use crate::probe::probe;
+
+/// Loudness and level statistics of an audio track. Silent tracks have
+/// infinitely low levels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioStats {
+    /// EBU R128 integrated loudness in LUFS.
+    pub integrated: f64,
+    /// EBU R128 loudness range in LU.
+    pub loudness_range: f64,
+    /// The oversampled true peak in dBTP.
+    pub true_peak: f64,
+    /// The highest sample in dBFS.
+    pub peak: f64,
+    pub rms: f64,
+    /// How many samples sit at full scale, which is where clipping leaves
+    /// them. `None` when ffmpeg's astats doesn't count peaks.
+    pub clipped_samples: Option<u64>,
+}
+
+impl AudioStats {
+    /// Reads the summaries ebur128 and astats print when they finish.
+    pub fn parse(stderr: &str) -> Result<Self, Error> {
+        let summary = &stderr[stderr.rfind("Summary:").ok_or(Error::ParseError)?..];
+        let loudness = |label: &str| {
+            summary
+                .lines()
+                .find_map(|line| line.trim().strip_prefix(label))
+                .and_then(|rest| rest.split_whitespace().next()?.parse::<f64>().ok())
+                .ok_or(Error::ParseError)
+        };
+
+        // Only the overall section, not the per-channel ones before it.
+        let overall = stderr
+            .rfind("] Overall")
+            .map(|start| &stderr[start..])
+            .ok_or(Error::ParseError)?;
+        let level = |key: &str| {
+            overall
+                .lines()
+                .filter_map(|line| line.split_once("] ").map(|(_, rest)| rest))
+                .find_map(|rest| rest.strip_prefix(key)?.strip_prefix(':'))
+                .and_then(|value| value.trim().parse::<f64>().ok())
+        };
+
+        let peak = level("Peak level dB").ok_or(Error::ParseError)?;
+        Ok(AudioStats {
+            integrated: loudness("I:")?,
+            loudness_range: loudness("LRA:")?,
+            true_peak: loudness("Peak:")?,
+            peak,
+            rms: level("RMS level dB").ok_or(Error::ParseError)?,
+            clipped_samples: level("Peak count")
+                .map(|count| if peak >= -0.01 { count as u64 } else { 0 }),
+        })
+    }
+
+    pub fn to_json(&self) -> Json {
+        let level = |value: f64| Json::from(value.is_finite().then_some(value));
+        Json::object([
+            ("integrated", level(self.integrated)),
+            ("loudness_range", level(self.loudness_range)),
+            ("true_peak", level(self.true_peak)),
+            ("peak", level(self.peak)),
+            ("rms", level(self.rms)),
+            ("clipped_samples", self.clipped_samples.into()),
+        ])
+    }
+}
+
+/// Decodes the first audio track through ebur128 and astats.
+pub fn audio_stats_command(input: &Path) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+    let info = probe(input)?;
+    if info.audio().is_none() {
+        return Err(Error::StreamNotFound);
+    }
+
+    let mut command = FfmpegCommand::new()
+        .input(input)
+        .map("0:a:0")
+        .audio_filter("ebur128=peak=true:framelog=verbose,astats=metadata=0")
+        .format("null")
+        .output("-");
+    if let Some(duration) = info.duration {
+        command = command.duration(duration);
+    }
+    Ok(command)
+}
+
+pub fn audio_stats(input: &Path) -> Result<AudioStats, Error> {
+    let result = audio_stats_command(input)?.run()?.check()?;
+    AudioStats::parse(&result.stderr)
+}
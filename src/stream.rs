@@ -0,0 +1,155 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::Error;
+use crate::capture::{CaptureOptions, camera_command};
+use crate::error::CONNECTION_MESSAGES;
+use crate::ffmpeg::{FfmpegCommand, Input, ProcessResult};
+use crate::log::warning;
+use crate::probe::probe;
+use crate::record::{RecordOptions, grab_command};
+use crate::time::format_timestamp;
+use crate::transcode::TranscodeOptions;
+
+/// What to send to the server.
+#[derive(Debug, Clone)]
+pub enum StreamSource {
+    /// A media file, read at its own speed.
+    File(PathBuf),
+    Camera(CaptureOptions),
+    Screen(RecordOptions),
+}
+
+#[derive(Debug, Clone)]
+pub struct StreamOptions {
+    pub source: StreamSource,
+    /// Start a file over when it ends instead of ending the stream.
+    pub looping: bool,
+    /// Servers and players usually want a keyframe every two seconds.
+    pub keyframe_interval: Duration,
+    /// How often to start over after the connection drops.
+    pub reconnect_attempts: u32,
+    pub reconnect_delay: Duration,
+    /// Replaces the transcode options of a camera or screen source.
+    pub transcode: TranscodeOptions,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        Self {
+            source: StreamSource::File(PathBuf::new()),
+            looping: false,
+            keyframe_interval: Duration::from_secs(2),
+            reconnect_attempts: 5,
+            reconnect_delay: Duration::from_secs(2),
+            transcode: TranscodeOptions::default(),
+        }
+    }
+}
+
+/// The container a server URL takes, `flv` for RTMP and `mpegts` for SRT.
+fn stream_format(url: &str) -> Result<&'static str, Error> {
+    let scheme = url.split_once("://").map(|(scheme, _)| scheme);
+    match scheme.map(str::to_ascii_lowercase).as_deref() {
+        Some("rtmp" | "rtmps") => Ok("flv"),
+        Some("srt") => Ok("mpegts"),
+        _ => Err(Error::invalid(format!(
+            "`{url}` is not an rtmp://, rtmps:// or srt:// URL"
+        ))),
+    }
+}
+
+/// Sends `options.source` to an RTMP or SRT server, encoded as H.264 and AAC
+/// unless other codecs are set. Runs until the source ends or ffmpeg is
+/// interrupted.
+pub fn stream_command(url: &str, options: &StreamOptions) -> Result<FfmpegCommand, Error> {
+    let format = stream_format(url)?;
+    let transcode = &options.transcode;
+
+    let mut command = match &options.source {
+        StreamSource::File(path) => {
+            if !path.is_file() {
+                return Err(Error::FileNotFound(path.clone()));
+            }
+            let mut input = Input::new(path).arg("-re");
+            if options.looping {
+                input = input.args(["-stream_loop", "-1"]);
+            }
+            let mut command = FfmpegCommand::new()
+                .add_input(input)
+                .map("0:v:0?")
+                .map("0:a:0?");
+            if !options.looping
+                && let Some(duration) = probe(path).ok().and_then(|info| info.duration)
+            {
+                command = command.duration(duration);
+            }
+            if transcode.video_encoder().is_none() {
+                command = command.video_codec("libx264");
+                if transcode.preset.is_none() {
+                    command = command.args(["-preset", "veryfast"]);
+                }
+            }
+            transcode.apply(command.video_filter("format=yuv420p"))
+        }
+        StreamSource::Camera(camera) => camera_command(&CaptureOptions {
+            transcode: transcode.clone(),
+            ..camera.clone()
+        })?,
+        StreamSource::Screen(screen) => grab_command(&RecordOptions {
+            transcode: transcode.clone(),
+            ..screen.clone()
+        })?,
+    };
+
+    if transcode.audio_codec.is_none() {
+        command = command.audio_codec("aac");
+    }
+    if transcode.video_encoder().as_deref() != Some("copy") {
+        let interval = format_timestamp(options.keyframe_interval);
+        command = command.args([
+            "-force_key_frames".to_string(),
+            format!("expr:gte(t,n_forced*{interval})"),
+        ]);
+        // Keeps the rate steady enough for the server's buffers.
+        if let Some(bitrate) = &transcode.video_bitrate {
+            command = command.args(["-maxrate", bitrate, "-bufsize", bitrate]);
+        }
+    }
+
+    Ok(command.format(format).output(url))
+}
+
+/// Whether ffmpeg stopped because the connection to the server failed, as
+/// opposed to a problem with the source or the settings.
+pub fn is_connection_error(error: &Error) -> bool {
+    match error {
+        Error::ProcessFailed(failure) => failure.stderr_tail.iter().any(|line| {
+            CONNECTION_MESSAGES
+                .iter()
+                .any(|message| line.contains(message))
+        }),
+        _ => false,
+    }
+}
+
+/// Runs [`stream_command`], starting over after a dropped connection up to
+/// `options.reconnect_attempts` times. A file source starts from its
+/// beginning again.
+pub fn stream(url: &str, options: &StreamOptions) -> Result<ProcessResult, Error> {
+    let command = stream_command(url, options)?;
+    let mut attempt = 0;
+    loop {
+        match command.run().and_then(ProcessResult::check) {
+            Err(e) if attempt < options.reconnect_attempts && is_connection_error(&e) => {
+                attempt += 1;
+                warning!(
+                    "{e}, reconnecting ({attempt}/{})",
+                    options.reconnect_attempts
+                );
+                std::thread::sleep(options.reconnect_delay);
+            }
+            result => return result,
+        }
+    }
+}
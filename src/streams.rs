@@ -0,0 +1,32 @@
+use crate::ffmpeg::FfmpegCommand;
+
+/// Which streams of the input end up in the output. By default ffmpeg picks
+/// one video and one audio stream, and a subtitle stream if the container
+/// takes it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamSelection {
+    /// `-map` specifiers such as `0:a:1`, passed on as they are.
+    pub maps: Vec<String>,
+    /// Keep the first video stream and this audio track, counting from 1 as
+    /// players do. Ignored when `maps` are given.
+    pub audio_track: Option<usize>,
+    pub drop_subtitles: bool,
+}
+
+impl StreamSelection {
+    pub(crate) fn apply(&self, mut command: FfmpegCommand) -> FfmpegCommand {
+        if !self.maps.is_empty() {
+            for spec in &self.maps {
+                command = command.map(spec);
+            }
+        } else if let Some(track) = self.audio_track {
+            command = command
+                .map("0:v:0?")
+                .map(&format!("0:a:{}", track.saturating_sub(1)));
+        }
+        if self.drop_subtitles {
+            command = command.arg("-sn");
+        }
+        command
+    }
+}
@@ -0,0 +1,116 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, ProcessResult, escape_filter_arg};
+use crate::probe::{StreamKind, probe};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SubtitleMode {
+    /// Render the subtitles into the picture, requires a re-encode.
+    Burn,
+    /// Add the subtitles as a separate, switchable track.
+    #[default]
+    Soft,
+}
+
+impl FromStr for SubtitleMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "burn" => Ok(SubtitleMode::Burn),
+            "soft" => Ok(SubtitleMode::Soft),
+            _ => Err(Error::invalid(format!("unknown subtitle mode `{s}`"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SubtitleOptions {
+    pub mode: SubtitleMode,
+    /// ISO 639-2 language code of a soft subtitle track, e.g. `eng`.
+    pub language: Option<String>,
+    pub title: Option<String>,
+}
+
+/// The subtitle codec a soft track needs for the output container.
+fn subtitle_codec(output: &Path) -> &'static str {
+    let ext = output
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    match ext.as_str() {
+        "mp4" | "m4v" | "mov" => "mov_text",
+        "webm" => "webvtt",
+        _ => "copy",
+    }
+}
+
+pub fn subtitles_command(
+    input: &Path,
+    subtitles: &Path,
+    output: &Path,
+    options: &SubtitleOptions,
+) -> Result<FfmpegCommand, Error> {
+    for path in [input, subtitles] {
+        if !path.is_file() {
+            return Err(Error::FileNotFound(path.to_path_buf()));
+        }
+    }
+
+    let info = probe(input)?;
+    let mut command = FfmpegCommand::new().input(input);
+    if let Some(duration) = info.duration {
+        command = command.duration(duration);
+    }
+
+    command = match options.mode {
+        SubtitleMode::Burn => {
+            let path = subtitles
+                .to_str()
+                .ok_or_else(|| Error::invalid("the subtitle path is not valid UTF-8"))?;
+            command
+                .video_filter(format!("subtitles=filename={}", escape_filter_arg(path)))
+                .audio_codec("copy")
+        }
+        SubtitleMode::Soft => {
+            let track = info
+                .streams
+                .iter()
+                .filter(|s| s.kind == StreamKind::Subtitle)
+                .count();
+            let mut command = command
+                .input(subtitles)
+                .map("0")
+                .map("1:0")
+                .codec_copy()
+                .args(["-c:s", subtitle_codec(output)]);
+            if let Some(language) = &options.language {
+                command = command.args([
+                    format!("-metadata:s:s:{track}"),
+                    format!("language={language}"),
+                ]);
+            }
+            if let Some(title) = &options.title {
+                command =
+                    command.args([format!("-metadata:s:s:{track}"), format!("title={title}")]);
+            }
+            command
+        }
+    };
+
+    Ok(command.output(output))
+}
+
+pub fn subtitles(
+    input: &Path,
+    subtitles: &Path,
+    output: &Path,
+    options: &SubtitleOptions,
+) -> Result<ProcessResult, Error> {
+    subtitles_command(input, subtitles, output, options)?
+        .run()?
+        .check()
+}
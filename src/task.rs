@@ -0,0 +1,111 @@
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use crate::Error;
+use crate::concat::{ConcatOptions, concat};
+use crate::ffmpeg::{FfmpegCommand, ProcessResult};
+use crate::probe::{MediaInfo, probe};
+use crate::transcode::{TranscodeOptions, transcode};
+use crate::trim::{TrimOptions, trim};
+
+struct Shared<T> {
+    /// What the job returned, or what it panicked with.
+    value: Option<thread::Result<T>>,
+    waker: Option<Waker>,
+}
+
+/// A future resolved by a dedicated thread, so waiting on ffmpeg never blocks
+/// an async executor. It works with any runtime since it only relies on the
+/// waker it is polled with. A panic in the job is raised again where the
+/// task is awaited, as [`thread::JoinHandle::join`] would report it.
+pub struct Task<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+impl<T: Send + 'static> Task<T> {
+    pub fn spawn<F>(job: F) -> Self
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let shared = Arc::new(Mutex::new(Shared {
+            value: None,
+            waker: None,
+        }));
+
+        let state = Arc::clone(&shared);
+        thread::spawn(move || {
+            let value = panic::catch_unwind(AssertUnwindSafe(job));
+            let mut state = state.lock().unwrap();
+            state.value = Some(value);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        });
+
+        Task { shared }
+    }
+}
+
+impl<T> Future for Task<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = self.shared.lock().unwrap();
+        match state.value.take() {
+            Some(Ok(value)) => Poll::Ready(value),
+            Some(Err(payload)) => {
+                drop(state);
+                panic::resume_unwind(payload)
+            }
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl FfmpegCommand {
+    pub fn run_async(self) -> Task<Result<ProcessResult, Error>> {
+        Task::spawn(move || self.run())
+    }
+}
+
+pub fn concat_async(
+    path: impl Into<PathBuf>,
+    prefix: impl Into<String>,
+    ext: impl Into<String>,
+    output: impl Into<PathBuf>,
+    options: ConcatOptions,
+) -> Task<Result<ProcessResult, Error>> {
+    let (path, prefix, ext, output) = (path.into(), prefix.into(), ext.into(), output.into());
+    Task::spawn(move || concat(&path, &prefix, &ext, &output, &options))
+}
+
+pub fn transcode_async(
+    input: impl Into<PathBuf>,
+    output: impl Into<PathBuf>,
+    options: TranscodeOptions,
+) -> Task<Result<ProcessResult, Error>> {
+    let (input, output) = (input.into(), output.into());
+    Task::spawn(move || transcode(&input, &output, &options))
+}
+
+pub fn trim_async(
+    input: impl Into<PathBuf>,
+    output: impl Into<PathBuf>,
+    options: TrimOptions,
+) -> Task<Result<ProcessResult, Error>> {
+    let (input, output) = (input.into(), output.into());
+    Task::spawn(move || trim(&input, &output, &options))
+}
+
+pub fn probe_async(path: impl Into<PathBuf>) -> Task<Result<MediaInfo, Error>> {
+    let path = path.into();
+    Task::spawn(move || probe(&path))
+}
@@ -0,0 +1,91 @@
+use std::fs::{File, OpenOptions};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::Error;
+
+static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+/// A file, or a folder with everything in it, in the platform temp directory
+/// that is removed when dropped.
+#[derive(Debug)]
+pub struct TempFile {
+    path: PathBuf,
+    keep: AtomicBool,
+}
+
+impl TempFile {
+    /// Creates a new, uniquely named file such as `media_tools-1234-0-5678.txt`.
+    pub fn create(prefix: &str, ext: &str) -> Result<(Self, File), Error> {
+        loop {
+            let path = unique_path(prefix, &format!(".{ext}"));
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => {
+                    let temp = TempFile {
+                        path,
+                        keep: AtomicBool::new(false),
+                    };
+                    return Ok((temp, file));
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(Error::CreateOutputError(e)),
+            }
+        }
+    }
+
+    /// Creates a new, empty folder such as `media_tools-1234-0-5678`.
+    pub fn create_dir(prefix: &str) -> Result<Self, Error> {
+        loop {
+            let path = unique_path(prefix, "");
+            match std::fs::create_dir(&path) {
+                Ok(()) => return Ok(TempFile::adopt(path)),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(Error::CreateOutputError(e)),
+            }
+        }
+    }
+
+    /// Removes a file another program creates at `path`, once dropped.
+    pub fn adopt(path: impl Into<PathBuf>) -> Self {
+        TempFile {
+            path: path.into(),
+            keep: AtomicBool::new(false),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Leaves the file in place when dropped.
+    pub fn persist(&self) {
+        self.keep.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if self.keep.load(Ordering::Relaxed) {
+            return;
+        }
+        if self.path.is_dir() {
+            let _ = std::fs::remove_dir_all(&self.path);
+        } else {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn unique_path(prefix: &str, suffix: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+    std::env::temp_dir().join(format!(
+        "{prefix}-{}-{}-{nanos}{suffix}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
@@ -0,0 +1,192 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use crate::Error;
+use crate::metadata::Metadata;
+use crate::probe::probe;
+use crate::time::format_datetime;
+
+/// An output name such as `{date}_{stem}_{resolution}.{ext}`.
+///
+/// - `{stem}`, `{ext}` and `{name}` are the input's file stem, extension and
+///   file name.
+/// - `{index}` is a counter, `{index:3}` pads it to three digits.
+/// - `{date}` is the day the input was recorded, from its `creation_time`
+///   tag or else the file's modification time, as `2024-05-01`.
+/// - `{resolution}` is the size of the first video stream, e.g. `1920x1080`.
+///
+/// Braces can't be escaped, so a name can't contain them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputTemplate(String);
+
+impl OutputTemplate {
+    pub fn new(template: impl Into<String>) -> Self {
+        Self(template.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Fills in the placeholders for `input`. Without an `index`, `{index}`
+    /// becomes an ffmpeg counter such as `%03d` for operations that write
+    /// numbered files themselves.
+    pub fn render(&self, input: &Path, index: Option<u64>) -> Result<String, Error> {
+        Ok(self
+            .render_path(input, index)?
+            .to_string_lossy()
+            .into_owned())
+    }
+
+    /// Like [`render`](Self::render), keeping the input's name as it is
+    /// where it isn't valid Unicode.
+    pub fn render_path(&self, input: &Path, index: Option<u64>) -> Result<PathBuf, Error> {
+        let mut rendered = OsString::with_capacity(self.0.len());
+        let mut rest = self.0.as_str();
+        let mut probed = None;
+        while let Some(start) = rest.find('{') {
+            if rest[..start].contains('}') {
+                return Err(self.unclosed());
+            }
+            rendered.push(&rest[..start]);
+            let end = rest[start..].find('}').ok_or_else(|| self.unclosed())? + start;
+            let (key, width) = match rest[start + 1..end].split_once(':') {
+                Some((key, width)) => (key, Some(width)),
+                None => (&rest[start + 1..end], None),
+            };
+            if width.is_some() && key != "index" {
+                return Err(Error::invalid(format!(
+                    "only {{index}} takes a width in `{}`",
+                    self.0
+                )));
+            }
+            let width = match width.map(str::parse::<usize>) {
+                Some(Ok(width)) => width,
+                Some(Err(_)) => {
+                    return Err(Error::invalid(format!("invalid width in `{}`", self.0)));
+                }
+                None if index.is_some() => 0,
+                None => 3,
+            };
+
+            match key {
+                "stem" => rendered.push(input.file_stem().unwrap_or_default()),
+                "ext" => rendered.push(input.extension().unwrap_or_default()),
+                "name" => rendered.push(input.file_name().unwrap_or_default()),
+                "index" => match index {
+                    Some(index) => rendered.push(format!("{index:0width$}")),
+                    None => rendered.push(format!("%0{width}d")),
+                },
+                "date" | "resolution" => {
+                    let info = match &mut probed {
+                        Some(info) => info,
+                        None => probed.insert(probe(input)?),
+                    };
+                    if key == "date" {
+                        rendered.push(recording_date(input, &Metadata::from(&*info)));
+                    } else {
+                        let (width, height) = info
+                            .video()
+                            .and_then(|video| Some((video.width?, video.height?)))
+                            .ok_or(Error::StreamNotFound)?;
+                        rendered.push(format!("{width}x{height}"));
+                    }
+                }
+                _ => {
+                    return Err(Error::invalid(format!(
+                        "unknown placeholder `{{{key}}}` in `{}`",
+                        self.0
+                    )));
+                }
+            }
+            rest = &rest[end + 1..];
+        }
+        if rest.contains('}') {
+            return Err(self.unclosed());
+        }
+        rendered.push(rest);
+        Ok(PathBuf::from(rendered))
+    }
+
+    fn unclosed(&self) -> Error {
+        Error::invalid(format!("unbalanced braces in `{}`", self.0))
+    }
+}
+
+impl From<&str> for OutputTemplate {
+    fn from(template: &str) -> Self {
+        Self::new(template)
+    }
+}
+
+fn recording_date(input: &Path, metadata: &Metadata) -> String {
+    let time = metadata.creation_time().or_else(|| {
+        std::fs::metadata(input)
+            .and_then(|meta| meta.modified())
+            .ok()
+    });
+    match time {
+        Some(time) => format_datetime(time)[..10].to_string(),
+        None => "unknown".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str, index: Option<u64>) -> Result<String, Error> {
+        OutputTemplate::new(template).render(Path::new("/videos/My Clip.mkv"), index)
+    }
+
+    fn message(result: Result<String, Error>) -> String {
+        match result {
+            Err(Error::InvalidArgument(message)) => message,
+            other => panic!("expected an invalid template, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn render_fills_in_the_name_placeholders() {
+        assert_eq!(
+            render("{stem}-small.{ext}", None).unwrap(),
+            "My Clip-small.mkv"
+        );
+        assert_eq!(
+            render("copy of {name}", None).unwrap(),
+            "copy of My Clip.mkv"
+        );
+        assert_eq!(render("no placeholders", None).unwrap(), "no placeholders");
+    }
+
+    #[test]
+    fn render_pads_the_index_or_leaves_it_to_ffmpeg() {
+        assert_eq!(render("{index:3}_{stem}", Some(7)).unwrap(), "007_My Clip");
+        assert_eq!(render("{index}_{stem}", Some(42)).unwrap(), "42_My Clip");
+        assert_eq!(render("frame_{index}.png", None).unwrap(), "frame_%03d.png");
+        assert_eq!(
+            render("frame_{index:5}.png", None).unwrap(),
+            "frame_%05d.png"
+        );
+    }
+
+    #[test]
+    fn render_refuses_unknown_placeholders_and_widths() {
+        assert!(message(render("{size}.mp4", None)).contains("unknown placeholder `{size}`"));
+        assert!(message(render("{}.mp4", None)).contains("unknown placeholder `{}`"));
+        assert!(message(render("{stem:3}.mp4", None)).contains("only {index} takes a width"));
+        assert!(message(render("{index:x}.mp4", None)).contains("invalid width"));
+    }
+
+    #[test]
+    fn render_refuses_unbalanced_braces() {
+        for template in ["{stem.mp4", "{stem}}.mp4", "}{stem}.mp4", "stem}.mp4"] {
+            assert!(
+                message(render(template, None)).contains("unbalanced braces"),
+                "{template}"
+            );
+        }
+        // Doubled braces aren't an escape.
+        assert!(message(render("{{stem}}", None)).contains("unknown placeholder `{{stem}`"));
+    }
+}
@@ -0,0 +1,159 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, ProcessResult, escape_filter_arg};
+use crate::overlay::{Position, enable_expr};
+use crate::probe::probe;
+use crate::transcode::TranscodeOptions;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextContent {
+    Text(String),
+    /// The input's file name.
+    FileName,
+    /// A running `hh:mm:ss:ff` timecode from this start, counting frames at
+    /// the input's frame rate.
+    Timecode(String),
+}
+
+impl Default for TextContent {
+    fn default() -> Self {
+        TextContent::Timecode("00:00:00:00".to_string())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TextOptions {
+    pub content: TextContent,
+    /// A font file, by default fontconfig's default font.
+    pub font: Option<PathBuf>,
+    pub font_size: u32,
+    pub color: String,
+    pub position: Position,
+    /// Distance from the edges in pixels.
+    pub margin: u32,
+    /// Draw a box of this color behind the text, e.g. `black@0.5`.
+    pub box_color: Option<String>,
+    pub start: Option<Duration>,
+    pub end: Option<Duration>,
+    pub transcode: TranscodeOptions,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        Self {
+            content: TextContent::default(),
+            font: None,
+            font_size: 32,
+            color: "white".to_string(),
+            position: Position::default(),
+            margin: 20,
+            box_color: None,
+            start: None,
+            end: None,
+            transcode: TranscodeOptions::default(),
+        }
+    }
+}
+
+/// Burns text into the video with drawtext, copying the audio.
+pub fn draw_text_command(
+    input: &Path,
+    output: &Path,
+    options: &TextOptions,
+) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+    if options.font_size == 0 {
+        return Err(Error::invalid("the font size must be positive"));
+    }
+    if let (Some(start), Some(end)) = (options.start, options.end)
+        && end <= start
+    {
+        return Err(Error::InvalidTimeRange);
+    }
+    let info = probe(input).ok();
+
+    // Text is drawn as it is, without drawtext's %{...} expansion.
+    let content = match &options.content {
+        TextContent::Text(text) => format!("expansion=none:text={}", escape_filter_arg(text)),
+        TextContent::FileName => {
+            let name = input.file_name().unwrap_or_default().to_string_lossy();
+            format!("expansion=none:text={}", escape_filter_arg(&name))
+        }
+        TextContent::Timecode(start) => {
+            if !is_timecode(start) {
+                return Err(Error::invalid(format!(
+                    "invalid timecode `{start}`, expected hh:mm:ss:ff"
+                )));
+            }
+            let rate = info
+                .as_ref()
+                .and_then(|info| info.video()?.frame_rate)
+                .ok_or_else(|| Error::invalid("cannot tell the frame rate for the timecode"))?;
+            format!(
+                "timecode={}:rate={}/{}",
+                escape_filter_arg(start),
+                rate.num,
+                rate.den
+            )
+        }
+    };
+
+    let mut filter = format!(
+        "drawtext={content}:fontsize={}:fontcolor={}:{}",
+        options.font_size,
+        escape_filter_arg(&options.color),
+        options.position.drawtext_expr(options.margin)
+    );
+    if let Some(font) = &options.font {
+        if !font.is_file() {
+            return Err(Error::FileNotFound(font.clone()));
+        }
+        // Filter graphs are text, unlike the paths ffmpeg is given directly.
+        let font = font
+            .to_str()
+            .ok_or_else(|| Error::invalid("the font path is not valid UTF-8"))?;
+        filter.push_str(&format!(":fontfile={}", escape_filter_arg(font)));
+    }
+    if let Some(color) = &options.box_color {
+        filter.push_str(&format!(
+            ":box=1:boxcolor={}:boxborderw={}",
+            escape_filter_arg(color),
+            options.font_size / 4
+        ));
+    }
+    if let Some(enable) = enable_expr(options.start, options.end) {
+        filter.push_str(&format!(":enable='{enable}'"));
+    }
+
+    let mut command = FfmpegCommand::new().input(input).video_filter(filter);
+    if let Some(duration) = info.and_then(|info| info.duration) {
+        command = command.duration(duration);
+    }
+    let transcode = &options.transcode;
+    if transcode.audio_codec.is_none() && transcode.audio_bitrate.is_none() {
+        command = command.audio_codec("copy");
+    }
+
+    Ok(transcode.apply(command).output(output))
+}
+
+pub fn draw_text(
+    input: &Path,
+    output: &Path,
+    options: &TextOptions,
+) -> Result<ProcessResult, Error> {
+    draw_text_command(input, output, options)?.run()?.check()
+}
+
+/// `hh:mm:ss:ff`, or with `;` before the frames for drop-frame rates.
+fn is_timecode(value: &str) -> bool {
+    let parts: Vec<&str> = value.split([':', ';']).collect();
+    parts.len() == 4
+        && parts.iter().all(|part| {
+            !part.is_empty() && part.len() <= 2 && part.bytes().all(|b| b.is_ascii_digit())
+        })
+}
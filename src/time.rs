@@ -0,0 +1,176 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::Error;
+use crate::json::Json;
+
+/// A stretch of a media file, e.g. one a detection filter found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeRange {
+    pub start: Duration,
+    pub end: Duration,
+}
+
+impl TimeRange {
+    pub fn duration(&self) -> Duration {
+        self.end.saturating_sub(self.start)
+    }
+
+    pub fn to_json(&self) -> Json {
+        Json::object([
+            ("start", self.start),
+            ("end", self.end),
+            ("duration", self.duration()),
+        ])
+    }
+}
+
+/// Parses `hh:mm:ss[.ms]`, `mm:ss[.ms]` or plain seconds (`90.5`).
+pub fn parse_timestamp(value: &str) -> Result<Duration, Error> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(Error::InvalidTimestamp);
+    }
+
+    let mut seconds = 0f64;
+    let parts = value.split(':').collect::<Vec<_>>();
+    if parts.len() > 3 {
+        return Err(Error::InvalidTimestamp);
+    }
+
+    for (i, part) in parts.iter().enumerate() {
+        let last = i == parts.len() - 1;
+        let number = if last {
+            part.parse::<f64>().ok().filter(|n| n.is_finite())
+        } else {
+            part.parse::<u64>().ok().map(|n| n as f64)
+        }
+        .filter(|n| *n >= 0.0)
+        .ok_or(Error::InvalidTimestamp)?;
+
+        if i > 0 && number >= 60.0 {
+            return Err(Error::InvalidTimestamp);
+        }
+        seconds = seconds * 60.0 + number;
+    }
+
+    Duration::try_from_secs_f64(seconds).map_err(|_| Error::InvalidTimestamp)
+}
+
+/// Formats a duration the way ffmpeg expects it on the command line.
+pub fn format_timestamp(duration: Duration) -> String {
+    format!("{}.{:03}", duration.as_secs(), duration.subsec_millis())
+}
+
+/// Parses a UTC date and time as containers store it, e.g.
+/// `2024-05-01T12:30:00.000000Z`. The time may be left out, and a space can
+/// stand in for the `T`.
+pub fn parse_datetime(value: &str) -> Result<SystemTime, Error> {
+    let value = value.trim().trim_end_matches('Z');
+    let (date, time) = value.split_once(['T', ' ']).unwrap_or((value, "00:00:00"));
+
+    let mut date = date.split('-').map(|part| part.parse::<u32>().ok());
+    let (Some(Some(year)), Some(Some(month)), Some(Some(day)), None) =
+        (date.next(), date.next(), date.next(), date.next())
+    else {
+        return Err(Error::InvalidTimestamp);
+    };
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || year < 1970 {
+        return Err(Error::InvalidTimestamp);
+    }
+
+    let time = parse_timestamp(time)?;
+    if time >= Duration::from_secs(86_400) {
+        return Err(Error::InvalidTimestamp);
+    }
+    let days = days_from_civil(year as i64, month as i64, day as i64) as u64;
+    Ok(UNIX_EPOCH + Duration::from_secs(days * 86_400) + time)
+}
+
+/// Formats a time the way ffmpeg writes `creation_time`.
+pub fn format_datetime(time: SystemTime) -> String {
+    let since = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:06}Z",
+        secs / 3600 % 24,
+        secs / 60 % 60,
+        secs % 60,
+        since.subsec_micros()
+    )
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_timestamp_reads_clock_times() {
+        assert_eq!(
+            parse_timestamp("01:02:03.5").unwrap(),
+            Duration::from_millis(3_723_500)
+        );
+        assert_eq!(parse_timestamp("2:03").unwrap(), Duration::from_secs(123));
+        assert_eq!(
+            parse_timestamp(" 00:00:00.250 ").unwrap(),
+            Duration::from_millis(250)
+        );
+    }
+
+    #[test]
+    fn parse_timestamp_reads_bare_seconds() {
+        assert_eq!(
+            parse_timestamp("90.5").unwrap(),
+            Duration::from_millis(90_500)
+        );
+        assert_eq!(parse_timestamp("0").unwrap(), Duration::ZERO);
+    }
+
+    #[test]
+    fn parse_timestamp_refuses_malformed_values() {
+        for value in [
+            "", "-1", "-0:10", "1:-10", "1:60", "1:2:3:4", "1.5:00", "abc", "NaN", "inf",
+        ] {
+            assert!(
+                matches!(parse_timestamp(value), Err(Error::InvalidTimestamp)),
+                "{value:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_timestamp_refuses_overflow() {
+        assert!(matches!(
+            parse_timestamp("1e30"),
+            Err(Error::InvalidTimestamp)
+        ));
+        assert!(matches!(
+            parse_timestamp("18446744073709551615:00:00"),
+            Err(Error::InvalidTimestamp)
+        ));
+    }
+}
@@ -0,0 +1,53 @@
+use std::str::FromStr;
+
+use crate::Error;
+
+/// The curve HDR brightness is squeezed into SDR's range with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ToneMap {
+    /// Filmic, keeps detail in the highlights and shadows.
+    #[default]
+    Hable,
+    /// Leaves the midtones alone and rolls off only the highlights.
+    Mobius,
+    /// Simple and a little flat.
+    Reinhard,
+    /// Cuts off whatever SDR cannot show.
+    Clip,
+}
+
+impl ToneMap {
+    pub fn name(self) -> &'static str {
+        match self {
+            ToneMap::Hable => "hable",
+            ToneMap::Mobius => "mobius",
+            ToneMap::Reinhard => "reinhard",
+            ToneMap::Clip => "clip",
+        }
+    }
+
+    /// Converts HDR10 or HLG to BT.709 SDR. zscale reads the source's
+    /// transfer from the frames, tonemap works on linear light, and
+    /// desaturating the highlights is left off since it washes them out.
+    pub(crate) fn filter(self) -> String {
+        format!(
+            "zscale=t=linear:npl=100,format=gbrpf32le,zscale=p=bt709,\
+             tonemap=tonemap={}:desat=0,zscale=t=bt709:m=bt709:r=tv,format=yuv420p",
+            self.name()
+        )
+    }
+}
+
+impl FromStr for ToneMap {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hable" => Ok(ToneMap::Hable),
+            "mobius" => Ok(ToneMap::Mobius),
+            "reinhard" => Ok(ToneMap::Reinhard),
+            "clip" => Ok(ToneMap::Clip),
+            _ => Err(Error::invalid(format!("unknown tone mapping `{s}`"))),
+        }
+    }
+}
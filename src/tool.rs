@@ -0,0 +1,192 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::RwLock;
+
+use crate::Error;
+use crate::ffmpeg::FfmpegCommand;
+use crate::probe::{MediaInfo, probe_with};
+
+/// Environment variable naming the ffmpeg binary to use.
+pub const FFMPEG_ENV: &str = "MEDIA_TOOLS_FFMPEG";
+/// Environment variable naming the ffprobe binary to use.
+pub const FFPROBE_ENV: &str = "MEDIA_TOOLS_FFPROBE";
+
+static DEFAULT: RwLock<Option<Tool>> = RwLock::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncoderKind {
+    Video,
+    Audio,
+    Subtitle,
+}
+
+#[derive(Debug, Clone)]
+pub struct Encoder {
+    pub kind: EncoderKind,
+    pub name: String,
+    pub description: String,
+}
+
+/// The ffmpeg and ffprobe binaries operations run.
+#[derive(Debug, Clone)]
+pub struct Tool {
+    ffmpeg: PathBuf,
+    ffprobe: PathBuf,
+}
+
+impl Default for Tool {
+    fn default() -> Self {
+        Self {
+            ffmpeg: PathBuf::from("ffmpeg"),
+            ffprobe: PathBuf::from("ffprobe"),
+        }
+    }
+}
+
+impl Tool {
+    /// Uses `path` for ffmpeg and the ffprobe next to it.
+    pub fn with_binary(path: impl Into<PathBuf>) -> Self {
+        let ffmpeg = path.into();
+        let ffprobe = sibling(&ffmpeg, "ffprobe");
+        Self { ffmpeg, ffprobe }
+    }
+
+    pub fn with_ffprobe(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ffprobe = path.into();
+        self
+    }
+
+    /// Reads [`FFMPEG_ENV`] and [`FFPROBE_ENV`], falling back to the
+    /// binaries on `PATH`.
+    pub fn from_env() -> Self {
+        let mut tool = match std::env::var_os(FFMPEG_ENV) {
+            Some(path) if !path.is_empty() => Tool::with_binary(path),
+            _ => Tool::default(),
+        };
+        if let Some(path) = std::env::var_os(FFPROBE_ENV).filter(|p| !p.is_empty()) {
+            tool.ffprobe = path.into();
+        }
+        tool
+    }
+
+    /// Makes this the tool used by [`FfmpegCommand::new`] and [`crate::probe`].
+    pub fn install(self) {
+        *DEFAULT.write().unwrap() = Some(self);
+    }
+
+    /// The installed tool, or [`Tool::from_env`] when none was installed.
+    pub fn current() -> Self {
+        DEFAULT
+            .read()
+            .unwrap()
+            .clone()
+            .unwrap_or_else(Tool::from_env)
+    }
+
+    pub fn ffmpeg(&self) -> &Path {
+        &self.ffmpeg
+    }
+
+    pub fn ffprobe(&self) -> &Path {
+        &self.ffprobe
+    }
+
+    pub fn command(&self) -> FfmpegCommand {
+        FfmpegCommand::new().program(&self.ffmpeg)
+    }
+
+    pub fn probe(&self, path: &Path) -> Result<MediaInfo, Error> {
+        probe_with(&self.ffprobe, path)
+    }
+
+    /// Where a binary would be found, searching `PATH` for bare names.
+    pub fn resolve(binary: &Path) -> Option<PathBuf> {
+        if binary.components().count() > 1 {
+            return binary.is_file().then(|| binary.to_path_buf());
+        }
+
+        let names = if cfg!(windows) && binary.extension().is_none() {
+            vec![binary.with_extension("exe"), binary.to_path_buf()]
+        } else {
+            vec![binary.to_path_buf()]
+        };
+        std::env::split_paths(&std::env::var_os("PATH")?)
+            .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+            .find(|path| path.is_file())
+    }
+
+    /// The first line of `-version`, e.g. `ffmpeg version 7.1 Copyright ...`.
+    pub fn version(binary: &Path) -> Result<String, Error> {
+        let output = Command::new(binary)
+            .arg("-version")
+            .output()
+            .map_err(Error::CommandError)?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout.lines().next().unwrap_or_default().trim().to_string())
+    }
+
+    pub fn encoders(&self) -> Result<Vec<Encoder>, Error> {
+        let output = Command::new(&self.ffmpeg)
+            .args(["-hide_banner", "-encoders"])
+            .output()
+            .map_err(Error::CommandError)?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        // The list follows a legend that ends with a ` ------` line.
+        Ok(stdout
+            .lines()
+            .skip_while(|line| !line.trim_start().starts_with("---"))
+            .skip(1)
+            .filter_map(parse_encoder)
+            .collect())
+    }
+
+    /// Whether the installed ffmpeg was built with the given encoder.
+    pub fn has_encoder(&self, name: &str) -> Result<bool, Error> {
+        Ok(self.encoders()?.iter().any(|e| e.name == name))
+    }
+
+    /// Whether the installed ffmpeg was built with the given filter, e.g.
+    /// `libvmaf`.
+    pub fn has_filter(&self, name: &str) -> Result<bool, Error> {
+        let output = Command::new(&self.ffmpeg)
+            .args(["-hide_banner", "-filters"])
+            .output()
+            .map_err(Error::CommandError)?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        // Filters are listed as `flags name inputs->outputs description`.
+        Ok(stdout.lines().any(|line| {
+            let mut parts = line.split_whitespace();
+            parts.nth(1) == Some(name) && parts.next().is_some_and(|io| io.contains("->"))
+        }))
+    }
+}
+
+fn sibling(ffmpeg: &Path, name: &str) -> PathBuf {
+    if ffmpeg.components().count() <= 1 {
+        return PathBuf::from(name);
+    }
+    let mut path = ffmpeg.with_file_name(name);
+    if let Some(ext) = ffmpeg.extension() {
+        path.set_extension(ext);
+    }
+    path
+}
+
+fn parse_encoder(line: &str) -> Option<Encoder> {
+    let mut parts = line.split_whitespace();
+    let flags = parts.next()?;
+    let kind = match flags.chars().next()? {
+        'V' => EncoderKind::Video,
+        'A' => EncoderKind::Audio,
+        'S' => EncoderKind::Subtitle,
+        _ => return None,
+    };
+    let name = parts.next()?.to_string();
+    Some(Encoder {
+        kind,
+        name,
+        description: parts.collect::<Vec<_>>().join(" "),
+    })
+}
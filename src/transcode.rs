@@ -0,0 +1,371 @@
+use std::path::Path;
+
+use crate::Error;
+use crate::container::{Container, StreamAction, StreamPlan, apply_plan, plan_transcode};
+use crate::deinterlace::Deinterlacer;
+use crate::fade::Fades;
+use crate::ffmpeg::{FfmpegCommand, ProcessResult};
+use crate::filters::VideoFilters;
+use crate::hwaccel::HwAccel;
+use crate::network::{self, check_input};
+use crate::pipeline::{Operation, Stage};
+use crate::probe::{MediaInfo, StreamKind, probe};
+use crate::streams::StreamSelection;
+use crate::temp::TempFile;
+use crate::tonemap::ToneMap;
+use crate::tool::Tool;
+
+#[derive(Debug, Clone, Default)]
+pub struct TranscodeOptions {
+    pub video_codec: Option<String>,
+    pub audio_codec: Option<String>,
+    /// Constant rate factor, lower is better quality.
+    pub crf: Option<u8>,
+    /// Encoder speed preset, e.g. `medium` or `slow` for x264/x265.
+    pub preset: Option<String>,
+    pub video_bitrate: Option<String>,
+    pub audio_bitrate: Option<String>,
+    /// The pixel format to encode, e.g. `yuv420p`, which most players need.
+    pub pixel_format: Option<String>,
+    /// Move the MP4 index to the front so playback can start before the
+    /// whole file is downloaded.
+    pub faststart: bool,
+    /// Encode the video on this hardware, the crf becomes its quality value.
+    pub hwaccel: Option<HwAccel>,
+    /// Analyse the video in a first pass to hit the video bitrate more
+    /// closely. Only [`transcode`] and [`transcode_passes`] run both passes.
+    pub two_pass: bool,
+    /// The streams to keep. Only [`transcode`] and [`crate::concat`] look at
+    /// this, the other operations pick their streams themselves.
+    pub streams: StreamSelection,
+    /// Only [`transcode`] and [`crate::trim`] look at this either.
+    pub fades: Fades,
+    /// Deinterlace the video with this filter if the input is interlaced.
+    /// Only [`transcode`] looks at this.
+    pub deinterlace: Option<Deinterlacer>,
+    /// Convert HDR video to SDR with this curve, if the input is HDR. Only
+    /// [`transcode`] looks at this.
+    pub tonemap: Option<ToneMap>,
+    /// Cleanup and color filters, after any deinterlacing. Only
+    /// [`transcode`] looks at this.
+    pub filters: VideoFilters,
+    /// Copy the streams that already have the requested codecs and fit the
+    /// output container, and re-encode only the others, see
+    /// [`transcode_plan`]. Keeps every stream of the input unless
+    /// [`Self::streams`] drops some, and quality settings only reach the
+    /// re-encoded streams. Only [`transcode`] looks at this.
+    pub smart: bool,
+}
+
+impl TranscodeOptions {
+    /// The video encoder to ask for, if any.
+    pub(crate) fn video_encoder(&self) -> Option<String> {
+        match self.hwaccel {
+            Some(accel) => Some(accel.encoder(self.video_codec.as_deref())),
+            None => self.video_codec.clone(),
+        }
+    }
+
+    pub(crate) fn apply(&self, mut command: FfmpegCommand) -> FfmpegCommand {
+        if let Some(filter) = self.hwaccel.and_then(HwAccel::upload_filter) {
+            command = command.video_filter(filter);
+        }
+        self.apply_codecs(command)
+    }
+
+    /// [`apply`](Self::apply) for commands whose video comes out of a filter
+    /// graph, which has to do any hardware upload itself.
+    pub(crate) fn apply_codecs(&self, mut command: FfmpegCommand) -> FfmpegCommand {
+        if let Some(codec) = self.video_encoder() {
+            command = command.video_codec(&codec);
+        }
+        if let Some(codec) = &self.audio_codec {
+            command = command.audio_codec(codec);
+        }
+        self.apply_settings(command)
+    }
+
+    /// Everything [`apply_codecs`](Self::apply_codecs) sets but the codecs.
+    fn apply_settings(&self, mut command: FfmpegCommand) -> FfmpegCommand {
+        if let Some(accel) = self.hwaccel {
+            command = command.global_args(accel.global_args());
+        }
+        if let Some(crf) = self.crf {
+            let arg = self.hwaccel.map_or("-crf", HwAccel::quality_arg);
+            command = command.args([arg, &crf.to_string()]);
+        }
+        if let Some(preset) = &self.preset {
+            command = command.args(["-preset", preset]);
+        }
+        if let Some(bitrate) = &self.video_bitrate {
+            command = command.args(["-b:v", bitrate]);
+        }
+        if let Some(bitrate) = &self.audio_bitrate {
+            command = command.args(["-b:a", bitrate]);
+        }
+        // Uploaded frames are in the hardware's own format.
+        if let Some(format) = &self.pixel_format
+            && self.hwaccel.and_then(HwAccel::upload_filter).is_none()
+        {
+            command = command.args(["-pix_fmt", format]);
+        }
+        if self.faststart {
+            command = command.args(["-movflags", "+faststart"]);
+        }
+
+        command
+    }
+}
+
+impl Operation for TranscodeOptions {
+    fn name(&self) -> &'static str {
+        "transcode"
+    }
+
+    fn options(&self) -> Option<&TranscodeOptions> {
+        Some(self)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if self.two_pass {
+            return Err(Error::invalid("a pipeline step runs in a single pass"));
+        }
+        if self.smart && !self.streams.maps.is_empty() {
+            return Err(Error::invalid(
+                "smart transcoding picks the streams itself, without maps",
+            ));
+        }
+        Ok(())
+    }
+
+    fn build_args(&self, input: &Path, output: &Path) -> Result<FfmpegCommand, Error> {
+        transcode_command(input, output, self)
+    }
+
+    /// Only the encoding settings join a shared run, anything that has to
+    /// look at the input first runs on its own.
+    fn fuse(&self, _stage: &mut Stage) -> Result<bool, Error> {
+        Ok(self.deinterlace.is_none()
+            && self.tonemap.is_none()
+            && self.filters == VideoFilters::default()
+            && self.fades.is_empty()
+            && self.streams == StreamSelection::default()
+            && !self.smart)
+    }
+}
+
+pub fn transcode_command(
+    input: &Path,
+    output: &Path,
+    options: &TranscodeOptions,
+) -> Result<FfmpegCommand, Error> {
+    let command = options.fades.apply(input_command(input, options)?, true)?;
+    if options.smart {
+        let plans = smart_plan(&command, &probe(input)?, output, options)?;
+        return Ok(smart_command(command, &plans, options).output(output));
+    }
+    let command = options.apply(command);
+    Ok(options.streams.apply(command).output(output))
+}
+
+/// What [`transcode_command`] does with each stream when
+/// [`TranscodeOptions::smart`] is set.
+pub fn transcode_plan(
+    input: &Path,
+    output: &Path,
+    options: &TranscodeOptions,
+) -> Result<Vec<StreamPlan>, Error> {
+    let command = options.fades.apply(input_command(input, options)?, true)?;
+    smart_plan(&command, &probe(input)?, output, options)
+}
+
+fn smart_plan(
+    command: &FfmpegCommand,
+    info: &MediaInfo,
+    output: &Path,
+    options: &TranscodeOptions,
+) -> Result<Vec<StreamPlan>, Error> {
+    if !options.streams.maps.is_empty() {
+        return Err(Error::invalid(
+            "smart transcoding picks the streams itself, without maps",
+        ));
+    }
+    let container = Container::from_path(output);
+    let video_encoder = options.video_encoder();
+    let mut plans = plan_transcode(
+        info,
+        container,
+        video_encoder.as_deref(),
+        options.audio_codec.as_deref(),
+    );
+
+    // Filtered streams have to be decoded and encoded again, as do frames
+    // in another pixel format than the one asked for.
+    let upload = options.hwaccel.and_then(HwAccel::upload_filter).is_some();
+    let mut audio_track = 0;
+    for (plan, stream) in plans.iter_mut().zip(&info.streams) {
+        let reencode = match plan.kind {
+            StreamKind::Video => {
+                command.has_video_filters()
+                    || upload
+                    || options
+                        .pixel_format
+                        .as_ref()
+                        .is_some_and(|format| stream.pixel_format.as_ref() != Some(format))
+            }
+            StreamKind::Audio => command.has_audio_filters(),
+            _ => false,
+        };
+        if reencode && plan.action == StreamAction::Copy {
+            let encoder = match plan.kind {
+                StreamKind::Video => video_encoder.clone(),
+                _ => options.audio_codec.clone(),
+            };
+            plan.action = StreamAction::Convert(
+                encoder
+                    .or_else(|| container.fallback(stream).map(str::to_string))
+                    .unwrap_or_else(|| {
+                        let default = if plan.kind == StreamKind::Video {
+                            "libx264"
+                        } else {
+                            "aac"
+                        };
+                        default.to_string()
+                    }),
+            );
+        }
+
+        if plan.kind == StreamKind::Audio {
+            audio_track += 1;
+            if options
+                .streams
+                .audio_track
+                .is_some_and(|track| track != audio_track)
+            {
+                plan.action = StreamAction::Drop;
+            }
+        }
+        if plan.kind == StreamKind::Subtitle && options.streams.drop_subtitles {
+            plan.action = StreamAction::Drop;
+        }
+    }
+
+    if plans.iter().all(|plan| plan.action == StreamAction::Drop) {
+        return Err(Error::StreamNotFound);
+    }
+    Ok(plans)
+}
+
+fn smart_command(
+    mut command: FfmpegCommand,
+    plans: &[StreamPlan],
+    options: &TranscodeOptions,
+) -> FfmpegCommand {
+    let encodes_video = plans.iter().any(|plan| {
+        plan.kind == StreamKind::Video && matches!(plan.action, StreamAction::Convert(_))
+    });
+    if encodes_video && let Some(filter) = options.hwaccel.and_then(HwAccel::upload_filter) {
+        command = command.video_filter(filter);
+    }
+    options.apply_settings(apply_plan(command, plans))
+}
+
+fn input_command(input: &Path, options: &TranscodeOptions) -> Result<FfmpegCommand, Error> {
+    check_input(input)?;
+
+    let mut command = FfmpegCommand::new().add_input(network::input(input));
+    let info = probe(input).ok();
+    if let Some(duration) = info.as_ref().and_then(|info| info.duration) {
+        command = command.duration(duration);
+    }
+    let video = info.as_ref().and_then(|info| info.video());
+    if let Some(deinterlacer) = options.deinterlace
+        && video.is_some_and(|video| video.is_interlaced())
+    {
+        command = command.video_filter(deinterlacer.name());
+    }
+    if let Some(tonemap) = options.tonemap
+        && video.is_some_and(|video| video.is_hdr())
+    {
+        if !Tool::current().has_filter("zscale")? {
+            return Err(Error::invalid(
+                "tone mapping needs an ffmpeg built with zscale (libzimg)",
+            ));
+        }
+        command = command.video_filter(tonemap.filter());
+    }
+    options.filters.apply(command)
+}
+
+/// The commands to run in order, two with [`TranscodeOptions::two_pass`].
+pub fn transcode_passes(
+    input: &Path,
+    output: &Path,
+    options: &TranscodeOptions,
+) -> Result<Vec<FfmpegCommand>, Error> {
+    if !options.two_pass {
+        return Ok(vec![transcode_command(input, output, options)?]);
+    }
+    if options.smart {
+        return Err(Error::invalid("smart transcoding runs in a single pass"));
+    }
+    if options.video_bitrate.is_none() {
+        return Err(Error::invalid("two-pass encoding needs a video bitrate"));
+    }
+    if options.hwaccel.is_some() {
+        return Err(Error::invalid("two-pass encoding needs a software encoder"));
+    }
+
+    // ffmpeg names the logs after this prefix, e.g. `<prefix>-0.log` and
+    // x264's `<prefix>-0.log.mbtree`.
+    let (prefix, _) = TempFile::create("media_tools-passlog", "log")?;
+    let log = prefix.path().as_os_str().to_owned();
+    let base = [
+        "-0.log",
+        "-0.log.mbtree",
+        "-0.log.temp",
+        "-0.log.mbtree.temp",
+    ]
+    .into_iter()
+    .fold(input_command(input, options)?, |command, suffix| {
+        let mut path = log.clone();
+        path.push(suffix);
+        command.temp_file(TempFile::adopt(path))
+    })
+    .temp_file(prefix);
+
+    let first = TranscodeOptions {
+        audio_codec: None,
+        audio_bitrate: None,
+        ..options.clone()
+    };
+    // Written to a pipe, which ffmpeg doesn't ask about overwriting.
+    let pass1 = first
+        .streams
+        .apply(first.apply(options.fades.apply(base.clone(), false)?))
+        .args(["-pass", "1", "-passlogfile"])
+        .arg(&log)
+        .arg("-an")
+        .format("null")
+        .output("-");
+    let pass2 = options
+        .streams
+        .apply(options.apply(options.fades.apply(base, true)?))
+        .args(["-pass", "2", "-passlogfile"])
+        .arg(&log)
+        .output(output);
+
+    Ok(vec![pass1, pass2])
+}
+
+pub fn transcode(
+    input: &Path,
+    output: &Path,
+    options: &TranscodeOptions,
+) -> Result<ProcessResult, Error> {
+    let mut result = None;
+    for command in transcode_passes(input, output, options)? {
+        result = Some(command.run()?.check()?);
+    }
+    Ok(result.expect("at least one pass"))
+}
@@ -0,0 +1,143 @@
+use std::path::Path;
+use std::time::Duration;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, Input, ProcessResult};
+use crate::pipeline::{Operation, Stage};
+use crate::probe::probe;
+use crate::time::{format_timestamp, parse_timestamp};
+use crate::transcode::TranscodeOptions;
+
+// Built once per command, so the size of the options doesn't matter.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, Default)]
+pub enum TrimMode {
+    /// Stream copy, fast but cuts snap to the nearest keyframe.
+    #[default]
+    Copy,
+    /// Re-encode for frame-accurate cuts, or to apply
+    /// [`TranscodeOptions::fades`].
+    Reencode(TranscodeOptions),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TrimOptions {
+    pub start: Option<Duration>,
+    pub end: Option<Duration>,
+    pub mode: TrimMode,
+}
+
+impl TrimOptions {
+    pub fn from_timestamps(start: Option<&str>, end: Option<&str>) -> Result<Self, Error> {
+        Ok(Self {
+            start: start.map(parse_timestamp).transpose()?,
+            end: end.map(parse_timestamp).transpose()?,
+            mode: TrimMode::Copy,
+        })
+    }
+
+    fn check(&self) -> Result<(), Error> {
+        if self
+            .end
+            .is_some_and(|end| end <= self.start.unwrap_or_default())
+        {
+            return Err(Error::InvalidTimeRange);
+        }
+        Ok(())
+    }
+}
+
+impl Operation for TrimOptions {
+    fn name(&self) -> &'static str {
+        "trim"
+    }
+
+    fn options(&self) -> Option<&TranscodeOptions> {
+        match &self.mode {
+            TrimMode::Copy => None,
+            TrimMode::Reencode(transcode) => Some(transcode),
+        }
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        self.check()
+    }
+
+    fn build_args(&self, input: &Path, output: &Path) -> Result<FfmpegCommand, Error> {
+        trim_command(input, output, self)
+    }
+
+    /// A stream copy runs on its own, as it is cheap and the steps after it
+    /// can still share a run.
+    fn fuse(&self, stage: &mut Stage) -> Result<bool, Error> {
+        let TrimMode::Reencode(transcode) = &self.mode else {
+            return Ok(false);
+        };
+        if !transcode.fades.is_empty() {
+            return Ok(false);
+        }
+        self.check()?;
+
+        let start = self.start.unwrap_or_default();
+        let length = self.end.map(|end| end - start);
+        if stage.is_empty() {
+            if !start.is_zero() {
+                stage.input_args(["-ss".to_string(), format_timestamp(start)])?;
+            }
+            if let Some(length) = length {
+                stage.input_args(["-t".to_string(), format_timestamp(length)])?;
+            }
+        } else {
+            let mut range = format!("start={:.3}", start.as_secs_f64());
+            if let Some(end) = self.end {
+                range.push_str(&format!(":end={:.3}", end.as_secs_f64()));
+            }
+            stage.video(format!("trim={range},setpts=PTS-STARTPTS"));
+            stage.audio(format!("atrim={range},asetpts=PTS-STARTPTS"));
+        }
+        stage.set_duration(
+            length.or_else(|| stage.duration().map(|total| total.saturating_sub(start))),
+        );
+        Ok(true)
+    }
+}
+
+pub fn trim_command(
+    input: &Path,
+    output: &Path,
+    options: &TrimOptions,
+) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+
+    options.check()?;
+    let start = options.start.unwrap_or_default();
+
+    let mut source = Input::new(input);
+    if !start.is_zero() {
+        source = source.args(["-ss", &format_timestamp(start)]);
+    }
+
+    let mut command = FfmpegCommand::new().add_input(source);
+    if let Some(end) = options.end {
+        command = command
+            .args(["-t", &format_timestamp(end - start)])
+            .duration(end - start);
+    } else if let Some(total) = probe(input).ok().and_then(|info| info.duration) {
+        command = command.duration(total.saturating_sub(start));
+    }
+
+    command = match &options.mode {
+        TrimMode::Copy => command
+            .codec_copy()
+            .args(["-avoid_negative_ts", "make_zero"]),
+        TrimMode::Reencode(transcode) => transcode.apply(transcode.fades.apply(command, true)?),
+    };
+
+    Ok(command.output(output))
+}
+
+pub fn trim(input: &Path, output: &Path, options: &TrimOptions) -> Result<ProcessResult, Error> {
+    trim_command(input, output, options)?.run()?.check()
+}
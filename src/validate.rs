@@ -0,0 +1,100 @@
+use std::ffi::OsStr;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, Input};
+use crate::network::is_url;
+use crate::probe::probe;
+use crate::time::format_timestamp;
+use crate::tool::Tool;
+
+/// Checks that ffmpeg really wrote a usable `path`: it probes, its duration
+/// is within `tolerance` of `expected`, and its first and last frames
+/// decode. ffmpeg can exit successfully with a truncated file, e.g. when
+/// the disk fills up.
+pub fn validate_output(
+    path: &Path,
+    expected: Option<Duration>,
+    tolerance: Duration,
+) -> Result<(), Error> {
+    check(
+        Tool::current().ffmpeg().as_os_str(),
+        path,
+        expected,
+        tolerance,
+    )
+}
+
+/// Whether `path` names a file ffmpeg writes as is, as opposed to a numbered
+/// pattern, a pipe, a device or a URL.
+pub(crate) fn is_plain_output(path: &Path) -> bool {
+    let text = path.to_string_lossy();
+    !(text.contains('%')
+        || text == "-"
+        || text.eq_ignore_ascii_case("NUL")
+        || text.starts_with("/dev/")
+        || is_url(path))
+}
+
+pub(crate) fn check(
+    ffmpeg: &OsStr,
+    path: &Path,
+    expected: Option<Duration>,
+    tolerance: Duration,
+) -> Result<(), Error> {
+    let failed = |reason: String| Error::OutputValidationFailed {
+        path: path.to_path_buf(),
+        reason,
+    };
+
+    let size = std::fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+    if size == 0 {
+        return Err(failed("the file is missing or empty".to_string()));
+    }
+    let info = probe(path).map_err(|e| failed(e.to_string()))?;
+    if info.streams.is_empty() {
+        return Err(failed("the file has no streams".to_string()));
+    }
+    // Playlists and manifests last as long as their segments, which are
+    // checked, if at all, as outputs of their own.
+    let manifest = matches!(
+        path.extension().and_then(OsStr::to_str),
+        Some("m3u8" | "mpd")
+    );
+    if !manifest
+        && let (Some(expected), Some(actual)) = (expected, info.duration)
+        && actual.abs_diff(expected) > tolerance
+    {
+        return Err(failed(format!(
+            "it lasts {}s instead of {}s",
+            format_timestamp(actual),
+            format_timestamp(expected)
+        )));
+    }
+
+    if info.video().is_some() {
+        decodes(ffmpeg, path, &[], &["-frames:v", "1"])
+            .map_err(|e| failed(format!("the first frame does not decode: {e}")))?;
+        // Stills have no end to seek to.
+        if info.duration.is_some_and(|d| !d.is_zero()) && !manifest {
+            decodes(ffmpeg, path, &["-sseof", "-1"], &[])
+                .map_err(|e| failed(format!("the last frames do not decode: {e}")))?;
+        }
+    }
+    Ok(())
+}
+
+fn decodes(ffmpeg: &OsStr, path: &Path, input: &[&str], output: &[&str]) -> Result<(), Error> {
+    FfmpegCommand::new()
+        .program(ffmpeg)
+        .global_args(["-v", "error", "-xerror"])
+        .add_input(Input::new(path).args(input))
+        .args(output)
+        .arg("-an")
+        .format("null")
+        .output("-")
+        .run()?
+        .check()?;
+    Ok(())
+}
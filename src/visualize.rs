@@ -0,0 +1,140 @@
+use std::path::Path;
+
+use crate::Error;
+use crate::ffmpeg::{FfmpegCommand, ProcessResult};
+use crate::probe::probe;
+
+#[derive(Debug, Clone)]
+pub struct WaveformOptions {
+    pub size: (u32, u32),
+    /// One color per channel, e.g. `white` or `0x3080ff`; the last is
+    /// repeated for any further channels.
+    pub colors: Vec<String>,
+    /// Fills the image behind the waveform, which is transparent otherwise.
+    pub background: Option<String>,
+    /// Draw each channel in its own row instead of on top of each other.
+    pub split_channels: bool,
+}
+
+impl Default for WaveformOptions {
+    fn default() -> Self {
+        Self {
+            size: (1280, 240),
+            colors: vec!["white".to_string()],
+            background: None,
+            split_channels: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SpectrogramOptions {
+    /// The size of the spectrum itself; the legend adds a border around it.
+    pub size: (u32, u32),
+    /// One of showspectrumpic's color schemes, e.g. `intensity`, `fire`,
+    /// `viridis` or `magma`.
+    pub color: String,
+    /// Label the time and frequency axes.
+    pub legend: bool,
+}
+
+impl Default for SpectrogramOptions {
+    fn default() -> Self {
+        Self {
+            size: (1280, 512),
+            color: "intensity".to_string(),
+            legend: true,
+        }
+    }
+}
+
+/// Renders the first audio track of `input` as a waveform image.
+pub fn waveform_command(
+    input: &Path,
+    output: &Path,
+    options: &WaveformOptions,
+) -> Result<FfmpegCommand, Error> {
+    let command = image_command(input, options.size)?;
+    if options.colors.is_empty() {
+        return Err(Error::invalid("the waveform needs a color"));
+    }
+    for color in options.colors.iter().chain(&options.background) {
+        check_color(color)?;
+    }
+
+    let (width, height) = options.size;
+    let waveform = format!(
+        "showwavespic=s={width}x{height}:colors={}:split_channels={}",
+        options.colors.join("|"),
+        u8::from(options.split_channels)
+    );
+    let graph = match &options.background {
+        Some(background) => format!(
+            "color=c={background}:s={width}x{height}[bg];\
+             [0:a:0]{waveform}[wave];[bg][wave]overlay=format=auto:shortest=1"
+        ),
+        None => format!("[0:a:0]{waveform}"),
+    };
+    Ok(command
+        .filter_complex(graph)
+        .args(["-frames:v", "1"])
+        .output(output))
+}
+
+pub fn waveform(
+    input: &Path,
+    output: &Path,
+    options: &WaveformOptions,
+) -> Result<ProcessResult, Error> {
+    waveform_command(input, output, options)?.run()?.check()
+}
+
+/// Renders the first audio track of `input` as a spectrogram image.
+pub fn spectrogram_command(
+    input: &Path,
+    output: &Path,
+    options: &SpectrogramOptions,
+) -> Result<FfmpegCommand, Error> {
+    let command = image_command(input, options.size)?;
+    check_color(&options.color)?;
+
+    let (width, height) = options.size;
+    Ok(command
+        .filter_complex(format!(
+            "[0:a:0]showspectrumpic=s={width}x{height}:color={}:legend={}",
+            options.color,
+            u8::from(options.legend)
+        ))
+        .args(["-frames:v", "1"])
+        .output(output))
+}
+
+pub fn spectrogram(
+    input: &Path,
+    output: &Path,
+    options: &SpectrogramOptions,
+) -> Result<ProcessResult, Error> {
+    spectrogram_command(input, output, options)?.run()?.check()
+}
+
+fn image_command(input: &Path, (width, height): (u32, u32)) -> Result<FfmpegCommand, Error> {
+    if !input.is_file() {
+        return Err(Error::FileNotFound(input.to_path_buf()));
+    }
+    if width == 0 || height == 0 {
+        return Err(Error::invalid("the image size must be positive"));
+    }
+    if probe(input)?.audio().is_none() {
+        return Err(Error::StreamNotFound);
+    }
+    Ok(FfmpegCommand::new().input(input))
+}
+
+/// Colors go into the filter graph as they are, so anything that would end
+/// the option is refused.
+pub(crate) fn check_color(color: &str) -> Result<(), Error> {
+    if color.is_empty() || color.contains([':', ',', ';', '[', ']', '\'', '|', '\\']) {
+        return Err(Error::invalid(format!("invalid color `{color}`")));
+    }
+    Ok(())
+}
@@ -0,0 +1,213 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::Error;
+use crate::batch::{BatchItem, BatchOperation};
+use crate::ffmpeg::{OverwritePolicy, ProcessResult};
+use crate::job::CancelToken;
+use crate::scan::{name_matches, walk};
+use crate::template::OutputTemplate;
+
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    /// Glob matched against file names, e.g. `*.MP4`.
+    pub pattern: String,
+    pub recursive: bool,
+    pub output_dir: PathBuf,
+    /// Output name relative to `output_dir`, see [`OutputTemplate`].
+    /// `{index}` counts the files processed since watching started.
+    pub template: String,
+    /// Existing outputs are left alone unless this is
+    /// [`OverwritePolicy::Always`], so a restarted watch doesn't redo files.
+    pub overwrite: OverwritePolicy,
+    /// How often the folder is looked at.
+    pub poll_interval: Duration,
+    /// A file counts as complete once its size and modification time stay
+    /// the same for this long, so files still being copied in are left for
+    /// later.
+    pub settle_time: Duration,
+    /// Where inputs are moved once processed. Otherwise they stay in place
+    /// and are only remembered while watching.
+    pub done_dir: Option<PathBuf>,
+    /// Where inputs that failed are moved, by default the same as
+    /// `done_dir`.
+    pub failed_dir: Option<PathBuf>,
+    /// Return once no file is waiting, instead of watching until cancelled.
+    pub stop_when_idle: bool,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            pattern: "*".to_string(),
+            recursive: false,
+            output_dir: PathBuf::from("."),
+            template: "{name}".to_string(),
+            overwrite: OverwritePolicy::Never,
+            poll_interval: Duration::from_secs(2),
+            settle_time: Duration::from_secs(5),
+            done_dir: None,
+            failed_dir: None,
+            stop_when_idle: false,
+        }
+    }
+}
+
+/// A file seen in the folder and how long it has looked the same.
+struct Candidate {
+    len: u64,
+    modified: Option<SystemTime>,
+    since: Instant,
+}
+
+/// Runs `operation` on every file that appears in `dir`, including the ones
+/// already there, one at a time, until `cancel` is cancelled; the file being
+/// processed then still finishes. Calls `on_item` as each file finishes, a
+/// failed file doesn't stop the watch.
+pub fn watch<F>(
+    dir: &Path,
+    operation: &BatchOperation,
+    options: &WatchOptions,
+    cancel: &CancelToken,
+    mut on_item: F,
+) -> Result<(), Error>
+where
+    F: FnMut(&BatchItem),
+{
+    if !dir.is_dir() {
+        return Err(Error::FolderNotFound(dir.to_path_buf()));
+    }
+    let absolute = |path: &Path| std::path::absolute(path).map_err(Error::AccessDenied);
+    let root = absolute(dir)?;
+    // Never pick up our own outputs or the files already moved aside.
+    let mut skip_dirs = Vec::new();
+    for other in [
+        Some(&options.output_dir),
+        options.done_dir.as_ref(),
+        options.failed_dir.as_ref(),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        let other = absolute(other)?;
+        if other != root {
+            skip_dirs.push(other);
+        }
+    }
+
+    let template = OutputTemplate::new(options.template.as_str());
+    let mut candidates: HashMap<PathBuf, Candidate> = HashMap::new();
+    let mut handled = HashSet::new();
+    let mut index = 0;
+    while !cancel.is_cancelled() {
+        let mut waiting = false;
+        let mut files = walk(dir, options.recursive)?;
+        files.sort();
+        for input in files {
+            if handled.contains(&input) || !name_matches(&input, &options.pattern) {
+                continue;
+            }
+            let full = absolute(&input)?;
+            if skip_dirs.iter().any(|skip| full.starts_with(skip)) {
+                continue;
+            }
+            // Gone between the listing and now, e.g. moved away again.
+            let Ok(metadata) = input.metadata() else {
+                candidates.remove(&input);
+                continue;
+            };
+            let (len, modified) = (metadata.len(), metadata.modified().ok());
+            let candidate = candidates.entry(input.clone()).or_insert(Candidate {
+                len,
+                modified,
+                since: Instant::now(),
+            });
+            if candidate.len != len || candidate.modified != modified {
+                *candidate = Candidate {
+                    len,
+                    modified,
+                    since: Instant::now(),
+                };
+            }
+            if candidate.since.elapsed() < options.settle_time {
+                waiting = true;
+                continue;
+            }
+            candidates.remove(&input);
+
+            index += 1;
+            let relative = input
+                .parent()
+                .and_then(|parent| parent.strip_prefix(dir).ok())
+                .unwrap_or(Path::new(""));
+            let output = match template.render_path(&input, Some(index)) {
+                Ok(name) => options.output_dir.join(relative).join(name),
+                Err(e) => {
+                    handled.insert(input.clone());
+                    on_item(&BatchItem {
+                        output: PathBuf::new(),
+                        result: Err(e),
+                        input,
+                    });
+                    continue;
+                }
+            };
+            if output.exists() && options.overwrite != OverwritePolicy::Always {
+                handled.insert(input);
+                continue;
+            }
+
+            let result = process(operation, &input, &output, options);
+            let moved = match (&result, &options.done_dir, &options.failed_dir) {
+                (Ok(_), Some(done), _) | (Err(_), _, Some(done)) | (Err(_), Some(done), None) => {
+                    move_into(&input, &done.join(relative))
+                }
+                _ => Ok(()),
+            };
+            let result = result.and_then(|result| moved.map(|()| result));
+            handled.insert(input.clone());
+            on_item(&BatchItem {
+                input,
+                output,
+                result,
+            });
+        }
+
+        if options.stop_when_idle && !waiting {
+            break;
+        }
+        // The walk lists files that were moved away by then, which would
+        // otherwise pile up.
+        handled.retain(|path: &PathBuf| path.exists());
+        std::thread::sleep(options.poll_interval);
+    }
+    Ok(())
+}
+
+fn process(
+    operation: &BatchOperation,
+    input: &Path,
+    output: &Path,
+    options: &WatchOptions,
+) -> Result<ProcessResult, Error> {
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent).map_err(Error::CreateOutputError)?;
+    }
+    operation
+        .command(input, output)?
+        .overwrite_policy(options.overwrite)
+        .run()?
+        .check()
+}
+
+/// Moves `file` into `dir`, copying it when they are on different disks.
+fn move_into(file: &Path, dir: &Path) -> Result<(), Error> {
+    std::fs::create_dir_all(dir).map_err(Error::CreateOutputError)?;
+    let target = dir.join(file.file_name().unwrap_or_default());
+    if std::fs::rename(file, &target).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(file, &target).map_err(Error::WriteFileError)?;
+    std::fs::remove_file(file).map_err(Error::WriteFileError)
+}